@@ -0,0 +1,52 @@
+//! Scan a directory and observe progress through an event callback instead of
+//! polling the event channel.
+//!
+//! Run with: `cargo run --example scan_events -- /some/path`
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use disklens::config::settings::Settings;
+use disklens::core::events::{create_event_channel, Event};
+use disklens::core::scanner::Scanner;
+use disklens::models::node::human_readable_size;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let path = std::fs::canonicalize(path)?;
+
+    // The scanner still needs a channel; without a receiver loop the receiver can simply be dropped.
+    let (event_tx, _rx) = create_event_channel();
+
+    // Callbacks may run concurrently on several scan tasks, so shared state must be thread-safe.
+    let errors = Arc::new(AtomicUsize::new(0));
+    let errors_seen = Arc::clone(&errors);
+
+    let scanner = Scanner::new(Settings::default(), event_tx).on_event(move |event| match event {
+        Event::Progress {
+            scanned,
+            current_path,
+            ..
+        } => eprintln!("{} files, at {}", scanned, current_path.display()),
+        Event::ScanError { path, error } => {
+            errors_seen.fetch_add(1, Ordering::Relaxed);
+            eprintln!("error: {}: {}", path.display(), error);
+        }
+        _ => {}
+    });
+
+    let result = scanner.scan(path).await?;
+    println!(
+        "{}: {} in {} files ({} errors)",
+        result.scan_path.display(),
+        human_readable_size(result.total_size),
+        result.total_files,
+        errors.load(Ordering::Relaxed),
+    );
+    Ok(())
+}
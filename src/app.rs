@@ -1,8 +1,8 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
-use crossterm::event::Event;
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture, Event};
 use crossterm::execute;
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::CrosstermBackend;
@@ -10,62 +10,250 @@ use ratatui::Terminal;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 
-use crate::config::settings::Settings;
+use crate::config::notes::NotesStore;
+use crate::config::settings::{ScanBackend, Settings};
+use crate::core::cache::Cache;
+use crate::core::compression::{self, CompressionEstimate, CompressionJob};
+use crate::core::diff::DiffResult;
 use crate::core::events;
+use crate::core::mounts::MountInfo;
+use crate::core::profile::IoProfile;
 use crate::core::progress::ProgressTracker;
-use crate::core::scanner::Scanner;
+use crate::core::scanner::{ScanControl, Scanner, ScannerSync};
 use crate::models::scan_result::ScanResult;
-use crate::ui::app_state::AppState;
+use crate::ui::app_state::{AppState, ExportScope, ViewMode};
 use crate::ui::input::{self, InputAction};
 use crate::ui::renderer;
 
 pub struct App {
     state: AppState,
+    /// Paths every scan and rescan covers.
+    roots: Vec<PathBuf>,
     settings: Settings,
+    /// I/O timings of the latest scan, when `--profile` is set.
+    io_profile: Option<Arc<IoProfile>>,
+}
+
+/// A scan running in the background, and the channels the event loop reads it through.
+struct ScanTask {
+    events: events::EventReceiver,
+    progress: Arc<ProgressTracker>,
+    control: ScanControl,
+    handle: JoinHandle<anyhow::Result<ScanResult>>,
 }
 
 impl App {
-    pub fn new(root_path: PathBuf, settings: Settings) -> Self {
+    /// With several roots the TUI opens on a virtual root holding each of them.
+    pub fn new(roots: Vec<PathBuf>, settings: Settings) -> Self {
+        let root_path = match roots.as_slice() {
+            [root] => root.clone(),
+            _ => PathBuf::new(),
+        };
+        let mut state = AppState::new(root_path);
+        state.list_density = settings.list_density;
+        state.near_empty_size = settings.near_empty_size;
+        state.junk_dirs = settings.junk_dirs.clone();
+        match NotesStore::load(settings.notes_file()) {
+            Ok(notes) => state.notes = notes,
+            Err(e) => tracing::warn!("Failed to load notes: {}", e),
+        }
         Self {
-            state: AppState::new(root_path),
+            state,
+            roots,
             settings,
+            io_profile: None,
         }
     }
 
+    /// Open on a picker of `mounts` and scan the ones chosen there, instead of `roots`.
+    pub fn with_mount_picker(mut self, mounts: Vec<MountInfo>) -> Self {
+        self.state.open_mount_picker(mounts);
+        self
+    }
+
     pub async fn run(&mut self) -> anyhow::Result<()> {
         // Initialize terminal
         terminal::enable_raw_mode()?;
-        let mut stdout = std::io::stdout();
-        execute!(stdout, EnterAlternateScreen)?;
-        let backend = CrosstermBackend::new(stdout);
-        let mut terminal = Terminal::new(backend)?;
-        terminal.clear()?;
+        let mut terminal = match self.init_terminal() {
+            Ok(terminal) => terminal,
+            Err(e) => {
+                let _ = terminal::disable_raw_mode();
+                let _ = execute!(std::io::stdout(), DisableMouseCapture);
+                if self.settings.alternate_screen {
+                    let _ = execute!(std::io::stdout(), LeaveAlternateScreen);
+                }
+                return Err(e);
+            }
+        };
 
-        // Start scan task
-        let (event_tx, event_rx) = events::create_event_channel();
-        let scanner = Scanner::new(self.settings.clone(), event_tx);
-        let scan_path = self.state.current_path.clone();
-        let progress = scanner.progress().clone();
+        if self.state.view_mode == ViewMode::MountPicker {
+            let picked = self.pick_mounts(&mut terminal).await;
+            if !matches!(picked, Ok(true)) {
+                self.restore_terminal(&mut terminal)?;
+                return picked.map(drop);
+            }
+        }
 
-        let scan_handle = tokio::spawn(async move { scanner.scan(scan_path).await });
+        // Open on the saved scan of the root when there is one, rescanning in the
+        // background if it is stale; otherwise start a scan that reuses the unchanged
+        // directories earlier scans saved entries for, with `cache_dirs` on
+        let cache = Cache::from_settings(&self.settings);
+        if self.settings.use_cache {
+            if let Err(e) = cache.remove_expired().await {
+                tracing::warn!("Failed to remove expired cache entries: {}", e);
+            }
+        }
+        let scan = match self.load_cached(&cache).await {
+            Some((cached, stale)) => {
+                let scan = stale.then(|| self.start_scan(Some(cached.clone()), true));
+                self.state.size_history = Some(cache.load_history(&cached.scan_path).await);
+                self.state.show_cached_scan(cached, stale);
+                scan
+            }
+            None => Some(self.start_scan(None, true)),
+        };
 
         // Run main event loop
-        let result = self.event_loop(&mut terminal, event_rx, &progress, scan_handle).await;
+        let result = self.event_loop(&mut terminal, scan).await;
+        self.restore_terminal(&mut terminal)?;
 
-        // Restore terminal
+        if let (Some(profile), Some(profile_path)) = (&self.io_profile, &self.settings.profile_output) {
+            let root = self
+                .state
+                .scan_result
+                .as_ref()
+                .map(|r| r.scan_path.clone())
+                .unwrap_or_else(|| self.state.current_path.clone());
+            profile.write_folded(&root, profile_path)?;
+        }
+
+        result
+    }
+
+    fn restore_terminal(
+        &self,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    ) -> anyhow::Result<()> {
         terminal::disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+        if self.settings.alternate_screen {
+            execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        } else {
+            // Park the cursor below the last frame so the shell prompt doesn't overwrite it
+            let size = terminal.size()?;
+            terminal.set_cursor_position((0, size.height.saturating_sub(1)))?;
+            println!();
+        }
         terminal.show_cursor()?;
+        Ok(())
+    }
 
-        result
+    /// Run the mount picker until mounts are chosen, which become the roots to scan.
+    /// Returns false if the user quit instead.
+    async fn pick_mounts(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+    ) -> anyhow::Result<bool> {
+        loop {
+            terminal.draw(|frame| {
+                renderer::render(frame, &self.state);
+            })?;
+            let poll = || input::poll_event(Duration::from_millis(250));
+            let event = tokio::task::spawn_blocking(poll).await??;
+            let Some(Event::Key(key)) = event else {
+                continue;
+            };
+            match input::handle_key_event(key, &mut self.state) {
+                InputAction::Quit => return Ok(false),
+                InputAction::ScanMounts => {
+                    let roots = self.state.confirm_mounts();
+                    if !roots.is_empty() {
+                        self.roots = roots;
+                        return Ok(true);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn init_terminal(&self) -> anyhow::Result<Terminal<CrosstermBackend<std::io::Stdout>>> {
+        let mut stdout = std::io::stdout();
+        if self.settings.alternate_screen {
+            execute!(stdout, EnterAlternateScreen)?;
+        }
+        execute!(stdout, EnableMouseCapture)?;
+        let backend = CrosstermBackend::new(stdout);
+        let mut terminal = Terminal::new(backend)?;
+        terminal.clear()?;
+        Ok(terminal)
+    }
+
+    /// The saved scan of the single root being scanned, and whether it is stale: past
+    /// `cache_stale_minutes`, or taken before the root directory last changed.
+    async fn load_cached(&self, cache: &Cache) -> Option<(ScanResult, bool)> {
+        let [root] = self.roots.as_slice() else {
+            return None;
+        };
+        if !self.settings.use_cache {
+            return None;
+        }
+        let (result, root_changed) = match cache.load(root).await {
+            Some(result) => (result, false),
+            None => (cache.load_previous(root).await?, true),
+        };
+        // A tree built with other excludes or limits is not this session's view of it
+        if result.tree_settings != self.settings.tree_settings() {
+            return None;
+        }
+        let max_age = Duration::from_secs(self.settings.cache_stale_minutes.saturating_mul(60));
+        let age = SystemTime::now().duration_since(result.timestamp).unwrap_or_default();
+        Some((result, root_changed || age > max_age))
+    }
+
+    /// Start scanning the roots. An `incremental` scan reuses the unchanged directories
+    /// of `previous` and, with `cache_dirs` on, of the cache's directory entries.
+    fn start_scan(&mut self, previous: Option<ScanResult>, incremental: bool) -> ScanTask {
+        let (event_tx, event_rx) = events::create_event_channel();
+        let roots = self.roots.clone();
+        let (progress, control, handle) = match self.settings.backend {
+            ScanBackend::Tasks => {
+                let mut scanner =
+                    Scanner::new(self.settings.clone(), event_tx).with_subtree_events();
+                if let Some(previous) = previous {
+                    scanner = scanner.with_previous(previous);
+                }
+                if incremental && self.settings.use_cache && self.settings.cache_dirs {
+                    scanner = scanner.with_cache(Cache::from_settings(&self.settings));
+                }
+                self.io_profile = scanner.io_profile().cloned();
+                let (progress, control) = (scanner.progress().clone(), scanner.control());
+                let handle = tokio::spawn(async move { scanner.scan_roots(roots).await });
+                (progress, control, handle)
+            }
+            // No incremental rescans: `previous` and the cache are ignored and everything
+            // is read again
+            ScanBackend::Threads | ScanBackend::Uring | ScanBackend::Mft => {
+                let scanner =
+                    ScannerSync::new(self.settings.clone(), event_tx).with_subtree_events();
+                self.io_profile = scanner.io_profile().cloned();
+                let (progress, control) = (scanner.progress().clone(), scanner.control());
+                let handle = tokio::task::spawn_blocking(move || scanner.scan_roots(roots));
+                (progress, control, handle)
+            }
+        };
+        ScanTask {
+            events: event_rx,
+            progress,
+            control,
+            handle,
+        }
     }
 
     async fn event_loop(
         &mut self,
         terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
-        mut event_rx: events::EventReceiver,
-        progress: &Arc<ProgressTracker>,
-        scan_handle: JoinHandle<anyhow::Result<ScanResult>>,
+        scan: Option<ScanTask>,
     ) -> anyhow::Result<()> {
         // Spawn a dedicated blocking thread for terminal input.
         // This sends crossterm events to the async world via an unbounded channel,
@@ -86,9 +274,20 @@ impl App {
         });
 
         let mut tick_interval = tokio::time::interval(Duration::from_millis(100));
-        let mut scan_channel_open = true;
-        // Wrap scan_handle in Option so we can take it once to await
-        let mut scan_handle = Some(scan_handle);
+        // Without a scan, as when a fresh cached scan is shown, the channels stay idle
+        // until a rescan starts one
+        let mut scan_channel_open = scan.is_some();
+        let (mut event_rx, mut progress, mut control, mut scan_handle) = match scan {
+            Some(scan) => (scan.events, scan.progress, Some(scan.control), Some(scan.handle)),
+            None => {
+                let (_, events) = events::create_event_channel();
+                (events, Arc::new(ProgressTracker::new()), None, None)
+            }
+        };
+        // `control` stops the background scan when we quit, so it doesn't keep walking
+        // the disk; `scan_handle` is taken once to await the result
+        // Compression estimate being made in the background, and for which directory
+        let mut compression: Option<(PathBuf, JoinHandle<Vec<CompressionEstimate>>)> = None;
 
         loop {
             tokio::select! {
@@ -97,15 +296,88 @@ impl App {
                     match input_event {
                         Some(Event::Key(key)) => {
                             let action = input::handle_key_event(key, &mut self.state);
+                            let mut rescan = false;
+                            let mut incremental = false;
                             match action {
-                                InputAction::Quit => return Ok(()),
+                                InputAction::Quit => {
+                                    if let Some(control) = &control {
+                                        control.cancel();
+                                    }
+                                    return Ok(());
+                                }
+                                InputAction::TogglePause => {
+                                    if let Some(control) = &control {
+                                        control.toggle_pause();
+                                    }
+                                }
+                                InputAction::CancelScan => {
+                                    if let Some(control) = &control {
+                                        control.cancel();
+                                    }
+                                    self.state.status_message =
+                                        Some("Stopping scan\u{2026}".to_string());
+                                }
                                 InputAction::Export => self.handle_export(),
+                                InputAction::WriteView => self.handle_write_view(),
+                                InputAction::DeleteEmptyDirs => self.handle_delete_empty_dirs(),
+                                InputAction::ExportOwners => self.handle_export_owners(),
+                                InputAction::EstimateCompression => {
+                                    if let Some(node) = self.state.current_node() {
+                                        let jobs: Vec<CompressionJob> =
+                                            node.children.iter().map(CompressionJob::new).collect();
+                                        let handle = tokio::task::spawn_blocking(move || {
+                                            compression::run_all(jobs)
+                                        });
+                                        compression = Some((node.path.clone(), handle));
+                                    }
+                                }
+                                InputAction::Refresh => {
+                                    rescan = true;
+                                    incremental = true;
+                                }
+                                InputAction::FullRefresh => rescan = true,
+                                InputAction::ToggleSymlinks => {
+                                    self.toggle_follow_symlinks();
+                                    rescan = true;
+                                }
                                 _ => {}
                             }
+                            if rescan {
+                                // A new scanner starts with an empty visited set, so links
+                                // skipped last time are followed now
+                                if let Some(control) = &control {
+                                    control.cancel();
+                                }
+                                if let Some(handle) = scan_handle.take() {
+                                    handle.abort();
+                                }
+                                // A provisional tree is incomplete, so it can't stand in
+                                // for unchanged directories
+                                let previous = if incremental && !self.state.scanning {
+                                    self.state.scan_result.clone()
+                                } else {
+                                    None
+                                };
+                                let scan = self.start_scan(previous, incremental);
+                                self.state.begin_rescan();
+                                event_rx = scan.events;
+                                progress = scan.progress;
+                                control = Some(scan.control);
+                                scan_handle = Some(scan.handle);
+                                scan_channel_open = true;
+                            }
+                        }
+                        Some(Event::Mouse(mouse)) => {
+                            input::handle_mouse_event(mouse, &mut self.state);
                         }
                         Some(Event::Resize(_, _)) => {}
                         Some(_) => {}
-                        None => return Ok(()),
+                        None => {
+                            if let Some(control) = &control {
+                                control.cancel();
+                            }
+                            return Ok(());
+                        }
                     }
                     // Render immediately after input for responsiveness
                     terminal.draw(|frame| {
@@ -130,6 +402,14 @@ impl App {
                             );
                             self.state.error_count = snapshot.errors_count;
                         }
+                        Some(events::Event::SubtreeCompleted { node }) => {
+                            self.state.add_subtree(&self.roots, self.settings.size_mode, *node);
+                        }
+                        Some(events::Event::ScanPaused) => self.state.scan_paused = true,
+                        Some(events::Event::ScanResumed) => self.state.scan_paused = false,
+                        Some(events::Event::NetworkFilesystem { fs_type, .. }) => {
+                            self.state.network_fs = Some(fs_type);
+                        }
                         Some(events::Event::ScanError { .. }) => {
                             let snapshot = progress.snapshot();
                             self.state.error_count = snapshot.errors_count;
@@ -144,16 +424,24 @@ impl App {
                 }
                 // Periodic tick for rendering and progress updates
                 _ = tick_interval.tick() => {
-                    if self.state.scan_result.is_none() {
+                    if self.state.scanning || self.state.refreshing {
                         let snapshot = progress.snapshot();
                         self.state.update_progress(
                             snapshot.files_scanned,
                             snapshot.total_size,
                             snapshot.files_per_second,
-                            self.state.current_scanning_path.clone(),
+                            snapshot.current_path.to_string_lossy().to_string(),
                         );
                         self.state.error_count = snapshot.errors_count;
                     }
+                    if compression.as_ref().is_some_and(|(_, handle)| handle.is_finished()) {
+                        if let Some((path, handle)) = compression.take() {
+                            match handle.await {
+                                Ok(estimates) => self.state.set_compression(&path, estimates),
+                                Err(e) => tracing::error!("Compression estimate panicked: {}", e),
+                            }
+                        }
+                    }
                     // Render on tick (every 100ms)
                     terminal.draw(|frame| {
                         renderer::render(frame, &self.state);
@@ -162,10 +450,38 @@ impl App {
             }
 
             // When the scan event channel closes, collect the ScanResult
-            if !scan_channel_open && self.state.scan_result.is_none() {
+            if !scan_channel_open && (self.state.scanning || self.state.refreshing) {
                 if let Some(handle) = scan_handle.take() {
                     match handle.await {
-                        Ok(Ok(result)) => self.state.set_scan_result(result),
+                        Ok(Ok(result)) => {
+                            // A partial or approximate tree would look like a sudden drop
+                            // in the history, and the cache is keyed by a single scan path
+                            let mut diff = None;
+                            if result.is_cacheable() && self.settings.use_cache {
+                                let cache = Cache::from_settings(&self.settings);
+                                diff = cache
+                                    .load_previous(&result.scan_path)
+                                    .await
+                                    .filter(|previous| previous.timestamp != result.timestamp)
+                                    .map(|previous| DiffResult::compare(&previous, &result));
+                                match cache.save(&result).await {
+                                    Ok(history) => self.state.size_history = Some(history),
+                                    Err(e) => tracing::warn!("Failed to save scan to cache: {}", e),
+                                }
+                                // Written in the background, so the tree shows at once
+                                if self.settings.cache_dirs {
+                                    let saving = cache.save_dirs(&result);
+                                    tokio::spawn(async move {
+                                        let saved = async { anyhow::Ok(saving?.await??) };
+                                        if let Err(e) = saved.await {
+                                            tracing::warn!("Failed to save directories to cache: {}", e);
+                                        }
+                                    });
+                                }
+                            }
+                            self.state.set_scan_result(result);
+                            self.state.set_scan_diff(diff.as_ref());
+                        }
                         Ok(Err(e)) => tracing::error!("Scan failed: {}", e),
                         Err(e) => tracing::error!("Scan task panicked: {}", e),
                     }
@@ -173,22 +489,127 @@ impl App {
             }
 
             if self.state.should_quit {
+                if let Some(control) = &control {
+                    control.cancel();
+                }
                 return Ok(());
             }
         }
     }
 
-    fn handle_export(&self) {
-        if let Some(ref result) = self.state.scan_result {
-            let path = PathBuf::from(format!(
-                "disklens_report_{}.json",
-                chrono::Local::now().format("%Y%m%d_%H%M%S")
-            ));
-            if let Err(e) = crate::export::json::export_json(result, &path) {
+    fn toggle_follow_symlinks(&mut self) {
+        self.settings.follow_symlinks = !self.settings.follow_symlinks;
+        self.state.status_message = Some(if self.settings.follow_symlinks {
+            "Following symlinks".to_string()
+        } else {
+            "Not following symlinks".to_string()
+        });
+    }
+
+    /// Delete the directories picked in the empty directory finder, each only after
+    /// checking on disk that it holds no more than it was listed with.
+    fn handle_delete_empty_dirs(&mut self) {
+        let dirs: Vec<_> = self.state.dirs_to_delete().into_iter().cloned().collect();
+        let mut deleted = Vec::new();
+        let mut failed = Vec::new();
+        for dir in dirs {
+            match crate::core::cleanup::remove_dir_checked(&dir.path, dir.file_count, dir.size) {
+                Ok(()) => deleted.push(dir.path),
+                Err(e) => {
+                    tracing::warn!("Not deleting {}: {}", dir.path.display(), e);
+                    failed.push(format!("{}: {}", dir.path.display(), e));
+                }
+            }
+        }
+        self.state.remove_deleted(&deleted);
+        let mut message = format!("Deleted {} directories", deleted.len());
+        if let Some(first) = failed.first() {
+            message.push_str(&format!("; {} left alone ({})", failed.len(), first));
+        }
+        self.state.status_message = Some(message);
+    }
+
+    /// Snapshot the visible file list into a Markdown table in the working directory.
+    fn handle_write_view(&mut self) {
+        let Some(dir) = self.state.current_node().map(|n| n.path.clone()) else {
+            return;
+        };
+        let path = PathBuf::from(format!(
+            "disklens_view_{}.md",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        ));
+        let result = crate::export::markdown::export_view_markdown(
+            &dir,
+            &self.state.view_description(),
+            &self.state.sorted_children(),
+            self.state.percentage_base(),
+            &path,
+        );
+        self.state.status_message = Some(match result {
+            Ok(()) => format!("View written to {}", path.display()),
+            Err(e) => format!("Failed to write view: {}", e),
+        });
+    }
+
+    /// Save the per-user table of the owners overlay as CSV in the working directory.
+    fn handle_export_owners(&mut self) {
+        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+        let path = PathBuf::from(format!("disklens_owners_{}.csv", timestamp));
+        match crate::export::csv::export_owners_csv(&self.state.owners, &path) {
+            Ok(()) => {
+                tracing::info!("Exported to: {}", path.display());
+                self.state.status_message = Some(format!("Exported to {}", path.display()));
+            }
+            Err(e) => {
                 tracing::error!("Export failed: {}", e);
-            } else {
+                self.state.status_message = Some(format!("Export failed: {}", e));
+            }
+        }
+    }
+
+    /// Write the export chosen in the export dialog and close it. On failure the
+    /// dialog stays open, so the file name can be fixed.
+    fn handle_export(&mut self) {
+        let path = PathBuf::from(self.state.export_path.trim());
+        match self.write_export(&path) {
+            Ok(()) => {
                 tracing::info!("Exported to: {}", path.display());
+                self.state.status_message = Some(format!(
+                    "Exported {} to {}",
+                    self.state.export_format.label(),
+                    path.display()
+                ));
+                self.state.close_export_dialog();
+            }
+            Err(e) => {
+                tracing::error!("Export failed: {}", e);
+                self.state.status_message = Some(format!("Export failed: {}", e));
             }
         }
     }
+
+    fn write_export(&self, path: &Path) -> anyhow::Result<()> {
+        let Some(ref result) = self.state.scan_result else {
+            anyhow::bail!("nothing has been scanned");
+        };
+        if path.as_os_str().is_empty() {
+            anyhow::bail!("no file name given");
+        }
+        let scoped = match self.state.export_scope {
+            ExportScope::Scan => None,
+            ExportScope::CurrentDirectory => {
+                let dir = self.state.current_node().map(|node| node.path.clone());
+                Some(
+                    dir.and_then(|dir| result.subtree(&dir))
+                        .ok_or_else(|| anyhow::anyhow!("the current directory is gone"))?,
+                )
+            }
+            ExportScope::Marked => {
+                let marked: Vec<PathBuf> = self.state.marked.iter().cloned().collect();
+                Some(result.select_paths(&marked))
+            }
+        };
+        let view = self.settings.export_view(scoped.as_ref().unwrap_or(result))?;
+        self.state.export_format.export(&view, path)
+    }
 }
@@ -1 +1,6 @@
 pub mod settings;
+pub mod time_spec;
+pub mod size_spec;
+pub mod path;
+pub mod notes;
+pub mod profiles;
@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Free-text notes attached to absolute paths, persisted as JSON so they
+/// survive across runs and rescans.
+#[derive(Debug, Default)]
+pub struct NotesStore {
+    /// Backing file; `None` keeps notes in memory only.
+    file: Option<PathBuf>,
+    notes: BTreeMap<PathBuf, String>,
+}
+
+impl NotesStore {
+    /// Load notes from `file`. A missing file yields an empty store that will be
+    /// created on the first save.
+    pub fn load(file: PathBuf) -> anyhow::Result<Self> {
+        let notes = match std::fs::read(&file) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            file: Some(file),
+            notes,
+        })
+    }
+
+    pub fn get(&self, path: &Path) -> Option<&str> {
+        self.notes.get(path).map(String::as_str)
+    }
+
+    /// Set or replace the note on `path`; a blank note removes it.
+    pub fn set(&mut self, path: PathBuf, note: &str) {
+        let note = note.trim();
+        if note.is_empty() {
+            self.notes.remove(&path);
+        } else {
+            self.notes.insert(path, note.to_string());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.notes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+    }
+
+    /// Write the notes back to their file (temp file + rename).
+    pub fn save(&self) -> anyhow::Result<()> {
+        let Some(file) = &self.file else {
+            return Ok(());
+        };
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = file.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(&self.notes)?)?;
+        std::fs::rename(&tmp, file)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,30 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::bail;
+
+/// Turn the path given on the command line into the absolute path to scan.
+///
+/// Canonicalization is preferred, but it fails on things like an unreadable
+/// ancestor; rather than refuse to start, fall back to a plain absolute path.
+/// Only a path that doesn't exist at all is an error.
+pub fn resolve_scan_path(path: &Path) -> anyhow::Result<PathBuf> {
+    let err = match std::fs::canonicalize(path) {
+        Ok(canonical) => return Ok(canonical),
+        Err(e) => e,
+    };
+
+    if let Err(meta_err) = std::fs::symlink_metadata(path) {
+        if meta_err.kind() == std::io::ErrorKind::NotFound {
+            bail!("path does not exist: {}", path.display());
+        }
+    }
+
+    let absolute = std::path::absolute(path)?;
+    tracing::warn!(
+        "could not canonicalize {} ({}), using {}",
+        path.display(),
+        err,
+        absolute.display()
+    );
+    Ok(absolute)
+}
@@ -0,0 +1,22 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::settings::ScanProfile;
+
+/// Read per-path scan profiles from a JSON object keyed by path, for example
+/// `{"/mnt/nas": {"max_concurrent_io": 8, "max_depth": 4}}`.
+///
+/// A trailing `/**` on a key is accepted and dropped, since a profile always covers
+/// everything below its path. Keys naming existing paths are canonicalized so they
+/// line up with the canonical paths that get scanned.
+pub fn load_profiles(file: &Path) -> anyhow::Result<BTreeMap<PathBuf, ScanProfile>> {
+    let raw: BTreeMap<String, ScanProfile> = serde_json::from_slice(&std::fs::read(file)?)?;
+    Ok(raw
+        .into_iter()
+        .map(|(key, profile)| {
+            let path = PathBuf::from(key.strip_suffix("/**").unwrap_or(&key));
+            let path = std::fs::canonicalize(&path).unwrap_or(path);
+            (path, profile)
+        })
+        .collect())
+}
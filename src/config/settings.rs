@@ -1,17 +1,199 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
 
 use serde::{Deserialize, Serialize};
 
+use crate::core::mounts::DEFAULT_PSEUDO_FS_TYPES;
+use crate::models::node::SizeMode;
+use crate::models::scan_result::ScanResult;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Settings {
     pub max_depth: Option<usize>,
     pub max_concurrent_io: usize,
+    /// Which scanner walks the tree.
+    pub backend: ScanBackend,
+    /// Throttle directories on network filesystems (NFS, SMB, sshfs, ...) with the
+    /// two limits below, which replace `max_concurrent_io` there.
+    pub throttle_network_fs: bool,
+    /// Concurrent directory reads allowed across all network filesystems.
+    pub network_max_concurrent_io: usize,
+    /// Minimum time between the starts of two directory reads on network filesystems.
+    pub network_request_interval: Duration,
     pub follow_symlinks: bool,
     pub merge_threshold: f64,
     pub ignore_patterns: Vec<String>,
+    /// Leave out hidden entries: dotfiles, or on Windows those with the hidden attribute.
+    pub skip_hidden: bool,
+    /// What to do with entries matched by `.gitignore` and `.git/info/exclude` files.
+    pub gitignore: GitignoreMode,
     pub cache_dir: PathBuf,
+    /// Start from saved scans and save finished ones in `cache_dir`.
+    pub use_cache: bool,
+    /// Also save an entry per directory in `cache_dir`, and have scans of any root
+    /// reuse the directories whose mtime still matches. A file rewritten in place
+    /// keeps its mtime-unchanged directory's cached size, so this is off by default.
+    pub cache_dirs: bool,
+    /// Where user data such as directory notes is kept.
+    pub config_dir: PathBuf,
+    /// Encrypt saved scans and snapshots with a key derived from this passphrase.
+    #[serde(skip)]
+    pub cache_passphrase: Option<String>,
     pub cache_max_size_mb: u64,
     pub cache_max_age_days: u64,
+    /// A saved scan the TUI opens with is refreshed by a background rescan once it is
+    /// older than this, or right away when the root directory itself changed.
+    pub cache_stale_minutes: u64,
+    /// Count a file with several hard links once, at the first link found; the other
+    /// links are listed with a size of 0.
+    pub dedupe_hardlinks: bool,
+    /// Whether sizes report file lengths or allocated disk blocks.
+    pub size_mode: SizeMode,
+    /// Add each directory's own metadata size to its total, as `du` does.
+    pub count_dir_overhead: bool,
+    /// Ask the filesystem which file extents are shared with clones or reflinks,
+    /// filling `Node::shared_size`. Costs an extra open and ioctl per file.
+    pub detect_clones: bool,
+    /// List the contents of .zip, .tar and .tar.gz files as `NodeType::ArchiveEntry`
+    /// children, so archives can be browsed like directories.
+    pub peek_archives: bool,
+    /// Stat only this fraction of files in very large directories and extrapolate the rest.
+    pub sample_ratio: Option<f64>,
+    /// In directories with many subdirectories, descend into only about this fraction
+    /// of them, drawn at random weighted by the size of their directory entries, and
+    /// extrapolate the rest.
+    pub estimate_ratio: Option<f64>,
+    /// Stop starting new directories once the scan has run this long; directories not
+    /// yet started are listed as unscanned and the result is marked truncated.
+    pub max_duration: Option<Duration>,
+    /// Files smaller than this are not kept as nodes; each directory gets one entry
+    /// holding their combined size and count instead.
+    pub min_file_size: Option<u64>,
+    /// Exports keep only files modified at or after this time.
+    pub modified_after: Option<SystemTime>,
+    /// Exports keep only files modified before this time.
+    pub modified_before: Option<SystemTime>,
+    /// Exports cover only the entry at this path and what's below it.
+    pub export_root: Option<PathBuf>,
+    /// Exports stop at this depth below their root.
+    pub export_depth: Option<usize>,
+    /// Exports leave out entries smaller than this.
+    pub export_min_size: Option<u64>,
+    /// Draw the TUI on the alternate screen; when off it renders inline and stays in scrollback.
+    pub alternate_screen: bool,
+    /// When set, per-directory I/O time is recorded and written here as folded stacks.
+    pub profile_output: Option<PathBuf>,
+    /// Stay on the scan root's filesystem: directories on other devices are skipped.
+    pub one_file_system: bool,
+    /// Mount points of these filesystem types are not descended into.
+    pub pseudo_fs_types: Vec<String>,
+    /// Initial file list density in the TUI.
+    pub list_density: ListDensity,
+    /// Besides directories without any files, the empty directory finder lists those
+    /// holding at most this many bytes. 0, the default, lists only empty ones.
+    pub near_empty_size: u64,
+    /// Directory names to list as cleanup candidates, besides the built-in catalogue
+    /// of package installs, build output and caches.
+    pub junk_dirs: Vec<String>,
+    /// Overrides for the subtrees below these paths. Each directory uses the profile
+    /// with the longest path containing it.
+    pub profiles: BTreeMap<PathBuf, ScanProfile>,
+}
+
+/// Scan settings for one subtree; see `Settings::profiles`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanProfile {
+    /// Concurrent directory reads within the subtree, in place of `max_concurrent_io`.
+    pub max_concurrent_io: Option<usize>,
+    /// Directory levels to read below the profile's path; deeper ones are listed empty.
+    pub max_depth: Option<usize>,
+    /// Extra exclude patterns, as if in an ignore file at the profile's path.
+    pub ignore_patterns: Vec<String>,
+}
+
+/// The settings that decide which entries a scan's tree holds and how they are sized.
+/// Results record them, so a saved scan is only reused by a scan that would have
+/// built the same tree.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TreeSettings {
+    pub max_depth: Option<usize>,
+    pub follow_symlinks: bool,
+    pub ignore_patterns: Vec<String>,
+    pub skip_hidden: bool,
+    pub gitignore: GitignoreMode,
+    pub dedupe_hardlinks: bool,
+    pub count_dir_overhead: bool,
+    pub detect_clones: bool,
+    pub sample_ratio: Option<f64>,
+    pub estimate_ratio: Option<f64>,
+    pub min_file_size: Option<u64>,
+    pub one_file_system: bool,
+    pub pseudo_fs_types: Vec<String>,
+    /// `Settings::profiles` without their concurrency limits.
+    pub profiles: BTreeMap<PathBuf, ScanProfile>,
+}
+
+impl TreeSettings {
+    /// Whether trees built with these settings give every entry its measured size:
+    /// nothing is sampled, estimated, totalled past a depth limit or folded below a
+    /// minimum size.
+    pub fn is_exact(&self) -> bool {
+        self.sample_ratio.is_none()
+            && self.estimate_ratio.is_none()
+            && self.max_depth.is_none()
+            && self.min_file_size.is_none()
+            && self.profiles.values().all(|profile| profile.max_depth.is_none())
+    }
+}
+
+/// How many lines the file list spends on each entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ListDensity {
+    /// One line per entry.
+    #[default]
+    Compact,
+    /// A second, dimmed line with the modification date and full path.
+    Comfortable,
+}
+
+impl ListDensity {
+    pub fn toggle(self) -> Self {
+        match self {
+            ListDensity::Compact => ListDensity::Comfortable,
+            ListDensity::Comfortable => ListDensity::Compact,
+        }
+    }
+}
+
+/// The engine behind a scan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ScanBackend {
+    /// `Scanner`: a tokio task per directory, reading on the blocking pool.
+    #[default]
+    Tasks,
+    /// `ScannerSync`: blocking reads on a rayon thread pool, which has less overhead on
+    /// fast local disks.
+    Threads,
+    /// `ScannerSync` with each directory's `statx` calls submitted as one io_uring
+    /// batch. Needs Linux and the `uring` cargo feature.
+    Uring,
+    /// `ScannerSync` listing directories from the volume's NTFS Master File Table, read
+    /// in one pass up front. Needs Windows and an elevated prompt.
+    Mft,
+}
+
+/// How the scanner treats entries that git would ignore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum GitignoreMode {
+    /// Git's ignore files are not read.
+    #[default]
+    Off,
+    /// Ignored entries are left out of the scan.
+    Skip,
+    /// Ignored entries are scanned and flagged with `Node::gitignored`.
+    Tag,
 }
 
 impl Default for Settings {
@@ -30,16 +212,111 @@ impl Default for Settings {
         Self {
             max_depth: None,
             max_concurrent_io,
+            backend: ScanBackend::Tasks,
+            throttle_network_fs: true,
+            network_max_concurrent_io: 4,
+            network_request_interval: Duration::from_millis(5),
             follow_symlinks: false,
             merge_threshold: 0.01,
             ignore_patterns: vec![],
+            skip_hidden: false,
+            gitignore: GitignoreMode::Off,
             cache_dir,
+            use_cache: true,
+            cache_dirs: false,
+            config_dir: dirs_config_dir().unwrap_or_else(|| PathBuf::from(".disklens")),
+            cache_passphrase: None,
             cache_max_size_mb: 512,
             cache_max_age_days: 7,
+            cache_stale_minutes: 60,
+            dedupe_hardlinks: true,
+            size_mode: SizeMode::Apparent,
+            count_dir_overhead: false,
+            detect_clones: false,
+            peek_archives: false,
+            sample_ratio: None,
+            estimate_ratio: None,
+            max_duration: None,
+            min_file_size: None,
+            modified_after: None,
+            modified_before: None,
+            export_root: None,
+            export_depth: None,
+            export_min_size: None,
+            alternate_screen: true,
+            profile_output: None,
+            one_file_system: false,
+            pseudo_fs_types: DEFAULT_PSEUDO_FS_TYPES.iter().map(|t| t.to_string()).collect(),
+            list_density: ListDensity::Compact,
+            near_empty_size: 0,
+            junk_dirs: Vec::new(),
+            profiles: BTreeMap::new(),
         }
     }
 }
 
+impl Settings {
+    /// Narrow a result to what exports should contain: the configured subtree, then the
+    /// modification-time range, then the depth and size limits.
+    pub fn export_view(&self, result: &ScanResult) -> anyhow::Result<ScanResult> {
+        let subtree;
+        let result = match &self.export_root {
+            Some(root) => {
+                subtree = result
+                    .subtree(root)
+                    .ok_or_else(|| anyhow::anyhow!("{} is not in the scan", root.display()))?;
+                &subtree
+            }
+            None => result,
+        };
+        let mut view = if self.modified_after.is_none() && self.modified_before.is_none() {
+            result.clone()
+        } else {
+            result.filter_modified(self.modified_after, self.modified_before)
+        };
+        if self.export_depth.is_some() || self.export_min_size.is_some() {
+            view.trim(self.export_depth, self.export_min_size.unwrap_or(0));
+        }
+        Ok(view)
+    }
+
+    /// The part of these settings a scan's tree depends on.
+    pub fn tree_settings(&self) -> TreeSettings {
+        let profiles = self
+            .profiles
+            .iter()
+            .map(|(path, profile)| {
+                let profile = ScanProfile {
+                    max_concurrent_io: None,
+                    ..profile.clone()
+                };
+                (path.clone(), profile)
+            })
+            .collect();
+        TreeSettings {
+            max_depth: self.max_depth,
+            follow_symlinks: self.follow_symlinks,
+            ignore_patterns: self.ignore_patterns.clone(),
+            skip_hidden: self.skip_hidden,
+            gitignore: self.gitignore,
+            dedupe_hardlinks: self.dedupe_hardlinks,
+            count_dir_overhead: self.count_dir_overhead,
+            detect_clones: self.detect_clones,
+            sample_ratio: self.sample_ratio,
+            estimate_ratio: self.estimate_ratio,
+            min_file_size: self.min_file_size,
+            one_file_system: self.one_file_system,
+            pseudo_fs_types: self.pseudo_fs_types.clone(),
+            profiles,
+        }
+    }
+
+    /// File holding the user's directory notes.
+    pub fn notes_file(&self) -> PathBuf {
+        self.config_dir.join("notes.json")
+    }
+}
+
 fn dirs_cache_dir() -> Option<PathBuf> {
     #[cfg(target_os = "macos")]
     {
@@ -58,6 +335,25 @@ fn dirs_cache_dir() -> Option<PathBuf> {
     }
 }
 
+fn dirs_config_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    {
+        std::env::var_os("HOME")
+            .map(|h| PathBuf::from(h).join("Library/Application Support/disklens"))
+    }
+    #[cfg(target_os = "linux")]
+    {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+            .map(|p| p.join("disklens"))
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        Some(PathBuf::from(".disklens"))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StorageType {
     SSD,
@@ -132,6 +428,68 @@ fn detect_storage_type_linux() -> StorageType {
     StorageType::Unknown
 }
 
+/// Filesystems where copies can share blocks (reflinks / clones), so summed
+/// on-disk sizes may overstate real usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FilesystemType {
+    Apfs,
+    Btrfs,
+    Zfs,
+    Xfs,
+}
+
+impl FilesystemType {
+    pub fn name(self) -> &'static str {
+        match self {
+            FilesystemType::Apfs => "APFS",
+            FilesystemType::Btrfs => "Btrfs",
+            FilesystemType::Zfs => "ZFS",
+            FilesystemType::Xfs => "XFS",
+        }
+    }
+}
+
+/// Detect whether `path` lives on a copy-on-write filesystem.
+/// Returns `None` for everything else, or when detection isn't supported.
+pub fn detect_filesystem_type(path: &std::path::Path) -> Option<FilesystemType> {
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+        let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statfs(c_path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            let name = unsafe { std::ffi::CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+            match name.to_bytes() {
+                b"apfs" => Some(FilesystemType::Apfs),
+                b"zfs" => Some(FilesystemType::Zfs),
+                _ => None,
+            }
+        }
+        #[cfg(target_os = "linux")]
+        {
+            // Magic numbers from linux/magic.h; f_type's width varies by platform
+            match stat.f_type as u32 {
+                0x9123_683E => Some(FilesystemType::Btrfs),
+                0x2FC1_2FC1 => Some(FilesystemType::Zfs),
+                // XFS only shares blocks when created with reflink=1, but that is the default
+                0x5846_5342 => Some(FilesystemType::Xfs),
+                _ => None,
+            }
+        }
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        let _ = path;
+        None
+    }
+}
+
 /// Cap concurrency based on the system's file descriptor soft limit.
 /// Reserves 25% of fds for non-scan use (stdin/stdout, terminal, channels, etc.).
 fn cap_by_fd_limit(max_io: usize) -> usize {
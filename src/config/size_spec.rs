@@ -0,0 +1,37 @@
+use anyhow::bail;
+
+/// Parse a size given on the command line.
+///
+/// Accepts a plain byte count (`4096`) or a number with a binary unit suffix:
+/// `K`, `M`, `G` or `T`, optionally followed by `B` or `iB` and in either case
+/// (`10M`, `1.5GiB`, `512kb`). Units are powers of 1024, matching how sizes are shown.
+pub fn parse_size_spec(spec: &str) -> anyhow::Result<u64> {
+    let spec = spec.trim();
+    let split = spec
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(spec.len());
+    let (number, unit) = spec.split_at(split);
+
+    let Ok(value) = number.parse::<f64>() else {
+        bail!("invalid size '{}': expected a number like 500K or 10M", spec);
+    };
+    let unit = unit.trim().to_ascii_lowercase();
+    let unit = unit
+        .strip_suffix("ib")
+        .or_else(|| unit.strip_suffix('b'))
+        .unwrap_or(&unit);
+    let multiplier: u64 = match unit {
+        "" => 1,
+        "k" => 1 << 10,
+        "m" => 1 << 20,
+        "g" => 1 << 30,
+        "t" => 1 << 40,
+        _ => bail!("invalid size unit in '{}': expected K, M, G or T", spec),
+    };
+
+    let bytes = value * multiplier as f64;
+    if !bytes.is_finite() || bytes > u64::MAX as f64 {
+        bail!("size out of range: {}", spec);
+    }
+    Ok(bytes.round() as u64)
+}
@@ -0,0 +1,74 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+
+/// Parse a point in time given on the command line.
+///
+/// Accepts an ISO date (`2024-03-01`, local midnight), a local date-time
+/// (`2024-03-01T09:30:00`), an RFC 3339 timestamp (`2024-03-01T09:30:00Z`),
+/// or a relative age such as `90s`, `30m`, `12h`, `7d` or `2w`, meaning that
+/// long before `now`.
+pub fn parse_time_spec(spec: &str, now: SystemTime) -> anyhow::Result<SystemTime> {
+    let spec = spec.trim();
+
+    if let Some(age) = parse_relative(spec) {
+        return now
+            .checked_sub(age)
+            .ok_or_else(|| anyhow!("relative time out of range: {}", spec));
+    }
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(spec) {
+        return Ok(dt.into());
+    }
+
+    let naive = NaiveDateTime::parse_from_str(spec, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(spec, "%Y-%m-%d %H:%M:%S"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(spec, "%Y-%m-%d")
+                .map(|d| d.and_hms_opt(0, 0, 0).expect("midnight is valid"))
+        });
+    match naive {
+        Ok(naive) => match Local.from_local_datetime(&naive).earliest() {
+            Some(local) => Ok(local.into()),
+            None => bail!("time does not exist in the local time zone: {}", spec),
+        },
+        Err(_) => bail!(
+            "invalid time '{}': expected YYYY-MM-DD, an RFC 3339 timestamp, or an age like 7d",
+            spec
+        ),
+    }
+}
+
+/// Parse a length of time such as `90s`, `30m` or `2h`.
+pub fn parse_duration_spec(spec: &str) -> anyhow::Result<Duration> {
+    parse_relative(spec.trim())
+        .ok_or_else(|| anyhow!("invalid duration '{}': expected e.g. 30s, 5m or 2h", spec))
+}
+
+/// `age` in its largest whole unit, as accepted by [`parse_duration_spec`]: `45s`,
+/// `12m`, `3h`, `2d` or `5w`.
+pub fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    let units = [('w', 7 * 24 * 60 * 60), ('d', 24 * 60 * 60), ('h', 60 * 60), ('m', 60)];
+    match units.iter().find(|(_, unit)| secs >= *unit) {
+        Some((name, unit)) => format!("{}{}", secs / unit, name),
+        None => format!("{}s", secs),
+    }
+}
+
+/// `<number><unit>` with unit one of s, m, h, d, w.
+fn parse_relative(spec: &str) -> Option<Duration> {
+    let unit = spec.chars().last()?;
+    let seconds_per_unit = match unit {
+        's' => 1,
+        'm' => 60,
+        'h' => 60 * 60,
+        'd' => 24 * 60 * 60,
+        'w' => 7 * 24 * 60 * 60,
+        _ => return None,
+    };
+    let amount: u64 = spec[..spec.len() - 1].parse().ok()?;
+    Some(Duration::from_secs(amount.checked_mul(seconds_per_unit)?))
+}
+
@@ -1,12 +1,21 @@
-use crate::models::node::{Node, NodeType};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::models::category::FileCategory;
+use crate::models::node::{Node, NodeType, SizeMode};
+
+use super::cache::SizeHistory;
+use super::junk;
+use super::owners;
 
 pub struct Analyzer;
 
 impl Analyzer {
     pub fn sort_by_size(node: &mut Node) {
-        node.children.sort_by(|a, b| b.size.cmp(&a.size));
+        node.children.sort_by_key(|c| std::cmp::Reverse(c.size));
         for child in &mut node.children {
-            if child.node_type == NodeType::Directory {
+            if child.is_container() {
                 Self::sort_by_size(child);
             }
         }
@@ -54,9 +63,745 @@ impl Analyzer {
         result
     }
 
+    /// Fill in `newest_modified` on `node` and every directory below it: the latest
+    /// modification time of anything they contain, so a tree where something changed
+    /// recently stands out even when its own directory entries didn't change.
+    /// Returns the newest time found in `node`, itself included.
+    pub fn compute_newest_modified(node: &mut Node) -> Option<SystemTime> {
+        let mut newest = None;
+        for child in &mut node.children {
+            newest = newest.max(Self::compute_newest_modified(child));
+        }
+        if node.node_type == NodeType::Directory {
+            node.newest_modified = newest;
+        }
+        newest.max(node.modified)
+    }
+
     pub fn compute_stats(node: &Node) -> (usize, usize) {
         (node.file_count, node.dir_count)
     }
+
+    /// Size and file count below `node` per owning user, largest first. Entries
+    /// without a recorded owner (folded small files, estimates, non-unix scans) are
+    /// grouped under `uid: None`.
+    pub fn usage_by_owner(node: &Node) -> Vec<OwnerUsage> {
+        fn collect(node: &Node, usage: &mut HashMap<Option<u32>, (u64, usize)>) {
+            if node.node_type == NodeType::Directory {
+                for child in &node.children {
+                    collect(child, usage);
+                }
+                return;
+            }
+            let entry = usage.entry(node.owner()).or_default();
+            entry.0 += node.size;
+            entry.1 += node.file_count;
+        }
+
+        let mut usage = HashMap::new();
+        collect(node, &mut usage);
+        let mut result: Vec<OwnerUsage> = usage
+            .into_iter()
+            .map(|(uid, (size, file_count))| OwnerUsage {
+                uid,
+                name: uid.and_then(owners::user_name),
+                size,
+                file_count,
+            })
+            .collect();
+        result.sort_by(|a, b| b.size.cmp(&a.size).then(a.uid.cmp(&b.uid)));
+        result
+    }
+
+    /// Size and file count below `node` per file extension (lowercased), largest
+    /// first. Files without an extension, and stand-ins that only total several files
+    /// (folded small files, estimates), are grouped under `extension: None`.
+    pub fn by_extension(node: &Node) -> Vec<ExtensionUsage> {
+        fn collect(node: &Node, usage: &mut HashMap<Option<String>, (u64, usize)>) {
+            if matches!(node.node_type, NodeType::File | NodeType::Summary) {
+                let single = node.node_type == NodeType::File && node.file_count == 1;
+                let extension = if single && !node.estimated {
+                    node.path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .map(str::to_ascii_lowercase)
+                } else {
+                    None
+                };
+                let entry = usage.entry(extension).or_default();
+                entry.0 += node.size;
+                entry.1 += node.file_count;
+            }
+            for child in &node.children {
+                collect(child, usage);
+            }
+        }
+
+        let mut usage = HashMap::new();
+        collect(node, &mut usage);
+        let mut result: Vec<ExtensionUsage> = usage
+            .into_iter()
+            .map(|(extension, (size, file_count))| ExtensionUsage {
+                category: extension
+                    .as_deref()
+                    .map_or(FileCategory::Other, FileCategory::from_extension),
+                extension,
+                size,
+                file_count,
+            })
+            .collect();
+        result.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.extension.cmp(&b.extension)));
+        result
+    }
+
+    /// [`Analyzer::by_extension`] rolled up per category, largest first. Categories
+    /// without files are left out.
+    pub fn by_category(extensions: &[ExtensionUsage]) -> Vec<CategoryUsage> {
+        let mut result: Vec<CategoryUsage> = FileCategory::ALL
+            .into_iter()
+            .map(|category| {
+                let matching = extensions.iter().filter(|e| e.category == category);
+                CategoryUsage {
+                    category,
+                    size: matching.clone().map(|e| e.size).sum(),
+                    file_count: matching.map(|e| e.file_count).sum(),
+                }
+            })
+            .filter(|usage| usage.file_count > 0)
+            .collect();
+        result.sort_by_key(|usage| std::cmp::Reverse(usage.size));
+        result
+    }
+
+    /// Growth of the scanned root's total across the scans in `history`; `None` with
+    /// fewer than two scans to compare.
+    pub fn total_growth(history: &SizeHistory) -> Option<GrowthTrend> {
+        let sizes = history.snapshots.iter().map(|s| s.total_size).collect();
+        growth(history, PathBuf::new(), sizes)
+    }
+
+    /// Growth of each top-level entry across the scans in `history`, fastest growing
+    /// first. Entries missing from a scan count as zero there.
+    pub fn growth_trends(history: &SizeHistory) -> Vec<GrowthTrend> {
+        let paths: BTreeSet<&PathBuf> =
+            history.snapshots.iter().flat_map(|s| s.children.keys()).collect();
+        let mut trends: Vec<GrowthTrend> = paths
+            .into_iter()
+            .filter_map(|path| growth(history, path.clone(), history.trend(path)))
+            .collect();
+        trends.sort_by(|a, b| b.bytes_per_day.total_cmp(&a.bytes_per_day));
+        trends
+    }
+
+    /// Set `Node::junk` on directories below `node` that the junk catalogue, or one of
+    /// the `extra` names, recognizes. Tagged directories aren't searched further, so a
+    /// node_modules inside another counts once. Directories reached through a followed
+    /// symlink are left untagged.
+    pub fn tag_junk(node: &mut Node, extra: &[String]) {
+        for i in 0..node.children.len() {
+            let child = &node.children[i];
+            let label = if child.node_type == NodeType::Directory && child.symlink_target.is_none()
+            {
+                junk::match_junk(&child.name, &node.children, extra)
+            } else {
+                None
+            };
+            let child = &mut node.children[i];
+            child.junk = label.map(str::to_string);
+            if label.is_none() {
+                Self::tag_junk(child, extra);
+            }
+        }
+    }
+
+    /// Directories below `node` tagged by [`Analyzer::tag_junk`], largest first.
+    pub fn junk_dirs(node: &Node) -> Vec<JunkDir> {
+        fn walk(node: &Node, out: &mut Vec<JunkDir>) {
+            for child in &node.children {
+                match &child.junk {
+                    Some(label) => out.push(JunkDir {
+                        path: child.path.clone(),
+                        label: label.clone(),
+                        size: child.size,
+                    }),
+                    None => walk(child, out),
+                }
+            }
+        }
+        let mut dirs = Vec::new();
+        walk(node, &mut dirs);
+        dirs.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)));
+        dirs
+    }
+
+    /// Ways to free space below `node`, most worthwhile first: directories tagged by
+    /// [`Analyzer::tag_junk`], trash folders, files sharing their name and size with
+    /// another (likely copies, though their contents aren't compared), and large files
+    /// untouched for a year as of `now`. Each path is suggested once, and nothing is
+    /// suggested inside a directory that already is. Ranking weighs the bytes freed
+    /// by how safe removing them is.
+    pub fn suggestions(node: &Node, now: SystemTime) -> Vec<Suggestion> {
+        fn walk<'a>(
+            node: &'a Node,
+            now: SystemTime,
+            found: &mut Vec<Suggestion>,
+            copies: &mut HashMap<(&'a str, u64), Vec<&'a Node>>,
+        ) {
+            for child in &node.children {
+                // Followed symlinks would show up as copies of what they point at
+                if child.symlink_target.is_some() {
+                    continue;
+                }
+                let suggest = |kind: SuggestionKind, reason: String| Suggestion {
+                    path: child.path.clone(),
+                    kind,
+                    reason,
+                    reclaimable: child.size,
+                    risk: kind.risk(),
+                };
+                if let Some(label) = &child.junk {
+                    let reason = format!("{}, recreated when needed", label);
+                    found.push(suggest(SuggestionKind::Junk, reason));
+                } else if is_trash(child) {
+                    let reason = "deleted files waiting in the trash".to_string();
+                    found.push(suggest(SuggestionKind::Trash, reason));
+                } else if child.node_type == NodeType::Directory {
+                    walk(child, now, found, copies);
+                } else if child.node_type == NodeType::File
+                    && child.file_count == 1
+                    && !child.estimated
+                {
+                    let age = child.modified.and_then(|m| now.duration_since(m).ok());
+                    let days = age.map_or(0, |age| age.as_secs() / 86_400);
+                    if child.size >= OLD_FILE_MIN_SIZE && days >= OLD_FILE_DAYS {
+                        let reason = format!("not modified in {} days", days);
+                        found.push(suggest(SuggestionKind::OldFile, reason));
+                    }
+                    // Removing one of several hard links frees nothing
+                    if child.size >= DUPLICATE_MIN_SIZE && !child.is_hardlinked() {
+                        copies.entry((&child.name, child.size)).or_default().push(child);
+                    }
+                }
+            }
+        }
+
+        let mut found = Vec::new();
+        let mut copies = HashMap::new();
+        walk(node, now, &mut found, &mut copies);
+        let mut duplicates = Vec::new();
+        for mut group in copies.into_values().filter(|group| group.len() > 1) {
+            group.sort_by(|a, b| a.path.cmp(&b.path));
+            let kept = &group[0].path;
+            duplicates.extend(group[1..].iter().map(|copy| Suggestion {
+                path: copy.path.clone(),
+                kind: SuggestionKind::Duplicate,
+                reason: format!("same name and size as {}", kept.display()),
+                reclaimable: copy.size,
+                risk: SuggestionKind::Duplicate.risk(),
+            }));
+        }
+        // A copy is safer to remove than the only one, so that suggestion wins
+        let copy_paths: HashSet<PathBuf> = duplicates.iter().map(|s| s.path.clone()).collect();
+        found.retain(|s| !copy_paths.contains(&s.path));
+        found.extend(duplicates);
+        found.sort_by(|a, b| {
+            b.score().total_cmp(&a.score()).then_with(|| a.path.cmp(&b.path))
+        });
+        found
+    }
+
+    /// How deep and how wide the tree below `node` is: the depth of its deepest entry
+    /// (`node`'s own entries are at depth 1), how many entries its directories hold
+    /// directly, and the [`TREE_STATS_LIMIT`] deepest entries and widest directories.
+    /// Directories reached through a followed symlink count as entries but aren't
+    /// entered. Folded small files count as one entry, so fan-out can read low.
+    pub fn tree_stats(node: &Node) -> TreeStats {
+        fn walk(
+            node: &Node,
+            depth: usize,
+            fan_outs: &mut Vec<usize>,
+            deepest: &mut Vec<RankedPath>,
+            widest: &mut Vec<RankedPath>,
+        ) {
+            fan_outs.push(node.children.len());
+            widest.push(RankedPath { path: node.path.clone(), value: node.children.len() });
+            for child in &node.children {
+                if child.node_type == NodeType::Directory
+                    && child.symlink_target.is_none()
+                    && !child.children.is_empty()
+                {
+                    walk(child, depth + 1, fan_outs, deepest, widest);
+                } else {
+                    if child.node_type == NodeType::Directory && child.symlink_target.is_none() {
+                        fan_outs.push(0);
+                    }
+                    deepest.push(RankedPath { path: child.path.clone(), value: depth + 1 });
+                }
+            }
+        }
+
+        let mut fan_outs = Vec::new();
+        let mut deepest = Vec::new();
+        let mut widest = Vec::new();
+        if node.node_type == NodeType::Directory {
+            walk(node, 0, &mut fan_outs, &mut deepest, &mut widest);
+        }
+        fan_outs.sort_unstable();
+        let rank = |list: &mut Vec<RankedPath>| {
+            list.sort_by(|a, b| b.value.cmp(&a.value).then_with(|| a.path.cmp(&b.path)));
+            list.truncate(TREE_STATS_LIMIT);
+        };
+        rank(&mut deepest);
+        rank(&mut widest);
+
+        let percentile = |p: usize| {
+            // Nearest rank
+            let rank = (p * fan_outs.len()).div_ceil(100);
+            fan_outs.get(rank.saturating_sub(1)).copied().unwrap_or(0)
+        };
+        TreeStats {
+            max_depth: deepest.first().map_or(0, |d| d.value),
+            directories: fan_outs.len(),
+            average_fan_out: fan_outs.iter().sum::<usize>() as f64 / fan_outs.len().max(1) as f64,
+            fan_out_percentiles: FAN_OUT_PERCENTILES.map(percentile),
+            max_fan_out: fan_outs.last().copied().unwrap_or(0),
+            deepest,
+            widest,
+        }
+    }
+
+    /// Directories below `node` holding no files at all, or, unless `max_size` is 0, at
+    /// most `max_size` bytes, in path order. Only the outermost of nested matches is listed, since deleting
+    /// it takes the rest along. Directories reached through a followed symlink or
+    /// holding an estimate are left out.
+    pub fn find_empty_dirs(node: &Node, max_size: u64) -> Vec<EmptyDir> {
+        fn collect(node: &Node, max_size: u64, found: &mut Vec<EmptyDir>) {
+            for child in &node.children {
+                if child.node_type != NodeType::Directory
+                    || child.symlink_target.is_some()
+                    || child.estimated
+                {
+                    continue;
+                }
+                if child.file_count == 0 || (max_size > 0 && child.size <= max_size) {
+                    found.push(EmptyDir {
+                        path: child.path.clone(),
+                        size: child.size,
+                        file_count: child.file_count,
+                    });
+                } else {
+                    collect(child, max_size, found);
+                }
+            }
+        }
+
+        let mut found = Vec::new();
+        collect(node, max_size, &mut found);
+        found.sort_by(|a, b| a.path.cmp(&b.path));
+        found
+    }
+
+    /// The `limit` most recently modified files below `node`, newest first. Stand-ins
+    /// for several files (folded small files, estimates), entries inside archives, and
+    /// anything reached through a followed symlink are left out.
+    pub fn recently_modified(node: &Node, limit: usize) -> Vec<RecentFile> {
+        fn collect<'a>(node: &'a Node, files: &mut Vec<(&'a Node, SystemTime)>) {
+            for child in &node.children {
+                match child.node_type {
+                    NodeType::File if child.file_count == 1 && !child.estimated => {
+                        if let Some(modified) = child.modified {
+                            files.push((child, modified));
+                        }
+                    }
+                    NodeType::Directory if child.symlink_target.is_none() => {
+                        collect(child, files)
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut files = Vec::new();
+        collect(node, &mut files);
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.path.cmp(&b.0.path)));
+        files
+            .into_iter()
+            .take(limit)
+            .map(|(file, modified)| RecentFile {
+                path: file.path.clone(),
+                size: file.size,
+                modified,
+            })
+            .collect()
+    }
+
+    /// Bytes below `node` bucketed by how long ago they were last modified, as of
+    /// `now`. Each node's own bytes (a file, or a directory entry's overhead) go by its
+    /// own modification time; times in the future count as new.
+    pub fn age_breakdown(node: &Node, now: SystemTime) -> AgeBreakdown {
+        let mut ages = AgeBreakdown::default();
+        let mut own = node.size;
+        for child in &node.children {
+            ages.merge(&Self::age_breakdown(child, now));
+            own = own.saturating_sub(child.size);
+        }
+        ages.add(own, node.modified, now);
+        ages
+    }
+
+    /// [`Analyzer::age_breakdown`] for each entry directly in `node`, the ones holding
+    /// the most long-untouched bytes first.
+    pub fn age_report(node: &Node, now: SystemTime) -> Vec<EntryAge> {
+        let mut result: Vec<EntryAge> = node
+            .children
+            .iter()
+            .map(|child| EntryAge {
+                path: child.path.clone(),
+                name: child.name.clone(),
+                is_container: child.is_container(),
+                ages: Self::age_breakdown(child, now),
+            })
+            .collect();
+        result.sort_by(|a, b| {
+            b.ages
+                .buckets
+                .iter()
+                .rev()
+                .cmp(a.ages.buckets.iter().rev())
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        result
+    }
+
+    /// Sparse files below `node`, whose sizes hold `mode`, with the most bytes in
+    /// holes first.
+    pub fn sparse_files(node: &Node, mode: SizeMode) -> Vec<SparseFile> {
+        fn collect(node: &Node, mode: SizeMode, found: &mut Vec<SparseFile>) {
+            if node.is_sparse(mode) {
+                let (apparent_size, allocated_size) = node.apparent_and_allocated(mode);
+                found.push(SparseFile {
+                    path: node.path.clone(),
+                    apparent_size,
+                    allocated_size,
+                });
+            }
+            for child in &node.children {
+                collect(child, mode, found);
+            }
+        }
+
+        let mut found = Vec::new();
+        collect(node, mode, &mut found);
+        found.sort_by(|a, b| b.hole_size().cmp(&a.hole_size()).then(a.path.cmp(&b.path)));
+        found
+    }
+}
+
+/// One extension's share of a tree, from [`Analyzer::by_extension`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtensionUsage {
+    /// Lowercased, without the dot.
+    pub extension: Option<String>,
+    pub category: FileCategory,
+    pub size: u64,
+    pub file_count: usize,
+}
+
+impl ExtensionUsage {
+    /// `.ext`, or a placeholder for files without one.
+    pub fn label(&self) -> String {
+        match &self.extension {
+            Some(ext) => format!(".{}", ext),
+            None => "(none)".to_string(),
+        }
+    }
+}
+
+/// One category's share of a tree, from [`Analyzer::by_category`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CategoryUsage {
+    pub category: FileCategory,
+    pub size: u64,
+    pub file_count: usize,
+}
+
+/// How one entry's size developed over past scans, from [`Analyzer::growth_trends`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrowthTrend {
+    /// Empty for the scanned root's total.
+    pub path: PathBuf,
+    /// One per scan, oldest first.
+    pub sizes: Vec<u64>,
+    /// Least-squares slope of size over time, so a single odd scan doesn't dominate.
+    pub bytes_per_day: f64,
+}
+
+impl GrowthTrend {
+    /// Size in the latest scan.
+    pub fn current(&self) -> u64 {
+        self.sizes.last().copied().unwrap_or(0)
+    }
+
+    /// Change from the oldest scan to the latest.
+    pub fn change(&self) -> i64 {
+        self.current() as i64 - self.sizes.first().copied().unwrap_or(0) as i64
+    }
+}
+
+fn growth(history: &SizeHistory, path: PathBuf, sizes: Vec<u64>) -> Option<GrowthTrend> {
+    let first = history.snapshots.first()?.timestamp;
+    if sizes.len() < 2 {
+        return None;
+    }
+    let days: Vec<f64> = history
+        .snapshots
+        .iter()
+        .map(|s| s.timestamp.duration_since(first).map_or(0.0, |d| d.as_secs_f64() / 86_400.0))
+        .collect();
+    let n = sizes.len() as f64;
+    let mean_day = days.iter().sum::<f64>() / n;
+    let mean_size = sizes.iter().map(|&s| s as f64).sum::<f64>() / n;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (day, &size) in days.iter().zip(&sizes) {
+        covariance += (day - mean_day) * (size as f64 - mean_size);
+        variance += (day - mean_day).powi(2);
+    }
+    let bytes_per_day = if variance > 0.0 { covariance / variance } else { 0.0 };
+    Some(GrowthTrend { path, sizes, bytes_per_day })
+}
+
+/// Files at least this large and not modified for [`OLD_FILE_DAYS`] are suggested
+/// for cleanup.
+pub const OLD_FILE_MIN_SIZE: u64 = 100 * 1024 * 1024;
+pub const OLD_FILE_DAYS: u64 = 365;
+/// Smaller files aren't worth suggesting as duplicates.
+pub const DUPLICATE_MIN_SIZE: u64 = 1024 * 1024;
+
+/// How likely removing a suggested path is to lose something wanted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Risk {
+    Low,
+    Medium,
+    High,
+}
+
+impl Risk {
+    pub fn label(self) -> &'static str {
+        match self {
+            Risk::Low => "low",
+            Risk::Medium => "medium",
+            Risk::High => "high",
+        }
+    }
+
+    /// Share of the bytes counted when ranking suggestions.
+    fn weight(self) -> f64 {
+        match self {
+            Risk::Low => 1.0,
+            Risk::Medium => 0.5,
+            Risk::High => 0.25,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuggestionKind {
+    /// A directory tools regenerate, such as node_modules or a build cache.
+    Junk,
+    Trash,
+    /// A file with the same name and size as one elsewhere.
+    Duplicate,
+    /// A large file not modified in a long time.
+    OldFile,
+}
+
+impl SuggestionKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            SuggestionKind::Junk => "regenerable",
+            SuggestionKind::Trash => "trash",
+            SuggestionKind::Duplicate => "duplicate",
+            SuggestionKind::OldFile => "old file",
+        }
+    }
+
+    pub fn risk(self) -> Risk {
+        match self {
+            SuggestionKind::Junk | SuggestionKind::Trash => Risk::Low,
+            SuggestionKind::Duplicate => Risk::Medium,
+            SuggestionKind::OldFile => Risk::High,
+        }
+    }
+}
+
+/// A path that could be removed to free space, from [`Analyzer::suggestions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    pub path: PathBuf,
+    pub kind: SuggestionKind,
+    pub reason: String,
+    pub reclaimable: u64,
+    pub risk: Risk,
+}
+
+impl Suggestion {
+    fn score(&self) -> f64 {
+        self.reclaimable as f64 * self.risk.weight()
+    }
+}
+
+/// Trash folders of desktop environments and Windows.
+fn is_trash(node: &Node) -> bool {
+    node.node_type == NodeType::Directory
+        && (node.name == ".Trash"
+            || node.name.starts_with(".Trash-")
+            || node.name.eq_ignore_ascii_case("$RECYCLE.BIN")
+            || node.path.ends_with(".local/share/Trash"))
+}
+
+/// A cleanup candidate from [`Analyzer::junk_dirs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JunkDir {
+    pub path: PathBuf,
+    /// What kind of regenerable directory it is, e.g. "npm packages".
+    pub label: String,
+    pub size: u64,
+}
+
+/// Entries listed in each of [`TreeStats::deepest`] and [`TreeStats::widest`].
+pub const TREE_STATS_LIMIT: usize = 10;
+/// The percentiles in [`TreeStats::fan_out_percentiles`].
+pub const FAN_OUT_PERCENTILES: [usize; 3] = [50, 90, 99];
+
+/// The shape of a tree, from [`Analyzer::tree_stats`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeStats {
+    pub max_depth: usize,
+    pub directories: usize,
+    /// Entries held directly, averaged over directories.
+    pub average_fan_out: f64,
+    /// Entries held directly at each of [`FAN_OUT_PERCENTILES`].
+    pub fan_out_percentiles: [usize; 3],
+    pub max_fan_out: usize,
+    /// Entries with nothing below them, the deepest first, with their depth.
+    pub deepest: Vec<RankedPath>,
+    /// Directories, the most entries first, with their entry count.
+    pub widest: Vec<RankedPath>,
+}
+
+/// A path with the figure it was ranked by in [`TreeStats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankedPath {
+    pub path: PathBuf,
+    pub value: usize,
+}
+
+/// A directory from [`Analyzer::find_empty_dirs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmptyDir {
+    pub path: PathBuf,
+    pub size: u64,
+    pub file_count: usize,
+}
+
+impl EmptyDir {
+    /// Whether it holds no files at all, rather than only a few small ones.
+    pub fn is_empty(&self) -> bool {
+        self.file_count == 0
+    }
+}
+
+/// Upper bounds, in days, of all but the last [`AgeBreakdown`] bucket.
+pub const AGE_BUCKET_DAYS: [u64; 3] = [30, 180, 365];
+/// Column headings for the [`AgeBreakdown`] buckets.
+pub const AGE_BUCKET_LABELS: [&str; 4] = ["< 30d", "30d\u{2013}180d", "180d\u{2013}1y", "> 1y"];
+
+/// Bytes by time since last modification, from [`Analyzer::age_breakdown`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AgeBreakdown {
+    /// Newest first, split at [`AGE_BUCKET_DAYS`].
+    pub buckets: [u64; 4],
+    /// Bytes with no modification time, such as folded small files and estimates.
+    pub unknown: u64,
+}
+
+impl AgeBreakdown {
+    fn add(&mut self, size: u64, modified: Option<SystemTime>, now: SystemTime) {
+        let Some(modified) = modified else {
+            self.unknown += size;
+            return;
+        };
+        let days = now.duration_since(modified).map_or(0, |age| age.as_secs() / 86_400);
+        let bucket = AGE_BUCKET_DAYS.iter().take_while(|&&limit| days >= limit).count();
+        self.buckets[bucket] += size;
+    }
+
+    fn merge(&mut self, other: &AgeBreakdown) {
+        for (bucket, size) in self.buckets.iter_mut().zip(other.buckets) {
+            *bucket += size;
+        }
+        self.unknown += other.unknown;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.buckets.iter().sum::<u64>() + self.unknown
+    }
+}
+
+/// One entry from [`Analyzer::age_report`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryAge {
+    pub path: PathBuf,
+    pub name: String,
+    pub is_container: bool,
+    pub ages: AgeBreakdown,
+}
+
+/// A file from [`Analyzer::recently_modified`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecentFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// A file from [`Analyzer::sparse_files`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseFile {
+    pub path: PathBuf,
+    /// Length, as `ls` reports it.
+    pub apparent_size: u64,
+    /// Blocks actually allocated, as `du` reports them.
+    pub allocated_size: u64,
+}
+
+impl SparseFile {
+    /// Bytes of the length that take no space on disk.
+    pub fn hole_size(&self) -> u64 {
+        self.apparent_size.saturating_sub(self.allocated_size)
+    }
+}
+
+/// One user's share of a tree, from [`Analyzer::usage_by_owner`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnerUsage {
+    pub uid: Option<u32>,
+    /// Login name, when the system's user database knows the ID.
+    pub name: Option<String>,
+    pub size: u64,
+    pub file_count: usize,
+}
+
+impl OwnerUsage {
+    /// The login name, the numeric ID when the name is unknown, or "unknown" for
+    /// entries without a recorded owner.
+    pub fn label(&self) -> String {
+        match (&self.name, self.uid) {
+            (Some(name), _) => name.clone(),
+            (None, Some(uid)) => format!("uid {}", uid),
+            (None, None) => "unknown".to_string(),
+        }
+    }
 }
 
 pub struct MergedItem {
@@ -0,0 +1,154 @@
+//! Listing the contents of archives so they can be browsed like directories.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::models::node::Node;
+
+/// Archive formats whose contents can be listed without extracting them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    /// Recognize an archive by its file name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// One file stored in an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path inside the archive, `/`-separated.
+    pub name: String,
+    /// Bytes the entry takes up in the archive.
+    pub compressed: u64,
+    /// Bytes the entry takes up once extracted.
+    pub uncompressed: u64,
+}
+
+/// List the files in the archive at `path`. Directory entries are left out; they
+/// are implied by the file paths.
+///
+/// Zip archives are listed from their central directory alone. Tarballs have no
+/// index, so the whole archive is read, and a gzipped one is decompressed along the
+/// way; its entries are assigned a share of the compressed size in proportion to
+/// their extracted size, since gzip compresses the stream as a whole.
+pub fn list_entries(path: &Path, kind: ArchiveKind) -> anyhow::Result<Vec<ArchiveEntry>> {
+    let file = File::open(path)?;
+    match kind {
+        ArchiveKind::Zip => list_zip(file),
+        ArchiveKind::Tar => list_tar(tar::Archive::new(file), false),
+        ArchiveKind::TarGz => {
+            let decoder = flate2::read::GzDecoder::new(BufReader::new(file));
+            let mut entries = list_tar(tar::Archive::new(decoder), true)?;
+            let archive_size = std::fs::metadata(path)?.len();
+            let total: u64 = entries.iter().map(|e| e.uncompressed).sum();
+            if total > 0 {
+                for entry in &mut entries {
+                    let share = entry.uncompressed as u128 * archive_size as u128 / total as u128;
+                    entry.compressed = share as u64;
+                }
+            }
+            Ok(entries)
+        }
+    }
+}
+
+fn list_zip(file: File) -> anyhow::Result<Vec<ArchiveEntry>> {
+    let archive = zip::ZipArchive::new(BufReader::new(file))?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let entry = archive.by_index_data(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+        entries.push(ArchiveEntry {
+            name: entry.name()?.into_owned(),
+            compressed: entry.compressed_size(),
+            uncompressed: entry.size(),
+        });
+    }
+    Ok(entries)
+}
+
+fn list_tar<R: std::io::Read>(
+    mut archive: tar::Archive<R>,
+    compressed: bool,
+) -> anyhow::Result<Vec<ArchiveEntry>> {
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if entry.header().entry_type().is_dir() {
+            continue;
+        }
+        let size = entry.size();
+        entries.push(ArchiveEntry {
+            name: entry.path()?.to_string_lossy().into_owned(),
+            // Filled in by the caller for compressed tarballs
+            compressed: if compressed { 0 } else { size },
+            uncompressed: size,
+        });
+    }
+    Ok(entries)
+}
+
+/// Arrange `entries` into a tree of `NodeType::ArchiveEntry` nodes below `archive`,
+/// with a node for every directory their paths pass through.
+pub fn entry_nodes(archive: &Path, entries: Vec<ArchiveEntry>) -> Vec<Node> {
+    #[derive(Default)]
+    struct Dir {
+        dirs: BTreeMap<String, Dir>,
+        files: Vec<(String, u64, u64)>,
+    }
+
+    fn into_nodes(dir: Dir, base: &Path) -> Vec<Node> {
+        let mut nodes = Vec::with_capacity(dir.dirs.len() + dir.files.len());
+        for (name, sub) in dir.dirs {
+            let path = base.join(&name);
+            let children = into_nodes(sub, &path);
+            let compressed = children.iter().map(|c| c.size).sum();
+            let uncompressed = children.iter().filter_map(|c| c.uncompressed_size).sum();
+            nodes.push(Node::archive_entry(path, name, compressed, uncompressed, children));
+        }
+        for (name, compressed, uncompressed) in dir.files {
+            let path = base.join(&name);
+            nodes.push(Node::archive_entry(path, name, compressed, uncompressed, Vec::new()));
+        }
+        nodes
+    }
+
+    let mut root = Dir::default();
+    for entry in entries {
+        // Leading `/` and `./` are common in tarballs; `..` can't be browsed into
+        let mut parts: Vec<&str> = entry
+            .name
+            .split(['/', '\\'])
+            .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+            .collect();
+        let Some(file_name) = parts.pop() else {
+            continue;
+        };
+        let mut dir = &mut root;
+        for part in parts {
+            dir = dir.dirs.entry(part.to_string()).or_default();
+        }
+        dir.files.push((file_name.to_string(), entry.compressed, entry.uncompressed));
+    }
+    into_nodes(root, archive)
+}
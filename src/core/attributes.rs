@@ -0,0 +1,48 @@
+//! Windows file attributes that change how an entry is sized or traversed.
+
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::fs::MetadataExt;
+use std::path::Path;
+
+const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+const FILE_ATTRIBUTE_SPARSE_FILE: u32 = 0x200;
+const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x800;
+
+const INVALID_FILE_SIZE: u32 = u32::MAX;
+const NO_ERROR: i32 = 0;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn GetCompressedFileSizeW(file_name: *const u16, file_size_high: *mut u32) -> u32;
+}
+
+pub fn is_hidden(metadata: &std::fs::Metadata) -> bool {
+    metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+/// Junctions, mount points and other reparse points, including the directory
+/// placeholders of cloud sync clients that std doesn't report as symlinks.
+pub fn is_reparse_point(metadata: &std::fs::Metadata) -> bool {
+    metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0
+}
+
+/// NTFS-compressed and sparse files are the ones that can occupy less than their length.
+pub fn is_compressed_or_sparse(metadata: &std::fs::Metadata) -> bool {
+    metadata.file_attributes() & (FILE_ATTRIBUTE_COMPRESSED | FILE_ATTRIBUTE_SPARSE_FILE) != 0
+}
+
+/// Bytes the file occupies on disk after compression and without sparse holes.
+pub fn compressed_size(path: &Path) -> Option<u64> {
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut high = 0u32;
+    // SAFETY: `wide` is a NUL-terminated UTF-16 path that outlives the call, and
+    // `high` is a valid out-pointer.
+    let low = unsafe { GetCompressedFileSizeW(wide.as_ptr(), &mut high) };
+    // INVALID_FILE_SIZE is also a valid low word, so the error code decides
+    if low == INVALID_FILE_SIZE && std::io::Error::last_os_error().raw_os_error() != Some(NO_ERROR)
+    {
+        return None;
+    }
+    Some(((high as u64) << 32) | low as u64)
+}
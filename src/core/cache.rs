@@ -1,10 +1,18 @@
 use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
-use std::path::PathBuf;
-use std::time::SystemTime;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
 
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use crate::config::settings::{Settings, TreeSettings};
+use crate::models::node::{Node, NodeType, SizeMode};
 use crate::models::scan_result::ScanResult;
 
 #[derive(Serialize, Deserialize)]
@@ -19,32 +27,320 @@ struct CacheMeta {
     root_inode: Option<u64>,
 }
 
+impl CacheMeta {
+    fn new(result: &ScanResult) -> Self {
+        Self {
+            original_path: result.scan_path.clone(),
+            scan_timestamp: result.timestamp,
+            total_size: result.total_size,
+            file_count: result.total_files,
+            dir_count: result.total_dirs,
+            root_mtime: result.root.modified,
+            #[cfg(unix)]
+            root_inode: result.root.inode,
+        }
+    }
+}
+
+/// Start of a `.cache` or `.dir` file, followed by [`CACHE_VERSION`] and a zstd frame,
+/// with a checksum, of the bincode-encoded scan or directory.
+const CACHE_MAGIC: &[u8; 4] = b"DLZC";
+/// Start of an encrypted `.cache` or `.dir` file, followed by [`CACHE_VERSION`], the
+/// salt the key was derived with, the nonce, and the sealed zstd frame.
+const ENCRYPTED_CACHE_MAGIC: &[u8; 4] = b"DLZE";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Bumped whenever the file layout or the serialized shape of `ScanResult` or `Node`
+/// changes, since bincode can't tell and would decode garbage.
+const CACHE_VERSION: u8 = 5;
+/// Compresses a large tree several times over while keeping saves fast.
+const CACHE_ZSTD_LEVEL: i32 = 3;
+
+/// Number of scans kept in a root's size history.
+pub const HISTORY_LIMIT: usize = 16;
+
+/// Sizes recorded by one scan: the root total plus each top-level child.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorySnapshot {
+    pub timestamp: SystemTime,
+    pub total_size: u64,
+    pub children: HashMap<PathBuf, u64>,
+}
+
+/// Bounded, oldest-first record of past scans of one root, kept in a sidecar file.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SizeHistory {
+    pub snapshots: Vec<HistorySnapshot>,
+}
+
+impl SizeHistory {
+    pub fn push(&mut self, result: &ScanResult) {
+        // Re-saving a result loaded from cache shouldn't count as a new scan
+        if self
+            .snapshots
+            .last()
+            .is_some_and(|last| last.timestamp == result.timestamp)
+        {
+            return;
+        }
+        let children = result
+            .root
+            .children
+            .iter()
+            .map(|c| (c.path.clone(), c.size))
+            .collect();
+        self.snapshots.push(HistorySnapshot {
+            timestamp: result.timestamp,
+            total_size: result.total_size,
+            children,
+        });
+        let excess = self.snapshots.len().saturating_sub(HISTORY_LIMIT);
+        self.snapshots.drain(..excess);
+    }
+
+    /// Recorded sizes of `path` (a top-level child), oldest first.
+    /// Scans in which it did not exist count as zero.
+    pub fn trend(&self, path: &Path) -> Vec<u64> {
+        self.snapshots
+            .iter()
+            .map(|s| s.children.get(path).copied().unwrap_or(0))
+            .collect()
+    }
+}
+
 pub struct Cache {
     cache_dir: PathBuf,
+    /// Bytes the cache directory may hold before the least recently used entries are
+    /// evicted; 0 for no limit.
+    max_size: u64,
+    /// How old a saved scan may get before it is no longer used; zero for no limit.
+    max_age: Duration,
+    /// Scans, directory entries and snapshots are encrypted with a key derived from
+    /// this.
+    passphrase: Option<String>,
+    /// Keys already derived from `passphrase`, by salt. The directory entries of one
+    /// save share a salt, so reading them takes a single derivation.
+    keys: Mutex<HashMap<[u8; SALT_LEN], Key>>,
+}
+
+/// The files kept for one scanned path, which are evicted together, or a single
+/// directory entry.
+struct CacheEntry {
+    /// The hashed path the file names start with.
+    key: String,
+    files: Vec<PathBuf>,
+    size: u64,
+    /// When the scan was last saved or loaded, from the mtime of its `.cache` file.
+    scan_used: Option<SystemTime>,
+    /// The latest write to any of the files.
+    newest: SystemTime,
+}
+
+/// An entry of the cache, from [`Cache::list`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedScan {
+    /// The scanned path; `None` when the metadata is missing or unreadable.
+    pub path: Option<PathBuf>,
+    pub scanned_at: Option<SystemTime>,
+    /// Total size of the scanned tree.
+    pub tree_size: Option<u64>,
+    /// False when only the size history is left.
+    pub has_scan: bool,
+    pub last_used: SystemTime,
+    /// Bytes the entry's files take up in the cache directory.
+    pub size: u64,
+}
+
+/// A named scan kept for later comparison, from [`Cache::list_snapshots`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub name: String,
+    pub path: PathBuf,
+    pub taken: SystemTime,
+    pub total_size: u64,
+    pub total_files: usize,
+}
+
+impl CacheEntry {
+    /// A history without a scan goes by its last write.
+    fn last_used(&self) -> SystemTime {
+        self.scan_used.unwrap_or(self.newest)
+    }
+
+    /// Whether this is a directory entry rather than a scanned path's files.
+    fn is_dir_entry(&self) -> bool {
+        self.key.ends_with(".dir")
+    }
+
+    async fn load_meta(&self) -> Option<CacheMeta> {
+        let file = self.files.iter().find(|f| f.to_string_lossy().ends_with(".meta.json"))?;
+        let bytes = tokio::fs::read(file).await.ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+}
+
+fn is_history(file: &Path) -> bool {
+    file.to_string_lossy().ends_with(".history.json")
 }
 
 impl Cache {
     pub fn new(cache_dir: PathBuf) -> Self {
-        Self { cache_dir }
+        Self {
+            cache_dir,
+            max_size: 0,
+            max_age: Duration::ZERO,
+            passphrase: None,
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cache in `settings.cache_dir`, kept within `settings.cache_max_size_mb` and
+    /// `settings.cache_max_age_days`.
+    pub fn from_settings(settings: &Settings) -> Self {
+        Self::new(settings.cache_dir.clone())
+            .with_max_size(settings.cache_max_size_mb.saturating_mul(1024 * 1024))
+            .with_max_age(Duration::from_secs(settings.cache_max_age_days.saturating_mul(86_400)))
+            .with_passphrase(settings.cache_passphrase.clone())
+    }
+
+    /// Evict the least recently used entries once the cache holds more than `bytes`.
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// Encrypt the scans, directory entries and snapshots saved from now on with
+    /// `passphrase`, which is then needed to read them. Metadata and size histories
+    /// stay readable.
+    pub fn with_passphrase(mut self, passphrase: Option<String>) -> Self {
+        self.passphrase = passphrase.filter(|p| !p.is_empty());
+        self.keys.get_mut().unwrap().clear();
+        self
+    }
+
+    /// Ignore, and let [`Cache::remove_expired`] delete, scans older than `age`.
+    pub fn with_max_age(mut self, age: Duration) -> Self {
+        self.max_age = age;
+        self
+    }
+
+    /// Whether a scan taken at `scanned` is past the age limit. Times in the future
+    /// count as fresh.
+    fn is_expired(&self, scanned: SystemTime) -> bool {
+        !self.max_age.is_zero()
+            && SystemTime::now().duration_since(scanned).is_ok_and(|age| age > self.max_age)
+    }
+
+    fn key(path: &Path) -> String {
+        format!("{:x}", Self::hash_path(path))
     }
 
-    fn hash_path(path: &PathBuf) -> u64 {
+    fn hash_path(path: &Path) -> u64 {
         let mut hasher = DefaultHasher::new();
         path.to_string_lossy().hash(&mut hasher);
         hasher.finish()
     }
 
-    fn cache_path(&self, path: &PathBuf) -> PathBuf {
+    fn cache_path(&self, path: &Path) -> PathBuf {
         let hash = Self::hash_path(path);
         self.cache_dir.join(format!("{:x}.cache", hash))
     }
 
-    fn meta_path(&self, path: &PathBuf) -> PathBuf {
+    fn meta_path(&self, path: &Path) -> PathBuf {
         let hash = Self::hash_path(path);
         self.cache_dir.join(format!("{:x}.meta.json", hash))
     }
 
-    pub async fn load(&self, path: &PathBuf) -> Option<ScanResult> {
+    fn history_path(&self, path: &Path) -> PathBuf {
+        let hash = Self::hash_path(path);
+        self.cache_dir.join(format!("{:x}.history.json", hash))
+    }
+
+    /// Where directory entries are kept: one `.dir` file per directory, holding its
+    /// node as a scan left it, named after its path, its modification time and the
+    /// tree settings it was scanned with.
+    fn dirs_dir(&self) -> PathBuf {
+        self.cache_dir.join("dirs")
+    }
+
+    /// The entry of directory `path` as of `mtime`, for `settings` serialized as JSON.
+    fn dir_entry_path(&self, path: &Path, mtime: SystemTime, settings: &[u8]) -> PathBuf {
+        let mut hasher = DefaultHasher::new();
+        path.to_string_lossy().hash(&mut hasher);
+        mtime.hash(&mut hasher);
+        settings.hash(&mut hasher);
+        self.dirs_dir().join(format!("{:x}.dir", hasher.finish()))
+    }
+
+    /// Past scan sizes for `path`; empty when none have been recorded.
+    pub async fn load_history(&self, path: &Path) -> SizeHistory {
+        match tokio::fs::read(self.history_path(path)).await {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+            Err(_) => SizeHistory::default(),
+        }
+    }
+
+    /// Append `result` to its root's history, dropping the oldest entries past
+    /// `HISTORY_LIMIT`, and return the updated history.
+    pub async fn record_history(&self, result: &ScanResult) -> anyhow::Result<SizeHistory> {
+        tokio::fs::create_dir_all(&self.cache_dir).await?;
+
+        let mut history = self.load_history(&result.scan_path).await;
+        history.push(result);
+
+        let history_file = self.history_path(&result.scan_path);
+        let tmp_history = history_file.with_extension("json.tmp");
+        tokio::fs::write(&tmp_history, serde_json::to_vec(&history)?).await?;
+        tokio::fs::rename(&tmp_history, &history_file).await?;
+
+        Ok(history)
+    }
+
+    /// The last saved scan of `path` even if the tree has changed since, as a base for
+    /// an incremental rescan.
+    pub async fn load_previous(&self, path: &Path) -> Option<ScanResult> {
+        let result = self.read_scan(path).await?;
+        if result.scan_path != *path || self.is_expired(result.timestamp) {
+            return None;
+        }
+        mark_used(&self.cache_path(path));
+        Some(result)
+    }
+
+    /// The node an earlier scan with `settings`, of any root, saved for directory
+    /// `path`, when the directory's modification time is still `mtime`. Its files are
+    /// complete; its subdirectories have no children, since they have entries of their
+    /// own. Entries unused for longer than the age limit are ignored.
+    pub async fn load_dir(
+        &self,
+        path: &Path,
+        mtime: SystemTime,
+        settings: &TreeSettings,
+    ) -> Option<Node> {
+        let settings = serde_json::to_vec(settings).ok()?;
+        let file = self.dir_entry_path(path, mtime, &settings);
+        let last_used = tokio::fs::metadata(&file).await.ok()?.modified().ok()?;
+        if self.is_expired(last_used) {
+            return None;
+        }
+        let bytes = tokio::fs::read(&file).await.ok()?;
+        let node: Node = match self.decode(&bytes) {
+            Ok(node) => node,
+            Err(DecodeError::Incompatible | DecodeError::Locked) => return None,
+            Err(DecodeError::Corrupt) => {
+                let _ = tokio::fs::remove_file(&file).await;
+                return None;
+            }
+        };
+        // Guard against another directory's entry under the same hash
+        if node.path != path || node.modified != Some(mtime) {
+            return None;
+        }
+        mark_used(&file);
+        Some(node)
+    }
+
+    pub async fn load(&self, path: &Path) -> Option<ScanResult> {
         let cache_file = self.cache_path(path);
         let meta_file = self.meta_path(path);
 
@@ -55,10 +351,13 @@ impl Cache {
 
         // Load and validate metadata
         let meta_bytes = tokio::fs::read(&meta_file).await.ok()?;
-        let meta: CacheMeta = serde_json::from_slice(&meta_bytes).ok()?;
+        let Ok(meta) = serde_json::from_slice::<CacheMeta>(&meta_bytes) else {
+            self.remove_damaged(path).await;
+            return None;
+        };
 
-        // Verify the cached path matches
-        if meta.original_path != *path {
+        // Verify the cached path matches and the scan hasn't expired
+        if meta.original_path != *path || self.is_expired(meta.scan_timestamp) {
             return None;
         }
 
@@ -85,65 +384,383 @@ impl Cache {
         }
 
         // Load and deserialize the scan result
-        let cache_bytes = tokio::fs::read(&cache_file).await.ok()?;
-        bincode::serde::decode_from_slice(&cache_bytes, bincode::config::standard())
-            .map(|(result, _)| result)
-            .ok()
+        let result = self.read_scan(path).await?;
+        mark_used(&cache_file);
+        Some(result)
+    }
+
+    /// The saved scan of `path`, deleting it if it turns out to be damaged. Scans
+    /// written by an incompatible version are left for the next save to replace.
+    async fn read_scan(&self, path: &Path) -> Option<ScanResult> {
+        let cache_bytes = tokio::fs::read(self.cache_path(path)).await.ok()?;
+        match self.decode(&cache_bytes) {
+            Ok(result) => Some(result),
+            Err(DecodeError::Incompatible | DecodeError::Locked) => None,
+            Err(DecodeError::Corrupt) => {
+                self.remove_damaged(path).await;
+                None
+            }
+        }
+    }
+
+    /// Delete the saved scan of `path` and its metadata, keeping the size history.
+    async fn remove_damaged(&self, path: &Path) {
+        tracing::warn!("Removing damaged cache entry for {}", path.display());
+        let _ = tokio::fs::remove_file(self.cache_path(path)).await;
+        let _ = tokio::fs::remove_file(self.meta_path(path)).await;
     }
 
-    pub async fn save(&self, result: &ScanResult) -> anyhow::Result<()> {
+    /// Save `result` as the latest scan of its path and add it to the path's size
+    /// history, which is returned. Other paths' entries
+    /// are then evicted, least recently used first, until the cache fits its size limit.
+    pub async fn save(&self, result: &ScanResult) -> anyhow::Result<SizeHistory> {
         // Ensure cache directory exists
         tokio::fs::create_dir_all(&self.cache_dir).await?;
 
         let path = &result.scan_path;
+        let cache_bytes = encode(result, self.seal()?.as_ref())?;
+        let meta_bytes = serde_json::to_vec_pretty(&CacheMeta::new(result))?;
+        write_atomic(&self.cache_path(path), &cache_bytes).await?;
+        write_atomic(&self.meta_path(path), &meta_bytes).await?;
 
-        // Build metadata
-        let root_mtime = result.root.modified;
-        #[cfg(unix)]
-        let root_inode = result.root.inode;
+        let history = self.record_history(result).await?;
+        if let Err(e) = self.evict(Some(path)).await {
+            tracing::warn!("Failed to evict old cache entries: {}", e);
+        }
+        Ok(history)
+    }
 
-        let meta = CacheMeta {
-            original_path: path.clone(),
-            scan_timestamp: result.timestamp,
-            total_size: result.total_size,
-            file_count: result.total_files,
-            dir_count: result.total_dirs,
-            root_mtime,
-            #[cfg(unix)]
-            root_inode,
-        };
+    /// Save an entry for each directory of `result` whose own read succeeded, for
+    /// later scans of any root to reuse while its modification time stays the same.
+    /// Entries saved before are only marked used, unless encryption was turned on or
+    /// off since. Only complete scans of a single root with every size measured are
+    /// saved from.
+    ///
+    /// The entries are written in one batch on a blocking thread; await the returned
+    /// handle to wait for them, or drop it to let them be written in the background.
+    pub fn save_dirs(
+        &self,
+        result: &ScanResult,
+    ) -> anyhow::Result<tokio::task::JoinHandle<anyhow::Result<()>>> {
+        if !result.is_cacheable() {
+            return Ok(tokio::task::spawn_blocking(|| Ok(())));
+        }
+        // One key for the whole save: deriving one per directory would take far too long
+        let seal = self.seal()?;
+        let settings = serde_json::to_vec(&result.tree_settings)?;
+        let failed: std::collections::HashSet<&Path> =
+            result.errors.iter().map(|e| e.path.as_path()).collect();
+        let mut entries = Vec::new();
+        let mut stack = vec![&result.root];
+        while let Some(node) = stack.pop() {
+            stack.extend(node.children.iter().filter(|c| c.node_type == NodeType::Directory));
+            let (NodeType::Directory, Some(mtime)) = (node.node_type, node.modified) else {
+                continue;
+            };
+            if failed.contains(node.path.as_path()) {
+                continue;
+            }
+            let mut entry = node.shallow_copy();
+            // Entries are kept in apparent sizes, as the scanner builds trees
+            if result.size_mode != SizeMode::Apparent {
+                entry.swap_sizes();
+            }
+            entries.push((self.dir_entry_path(&node.path, mtime, &settings), entry));
+        }
+        let dirs_dir = self.dirs_dir();
+        Ok(tokio::task::spawn_blocking(move || write_dir_entries(&dirs_dir, entries, seal)))
+    }
 
-        // Serialize scan result with bincode
-        let cache_bytes = bincode::serde::encode_to_vec(result, bincode::config::standard())?;
-        let meta_bytes = serde_json::to_vec_pretty(&meta)?;
+    /// Delete the saved scans (`.cache` and `.meta.json`) that are past the age limit,
+    /// keeping their size histories, and the directory entries unused for as long.
+    /// Returns how many scans were deleted.
+    pub async fn remove_expired(&self) -> std::io::Result<usize> {
+        if self.max_age.is_zero() {
+            return Ok(0);
+        }
+        for entry in self.dir_entries().await? {
+            if self.is_expired(entry.last_used()) {
+                tokio::fs::remove_file(&entry.files[0]).await?;
+            }
+        }
+        let mut removed = 0;
+        for entry in self.entries().await? {
+            let scanned = entry.load_meta().await.map(|meta| meta.scan_timestamp);
+            let scan_files: Vec<&PathBuf> = entry.files.iter().filter(|f| !is_history(f)).collect();
+            // Without readable metadata the scan can't be loaded anyway
+            if scan_files.is_empty() || scanned.is_some_and(|scanned| !self.is_expired(scanned)) {
+                continue;
+            }
+            for file in scan_files {
+                tokio::fs::remove_file(file).await?;
+            }
+            removed += 1;
+        }
+        Ok(removed)
+    }
 
-        // Atomic write: write to temp file, then rename
-        let cache_file = self.cache_path(path);
-        let meta_file = self.meta_path(path);
+    /// Remove the least recently used entries, scans together with their size
+    /// histories, and directory entries, until the cache is within its size limit.
+    /// Returns how many scans were removed.
+    pub async fn shrink_to_limit(&self) -> std::io::Result<usize> {
+        self.evict(None).await
+    }
+
+    /// Remove the least recently used entries other than `keep`'s until the cache is
+    /// within `max_size`. Returns how many were removed.
+    async fn evict(&self, keep: Option<&Path>) -> std::io::Result<usize> {
+        if self.max_size == 0 {
+            return Ok(0);
+        }
+        let mut entries = self.entries().await?;
+        entries.extend(self.dir_entries().await?);
+        let mut total: u64 = entries.iter().map(|e| e.size).sum();
+        entries.sort_by_key(CacheEntry::last_used);
+        let keep = keep.map(Self::key);
+        let mut evicted = 0;
+        for entry in entries.iter().filter(|e| Some(&e.key) != keep.as_ref()) {
+            if total <= self.max_size {
+                break;
+            }
+            for file in &entry.files {
+                tokio::fs::remove_file(file).await?;
+            }
+            total -= entry.size;
+            if !entry.is_dir_entry() {
+                evicted += 1;
+            }
+        }
+        Ok(evicted)
+    }
+
+    /// Every entry in the cache, the most recently used first.
+    pub async fn list(&self) -> std::io::Result<Vec<CachedScan>> {
+        let mut scans = Vec::new();
+        for entry in self.entries().await? {
+            let meta = entry.load_meta().await;
+            scans.push(CachedScan {
+                path: meta.as_ref().map(|meta| meta.original_path.clone()),
+                scanned_at: meta.as_ref().map(|meta| meta.scan_timestamp),
+                tree_size: meta.as_ref().map(|meta| meta.total_size),
+                has_scan: entry.scan_used.is_some(),
+                last_used: entry.last_used(),
+                size: entry.size,
+            });
+        }
+        scans.sort_by(|a, b| b.last_used.cmp(&a.last_used).then_with(|| a.path.cmp(&b.path)));
+        Ok(scans)
+    }
+
+    /// How many directory entries the cache holds, and the bytes they take up.
+    pub async fn dir_entry_usage(&self) -> std::io::Result<(usize, u64)> {
+        let entries = self.dir_entries().await?;
+        Ok((entries.len(), entries.iter().map(|e| e.size).sum()))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.cache_dir
+    }
 
-        let tmp_cache = cache_file.with_extension("cache.tmp");
-        let tmp_meta = meta_file.with_extension("meta.json.tmp");
+    pub fn max_size(&self) -> u64 {
+        self.max_size
+    }
 
-        tokio::fs::write(&tmp_cache, &cache_bytes).await?;
-        tokio::fs::rename(&tmp_cache, &cache_file).await?;
+    pub fn max_age(&self) -> Duration {
+        self.max_age
+    }
 
-        tokio::fs::write(&tmp_meta, &meta_bytes).await?;
-        tokio::fs::rename(&tmp_meta, &meta_file).await?;
+    /// Where snapshots are kept. Being a subdirectory, it is left alone by eviction,
+    /// expiry and [`Cache::clear`].
+    fn snapshot_dir(&self) -> PathBuf {
+        self.cache_dir.join("snapshots")
+    }
 
+    /// The snapshot file named `name` with `extension`, after checking that the name
+    /// can't reach outside the snapshot directory.
+    fn snapshot_file(&self, name: &str, extension: &str) -> anyhow::Result<PathBuf> {
+        let valid = |c: char| c.is_alphanumeric() || matches!(c, '-' | '_' | '.');
+        if name.is_empty() || name.starts_with('.') || !name.chars().all(valid) {
+            anyhow::bail!(
+                "invalid snapshot name {:?}: use letters, digits, '-', '_' and '.'",
+                name
+            );
+        }
+        Ok(self.snapshot_dir().join(format!("{}.{}", name, extension)))
+    }
+
+    /// Whether a snapshot called `name` exists; an error if it isn't a valid name.
+    pub fn has_snapshot(&self, name: &str) -> anyhow::Result<bool> {
+        Ok(self.snapshot_file(name, "cache")?.exists())
+    }
+
+    /// Keep `result` as the snapshot `name`, replacing an earlier one by that name
+    /// only when `replace` is set.
+    pub async fn save_snapshot(
+        &self,
+        name: &str,
+        result: &ScanResult,
+        replace: bool,
+    ) -> anyhow::Result<()> {
+        if !replace && self.has_snapshot(name)? {
+            anyhow::bail!("snapshot {:?} already exists", name);
+        }
+        let scan_file = self.snapshot_file(name, "cache")?;
+        tokio::fs::create_dir_all(self.snapshot_dir()).await?;
+        let meta_bytes = serde_json::to_vec_pretty(&CacheMeta::new(result))?;
+        let scan_bytes = encode(result, self.seal()?.as_ref())?;
+        write_atomic(&scan_file, &scan_bytes).await?;
+        write_atomic(&self.snapshot_file(name, "meta.json")?, &meta_bytes).await?;
         Ok(())
     }
 
+    /// The scan kept as the snapshot `name`. Unlike cached scans, a damaged snapshot
+    /// is reported rather than deleted.
+    pub async fn load_snapshot(&self, name: &str) -> anyhow::Result<ScanResult> {
+        let scan_file = self.snapshot_file(name, "cache")?;
+        let bytes = match tokio::fs::read(&scan_file).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                anyhow::bail!("no snapshot named {:?}", name)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        match self.decode(&bytes) {
+            Ok(result) => Ok(result),
+            Err(DecodeError::Incompatible) => {
+                anyhow::bail!("snapshot {:?} was saved by an incompatible version", name)
+            }
+            Err(DecodeError::Locked) => anyhow::bail!(
+                "snapshot {:?} is encrypted with another passphrase, or none was given",
+                name
+            ),
+            Err(DecodeError::Corrupt) => anyhow::bail!("snapshot {:?} is damaged", name),
+        }
+    }
+
+    /// Every snapshot with readable metadata, the oldest first.
+    pub async fn list_snapshots(&self) -> std::io::Result<Vec<Snapshot>> {
+        let mut dir = match tokio::fs::read_dir(self.snapshot_dir()).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut snapshots = Vec::new();
+        while let Some(file) = dir.next_entry().await? {
+            let file_name = file.file_name().to_string_lossy().into_owned();
+            let Some(name) = file_name.strip_suffix(".meta.json") else {
+                continue;
+            };
+            let Ok(bytes) = tokio::fs::read(file.path()).await else {
+                continue;
+            };
+            let Ok(meta) = serde_json::from_slice::<CacheMeta>(&bytes) else {
+                continue;
+            };
+            snapshots.push(Snapshot {
+                name: name.to_string(),
+                path: meta.original_path,
+                taken: meta.scan_timestamp,
+                total_size: meta.total_size,
+                total_files: meta.file_count,
+            });
+        }
+        snapshots.sort_by(|a, b| a.taken.cmp(&b.taken).then_with(|| a.name.cmp(&b.name)));
+        Ok(snapshots)
+    }
+
+    pub async fn remove_snapshot(&self, name: &str) -> anyhow::Result<()> {
+        if !self.has_snapshot(name)? {
+            anyhow::bail!("no snapshot named {:?}", name);
+        }
+        tokio::fs::remove_file(self.snapshot_file(name, "cache")?).await?;
+        let _ = tokio::fs::remove_file(self.snapshot_file(name, "meta.json")?).await;
+        Ok(())
+    }
+
+    /// The cache's files grouped by the scanned path they belong to. Files being
+    /// written are left out.
+    async fn entries(&self) -> std::io::Result<Vec<CacheEntry>> {
+        let mut dir = match tokio::fs::read_dir(&self.cache_dir).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut entries: HashMap<String, CacheEntry> = HashMap::new();
+        while let Some(file) = dir.next_entry().await? {
+            let name = file.file_name().to_string_lossy().into_owned();
+            let Some((key, suffix)) = name.split_once('.') else {
+                continue;
+            };
+            if !matches!(suffix, "cache" | "meta.json" | "history.json") {
+                continue;
+            }
+            let meta = file.metadata().await?;
+            if !meta.is_file() {
+                continue;
+            }
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            let entry = entries.entry(key.to_string()).or_insert_with(|| CacheEntry {
+                key: key.to_string(),
+                files: Vec::new(),
+                size: 0,
+                scan_used: None,
+                newest: SystemTime::UNIX_EPOCH,
+            });
+            entry.files.push(file.path());
+            entry.size += meta.len();
+            if suffix == "cache" {
+                entry.scan_used = Some(modified);
+            }
+            entry.newest = entry.newest.max(modified);
+        }
+        Ok(entries.into_values().collect())
+    }
+
+    /// The directory entries, one [`CacheEntry`] each, keyed by file name. Files
+    /// being written are left out.
+    async fn dir_entries(&self) -> std::io::Result<Vec<CacheEntry>> {
+        let mut dir = match tokio::fs::read_dir(self.dirs_dir()).await {
+            Ok(dir) => dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut entries = Vec::new();
+        while let Some(file) = dir.next_entry().await? {
+            let name = file.file_name().to_string_lossy().into_owned();
+            if !name.ends_with(".dir") {
+                continue;
+            }
+            let meta = file.metadata().await?;
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push(CacheEntry {
+                key: name,
+                files: vec![file.path()],
+                size: meta.len(),
+                scan_used: Some(modified),
+                newest: modified,
+            });
+        }
+        Ok(entries)
+    }
+
     pub async fn clear(&self) -> anyhow::Result<()> {
         if !self.cache_dir.exists() {
             return Ok(());
         }
+        match tokio::fs::remove_dir_all(self.dirs_dir()).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
 
         let mut entries = tokio::fs::read_dir(&self.cache_dir).await?;
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
             if path.is_file() {
                 let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-                if name.ends_with(".cache") || name.ends_with(".meta.json") || name.ends_with(".tmp") {
+                if name.ends_with(".cache")
+                    || name.ends_with(".meta.json")
+                    || name.ends_with(".history.json")
+                    || name.ends_with(".tmp") {
                     tokio::fs::remove_file(&path).await?;
                 }
             }
@@ -151,3 +768,182 @@ impl Cache {
         Ok(())
     }
 }
+
+/// Write `bytes` to a temporary file beside `file` and rename it into place, so
+/// readers never see a partial write.
+async fn write_atomic(file: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let mut tmp = file.as_os_str().to_owned();
+    tmp.push(".tmp");
+    tokio::fs::write(&tmp, bytes).await?;
+    tokio::fs::rename(&tmp, file).await
+}
+
+/// Write the directory entries that aren't in the cache yet, or were saved by another
+/// version or with encryption turned the other way, and mark the others used.
+fn write_dir_entries(
+    dirs_dir: &Path,
+    entries: Vec<(PathBuf, Node)>,
+    seal: Option<Seal>,
+) -> anyhow::Result<()> {
+    let mut created = false;
+    for (file, entry) in entries {
+        if has_format(&file, seal.is_some()) {
+            mark_used(&file);
+            continue;
+        }
+        if !created {
+            std::fs::create_dir_all(dirs_dir)?;
+            created = true;
+        }
+        let mut tmp = file.as_os_str().to_owned();
+        tmp.push(".tmp");
+        std::fs::write(&tmp, encode(&entry, seal.as_ref())?)?;
+        std::fs::rename(&tmp, &file)?;
+    }
+    Ok(())
+}
+
+/// Whether `cache_file` exists and starts as this version writes files, encrypted
+/// when `sealed`.
+fn has_format(cache_file: &Path, sealed: bool) -> bool {
+    use std::io::Read;
+    let magic = if sealed { ENCRYPTED_CACHE_MAGIC } else { CACHE_MAGIC };
+    let mut header = [0u8; 5];
+    std::fs::File::open(cache_file)
+        .and_then(|mut file| file.read_exact(&mut header))
+        .is_ok_and(|()| header[..4] == magic[..] && header[4] == CACHE_VERSION)
+}
+
+/// Record that the scan or directory entry in `cache_file` was just used, for
+/// least-recently-used eviction. File access times are too often disabled to rely on.
+fn mark_used(cache_file: &Path) {
+    let _ = std::fs::File::options()
+        .write(true)
+        .open(cache_file)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+}
+
+impl Cache {
+    /// A fresh salt and key for sealing files, when there is a passphrase.
+    fn seal(&self) -> anyhow::Result<Option<Seal>> {
+        let Some(passphrase) = self.passphrase.as_deref() else {
+            return Ok(None);
+        };
+        let mut salt = [0u8; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let key = derive_key(passphrase, &salt)?;
+        self.keys.lock().unwrap().insert(salt, key);
+        Ok(Some(Seal { salt, key }))
+    }
+
+    /// The key for a file sealed with `salt`, derived once per salt.
+    fn derived_key(&self, salt: &[u8]) -> Result<Key, DecodeError> {
+        let passphrase = self.passphrase.as_deref().ok_or(DecodeError::Locked)?;
+        let salt: [u8; SALT_LEN] = salt.try_into().map_err(|_| DecodeError::Corrupt)?;
+        if let Some(key) = self.keys.lock().unwrap().get(&salt) {
+            return Ok(*key);
+        }
+        let key = derive_key(passphrase, &salt).map_err(|_| DecodeError::Locked)?;
+        self.keys.lock().unwrap().insert(salt, key);
+        Ok(key)
+    }
+
+    /// The scan or directory in a file written by [`encode`]. Unencrypted files are
+    /// read with or without a passphrase.
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, DecodeError> {
+        if let Some(sealed) = bytes.strip_prefix(ENCRYPTED_CACHE_MAGIC) {
+            let frame = self.decrypt_frame(sealed)?;
+            return decode_frame(&frame);
+        }
+        match bytes.strip_prefix(CACHE_MAGIC) {
+            Some([CACHE_VERSION, frame @ ..]) => decode_frame(frame),
+            // A file cut off inside the header
+            Some([]) => Err(DecodeError::Corrupt),
+            _ => Err(DecodeError::Incompatible),
+        }
+    }
+
+    /// The zstd frame sealed in an encrypted file, from just after its magic.
+    fn decrypt_frame(&self, sealed: &[u8]) -> Result<Vec<u8>, DecodeError> {
+        let (&version, rest) = sealed.split_first().ok_or(DecodeError::Corrupt)?;
+        if version != CACHE_VERSION {
+            return Err(DecodeError::Incompatible);
+        }
+        if rest.len() < SALT_LEN + NONCE_LEN {
+            return Err(DecodeError::Corrupt);
+        }
+        let (salt, rest) = rest.split_at(SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let key = self.derived_key(salt)?;
+        // A wrong passphrase and a damaged file both fail authentication; either way the
+        // file is left for the next save to replace
+        ChaCha20Poly1305::new(&key)
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| DecodeError::Locked)
+    }
+}
+
+/// A salt and the key derived from it, for sealing files.
+struct Seal {
+    salt: [u8; SALT_LEN],
+    key: Key,
+}
+
+/// `value` as bincode compressed with zstd, behind the magic and format bytes, and
+/// encrypted when there is a `seal`.
+fn encode<T: Serialize>(value: &T, seal: Option<&Seal>) -> anyhow::Result<Vec<u8>> {
+    let mut encoder = zstd::Encoder::new(Vec::new(), CACHE_ZSTD_LEVEL)?;
+    encoder.include_checksum(true)?;
+    bincode::serde::encode_into_std_write(value, &mut encoder, bincode::config::standard())?;
+    let frame = encoder.finish()?;
+    let Some(seal) = seal else {
+        let mut bytes = CACHE_MAGIC.to_vec();
+        bytes.push(CACHE_VERSION);
+        bytes.extend_from_slice(&frame);
+        return Ok(bytes);
+    };
+
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let cipher = ChaCha20Poly1305::new(&seal.key);
+    let sealed = cipher
+        .encrypt(&nonce, frame.as_slice())
+        .map_err(|_| anyhow::anyhow!("failed to encrypt the scan"))?;
+    let mut bytes = ENCRYPTED_CACHE_MAGIC.to_vec();
+    bytes.push(CACHE_VERSION);
+    bytes.extend_from_slice(&seal.salt);
+    bytes.extend_from_slice(&nonce);
+    bytes.extend_from_slice(&sealed);
+    Ok(bytes)
+}
+
+/// The key for `passphrase` and a file's `salt`. Argon2 makes guessing passphrases
+/// against a stolen cache slow.
+fn derive_key(passphrase: &str, salt: &[u8]) -> anyhow::Result<Key> {
+    let mut key = Key::default();
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("failed to derive the cache key: {}", e))?;
+    Ok(key)
+}
+
+/// Why a `.cache` or `.dir` file couldn't be read.
+enum DecodeError {
+    /// Written by another version of DiskLens, or before files were versioned.
+    Incompatible,
+    /// Encrypted, and the passphrase is missing or isn't the one it was saved with.
+    Locked,
+    /// Truncated or damaged.
+    Corrupt,
+}
+
+/// The value in a zstd frame of bincode.
+fn decode_frame<T: DeserializeOwned>(frame: &[u8]) -> Result<T, DecodeError> {
+    let mut decoder = zstd::Decoder::new(frame).map_err(|_| DecodeError::Corrupt)?;
+    let value = bincode::serde::decode_from_std_read(&mut decoder, bincode::config::standard())
+        .map_err(|_| DecodeError::Corrupt)?;
+    // Reading to the end of the frame verifies its checksum; nothing may follow the value
+    match std::io::copy(&mut decoder, &mut std::io::sink()) {
+        Ok(0) => Ok(value),
+        _ => Err(DecodeError::Corrupt),
+    }
+}
@@ -0,0 +1,64 @@
+//! Deleting the directories picked in the empty directory finder.
+
+use std::fs::Metadata;
+use std::io;
+use std::path::Path;
+
+use crate::models::node::human_readable_size;
+
+/// Delete `dir` with everything in it, after checking on disk that it still holds at
+/// most `max_files` files totalling `max_bytes`: the scan may have left some out
+/// (hidden, excluded or filtered ones), and new ones may have appeared since. A
+/// directory with another filesystem mounted below it is left alone.
+pub fn remove_dir_checked(dir: &Path, max_files: usize, max_bytes: u64) -> io::Result<()> {
+    let metadata = std::fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() {
+        return Err(io::Error::other("no longer a directory"));
+    }
+    let (files, bytes) = contents(dir, device(&metadata))?;
+    if files > max_files || bytes > max_bytes {
+        return Err(io::Error::other(format!(
+            "now holds {} files ({})",
+            files,
+            human_readable_size(bytes)
+        )));
+    }
+    std::fs::remove_dir_all(dir)
+}
+
+/// Files below `dir` and their total length, without following symlinks.
+fn contents(dir: &Path, dev: Option<u64>) -> io::Result<(usize, u64)> {
+    let mut files = 0;
+    let mut bytes = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if !metadata.is_dir() {
+            files += 1;
+            bytes += metadata.len();
+            continue;
+        }
+        if device(&metadata) != dev {
+            return Err(io::Error::other(format!(
+                "{} is on another filesystem",
+                entry.path().display()
+            )));
+        }
+        let (sub_files, sub_bytes) = contents(&entry.path(), dev)?;
+        files += sub_files;
+        bytes += sub_bytes;
+    }
+    Ok((files, bytes))
+}
+
+fn device(#[allow(unused_variables)] metadata: &Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some(metadata.dev())
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
@@ -0,0 +1,135 @@
+//! Estimating how much filesystem compression or archiving would save, by compressing
+//! samples of file contents with fast zstd. Unlike the rest of the analysis this reads
+//! file data, so it only runs when asked for.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+
+use crate::models::node::{Node, NodeType};
+
+/// Bytes read from the start of each sampled file.
+pub const SAMPLE_BYTES: usize = 64 * 1024;
+
+/// Files sampled per entry at most; larger subtrees get a spread across file sizes.
+pub const MAX_SAMPLED_FILES: usize = 64;
+
+/// The fastest regular level, close to what filesystems use for transparent compression.
+const ZSTD_LEVEL: i32 = 1;
+
+/// The files to sample for one entry, picked from the tree without touching the disk,
+/// so the reading can happen elsewhere.
+#[derive(Debug, Clone)]
+pub struct CompressionJob {
+    pub path: PathBuf,
+    pub name: String,
+    pub is_container: bool,
+    pub size: u64,
+    files: Vec<(PathBuf, u64)>,
+}
+
+impl CompressionJob {
+    pub fn new(node: &Node) -> Self {
+        fn collect(node: &Node, files: &mut Vec<(PathBuf, u64)>) {
+            match node.node_type {
+                NodeType::File if node.file_count == 1 && !node.estimated && node.size > 0 => {
+                    files.push((node.path.clone(), node.size));
+                }
+                NodeType::Directory => {
+                    for child in &node.children {
+                        collect(child, files);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut files = Vec::new();
+        collect(node, &mut files);
+        if files.len() > MAX_SAMPLED_FILES {
+            // Evenly spaced by size, so both the few large files and the many small
+            // ones are represented
+            files.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+            let len = files.len();
+            files = (0..MAX_SAMPLED_FILES)
+                .map(|i| files[i * len / MAX_SAMPLED_FILES].clone())
+                .collect();
+        }
+        Self {
+            path: node.path.clone(),
+            name: node.name.clone(),
+            is_container: node.is_container(),
+            size: node.size,
+            files,
+        }
+    }
+
+    /// Read and compress the samples. Files that can't be read are skipped.
+    pub fn run(self) -> CompressionEstimate {
+        let mut weighted = 0.0;
+        let mut weight = 0.0;
+        let mut sampled_files = 0;
+        for (path, size) in &self.files {
+            if let Some(ratio) = sample_ratio(path) {
+                weighted += ratio * *size as f64;
+                weight += *size as f64;
+                sampled_files += 1;
+            }
+        }
+        CompressionEstimate {
+            path: self.path,
+            name: self.name,
+            is_container: self.is_container,
+            size: self.size,
+            sampled_files,
+            ratio: (weight > 0.0).then(|| weighted / weight),
+        }
+    }
+}
+
+/// How well one entry's contents compress, from [`CompressionJob::run`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressionEstimate {
+    pub path: PathBuf,
+    pub name: String,
+    pub is_container: bool,
+    pub size: u64,
+    pub sampled_files: usize,
+    /// Compressed size as a share of the original, with each sample weighted by the
+    /// size of its file; `None` when nothing could be read.
+    pub ratio: Option<f64>,
+}
+
+impl CompressionEstimate {
+    /// Bytes compression would likely save across the whole entry.
+    pub fn savings(&self) -> u64 {
+        self.ratio.map_or(0, |ratio| (self.size as f64 * (1.0 - ratio)) as u64)
+    }
+}
+
+/// Estimate each job in parallel, the largest savings first.
+pub fn run_all(jobs: Vec<CompressionJob>) -> Vec<CompressionEstimate> {
+    let mut estimates: Vec<CompressionEstimate> =
+        jobs.into_par_iter().map(CompressionJob::run).collect();
+    estimates.sort_by(|a, b| b.savings().cmp(&a.savings()).then_with(|| a.name.cmp(&b.name)));
+    estimates
+}
+
+/// Estimates for each entry directly in `node`.
+pub fn estimate_children(node: &Node) -> Vec<CompressionEstimate> {
+    run_all(node.children.iter().map(CompressionJob::new).collect())
+}
+
+/// Compressed size over original size for the start of the file at `path`. Data that
+/// grows when compressed counts as stored as is, as filesystems do.
+fn sample_ratio(path: &Path) -> Option<f64> {
+    let mut sample = Vec::with_capacity(SAMPLE_BYTES);
+    File::open(path).ok()?.take(SAMPLE_BYTES as u64).read_to_end(&mut sample).ok()?;
+    if sample.is_empty() {
+        return None;
+    }
+    let compressed = zstd::bulk::compress(&sample, ZSTD_LEVEL).ok()?;
+    Some((compressed.len() as f64 / sample.len() as f64).min(1.0))
+}
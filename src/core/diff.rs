@@ -0,0 +1,136 @@
+//! Comparing two scans of the same tree: what appeared, disappeared, grew or shrank.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::models::node::{Node, NodeType};
+use crate::models::scan_result::ScanResult;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Grown,
+    Shrunk,
+}
+
+impl ChangeKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Grown => "grown",
+            ChangeKind::Shrunk => "shrunk",
+        }
+    }
+}
+
+/// One entry that differs between the scans. Sizes are zero on the side where the
+/// entry doesn't exist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeChange {
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+    pub is_dir: bool,
+    pub old_size: u64,
+    pub new_size: u64,
+}
+
+impl NodeChange {
+    pub fn delta(&self) -> i64 {
+        self.new_size as i64 - self.old_size as i64
+    }
+}
+
+/// Output of [`DiffResult::compare`].
+#[derive(Debug, Clone)]
+pub struct DiffResult {
+    pub old_timestamp: SystemTime,
+    pub new_timestamp: SystemTime,
+    pub old_total: u64,
+    pub new_total: u64,
+    /// Largest change first. Only the outermost entry of an added or removed subtree
+    /// is listed; entries present in both scans are listed at every level.
+    pub changes: Vec<NodeChange>,
+}
+
+impl DiffResult {
+    /// Compare `old` against `new`, matching entries by path. `old` is read in
+    /// `new`'s size mode.
+    pub fn compare(old: &ScanResult, new: &ScanResult) -> DiffResult {
+        let old_tree = if old.size_mode == new.size_mode {
+            Cow::Borrowed(&old.root)
+        } else {
+            let mut root = old.root.clone();
+            root.swap_sizes();
+            Cow::Owned(root)
+        };
+
+        let mut changes = Vec::new();
+        compare_nodes(&old_tree, &new.root, &mut changes);
+        changes.sort_by(|a, b| {
+            b.delta()
+                .unsigned_abs()
+                .cmp(&a.delta().unsigned_abs())
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        DiffResult {
+            old_timestamp: old.timestamp,
+            new_timestamp: new.timestamp,
+            old_total: old_tree.size,
+            new_total: new.root.size,
+            changes,
+        }
+    }
+
+    pub fn delta(&self) -> i64 {
+        self.new_total as i64 - self.old_total as i64
+    }
+
+    /// Size change of every entry present in the new scan that differs from the old.
+    pub fn deltas(&self) -> HashMap<PathBuf, i64> {
+        self.changes
+            .iter()
+            .filter(|change| change.kind != ChangeKind::Removed)
+            .map(|change| (change.path.clone(), change.delta()))
+            .collect()
+    }
+}
+
+fn compare_nodes(old: &Node, new: &Node, changes: &mut Vec<NodeChange>) {
+    let kind = match new.size.cmp(&old.size) {
+        std::cmp::Ordering::Greater => Some(ChangeKind::Grown),
+        std::cmp::Ordering::Less => Some(ChangeKind::Shrunk),
+        std::cmp::Ordering::Equal => None,
+    };
+    if let Some(kind) = kind {
+        changes.push(change(new, kind, old.size, new.size));
+    }
+
+    let old_children: HashMap<&PathBuf, &Node> =
+        old.children.iter().map(|child| (&child.path, child)).collect();
+    for child in &new.children {
+        match old_children.get(&child.path) {
+            Some(old_child) => compare_nodes(old_child, child, changes),
+            None => changes.push(change(child, ChangeKind::Added, 0, child.size)),
+        }
+    }
+    let new_paths: HashSet<&PathBuf> = new.children.iter().map(|child| &child.path).collect();
+    for child in &old.children {
+        if !new_paths.contains(&child.path) {
+            changes.push(change(child, ChangeKind::Removed, child.size, 0));
+        }
+    }
+}
+
+fn change(node: &Node, kind: ChangeKind, old_size: u64, new_size: u64) -> NodeChange {
+    NodeChange {
+        path: node.path.clone(),
+        kind,
+        is_dir: node.node_type == NodeType::Directory,
+        old_size,
+        new_size,
+    }
+}
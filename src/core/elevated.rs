@@ -0,0 +1,100 @@
+//! Re-scanning directories the current user can't read through `sudo`, so
+//! root-owned trees are counted instead of showing up as empty.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::config::settings::{GitignoreMode, ScanBackend, Settings};
+use crate::models::node::NodeType;
+use crate::models::scan_result::{ScanErrorType, ScanResult};
+
+/// Directories in `result` that couldn't be read for lack of permission, outermost
+/// first. Directories below another one in the list are left out: rescanning the
+/// outer one covers them.
+pub fn permission_denied_dirs(result: &ScanResult) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = result
+        .errors
+        .iter()
+        .filter(|e| e.error_type == ScanErrorType::PermissionDenied)
+        .filter(|e| {
+            result
+                .root
+                .find(&e.path)
+                .is_some_and(|node| node.node_type == NodeType::Directory)
+        })
+        .map(|e| e.path.clone())
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    let mut outermost: Vec<PathBuf> = Vec::with_capacity(dirs.len());
+    for dir in dirs {
+        if !outermost.last().is_some_and(|outer| dir.starts_with(outer)) {
+            outermost.push(dir);
+        }
+    }
+    outermost
+}
+
+/// Scan `dirs` again as root by running this executable under `sudo` with the same
+/// scan options, and read back its JSON export. `sudo` prompts on the terminal for
+/// a password when it needs one.
+pub fn rescan_with_sudo(dirs: &[PathBuf], settings: &Settings) -> anyhow::Result<ScanResult> {
+    // A new directory only we can enter, so the root-owned export lands somewhere we
+    // can read and no one else can plant a link to redirect it. It is removed on drop.
+    let work_dir = tempfile::Builder::new().prefix("disklens-rescan-").tempdir()?;
+    run_helper(dirs, settings, &work_dir.path().join("rescan.json"))
+}
+
+fn run_helper(dirs: &[PathBuf], settings: &Settings, output: &Path) -> anyhow::Result<ScanResult> {
+    let status = Command::new("sudo")
+        .arg(std::env::current_exe()?)
+        .arg("--export-json")
+        .arg(output)
+        .args(helper_args(settings))
+        .arg("--")
+        .args(dirs)
+        .stdout(Stdio::null())
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("elevated rescan failed ({})", status);
+    }
+    crate::export::json::import_json(output)
+}
+
+/// Command-line flags that reproduce the scan options in `settings`. The depth
+/// limit and per-path profiles are left out: the rescan starts below the original
+/// root, so neither would line up. The helper never touches the cache, whose entries
+/// would end up owned by root, and unencrypted since `sudo` drops the passphrase.
+fn helper_args(settings: &Settings) -> Vec<String> {
+    let backend = match settings.backend {
+        ScanBackend::Tasks => "tasks",
+        ScanBackend::Threads => "threads",
+        ScanBackend::Uring => "uring",
+        ScanBackend::Mft => "mft",
+    };
+    let mut args = vec!["--no-cache".to_string(), format!("--backend={}", backend)];
+    let flags = [
+        (settings.follow_symlinks, "--follow-symlinks"),
+        (settings.skip_hidden, "--skip-hidden"),
+        (settings.count_dir_overhead, "--count-dir-overhead"),
+        (!settings.dedupe_hardlinks, "--count-hardlinks"),
+        (settings.one_file_system, "--one-file-system"),
+        (settings.pseudo_fs_types.is_empty(), "--scan-pseudo-fs"),
+        (settings.detect_clones, "--detect-clones"),
+        (settings.peek_archives, "--peek-archives"),
+        (!settings.throttle_network_fs, "--no-network-throttle"),
+    ];
+    args.extend(flags.iter().filter(|(on, _)| *on).map(|(_, flag)| flag.to_string()));
+    for pattern in &settings.ignore_patterns {
+        args.push(format!("--exclude={}", pattern));
+    }
+    match settings.gitignore {
+        GitignoreMode::Off => {}
+        GitignoreMode::Skip => args.push("--respect-gitignore=skip".to_string()),
+        GitignoreMode::Tag => args.push("--respect-gitignore=tag".to_string()),
+    }
+    if let Some(min_size) = settings.min_file_size {
+        args.push(format!("--min-size={}", min_size));
+    }
+    args
+}
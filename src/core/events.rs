@@ -1,6 +1,8 @@
 use std::path::PathBuf;
 use tokio::sync::mpsc;
 
+use crate::models::node::Node;
+
 #[derive(Debug, Clone)]
 pub enum Event {
     // Scan progress
@@ -10,6 +12,16 @@ pub enum Event {
     ScanStarted { path: PathBuf },
     ScanCompleted { total_files: usize, total_size: u64, duration_ms: u64 },
     ScanError { path: PathBuf, error: String },
+    ScanPaused,
+    ScanResumed,
+    /// The scan reached a network filesystem, whose directories are read throttled.
+    NetworkFilesystem { path: PathBuf, fs_type: String },
+    /// A directory near the top of the tree finished, with everything below it. Only
+    /// sent by scanners built `with_subtree_events`; sizes are apparent sizes, as
+    /// [`ScanResult::insert_subtree`] expects.
+    ///
+    /// [`ScanResult::insert_subtree`]: crate::models::scan_result::ScanResult::insert_subtree
+    SubtreeCompleted { node: Box<Node> },
 
     // UI events
     Tick,
@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use globset::{GlobBuilder, GlobMatcher};
+
+/// Name of the per-directory ignore file honored during scans.
+pub const IGNORE_FILE_NAME: &str = ".disklensignore";
+
+/// Per-directory git ignore file, read with `Settings::gitignore`.
+pub const GITIGNORE_FILE_NAME: &str = ".gitignore";
+
+/// Repository-wide excludes, relative to the directory containing `.git`.
+pub const GIT_EXCLUDE_PATH: &str = ".git/info/exclude";
+
+struct IgnoreRule {
+    matcher: GlobMatcher,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// Gitignore-style rules accumulated while descending the tree.
+///
+/// Each frame holds the rules from one ignore file, relative to the directory
+/// that contained it. Frames link to their parent via `Arc`, so nested ignore
+/// files stack without copying and sibling subtrees share common ancestors.
+///
+/// Git's own ignore files are kept in separate frames, so entries they match can
+/// be tagged instead of skipped.
+#[derive(Default)]
+pub struct IgnoreStack {
+    parent: Option<Arc<IgnoreStack>>,
+    base: PathBuf,
+    rules: Vec<IgnoreRule>,
+    git: bool,
+}
+
+impl IgnoreStack {
+    pub fn empty() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Stack for a scan rooted at `root`, seeded with the configured exclude patterns.
+    /// They follow the same syntax as an ignore file placed in `root`.
+    pub fn with_patterns(root: &Path, patterns: &[String]) -> Arc<Self> {
+        Self::empty().push(root.to_path_buf(), &patterns.join("\n"))
+    }
+
+    /// Push the rules parsed from `contents` (relative to `base`) on top of this stack.
+    /// Returns `self` unchanged when the file contains no usable rules.
+    pub fn push(self: &Arc<Self>, base: PathBuf, contents: &str) -> Arc<Self> {
+        self.push_frame(base, contents, false)
+    }
+
+    /// Like `push`, for the rules of a `.gitignore` or `.git/info/exclude` file.
+    pub fn push_git(self: &Arc<Self>, base: PathBuf, contents: &str) -> Arc<Self> {
+        self.push_frame(base, contents, true)
+    }
+
+    fn push_frame(self: &Arc<Self>, base: PathBuf, contents: &str, git: bool) -> Arc<Self> {
+        let rules: Vec<IgnoreRule> = contents.lines().filter_map(parse_rule).collect();
+        if rules.is_empty() {
+            return Arc::clone(self);
+        }
+        Arc::new(Self {
+            parent: Some(Arc::clone(self)),
+            base,
+            rules,
+            git,
+        })
+    }
+
+    /// Whether `.disklensignore` files or exclude patterns leave out `path`.
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.matched(path, is_dir, false).unwrap_or(false)
+    }
+
+    /// Whether git's ignore files match `path`.
+    pub fn is_gitignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.matched(path, is_dir, true).unwrap_or(false)
+    }
+
+    /// Outer frames are evaluated first so deeper ignore files, and later lines
+    /// within a file, take precedence — the same "last match wins" as git.
+    fn matched(&self, path: &Path, is_dir: bool, git: bool) -> Option<bool> {
+        let mut result = self
+            .parent
+            .as_ref()
+            .and_then(|parent| parent.matched(path, is_dir, git));
+
+        if self.git != git {
+            return result;
+        }
+        if let Ok(relative) = path.strip_prefix(&self.base) {
+            for rule in &self.rules {
+                if rule.dir_only && !is_dir {
+                    continue;
+                }
+                if rule.matcher.is_match(relative) {
+                    result = Some(!rule.negated);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+fn parse_rule(line: &str) -> Option<IgnoreRule> {
+    let line = line.trim_end();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, pattern) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    // `\#` and `\!` escape a literal leading character
+    let pattern = pattern.strip_prefix('\\').unwrap_or(pattern);
+
+    let (dir_only, pattern) = match pattern.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, pattern),
+    };
+
+    // A slash anywhere but the end anchors the pattern to the ignore file's directory;
+    // otherwise it matches a name at any depth below it.
+    let anchored = pattern.contains('/');
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+    if pattern.is_empty() {
+        return None;
+    }
+
+    let glob = if anchored {
+        pattern.to_string()
+    } else {
+        format!("**/{}", pattern)
+    };
+
+    let matcher = GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()
+        .ok()?
+        .compile_matcher();
+
+    Some(IgnoreRule {
+        matcher,
+        negated,
+        dir_only,
+    })
+}
@@ -0,0 +1,67 @@
+//! The catalogue of directories that tools recreate on demand (installed packages,
+//! build output, caches), whose space can be reclaimed by deleting them.
+
+use crate::models::node::Node;
+
+/// A kind of regenerable directory, recognized by its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JunkRule {
+    pub name: &'static str,
+    /// An entry that must sit beside the directory for it to match, for names such as
+    /// `target` or `build` that are too common to trust on their own.
+    pub beside: Option<&'static str>,
+    pub label: &'static str,
+}
+
+const fn rule(name: &'static str, beside: Option<&'static str>, label: &'static str) -> JunkRule {
+    JunkRule { name, beside, label }
+}
+
+pub const JUNK_RULES: &[JunkRule] = &[
+    rule("node_modules", None, "npm packages"),
+    rule(".next", Some("package.json"), "Next.js build"),
+    rule(".nuxt", Some("package.json"), "Nuxt build"),
+    rule(".parcel-cache", None, "Parcel cache"),
+    rule(".turbo", None, "Turborepo cache"),
+    rule("target", Some("Cargo.toml"), "Rust build output"),
+    rule(".venv", None, "Python virtualenv"),
+    rule("__pycache__", None, "Python bytecode"),
+    rule(".pytest_cache", None, "pytest cache"),
+    rule(".mypy_cache", None, "mypy cache"),
+    rule(".ruff_cache", None, "Ruff cache"),
+    rule(".tox", None, "tox environments"),
+    rule(".gradle", None, "Gradle cache"),
+    rule("build", Some("build.gradle"), "Gradle build output"),
+    rule("build", Some("build.gradle.kts"), "Gradle build output"),
+    rule("target", Some("pom.xml"), "Maven build output"),
+    rule("CMakeFiles", None, "CMake build files"),
+    rule(".zig-cache", None, "Zig cache"),
+    rule("zig-cache", None, "Zig cache"),
+    rule("dist-newstyle", None, "Cabal build output"),
+    rule(".stack-work", None, "Stack build output"),
+    rule("DerivedData", None, "Xcode build data"),
+    rule(".terraform", None, "Terraform providers"),
+    rule(".cache", None, "cache directory"),
+    rule("Cache_Data", None, "browser cache"),
+    rule("Code Cache", None, "browser cache"),
+    rule("GPUCache", None, "browser cache"),
+    rule("cache2", None, "Firefox cache"),
+];
+
+/// Label for directory names the user adds to the catalogue.
+pub const CUSTOM_JUNK_LABEL: &str = "custom";
+
+/// The label of the rule a directory called `name` matches, given the entries of the
+/// directory holding it, with `extra` names matching as custom entries.
+pub fn match_junk(name: &str, siblings: &[Node], extra: &[String]) -> Option<&'static str> {
+    if extra.iter().any(|e| e == name) {
+        return Some(CUSTOM_JUNK_LABEL);
+    }
+    JUNK_RULES
+        .iter()
+        .find(|rule| {
+            rule.name == name
+                && rule.beside.is_none_or(|beside| siblings.iter().any(|s| s.name == beside))
+        })
+        .map(|rule| rule.label)
+}
@@ -0,0 +1,539 @@
+//! Reading the NTFS Master File Table for `--backend mft`. The MFT holds a record for
+//! every file and directory on a volume, with its names, parent directories, size and
+//! timestamps, so one sequential pass over it replaces listing every directory.
+//!
+//! The parser reads from any seekable source of volume bytes; opening a live volume
+//! needs Windows and an elevated process.
+
+use std::collections::HashMap;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Component, Path};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Record number of the volume's root directory.
+pub const ROOT_RECORD: u64 = 5;
+/// Records below this hold the filesystem's own metadata files (`$MFT`, `$Bitmap`,
+/// `$Extend` and so on), which directory listings don't show.
+const FIRST_USER_RECORD: u64 = 16;
+/// The last two bytes of every 512 of a record are swapped out on disk for the update
+/// sequence number, which reveals torn writes.
+const FIXUP_STRIDE: usize = 512;
+/// Bytes read from the volume at once. Reads of a raw volume must be aligned to its
+/// sectors; clusters always are, and so are offsets in steps of this.
+const READ_CHUNK: u64 = 1 << 20;
+/// Bytes read for the boot sector: a whole sector even on 4K-sector disks.
+const BOOT_READ: usize = 4096;
+
+const ATTR_STANDARD_INFORMATION: u32 = 0x10;
+const ATTR_FILE_NAME: u32 = 0x30;
+const ATTR_DATA: u32 = 0x80;
+const ATTR_REPARSE_POINT: u32 = 0xC0;
+const ATTR_END: u32 = 0xFFFF_FFFF;
+const ATTR_FLAG_COMPRESSED: u16 = 0x0001;
+const ATTR_FLAG_SPARSE: u16 = 0x8000;
+
+const RECORD_IN_USE: u16 = 0x1;
+const RECORD_IS_DIRECTORY: u16 = 0x2;
+/// The 8.3 alias of a long name, which listings don't show as an entry of its own.
+const NAMESPACE_DOS: u8 = 2;
+/// File references keep a sequence number in their top 16 bits.
+const RECORD_NUMBER_MASK: u64 = 0xFFFF_FFFF_FFFF;
+
+pub const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xA000_0003;
+const IO_REPARSE_TAG_SYMLINK: u32 = 0xA000_000C;
+
+/// Seconds from 1601-01-01, where `FILETIME` counts from, to the Unix epoch.
+const FILETIME_UNIX_OFFSET: u64 = 11_644_473_600;
+
+/// A file or directory as its MFT records describe it.
+#[derive(Debug, Clone, Default)]
+pub struct MftFile {
+    /// One name per hard link, each with the directory it is in.
+    pub links: Vec<MftLink>,
+    pub is_dir: bool,
+    /// Length of the unnamed data stream. Alternate streams don't count, as in
+    /// directory listings.
+    pub size: u64,
+    /// Bytes of the data on disk for compressed and sparse files, otherwise `size`, as
+    /// the regular scan reports them on Windows.
+    pub allocated_size: u64,
+    pub modified: Option<SystemTime>,
+    /// `FILE_ATTRIBUTE_*` flags.
+    pub attributes: u32,
+    pub reparse_tag: Option<u32>,
+    /// Whether the base record was seen, rather than only extension records.
+    has_base: bool,
+}
+
+/// One name of an [`MftFile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MftLink {
+    /// Record number of the directory holding the name.
+    pub parent: u64,
+    pub name: String,
+}
+
+impl MftFile {
+    /// Symlinks and mount points, and directory reparse points such as junctions,
+    /// which the scan treats like symlinks.
+    pub fn is_link(&self) -> bool {
+        if self.attributes & FILE_ATTRIBUTE_REPARSE_POINT == 0 {
+            return false;
+        }
+        self.is_dir
+            || matches!(self.reparse_tag, Some(IO_REPARSE_TAG_SYMLINK | IO_REPARSE_TAG_MOUNT_POINT))
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.attributes & FILE_ATTRIBUTE_HIDDEN != 0
+    }
+}
+
+/// Every file and directory of a volume, read from its MFT.
+#[derive(Debug, Default)]
+pub struct MftIndex {
+    files: HashMap<u64, MftFile>,
+    /// Each directory's entries, as the record and which of its links is named there.
+    children: HashMap<u64, Vec<(u64, usize)>>,
+}
+
+/// Where the MFT is and how it is laid out, from the boot sector.
+struct Geometry {
+    cluster_size: u64,
+    record_size: usize,
+    mft_offset: u64,
+}
+
+/// An extent of a non-resident attribute: its first cluster, or `None` for a sparse
+/// run, and its length in clusters.
+type Run = (Option<u64>, u64);
+
+/// What one record adds to the file it belongs to.
+struct RecordPart {
+    /// Record number of the base record, when this is an extension record.
+    base: Option<u64>,
+    is_dir: bool,
+    links: Vec<MftLink>,
+    modified: Option<SystemTime>,
+    attributes: Option<u32>,
+    reparse_tag: Option<u32>,
+    /// Sizes of the unnamed data stream, from the attribute holding its first extent.
+    data: Option<(u64, u64)>,
+    /// Extents of the unnamed data stream and the cluster they start at.
+    runs: Option<(u64, Vec<Run>)>,
+}
+
+impl MftIndex {
+    /// Read the MFT of the NTFS volume whose bytes `volume` gives.
+    pub fn read<R: Read + Seek>(mut volume: R) -> io::Result<Self> {
+        let geometry = Geometry::read(&mut volume)?;
+        let record_size = geometry.record_size;
+
+        // Record 0 is the MFT's own, and says where the rest of it is
+        let first_len = (record_size as u64).max(geometry.cluster_size) as usize;
+        let mut first = vec![0u8; first_len];
+        volume.seek(SeekFrom::Start(geometry.mft_offset))?;
+        volume.read_exact(&mut first)?;
+        let mft = parse_record(&mut first[..record_size]).ok_or_else(|| invalid("damaged $MFT record"))?;
+        let (mft_size, _) = mft.data.ok_or_else(|| invalid("$MFT has no data"))?;
+        let (_, mut runs) = mft.runs.ok_or_else(|| invalid("$MFT data is not in extents"))?;
+        let record_count = mft_size / record_size as u64;
+
+        let mut index = MftIndex::default();
+        // Further extents of a fragmented MFT, from its extension records, by the
+        // cluster of the MFT they start at
+        let mut more_runs: HashMap<u64, Vec<Run>> = HashMap::new();
+        let mut covered: u64 = runs.iter().map(|(_, len)| len).sum();
+        let mut pending: Vec<u8> = Vec::new();
+        let mut record = 0u64;
+        let mut next_run = 0;
+        while record < record_count {
+            let Some(&(lcn, clusters)) = runs.get(next_run) else {
+                return Err(invalid("$MFT extents end before its last record"));
+            };
+            next_run += 1;
+            let mut remaining = clusters.saturating_mul(geometry.cluster_size);
+            let mut offset = lcn.map(|lcn| lcn.saturating_mul(geometry.cluster_size));
+            if let Some(offset) = offset {
+                volume.seek(SeekFrom::Start(offset))?;
+            }
+            while remaining > 0 && record < record_count {
+                let len = remaining.min(READ_CHUNK) as usize;
+                let start = pending.len();
+                pending.resize(start + len, 0);
+                // A sparse extent holds no records; its zeros fail the record check
+                if offset.is_some() {
+                    volume.read_exact(&mut pending[start..])?;
+                }
+                remaining -= len as u64;
+                offset = offset.map(|offset| offset + len as u64);
+
+                let whole = pending.len() / record_size * record_size;
+                for bytes in pending[..whole].chunks_exact_mut(record_size) {
+                    if record >= record_count {
+                        break;
+                    }
+                    if let Some(part) = parse_record(bytes) {
+                        if part.base == Some(0) {
+                            if let Some((start_vcn, runs)) = &part.runs {
+                                more_runs.insert(*start_vcn, runs.clone());
+                            }
+                        }
+                        index.add(record, part);
+                    }
+                    record += 1;
+                }
+                pending.drain(..whole);
+            }
+            while let Some(extents) = more_runs.remove(&covered) {
+                covered += extents.iter().map(|(_, len)| len).sum::<u64>();
+                runs.extend(extents);
+            }
+        }
+        index.link_children();
+        Ok(index)
+    }
+
+    /// Read the MFT of the NTFS volume `volume_root` (such as `C:\`) is the root of.
+    /// Opening the volume needs an elevated process.
+    #[cfg(windows)]
+    pub fn open(volume_root: &Path) -> io::Result<Self> {
+        use std::path::Prefix;
+
+        let letter = match volume_root.components().next() {
+            Some(Component::Prefix(prefix)) => match prefix.kind() {
+                Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => letter as char,
+                _ => return Err(invalid("not on a drive letter")),
+            },
+            _ => return Err(invalid("not on a drive letter")),
+        };
+        let volume = std::fs::File::open(format!(r"\\.\{}:", letter)).map_err(|e| {
+            if e.kind() == io::ErrorKind::PermissionDenied {
+                io::Error::new(e.kind(), "reading the MFT needs an elevated (administrator) prompt")
+            } else {
+                e
+            }
+        })?;
+        Self::read(volume)
+    }
+
+    /// Fold `part`, read from record `record`, into the file it belongs to.
+    fn add(&mut self, record: u64, part: RecordPart) {
+        let file = self.files.entry(part.base.unwrap_or(record)).or_default();
+        if part.base.is_none() {
+            file.has_base = true;
+            file.is_dir = part.is_dir;
+        }
+        file.links.extend(part.links);
+        if part.modified.is_some() {
+            file.modified = part.modified;
+        }
+        if let Some(attributes) = part.attributes {
+            file.attributes = attributes;
+        }
+        if part.reparse_tag.is_some() {
+            file.reparse_tag = part.reparse_tag;
+        }
+        if let Some((size, allocated)) = part.data {
+            file.size = size;
+            file.allocated_size = allocated;
+        }
+    }
+
+    /// Drop the filesystem's own files and records only seen through extensions, and
+    /// list every remaining name under its directory.
+    fn link_children(&mut self) {
+        self.files.retain(|&record, file| {
+            file.has_base && (record >= FIRST_USER_RECORD || record == ROOT_RECORD)
+        });
+        for (&record, file) in &self.files {
+            for (link, name) in file.links.iter().enumerate() {
+                // The root lists itself as its own parent
+                if name.parent != record {
+                    self.children.entry(name.parent).or_default().push((record, link));
+                }
+            }
+        }
+    }
+
+    pub fn file(&self, record: u64) -> Option<&MftFile> {
+        self.files.get(&record)
+    }
+
+    /// The entries of directory `dir`, as their record and name there.
+    pub fn entries(&self, dir: u64) -> impl Iterator<Item = (u64, &str)> + '_ {
+        self.children.get(&dir).into_iter().flatten().filter_map(|&(record, link)| {
+            let name = &self.files.get(&record)?.links.get(link)?.name;
+            Some((record, name.as_str()))
+        })
+    }
+
+    /// The record of the entry at `relative`, a path from the volume's root. Names
+    /// match regardless of case, as NTFS resolves them.
+    pub fn lookup(&self, relative: &Path) -> Option<u64> {
+        let mut record = ROOT_RECORD;
+        for component in relative.components() {
+            let name = match component {
+                Component::Normal(name) => name.to_string_lossy(),
+                Component::CurDir => continue,
+                _ => return None,
+            };
+            let lower = name.to_lowercase();
+            let mut found = None;
+            for (entry, entry_name) in self.entries(record) {
+                if entry_name == name {
+                    found = Some(entry);
+                    break;
+                }
+                if found.is_none() && entry_name.to_lowercase() == lower {
+                    found = Some(entry);
+                }
+            }
+            record = found?;
+        }
+        Some(record)
+    }
+
+    /// Number of files and directories, the root included.
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl Geometry {
+    fn read<R: Read + Seek>(volume: &mut R) -> io::Result<Self> {
+        let mut boot = vec![0u8; BOOT_READ];
+        volume.seek(SeekFrom::Start(0))?;
+        // A tiny image may end inside the first sector's read
+        let read = read_up_to(volume, &mut boot)?;
+        let boot = &boot[..read];
+        if boot.get(3..11) != Some(b"NTFS    ".as_slice()) {
+            return Err(invalid("not an NTFS volume"));
+        }
+        let short = || invalid("short boot sector");
+        let bytes_per_sector = u64::from(le_u16(boot, 0x0B).ok_or_else(short)?);
+        let sectors_per_cluster = match *boot.get(0x0D).ok_or_else(short)? {
+            // Clusters past 64 KiB store the power of two, negated
+            raw @ 0x81.. => 1u64 << (256 - u32::from(raw)).min(31),
+            raw => u64::from(raw),
+        };
+        let cluster_size = bytes_per_sector * sectors_per_cluster;
+        let mft_lcn = le_u64(boot, 0x30).ok_or_else(short)?;
+        let record_size = match *boot.get(0x40).ok_or_else(short)? as i8 {
+            raw @ ..=-1 => 1u64 << (-i32::from(raw)).min(31),
+            raw => u64::from(raw as u8) * cluster_size,
+        };
+        let valid = cluster_size > 0
+            && (FIXUP_STRIDE as u64..=1 << 16).contains(&record_size)
+            && record_size % FIXUP_STRIDE as u64 == 0;
+        if !valid {
+            return Err(invalid("unsupported NTFS geometry"));
+        }
+        Ok(Self {
+            cluster_size,
+            record_size: record_size as usize,
+            mft_offset: mft_lcn.saturating_mul(cluster_size),
+        })
+    }
+}
+
+/// Read into `buf` until it is full or the source ends, returning the bytes read.
+fn read_up_to(source: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match source.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// The parts of the in-use record in `bytes` that the index needs, after undoing its
+/// update sequence. `None` for free, damaged and unused records.
+fn parse_record(bytes: &mut [u8]) -> Option<RecordPart> {
+    if bytes.get(0..4)? != b"FILE" || !apply_fixups(bytes) {
+        return None;
+    }
+    let flags = le_u16(bytes, 0x16)?;
+    if flags & RECORD_IN_USE == 0 {
+        return None;
+    }
+    // Zero in a base record; the MFT's own extension records still differ from it by
+    // the sequence number
+    let base_reference = le_u64(bytes, 0x20)?;
+    let mut part = RecordPart {
+        base: (base_reference != 0).then_some(base_reference & RECORD_NUMBER_MASK),
+        is_dir: flags & RECORD_IS_DIRECTORY != 0,
+        links: Vec::new(),
+        modified: None,
+        attributes: None,
+        reparse_tag: None,
+        data: None,
+        runs: None,
+    };
+
+    let used = (le_u32(bytes, 0x18)? as usize).min(bytes.len());
+    let mut offset = usize::from(le_u16(bytes, 0x14)?);
+    while offset + 8 <= used {
+        let kind = le_u32(bytes, offset)?;
+        if kind == ATTR_END {
+            break;
+        }
+        let len = le_u32(bytes, offset + 4)? as usize;
+        if len < 0x18 || offset + len > used {
+            return None;
+        }
+        let attr = &bytes[offset..offset + len];
+        offset += len;
+        let non_resident = attr[8] != 0;
+        let unnamed = attr[9] == 0;
+        let value = if non_resident {
+            None
+        } else {
+            let value_len = le_u32(attr, 0x10)? as usize;
+            let value_offset = usize::from(le_u16(attr, 0x14)?);
+            Some(attr.get(value_offset..value_offset.checked_add(value_len)?)?)
+        };
+        match (kind, value) {
+            (ATTR_STANDARD_INFORMATION, Some(value)) => {
+                part.modified = filetime(le_u64(value, 0x08)?);
+                part.attributes = Some(le_u32(value, 0x20)?);
+            }
+            (ATTR_FILE_NAME, Some(value)) => {
+                let name_len = usize::from(*value.get(0x40)?);
+                let namespace = *value.get(0x41)?;
+                if namespace == NAMESPACE_DOS {
+                    continue;
+                }
+                let units: Vec<u16> = value
+                    .get(0x42..0x42 + name_len * 2)?
+                    .chunks_exact(2)
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                    .collect();
+                part.links.push(MftLink {
+                    parent: le_u64(value, 0x00)? & RECORD_NUMBER_MASK,
+                    name: String::from_utf16_lossy(&units),
+                });
+            }
+            (ATTR_DATA, Some(value)) if unnamed => {
+                let size = value.len() as u64;
+                part.data = Some((size, size));
+            }
+            (ATTR_DATA, None) if unnamed => {
+                let start_vcn = le_u64(attr, 0x10)?;
+                let runs_offset = usize::from(le_u16(attr, 0x20)?);
+                part.runs = Some((start_vcn, decode_runs(attr.get(runs_offset..)?)?));
+                // Only the first extent's attribute carries the sizes
+                if start_vcn == 0 {
+                    let size = le_u64(attr, 0x30)?;
+                    let attr_flags = le_u16(attr, 0x0C)?;
+                    let allocated = if attr_flags & (ATTR_FLAG_COMPRESSED | ATTR_FLAG_SPARSE) != 0 {
+                        le_u64(attr, 0x40)?
+                    } else {
+                        size
+                    };
+                    part.data = Some((size, allocated));
+                }
+            }
+            (ATTR_REPARSE_POINT, Some(value)) => part.reparse_tag = le_u32(value, 0),
+            _ => {}
+        }
+    }
+    Some(part)
+}
+
+/// Put back the bytes the update sequence stands in for at the end of each 512-byte
+/// stride. False when one doesn't hold the sequence number: a torn write.
+fn apply_fixups(bytes: &mut [u8]) -> bool {
+    let (Some(offset), Some(count)) = (le_u16(bytes, 0x04), le_u16(bytes, 0x06)) else {
+        return false;
+    };
+    let (offset, count) = (usize::from(offset), usize::from(count));
+    if count == 0 || count - 1 != bytes.len() / FIXUP_STRIDE || offset + count * 2 > bytes.len() {
+        return false;
+    }
+    let usn = [bytes[offset], bytes[offset + 1]];
+    for stride in 1..count {
+        let end = stride * FIXUP_STRIDE;
+        if bytes[end - 2..end] != usn {
+            return false;
+        }
+        let saved = offset + stride * 2;
+        bytes[end - 2] = bytes[saved];
+        bytes[end - 1] = bytes[saved + 1];
+    }
+    true
+}
+
+/// The extents of a non-resident attribute from its mapping pairs: each a header byte
+/// giving the sizes of a length and of a signed offset from the previous extent.
+fn decode_runs(mut pairs: &[u8]) -> Option<Vec<Run>> {
+    let mut runs = Vec::new();
+    let mut lcn: i64 = 0;
+    while let Some((&header, rest)) = pairs.split_first() {
+        if header == 0 {
+            break;
+        }
+        let (len_size, offset_size) = (usize::from(header & 0xF), usize::from(header >> 4));
+        if len_size == 0 || len_size > 8 || offset_size > 8 {
+            return None;
+        }
+        let length = le_unsigned(rest.get(..len_size)?);
+        let delta = rest.get(len_size..len_size + offset_size)?;
+        pairs = &rest[len_size + offset_size..];
+        if delta.is_empty() {
+            runs.push((None, length));
+            continue;
+        }
+        lcn = lcn.checked_add(le_signed(delta))?;
+        runs.push((Some(u64::try_from(lcn).ok()?), length));
+    }
+    Some(runs)
+}
+
+fn le_unsigned(bytes: &[u8]) -> u64 {
+    bytes.iter().rev().fold(0, |value, &byte| value << 8 | u64::from(byte))
+}
+
+fn le_signed(bytes: &[u8]) -> i64 {
+    let unsigned = le_unsigned(bytes);
+    let unused = 64 - 8 * bytes.len() as u32;
+    // Shift the sign bit to the top and back to extend it
+    ((unsigned << unused) as i64) >> unused
+}
+
+fn le_u16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn le_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn le_u64(bytes: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(bytes.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// A `FILETIME`: 100-nanosecond ticks since 1601. Zero means unset.
+fn filetime(ticks: u64) -> Option<SystemTime> {
+    if ticks == 0 {
+        return None;
+    }
+    let since_1601 = Duration::new(ticks / 10_000_000, (ticks % 10_000_000) as u32 * 100);
+    UNIX_EPOCH
+        .checked_sub(Duration::from_secs(FILETIME_UNIX_OFFSET))?
+        .checked_add(since_1601)
+}
@@ -1,5 +1,21 @@
 pub mod scanner;
 pub mod analyzer;
 pub mod cache;
+pub mod profile;
 pub mod progress;
 pub mod events;
+pub mod ignore;
+pub mod mounts;
+pub mod cancel;
+pub mod archive;
+pub mod clones;
+pub mod owners;
+pub mod elevated;
+pub mod cleanup;
+pub mod diff;
+pub mod junk;
+pub mod compression;
+pub mod treemap;
+pub mod mft;
+#[cfg(windows)]
+pub mod attributes;
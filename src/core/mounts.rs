@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Virtual filesystems that expose kernel state rather than stored data. Their sizes
+/// are meaningless and some of them never finish listing.
+pub const DEFAULT_PSEUDO_FS_TYPES: &[&str] = &[
+    "proc",
+    "sysfs",
+    "devtmpfs",
+    "devpts",
+    "cgroup",
+    "cgroup2",
+    "debugfs",
+    "tracefs",
+    "securityfs",
+    "pstore",
+    "bpf",
+    "configfs",
+    "fusectl",
+    "mqueue",
+    "hugetlbfs",
+    "binfmt_misc",
+    "efivarfs",
+    "selinuxfs",
+    "autofs",
+];
+
+/// Filesystems served over the network, where every request is a round trip to a server.
+pub const NETWORK_FS_TYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "afpfs",
+    "webdav",
+    "davfs",
+    "fuse.sshfs",
+    "9p",
+    "afs",
+    "ceph",
+    "glusterfs",
+    "fuse.glusterfs",
+    "fuse.rclone",
+];
+
+/// Filesystem type of every mount point, as listed in `/proc/mounts`.
+#[derive(Debug, Default, Clone)]
+pub struct MountTable {
+    types: HashMap<PathBuf, String>,
+}
+
+impl MountTable {
+    /// Read the current mount table. Empty where `/proc/mounts` is unavailable.
+    pub fn load() -> Self {
+        std::fs::read_to_string("/proc/mounts")
+            .map(|contents| Self::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    /// Parse `/proc/mounts` (fstab) format: `device mount_point fs_type options ...`.
+    pub fn parse(contents: &str) -> Self {
+        let types = parse_entries(contents)
+            .into_iter()
+            .map(|entry| (entry.mount_point, entry.fs_type))
+            .collect();
+        Self { types }
+    }
+
+    /// The filesystem type mounted exactly at `path`, if `path` is a mount point.
+    pub fn fs_type(&self, path: &Path) -> Option<&str> {
+        self.types.get(path).map(String::as_str)
+    }
+
+    /// The network filesystem type mounted exactly at `path`, if any.
+    pub fn network_fs_type(&self, path: &Path) -> Option<&str> {
+        self.fs_type(path).filter(|t| NETWORK_FS_TYPES.contains(t))
+    }
+
+    /// The filesystem type of the innermost mount point containing `path`.
+    pub fn containing_fs_type(&self, path: &Path) -> Option<&str> {
+        self.types
+            .iter()
+            .filter(|(mount_point, _)| path.starts_with(mount_point))
+            .max_by_key(|(mount_point, _)| mount_point.as_os_str().len())
+            .map(|(_, fs_type)| fs_type.as_str())
+    }
+}
+
+/// One line of the mount table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountEntry {
+    pub device: String,
+    pub mount_point: PathBuf,
+    pub fs_type: String,
+}
+
+/// Parse `/proc/mounts` (fstab) format into its entries, in order.
+pub fn parse_entries(contents: &str) -> Vec<MountEntry> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = fields.next()?;
+            let mount_point = fields.next()?;
+            let fs_type = fields.next()?;
+            Some(MountEntry {
+                device: unescape(device),
+                mount_point: PathBuf::from(unescape(mount_point)),
+                fs_type: fs_type.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// A mounted filesystem and how full it is, for the `--all-mounts` overview.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MountInfo {
+    pub mount_point: PathBuf,
+    pub device: String,
+    pub fs_type: String,
+    /// Capacity in bytes.
+    pub total: u64,
+    /// Bytes not allocated to anything.
+    pub free: u64,
+    /// Free bytes that unprivileged users may allocate; less than `free` where space
+    /// is reserved for root.
+    pub available: u64,
+}
+
+impl MountInfo {
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+}
+
+/// The mounted filesystems worth scanning: everything in `/proc/mounts` except
+/// `pseudo_fs_types`, mounts with no capacity and repeat mounts of a device (bind
+/// mounts, btrfs subvolumes), with their `statvfs` figures. Empty where the mount
+/// table is unavailable.
+pub fn list_mounts(pseudo_fs_types: &[String]) -> Vec<MountInfo> {
+    let contents = std::fs::read_to_string("/proc/mounts").unwrap_or_default();
+    let mut devices = std::collections::HashSet::new();
+    parse_entries(&contents)
+        .into_iter()
+        .filter(|entry| !pseudo_fs_types.contains(&entry.fs_type))
+        // Device nodes identify a filesystem; names like `tmpfs` are shared by unrelated ones
+        .filter(|entry| !entry.device.starts_with('/') || devices.insert(entry.device.clone()))
+        .filter_map(|entry| {
+            let (total, free, available) = capacity(&entry.mount_point)?;
+            (total > 0).then_some(MountInfo {
+                mount_point: entry.mount_point,
+                device: entry.device,
+                fs_type: entry.fs_type,
+                total,
+                free,
+                available,
+            })
+        })
+        .collect()
+}
+
+/// Total, free and available bytes of the filesystem holding `path`, from `statvfs`.
+pub fn capacity(#[allow(unused_variables)] path: &Path) -> Option<(u64, u64, u64)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+        // SAFETY: `statvfs` is plain C data and `c_path` is NUL-terminated.
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+            return None;
+        }
+        // Field widths vary by platform
+        let fragment = stat.f_frsize as u64;
+        Some((
+            stat.f_blocks as u64 * fragment,
+            stat.f_bfree as u64 * fragment,
+            stat.f_bavail as u64 * fragment,
+        ))
+    }
+    #[cfg(not(unix))]
+    {
+        None
+    }
+}
+
+/// The network filesystem type holding `path`, asking `statfs` first. FUSE mounts such
+/// as sshfs all look alike to `statfs`, so their type comes from `mounts` instead.
+pub fn detect_network_fs(path: &Path, mounts: &MountTable) -> Option<String> {
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        use std::os::unix::ffi::OsStrExt;
+
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+        // SAFETY: `statfs` is plain C data and `c_path` is NUL-terminated.
+        let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statfs(c_path.as_ptr(), &mut stat) } == 0 {
+            #[cfg(target_os = "macos")]
+            {
+                // SAFETY: the kernel fills `f_fstypename` with a NUL-terminated name.
+                let name = unsafe { std::ffi::CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+                let name = name.to_string_lossy();
+                if NETWORK_FS_TYPES.contains(&name.as_ref()) {
+                    return Some(name.into_owned());
+                }
+            }
+            #[cfg(target_os = "linux")]
+            {
+                // Magic numbers from linux/magic.h; f_type's width varies by platform
+                let name = match stat.f_type as u32 {
+                    0x6969 => Some("nfs"),
+                    0x517B => Some("smbfs"),
+                    0xFF53_4D42 => Some("cifs"),
+                    0xFE53_4D42 => Some("smb3"),
+                    0x5346_414F => Some("afs"),
+                    0x00C3_6400 => Some("ceph"),
+                    0x0102_1997 => Some("9p"),
+                    _ => None,
+                };
+                if let Some(name) = name {
+                    return Some(name.to_string());
+                }
+            }
+        }
+    }
+    mounts
+        .containing_fs_type(path)
+        .filter(|t| NETWORK_FS_TYPES.contains(t))
+        .map(str::to_string)
+}
+
+/// Undo the octal escapes (`\040` for space, etc.) the kernel uses in mount points.
+fn unescape(field: &str) -> String {
+    if !field.contains('\\') {
+        return field.to_string();
+    }
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes.get(i + 1..i + 4).and_then(|digits| {
+            std::str::from_utf8(digits)
+                .ok()
+                .and_then(|s| u8::from_str_radix(s, 8).ok())
+        });
+        match (bytes[i], octal) {
+            (b'\\', Some(byte)) => {
+                out.push(byte);
+                i += 4;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
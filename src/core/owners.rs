@@ -0,0 +1,60 @@
+//! Resolving the user and group IDs recorded on nodes to names.
+//!
+//! Names come from the system's user database (`/etc/passwd`, NSS, directory
+//! services); IDs it doesn't know, such as those on a volume from another machine,
+//! stay unresolved. There are no numeric owners outside unix.
+
+/// Login name of the user with `uid`.
+#[cfg(unix)]
+pub fn user_name(uid: u32) -> Option<String> {
+    lookup(|pwd: &mut libc::passwd, buf, result| {
+        // SAFETY: every pointer is valid for the call and `buf` is as long as stated.
+        let rc = unsafe { libc::getpwuid_r(uid, pwd, buf.as_mut_ptr(), buf.len(), result) };
+        (rc, pwd.pw_name)
+    })
+}
+
+/// Name of the group with `gid`.
+#[cfg(unix)]
+pub fn group_name(gid: u32) -> Option<String> {
+    lookup(|grp: &mut libc::group, buf, result| {
+        // SAFETY: every pointer is valid for the call and `buf` is as long as stated.
+        let rc = unsafe { libc::getgrgid_r(gid, grp, buf.as_mut_ptr(), buf.len(), result) };
+        (rc, grp.gr_name)
+    })
+}
+
+/// Run a reentrant `get*id_r` lookup, growing the string buffer while it is too small.
+#[cfg(unix)]
+fn lookup<T>(
+    call: impl Fn(&mut T, &mut [libc::c_char], *mut *mut T) -> (libc::c_int, *const libc::c_char),
+) -> Option<String> {
+    const MAX_BUFFER: usize = 1 << 20;
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        // SAFETY: the entry structs are plain C data, for which all zeroes is valid.
+        let mut entry: T = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        let (rc, name) = call(&mut entry, &mut buf, &mut result);
+        if rc == libc::ERANGE && buf.len() < MAX_BUFFER {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if rc != 0 || result.is_null() || name.is_null() {
+            return None;
+        }
+        // SAFETY: on success `name` points at a NUL-terminated string inside `buf`.
+        let name = unsafe { std::ffi::CStr::from_ptr(name) };
+        return Some(name.to_string_lossy().into_owned());
+    }
+}
+
+#[cfg(not(unix))]
+pub fn user_name(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+pub fn group_name(_gid: u32) -> Option<String> {
+    None
+}
@@ -0,0 +1,68 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Per-directory wall time spent in the blocking `read_dir_batch` call.
+///
+/// Only a directory's own I/O is recorded, never its children, so the values
+/// map directly onto the "self time" of a flame graph frame.
+#[derive(Default)]
+pub struct IoProfile {
+    samples: Mutex<Vec<(PathBuf, Duration)>>,
+}
+
+impl IoProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, path: PathBuf, elapsed: Duration) {
+        self.samples.lock().unwrap().push((path, elapsed));
+    }
+
+    /// Collapsed-stack lines (`root;usr;lib 1234`, microseconds) for everything under `root`,
+    /// in the format consumed by `inferno-flamegraph` and `flamegraph.pl`.
+    pub fn folded_lines(&self, root: &Path) -> Vec<String> {
+        let root_frame = fold_frame(
+            &root
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| root.to_string_lossy().to_string()),
+        );
+
+        let mut stacks: BTreeMap<String, u128> = BTreeMap::new();
+        for (path, elapsed) in self.samples.lock().unwrap().iter() {
+            let Ok(relative) = path.strip_prefix(root) else {
+                continue;
+            };
+            let mut stack = root_frame.clone();
+            for component in relative.components() {
+                stack.push(';');
+                stack.push_str(&fold_frame(&component.as_os_str().to_string_lossy()));
+            }
+            *stacks.entry(stack).or_default() += elapsed.as_micros();
+        }
+
+        stacks
+            .into_iter()
+            .filter(|(_, micros)| *micros > 0)
+            .map(|(stack, micros)| format!("{} {}", stack, micros))
+            .collect()
+    }
+
+    pub fn write_folded(&self, root: &Path, output_path: &Path) -> anyhow::Result<()> {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(output_path)?);
+        for line in self.folded_lines(root) {
+            writeln!(out, "{}", line)?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Frame names may not contain the `;` stack separator or break the line.
+pub fn fold_frame(name: &str) -> String {
+    name.replace([';', '\n'], "_")
+}
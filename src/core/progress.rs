@@ -1,4 +1,6 @@
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
 pub struct ProgressTracker {
@@ -7,6 +9,16 @@ pub struct ProgressTracker {
     pub total_size: AtomicU64,
     pub errors_count: AtomicUsize,
     pub start_time: Instant,
+    /// Time spent paused so far, and when the current pause began.
+    paused: Mutex<(Duration, Option<Instant>)>,
+    /// Directory the scan entered most recently.
+    current_path: Mutex<PathBuf>,
+}
+
+impl Default for ProgressTracker {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ProgressTracker {
@@ -17,6 +29,8 @@ impl ProgressTracker {
             total_size: AtomicU64::new(0),
             errors_count: AtomicUsize::new(0),
             start_time: Instant::now(),
+            paused: Mutex::new((Duration::ZERO, None)),
+            current_path: Mutex::new(PathBuf::new()),
         }
     }
 
@@ -36,16 +50,46 @@ impl ProgressTracker {
         self.errors_count.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// Record the directory being entered. Many tasks call this at once, so a busy
+    /// lock is skipped rather than waited for; any recent path is good enough.
+    pub fn set_current_path(&self, path: &Path) {
+        if let Ok(mut current) = self.current_path.try_lock() {
+            // Reuse the buffer instead of allocating a new path each time
+            current.as_mut_os_string().clear();
+            current.push(path);
+        }
+    }
+
+    pub fn current_path(&self) -> PathBuf {
+        self.current_path.lock().unwrap().clone()
+    }
+
     pub fn files_per_second(&self) -> f64 {
-        let elapsed = self.start_time.elapsed().as_secs_f64();
+        let elapsed = self.elapsed().as_secs_f64();
         if elapsed < f64::EPSILON {
             return 0.0;
         }
         self.files_scanned.load(Ordering::Relaxed) as f64 / elapsed
     }
 
+    /// Stop the clock: time spent paused doesn't count toward `elapsed` or the speed.
+    pub fn pause(&self) {
+        let mut paused = self.paused.lock().unwrap();
+        paused.1.get_or_insert_with(Instant::now);
+    }
+
+    pub fn resume(&self) {
+        let mut paused = self.paused.lock().unwrap();
+        if let Some(since) = paused.1.take() {
+            paused.0 += since.elapsed();
+        }
+    }
+
     pub fn elapsed(&self) -> Duration {
-        self.start_time.elapsed()
+        let paused = self.paused.lock().unwrap();
+        // While paused the clock stands still at the moment the pause began
+        let end = paused.1.unwrap_or_else(Instant::now);
+        end.duration_since(self.start_time).saturating_sub(paused.0)
     }
 
     pub fn snapshot(&self) -> ProgressSnapshot {
@@ -56,6 +100,7 @@ impl ProgressTracker {
             errors_count: self.errors_count.load(Ordering::Relaxed),
             elapsed: self.elapsed(),
             files_per_second: self.files_per_second(),
+            current_path: self.current_path(),
         }
     }
 }
@@ -67,4 +112,5 @@ pub struct ProgressSnapshot {
     pub errors_count: usize,
     pub elapsed: Duration,
     pub files_per_second: f64,
+    pub current_path: PathBuf,
 }
@@ -1,387 +1,2251 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
 use std::future::Future;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use dashmap::DashSet;
-use tokio::sync::Semaphore;
+use tokio::sync::{watch, Notify, Semaphore};
 
-use crate::config::settings::Settings;
-use crate::models::node::{Node, NodeType};
-use crate::models::scan_result::{ScanError, ScanErrorType, ScanResult};
+use crate::config::settings::{
+    detect_filesystem_type, FilesystemType, GitignoreMode, ScanProfile, Settings, TreeSettings,
+};
+use crate::models::category::FileCategory;
+use crate::models::node::{human_readable_size, Node, NodeType, SizeMode};
+use crate::models::scan_result::{
+    SampleEstimate, SampleUnit, ScanError, ScanErrorType, ScanResult, SkippedEntry, TopFile,
+    TopNResult,
+};
 
+use super::archive::{self, ArchiveKind};
+use super::cache::Cache;
+use super::cancel::CancelToken;
+use super::clones;
 use super::events::{Event, EventSender};
+use super::ignore::{IgnoreStack, GITIGNORE_FILE_NAME, GIT_EXCLUDE_PATH, IGNORE_FILE_NAME};
+use super::mounts::{detect_network_fs, MountTable};
+use super::profile::IoProfile;
 use super::progress::ProgressTracker;
 
+mod mft;
+mod threads;
+#[cfg(all(target_os = "linux", feature = "uring"))]
+mod uring;
+
+pub use threads::ScannerSync;
+
 pub struct Scanner {
-    semaphore: Arc<Semaphore>,
+    ctx: Arc<ScanContext>,
+}
+
+/// Callback invoked inline for every scan event, see [`Scanner::on_event`].
+pub type EventCallback = Arc<dyn Fn(&Event) + Send + Sync>;
+
+/// State shared by every recursive `scan_directory` task of a single scanner.
+struct ScanContext {
+    semaphore: Semaphore,
+    /// `Settings::profiles`, most specific (deepest path) first.
+    profiles: Vec<ProfileSlot>,
+    /// Takes the place of `semaphore` for reads on network filesystems.
+    network_semaphore: Semaphore,
+    /// Network filesystems reached so far; directory reads below them are throttled.
+    network_roots: RwLock<Vec<PathBuf>>,
+    /// Earliest time the next directory read on a network filesystem may start.
+    next_network_read: Mutex<Instant>,
     event_tx: EventSender,
-    visited: Arc<DashSet<PathBuf>>,
+    visited: DashSet<PathBuf>,
     progress: Arc<ProgressTracker>,
-    settings: Arc<Settings>,
-    errors: Arc<std::sync::Mutex<Vec<ScanError>>>,
-    last_progress_time: Arc<AtomicU64>,
+    settings: Settings,
+    errors: Mutex<Vec<ScanError>>,
+    last_progress_time: AtomicU64,
+    io_profile: Option<Arc<IoProfile>>,
+    callbacks: RwLock<Vec<EventCallback>>,
+    sample_stats: Mutex<SampleStats>,
+    mounts: MountTable,
+    skipped: Mutex<Vec<SkippedEntry>>,
+    /// Directories not started because `max_duration` ran out.
+    unscanned: Mutex<Vec<PathBuf>>,
+    /// `(device, inode)` of every multiply-linked file counted so far.
+    seen_inodes: DashSet<(u64, u64)>,
+    /// Device of the root being scanned, for `one_file_system`.
+    root_dev: Mutex<Option<u64>>,
+    cancel: CancelToken,
+    /// While `true`, no new directory reads are started.
+    paused: watch::Sender<bool>,
+    /// Tree of an earlier scan whose unchanged directories can be reused.
+    previous: OnceLock<Node>,
+    /// Cache whose directory entries, saved by scans with these tree settings, can be
+    /// reused for directories `previous` doesn't hold unchanged.
+    cache: OnceLock<(Cache, TreeSettings)>,
+    /// Send `Event::SubtreeCompleted` for directories near the top of the tree.
+    subtree_events: AtomicBool,
+    /// Volumes whose directories [`ScannerSync`] lists from the MFT instead of the disk.
+    mft_volumes: RwLock<Vec<mft::MftVolume>>,
+}
+
+/// Deepest level below a root whose finished directories are sent as
+/// `Event::SubtreeCompleted`. Each event copies the directory's whole subtree, so
+/// stopping near the top keeps the copies to a small multiple of the tree.
+const SUBTREE_EVENT_DEPTH: usize = 2;
+
+/// A `Settings::profiles` entry, with its own permits when it limits concurrency.
+struct ProfileSlot {
+    path: PathBuf,
+    profile: ScanProfile,
+    semaphore: Option<Semaphore>,
+}
+
+/// Running totals of everything extrapolated by `--sample` or `--estimate`.
+#[derive(Default)]
+struct SampleStats {
+    estimated_files: usize,
+    estimated_dirs: usize,
+    estimated_bytes: u64,
+    variance: f64,
 }
 
-impl Scanner {
-    pub fn new(settings: Settings, event_tx: EventSender) -> Self {
-        let max_io = settings.max_concurrent_io;
-        Self {
-            semaphore: Arc::new(Semaphore::new(max_io)),
-            event_tx,
-            visited: Arc::new(DashSet::new()),
-            progress: Arc::new(ProgressTracker::new()),
-            settings: Arc::new(settings),
-            errors: Arc::new(std::sync::Mutex::new(Vec::new())),
-            last_progress_time: Arc::new(AtomicU64::new(0)),
-        }
+/// Directories with at most this many subdirectories are read in full by
+/// `--estimate`; a sample of so few would say little about the rest.
+const ESTIMATE_MIN_DIRS: usize = 4;
+
+/// Fewest subdirectories `--estimate` draws, so the spread of the sample shows.
+const ESTIMATE_MIN_DRAWS: usize = 2;
+
+/// A subdirectory waiting to be read.
+struct Subdir {
+    /// Where it is read from: the resolved path when reached through a symlink.
+    path: PathBuf,
+    mtime: Option<SystemTime>,
+    /// Path and name of the symlink it was reached through, which the node keeps.
+    link: Option<(PathBuf, String)>,
+    /// Size of its own directory entry, which grows with what it lists.
+    weight: u64,
+}
+
+impl Subdir {
+    /// The path its node will have.
+    fn node_path(&self) -> &Path {
+        self.link.as_ref().map_or(&self.path, |(link_path, _)| link_path)
+    }
+
+    /// Give `node`, read from this subdirectory, the path and name of the link it was
+    /// reached through, so navigating through the link finds it.
+    fn keep_link(self, node: &mut Node) {
+        if let Some((link_path, link_name)) = self.link {
+            node.path = link_path;
+            node.name = link_name;
+            node.symlink_target = Some(self.path);
+        }
+    }
+}
+
+/// Which subdirectories of one directory `--estimate` descends into.
+struct DirSample {
+    /// Path of the node each draw picked, and the chance it had of being picked. A
+    /// subdirectory can be drawn more than once.
+    draws: Vec<(PathBuf, f64)>,
+    /// Whether each subdirectory was drawn at all.
+    picked: Vec<bool>,
+}
+
+impl DirSample {
+    /// The subdirectories that were drawn, each once.
+    fn pick(&self, subdirs: Vec<Subdir>) -> Vec<Subdir> {
+        subdirs
+            .into_iter()
+            .zip(&self.picked)
+            .filter_map(|(subdir, &picked)| picked.then_some(subdir))
+            .collect()
+    }
+
+    fn skipped(&self) -> usize {
+        self.picked.iter().filter(|&&picked| !picked).count()
+    }
+}
+
+impl ScanContext {
+    fn new(settings: Settings, event_tx: EventSender, mounts: MountTable) -> Self {
+        let max_io = settings.max_concurrent_io;
+        let network_max_io = settings.network_max_concurrent_io.max(1);
+        let mut profiles: Vec<ProfileSlot> = settings
+            .profiles
+            .iter()
+            .map(|(path, profile)| ProfileSlot {
+                path: path.clone(),
+                profile: profile.clone(),
+                semaphore: profile.max_concurrent_io.map(|max| Semaphore::new(max.max(1))),
+            })
+            .collect();
+        profiles.sort_by_key(|slot| std::cmp::Reverse(slot.path.components().count()));
+        let io_profile = settings
+            .profile_output
+            .is_some()
+            .then(|| Arc::new(IoProfile::new()));
+        Self {
+            semaphore: Semaphore::new(max_io),
+            profiles,
+            network_semaphore: Semaphore::new(network_max_io),
+            network_roots: RwLock::new(Vec::new()),
+            next_network_read: Mutex::new(Instant::now()),
+            event_tx,
+            visited: DashSet::new(),
+            progress: Arc::new(ProgressTracker::new()),
+            settings,
+            errors: Mutex::new(Vec::new()),
+            last_progress_time: AtomicU64::new(0),
+            io_profile,
+            callbacks: RwLock::new(Vec::new()),
+            sample_stats: Mutex::new(SampleStats::default()),
+            mounts,
+            skipped: Mutex::new(Vec::new()),
+            unscanned: Mutex::new(Vec::new()),
+            seen_inodes: DashSet::new(),
+            root_dev: Mutex::new(None),
+            cancel: CancelToken::new(),
+            paused: watch::Sender::new(false),
+            previous: OnceLock::new(),
+            cache: OnceLock::new(),
+            subtree_events: AtomicBool::new(false),
+            mft_volumes: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Deliver an event to registered callbacks, then to the channel.
+    fn emit(&self, event: Event) {
+        for callback in self.callbacks.read().unwrap().iter() {
+            callback(&event);
+        }
+        let _ = self.event_tx.send(event);
+    }
+
+    /// Send a copy of directory `node`, just finished at `depth`, when subtree events
+    /// are on and it is near enough to the top.
+    fn emit_subtree(&self, depth: usize, node: &Node) {
+        let near_top = (1..=SUBTREE_EVENT_DEPTH).contains(&depth);
+        if !near_top || !self.subtree_events.load(Ordering::Relaxed) {
+            return;
+        }
+        self.emit(Event::SubtreeCompleted {
+            node: Box::new(node.clone()),
+        });
+    }
+
+    /// Send a progress event for `path`, at most once every 100ms across all tasks.
+    fn maybe_emit_progress(&self, path: PathBuf) {
+        let now_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let last = self.last_progress_time.load(Ordering::Relaxed);
+        if now_ms.saturating_sub(last) >= 100 {
+            self.last_progress_time.store(now_ms, Ordering::Relaxed);
+            let snapshot = self.progress.snapshot();
+            self.emit(Event::Progress {
+                scanned: snapshot.files_scanned,
+                total_size: snapshot.total_size,
+                current_path: path,
+            });
+        }
+    }
+
+    /// Whether `path` is the mount point of a pseudo filesystem we shouldn't descend
+    /// into; such directories are recorded in the result's skipped list.
+    fn skip_pseudo_fs(&self, path: &Path) -> bool {
+        let Some(fs_type) = self.mounts.fs_type(path) else {
+            return false;
+        };
+        if !self.settings.pseudo_fs_types.iter().any(|t| t == fs_type) {
+            return false;
+        }
+        self.skipped.lock().unwrap().push(SkippedEntry {
+            path: path.to_path_buf(),
+            reason: format!("pseudo filesystem ({})", fs_type),
+        });
+        true
+    }
+
+    /// The most specific profile covering `path`.
+    fn profile_for(&self, path: &Path) -> Option<&ProfileSlot> {
+        self.profiles.iter().find(|slot| path.starts_with(&slot.path))
+    }
+
+    /// Whether `path` lies deeper below its profile's path than the profile's
+    /// `max_depth` allows.
+    fn beyond_profile_depth(&self, path: &Path) -> bool {
+        let Some(slot) = self.profile_for(path) else {
+            return false;
+        };
+        slot.profile.max_depth.is_some_and(|max_depth| {
+            path.components().count() - slot.path.components().count() >= max_depth
+        })
+    }
+
+    /// The exclude stack for a scan of `root`: the configured patterns, then those of
+    /// profiles whose paths lie above `root`, outermost first.
+    fn root_ignore(&self, root: &Path) -> Arc<IgnoreStack> {
+        let mut ignore = IgnoreStack::with_patterns(root, &self.settings.ignore_patterns);
+        for slot in self.profiles.iter().rev() {
+            if root.starts_with(&slot.path) && root != slot.path {
+                ignore = ignore.push(slot.path.clone(), &slot.profile.ignore_patterns.join("\n"));
+            }
+        }
+        ignore
+    }
+
+    /// With `throttle_network_fs`, throttle reads below `path`, a network filesystem of
+    /// type `fs_type`, unless it is already covered, and tell the UI.
+    fn enter_network_fs(&self, path: &Path, fs_type: String) {
+        if !self.settings.throttle_network_fs || self.on_network_fs(path) {
+            return;
+        }
+        self.network_roots.write().unwrap().push(path.to_path_buf());
+        self.emit(Event::NetworkFilesystem {
+            path: path.to_path_buf(),
+            fs_type,
+        });
+    }
+
+    fn on_network_fs(&self, path: &Path) -> bool {
+        self.network_roots.read().unwrap().iter().any(|root| path.starts_with(root))
+    }
+
+    /// Wait until at least `network_request_interval` has passed since the previous
+    /// network read started.
+    async fn pace_network_read(&self) {
+        let start = {
+            let mut next = self.next_network_read.lock().unwrap();
+            let start = (*next).max(Instant::now());
+            *next = start + self.settings.network_request_interval;
+            start
+        };
+        tokio::time::sleep_until(start.into()).await;
+    }
+
+    /// Whether the `max_duration` budget has run out, so no new directories should be
+    /// started. Time spent paused doesn't count.
+    fn out_of_time(&self) -> bool {
+        self.settings
+            .max_duration
+            .is_some_and(|budget| self.progress.elapsed() >= budget)
+    }
+
+    /// An empty node for a directory left unread because time ran out, recorded in the
+    /// result's unscanned list.
+    fn unscanned_dir(&self, path: PathBuf, name: String, mtime: Option<SystemTime>) -> Node {
+        self.unscanned.lock().unwrap().push(path.clone());
+        let mut node = Node::from_directory(path, name, Vec::new());
+        node.modified = mtime;
+        node
+    }
+
+    /// With `one_file_system`, whether directory `path` is on another device than the
+    /// scan root; such directories are recorded in the result's skipped list.
+    fn skip_other_fs(&self, path: &Path, metadata: &impl EntryStat) -> bool {
+        #[cfg(unix)]
+        {
+            if !self.settings.one_file_system {
+                return false;
+            }
+            match *self.root_dev.lock().unwrap() {
+                Some(root_dev) if metadata.dev() != root_dev => {}
+                _ => return false,
+            }
+            self.skipped.lock().unwrap().push(SkippedEntry {
+                path: path.to_path_buf(),
+                reason: "other filesystem".to_string(),
+            });
+            true
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (path, metadata);
+            false
+        }
+    }
+
+    /// Remember the device the current root is on; roots are scanned one at a time.
+    fn set_root_dev(&self, #[allow(unused_variables)] metadata: &impl EntryStat) {
+        #[cfg(unix)]
+        {
+            *self.root_dev.lock().unwrap() = Some(metadata.dev());
+        }
+    }
+
+    /// Size a file contributes to totals: its length, or 0 when it is another hard link
+    /// to data already counted and `dedupe_hardlinks` is on.
+    fn counted_size(&self, metadata: &impl EntryStat) -> u64 {
+        #[cfg(unix)]
+        if self.settings.dedupe_hardlinks
+            && metadata.nlink() > 1
+            && !self.seen_inodes.insert((metadata.dev(), metadata.ino()))
+        {
+            return 0;
+        }
+        metadata.size()
+    }
+
+    /// Whether `node`, a file reused from a previous scan of a directory on device
+    /// `dev`, still counts: not when it is another hard link to data already counted
+    /// and `dedupe_hardlinks` is on. As in [`ScanContext::counted_size`], its inode is
+    /// recorded as counted only while deduplication is on.
+    fn reused_file_counts(&self, node: &Node, dev: Option<u64>) -> bool {
+        #[cfg(unix)]
+        if let (true, Some(dev), Some(inode)) = (node.is_hardlinked(), dev, node.inode) {
+            return !self.settings.dedupe_hardlinks || self.seen_inodes.insert((dev, inode));
+        }
+        #[cfg(not(unix))]
+        let _ = (node, dev);
+        true
+    }
+
+    /// A file node sized by [`ScanContext::counted_size`].
+    fn file_node(&self, path: PathBuf, name: String, metadata: &impl EntryStat) -> Node {
+        let size = self.counted_size(metadata);
+        self.progress.increment_files();
+        self.progress.add_size(size);
+        let mut node = file_node(path, name, metadata);
+        if size == 0 {
+            node.size = 0;
+            node.size_on_disk = 0;
+        }
+        node
+    }
+
+    /// List the archive behind a file node as its children, holding a permit while it
+    /// is read. An archive that can't be read is recorded and kept as a plain file.
+    async fn peek_archive(&self, mut node: Node, kind: ArchiveKind) -> anyhow::Result<Node> {
+        let _permit = self.semaphore.acquire().await?;
+        let path = node.path.clone();
+        let listing =
+            tokio::task::spawn_blocking(move || archive::list_entries(&path, kind)).await?;
+        self.attach_archive_entries(&mut node, listing);
+        Ok(node)
+    }
+
+    /// Make an archive's `listing` the children of its node, or record why it failed.
+    fn attach_archive_entries(
+        &self,
+        node: &mut Node,
+        listing: anyhow::Result<Vec<archive::ArchiveEntry>>,
+    ) {
+        match listing {
+            Ok(entries) => node.children = archive::entry_nodes(&node.path, entries),
+            Err(e) => self.record_error(
+                node.path.clone(),
+                ScanErrorType::IoError,
+                format!("Cannot list archive: {}", e),
+            ),
+        }
+    }
+
+    /// The archive format of a file to list, when `peek_archives` is on.
+    fn archive_kind(&self, node: &Node) -> Option<ArchiveKind> {
+        if !self.settings.peek_archives || node.node_type != NodeType::File {
+            return None;
+        }
+        ArchiveKind::from_name(&node.name)
+    }
+
+    fn record_io_error(&self, path: PathBuf, e: &std::io::Error) {
+        let error_type = match e.kind() {
+            std::io::ErrorKind::PermissionDenied => ScanErrorType::PermissionDenied,
+            std::io::ErrorKind::NotFound => ScanErrorType::NotFound,
+            _ => ScanErrorType::IoError,
+        };
+        self.record_error(path.clone(), error_type, e.to_string());
+        self.emit(Event::ScanError {
+            path,
+            error: e.to_string(),
+        });
+    }
+
+    /// Batch I/O: read a directory and all entry metadata in a single spawn_blocking.
+    /// The semaphore permit is held only during I/O, then released before the caller
+    /// processes entries or waits for children.
+    async fn read_dir(
+        &self,
+        path: &Path,
+        sample: Option<f64>,
+    ) -> anyhow::Result<std::io::Result<DirBatch>> {
+        // Reads already running finish; new ones wait until the scan is resumed
+        self.paused.subscribe().wait_for(|paused| !paused).await?;
+        let profile_semaphore = self.profile_for(path).and_then(|slot| slot.semaphore.as_ref());
+        let _permit = if let Some(semaphore) = profile_semaphore {
+            semaphore.acquire().await?
+        } else if self.on_network_fs(path) {
+            let permit = self.network_semaphore.acquire().await?;
+            self.pace_network_read().await;
+            permit
+        } else {
+            self.semaphore.acquire().await?
+        };
+        let path_clone = path.to_path_buf();
+        let stat_self = self.settings.count_dir_overhead;
+        let detect_clones = self.settings.detect_clones;
+        let io_start = Instant::now();
+        let result = tokio::task::spawn_blocking(move || {
+            read_dir_with_clones(&path_clone, stat_self, sample, detect_clones)
+        })
+        .await?;
+        if let Some(profile) = &self.io_profile {
+            profile.record(path.to_path_buf(), io_start.elapsed());
+        }
+        Ok(result)
+    }
+
+    /// Record the errors of entries a directory read couldn't stat.
+    fn record_entry_errors(&self, errors: Vec<(PathBuf, String)>) {
+        for (path, message) in errors {
+            self.record_error(path.clone(), ScanErrorType::IoError, message.clone());
+            self.emit(Event::ScanError {
+                path,
+                error: message,
+            });
+        }
+    }
+
+    /// Stand-in for the files of `dir` that sampling left unstatted, not counting those
+    /// hidden-file and ignore rules leave out; `None` when there are none.
+    fn unsampled_node(
+        &self,
+        dir: &Path,
+        ignore: &IgnoreStack,
+        unsampled: &[(PathBuf, String)],
+        sampled_sizes: &[u64],
+    ) -> Option<Node> {
+        let count = unsampled
+            .iter()
+            .filter(|(path, name)| {
+                // Unstatted, so only a dotfile name can mark these as hidden
+                let hidden = self.settings.skip_hidden && name.starts_with('.');
+                !hidden && !self.excluded(ignore, path, false)
+            })
+            .count();
+        (count > 0).then(|| self.estimate_unsampled(dir, sampled_sizes, count))
+    }
+
+    /// Stand-in node for `unsampled` files of `dir`, sized by extrapolating the mean
+    /// of the files that were statted.
+    fn estimate_unsampled(&self, dir: &Path, sampled_sizes: &[u64], unsampled: usize) -> Node {
+        let n = sampled_sizes.len() as f64;
+        let mean = if sampled_sizes.is_empty() {
+            0.0
+        } else {
+            sampled_sizes.iter().sum::<u64>() as f64 / n
+        };
+        // Variance of the extrapolated total: u² · s²/n, with a finite-population correction
+        let variance = if sampled_sizes.len() > 1 {
+            let s2 = sampled_sizes
+                .iter()
+                .map(|&size| (size as f64 - mean).powi(2))
+                .sum::<f64>()
+                / (n - 1.0);
+            let u = unsampled as f64;
+            u * u * s2 / n * (u / (u + n))
+        } else {
+            0.0
+        };
+        let estimated = (mean * unsampled as f64).round() as u64;
+
+        {
+            let mut stats = self.sample_stats.lock().unwrap();
+            stats.estimated_files += unsampled;
+            stats.estimated_bytes += estimated;
+            stats.variance += variance;
+        }
+        self.progress
+            .files_scanned
+            .fetch_add(unsampled, Ordering::Relaxed);
+        self.progress.add_size(estimated);
+
+        let name = format!("\u{2248} {} more files (estimated)", unsampled);
+        let mut node = Node::summary(dir, name, estimated, unsampled);
+        node.estimated = true;
+        node
+    }
+
+    /// With `--estimate`, draw which of `dir`'s `subdirs` to descend into, each with a
+    /// chance proportional to its weight. `None` means all of them.
+    fn draw_subdirs(&self, dir: &Path, subdirs: &[Subdir]) -> Option<DirSample> {
+        let ratio = self.settings.estimate_ratio?;
+        if subdirs.len() <= ESTIMATE_MIN_DIRS {
+            return None;
+        }
+        let draws = ((subdirs.len() as f64 * ratio).ceil() as usize).max(ESTIMATE_MIN_DRAWS);
+        let weights: Vec<f64> = subdirs.iter().map(|s| s.weight.max(1) as f64).collect();
+        let total: f64 = weights.iter().sum();
+
+        let mut sample = DirSample {
+            draws: Vec::with_capacity(draws),
+            picked: vec![false; subdirs.len()],
+        };
+        for draw in 0..draws {
+            let mut target = draw_fraction(dir, draw) * total;
+            let index = weights
+                .iter()
+                .position(|weight| {
+                    target -= weight;
+                    target < 0.0
+                })
+                .unwrap_or(subdirs.len() - 1);
+            sample.picked[index] = true;
+            let chance = weights[index] / total;
+            sample.draws.push((subdirs[index].node_path().to_path_buf(), chance));
+        }
+        Some(sample)
+    }
+
+    /// Stand-in for the subdirectories of `dir` that `sample` skipped, sized by the
+    /// Hansen-Hurwitz estimate of all of them, from the `drawn` nodes, less what the
+    /// drawn ones hold. `None` when nothing was skipped or no draw could be read.
+    fn estimate_skipped(&self, dir: &Path, sample: &DirSample, drawn: &[Node]) -> Option<Node> {
+        let skipped = sample.skipped();
+        if skipped == 0 {
+            return None;
+        }
+        let totals = |node: &Node| {
+            [
+                node.size as f64,
+                node.size_on_disk as f64,
+                node.file_count as f64,
+                node.dir_count as f64,
+            ]
+        };
+        // Each draw stands for its subdirectory scaled up by how unlikely it was to be drawn
+        let scaled: Vec<[f64; 4]> = sample
+            .draws
+            .iter()
+            .filter_map(|(path, chance)| {
+                let node = drawn.iter().find(|node| &node.path == path)?;
+                Some(totals(node).map(|total| total / chance))
+            })
+            .collect();
+        if scaled.is_empty() {
+            return None;
+        }
+        let n = scaled.len() as f64;
+        let mut estimate = [0.0; 4];
+        for draw in &scaled {
+            for (sum, value) in estimate.iter_mut().zip(draw) {
+                *sum += value / n;
+            }
+        }
+        for node in drawn {
+            for (sum, measured) in estimate.iter_mut().zip(totals(node)) {
+                *sum = (*sum - measured).max(0.0);
+            }
+        }
+        // Variance of the estimated total size across draws
+        let variance = if scaled.len() > 1 {
+            let mean = scaled.iter().map(|draw| draw[0]).sum::<f64>() / n;
+            scaled.iter().map(|draw| (draw[0] - mean).powi(2)).sum::<f64>() / (n * (n - 1.0))
+        } else {
+            0.0
+        };
+        let [size, size_on_disk, files, dirs] = estimate.map(|value| value.round());
+
+        {
+            let mut stats = self.sample_stats.lock().unwrap();
+            stats.estimated_files += files as usize;
+            stats.estimated_dirs += skipped;
+            stats.estimated_bytes += size as u64;
+            stats.variance += variance;
+        }
+        self.progress
+            .files_scanned
+            .fetch_add(files as usize, Ordering::Relaxed);
+        self.progress.add_size(size as u64);
+
+        let name = format!("\u{2248} {} more directories (estimated)", skipped);
+        let mut node = Node::summary(dir, name, size as u64, files as usize);
+        node.size_on_disk = size_on_disk as u64;
+        node.dir_count = (dirs as usize).max(skipped);
+        node.estimated = true;
+        Some(node)
+    }
+
+    /// Stack the rules of `dir`'s ignore files on top of `ignore`: its `.disklensignore`
+    /// and, unless `gitignore` is off, `.git/info/exclude` and `.gitignore`.
+    /// `has_entry(name, is_dir)` tells which entries `dir` contains.
+    async fn push_ignore_files(
+        &self,
+        dir: &Path,
+        has_entry: impl Fn(&str, bool) -> bool,
+        ignore: Arc<IgnoreStack>,
+    ) -> Arc<IgnoreStack> {
+        let mut ignore = self.push_profile_patterns(dir, ignore);
+        for (path, git) in self.ignore_files(dir, has_entry) {
+            let contents = tokio::fs::read_to_string(&path).await;
+            ignore = self.push_ignore_file(dir, ignore, &path, git, contents);
+        }
+        ignore
+    }
+
+    /// The patterns of the profile for exactly `dir`, on top of `ignore`.
+    fn push_profile_patterns(&self, dir: &Path, ignore: Arc<IgnoreStack>) -> Arc<IgnoreStack> {
+        match self.profiles.iter().find(|slot| slot.path == dir) {
+            Some(slot) => ignore.push(dir.to_path_buf(), &slot.profile.ignore_patterns.join("\n")),
+            None => ignore,
+        }
+    }
+
+    /// The ignore files of `dir` to read, in the order their rules stack, each with
+    /// whether it is one of git's.
+    fn ignore_files(
+        &self,
+        dir: &Path,
+        has_entry: impl Fn(&str, bool) -> bool,
+    ) -> Vec<(PathBuf, bool)> {
+        let mut files = Vec::new();
+        if has_entry(IGNORE_FILE_NAME, false) {
+            files.push((dir.join(IGNORE_FILE_NAME), false));
+        }
+        if self.settings.gitignore == GitignoreMode::Off {
+            return files;
+        }
+        // Repository excludes rank below the `.gitignore` beside them, as in git
+        if has_entry(".git", true) {
+            files.push((dir.join(GIT_EXCLUDE_PATH), true));
+        }
+        if has_entry(GITIGNORE_FILE_NAME, false) {
+            files.push((dir.join(GITIGNORE_FILE_NAME), true));
+        }
+        files
+    }
+
+    /// Stack the rules read from the ignore file at `path` on top of `ignore`. A
+    /// missing `.git/info/exclude` is normal; other read failures are recorded.
+    fn push_ignore_file(
+        &self,
+        dir: &Path,
+        ignore: Arc<IgnoreStack>,
+        path: &Path,
+        git: bool,
+        contents: std::io::Result<String>,
+    ) -> Arc<IgnoreStack> {
+        match contents {
+            Ok(contents) if git => ignore.push_git(dir.to_path_buf(), &contents),
+            Ok(contents) => ignore.push(dir.to_path_buf(), &contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => ignore,
+            Err(e) => {
+                self.record_error(path.to_path_buf(), ScanErrorType::IoError, e.to_string());
+                ignore
+            }
+        }
+    }
+
+    /// Whether an entry is left out as hidden under `skip_hidden`.
+    fn skip_hidden<M: EntryStat>(&self, entry: &DirEntryData<M>) -> bool {
+        self.settings.skip_hidden && entry.metadata.is_hidden(&entry.name)
+    }
+
+    /// Whether `path` is left out of the scan by ignore rules.
+    fn excluded(&self, ignore: &IgnoreStack, path: &Path, is_dir: bool) -> bool {
+        ignore.is_ignored(path, is_dir)
+            || (self.settings.gitignore == GitignoreMode::Skip
+                && ignore.is_gitignored(path, is_dir))
+    }
+
+    /// Add the entries of `batch`, read from a directory past a depth limit, to
+    /// `totals`, queueing its subdirectories on `pending` with the `ignore` rules that
+    /// apply inside it. Symlinks count as the links themselves and are never followed.
+    fn tally_entries<M: EntryStat>(
+        &self,
+        batch: DirBatch<M>,
+        ignore: &Arc<IgnoreStack>,
+        totals: &mut DirTotals,
+        pending: &mut Vec<(PathBuf, Arc<IgnoreStack>)>,
+    ) {
+        self.progress.add_size(batch.overhead);
+        totals.size += batch.overhead;
+        totals.size_on_disk += batch.overhead;
+        self.record_entry_errors(batch.errors);
+
+        for entry in batch.entries {
+            if self.cancel.is_cancelled() {
+                break;
+            }
+            if self.skip_hidden(&entry) {
+                continue;
+            }
+            let metadata = &entry.metadata;
+            if self.excluded(ignore, &entry.path, metadata.is_dir()) {
+                continue;
+            }
+            if metadata.is_link() {
+                totals.size += metadata.size();
+                totals.size_on_disk += metadata.allocated_size(&entry.path);
+            } else if metadata.is_dir() {
+                if self.skip_other_fs(&entry.path, metadata)
+                    || self.skip_pseudo_fs(&entry.path)
+                    || !self.visited.insert(entry.path.clone())
+                {
+                    continue;
+                }
+                self.progress.increment_dirs();
+                totals.dirs += 1;
+                pending.push((entry.path, Arc::clone(ignore)));
+            } else if metadata.is_file() {
+                let size = self.counted_size(metadata);
+                self.progress.increment_files();
+                self.progress.add_size(size);
+                totals.files += 1;
+                totals.size += size;
+                if size > 0 {
+                    totals.size_on_disk += metadata.allocated_size(&entry.path);
+                }
+            }
+        }
+    }
+
+    /// In `GitignoreMode::Tag`, flag the nodes git ignores along with their subtrees.
+    fn tag_gitignored(&self, ignore: &IgnoreStack, nodes: &mut [Node]) {
+        if self.settings.gitignore != GitignoreMode::Tag {
+            return;
+        }
+        for node in nodes {
+            let is_dir = node.node_type == NodeType::Directory;
+            if !node.gitignored && ignore.is_gitignored(&node.path, is_dir) {
+                node.mark_gitignored();
+            }
+        }
+    }
+
+    /// The previous scan's node for directory `path`, or else the cache's entry for
+    /// it, if the directory's mtime still matches; subdirectories come without their
+    /// contents. Directories holding followed symlinks, estimates or stand-ins, those
+    /// summed up past a depth limit, those with hard links whose size went to another
+    /// link, and sampled scans are always read again.
+    async fn unchanged_dir(&self, path: &Path, mtime: Option<SystemTime>) -> Option<Node> {
+        let settings = &self.settings;
+        let mtime = mtime?;
+        if settings.sample_ratio.is_some() || settings.estimate_ratio.is_some() {
+            return None;
+        }
+        let node = match self.previous_dir(path).filter(|node| node.modified == Some(mtime)) {
+            Some(node) => node.shallow_copy(),
+            None => {
+                let (cache, tree_settings) = self.cache.get()?;
+                cache.load_dir(path, mtime, tree_settings).await?
+            }
+        };
+        // Only the totals of a directory past a depth limit were kept
+        let aggregated = node.children.is_empty() && (node.file_count > 0 || node.dir_count > 1);
+        let reusable = node.node_type == NodeType::Directory
+            && node.modified == Some(mtime)
+            && !node.estimated
+            && !aggregated
+            && node.children.iter().all(|c| {
+                let followed = c.node_type != NodeType::Symlink && c.symlink_target.is_some();
+                // A link counted elsewhere last time has lost its size
+                let uncounted_link = settings.dedupe_hardlinks && c.is_hardlinked() && c.size == 0;
+                !followed && c.node_type != NodeType::Summary && !uncounted_link
+            });
+        reusable.then_some(node)
+    }
+
+    /// The node of directory `path` in the previous scan's tree.
+    fn previous_dir(&self, path: &Path) -> Option<&Node> {
+        let mut node = self.previous.get()?;
+        if !path.starts_with(&node.path) {
+            return None;
+        }
+        while node.path != path {
+            node = node
+                .children
+                .iter()
+                .find(|c| c.node_type == NodeType::Directory && path.starts_with(&c.path))?;
+        }
+        Some(node)
+    }
+
+    /// The result of a scan that produced `root_node`, announced as completed.
+    fn finish(
+        &self,
+        scan_path: PathBuf,
+        root_node: Node,
+        filesystem: Option<FilesystemType>,
+    ) -> ScanResult {
+        let sampling = match (self.settings.sample_ratio, self.settings.estimate_ratio) {
+            (Some(ratio), _) => Some((ratio, SampleUnit::Files)),
+            (None, Some(ratio)) => Some((ratio, SampleUnit::Directories)),
+            (None, None) => None,
+        };
+        let estimate = sampling.map(|(ratio, unit)| {
+            let stats = self.sample_stats.lock().unwrap();
+            SampleEstimate {
+                ratio,
+                unit,
+                estimated_files: stats.estimated_files,
+                estimated_dirs: stats.estimated_dirs,
+                estimated_bytes: stats.estimated_bytes,
+                margin_bytes: (1.96 * stats.variance.sqrt()).round() as u64,
+            }
+        });
+
+        let mut result = ScanResult {
+            total_size: root_node.size,
+            total_files: root_node.file_count,
+            total_dirs: root_node.dir_count,
+            scan_duration: self.progress.elapsed(),
+            errors: self.errors.lock().unwrap().clone(),
+            timestamp: SystemTime::now(),
+            scan_path,
+            root: root_node,
+            filesystem,
+            estimate,
+            skipped: self.skipped.lock().unwrap().clone(),
+            size_mode: SizeMode::Apparent,
+            cancelled: self.cancel.is_cancelled(),
+            unscanned: self.unscanned.lock().unwrap().clone(),
+            tree_settings: self.settings.tree_settings(),
+        };
+        result.set_size_mode(self.settings.size_mode);
+        result.attribute_errors();
+
+        self.emit(Event::ScanCompleted {
+            total_files: result.total_files,
+            total_size: result.total_size,
+            duration_ms: result.scan_duration.as_millis() as u64,
+        });
+
+        result
+    }
+
+    fn record_error(&self, path: PathBuf, error_type: ScanErrorType, message: String) {
+        self.errors.lock().unwrap().push(ScanError {
+            path,
+            error_type,
+            message,
+        });
+        self.progress.increment_errors();
+    }
+}
+
+impl Scanner {
+    pub fn new(settings: Settings, event_tx: EventSender) -> Self {
+        Self::with_mounts(settings, event_tx, MountTable::load())
+    }
+
+    /// Like [`Scanner::new`], but consults `mounts` instead of the system mount table
+    /// when deciding which directories are pseudo filesystems.
+    pub fn with_mounts(settings: Settings, event_tx: EventSender, mounts: MountTable) -> Self {
+        Self {
+            ctx: Arc::new(ScanContext::new(settings, event_tx, mounts)),
+        }
+    }
+
+    /// Register a callback invoked for every event this scanner emits, alongside
+    /// the channel passed to [`Scanner::new`].
+    ///
+    /// The callback runs inline on whichever tokio worker produced the event, so it
+    /// may be called concurrently from many scan tasks and must be cheap and
+    /// non-blocking; hand heavy work off to another thread or channel.
+    pub fn on_event(self, callback: impl Fn(&Event) + Send + Sync + 'static) -> Self {
+        self.ctx.callbacks.write().unwrap().push(Arc::new(callback));
+        self
+    }
+
+    /// Send `Event::SubtreeCompleted` as directories in the top levels of the tree
+    /// finish, so a viewer can show the tree while the scan is still running.
+    pub fn with_subtree_events(self) -> Self {
+        self.ctx.subtree_events.store(true, Ordering::Relaxed);
+        self
+    }
+
+    /// Token that stops this scanner's scan early; the scan then returns a partial
+    /// result flagged as `cancelled`.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.ctx.cancel.clone()
+    }
+
+    /// Rescan incrementally against `previous`: a directory whose mtime hasn't changed
+    /// keeps its files from `previous` without being read, and only its subdirectories
+    /// are checked again.
+    ///
+    /// A directory's mtime only changes when entries are added, removed or renamed, so
+    /// a file rewritten in place inside an unchanged directory keeps its old size.
+    /// Cancelled and truncated results are ignored, since their trees are incomplete,
+    /// and so are results scanned with other
+    /// [`TreeSettings`](crate::config::settings::TreeSettings).
+    pub fn with_previous(self, mut previous: ScanResult) -> Self {
+        if !previous.is_partial() && previous.tree_settings == self.ctx.settings.tree_settings() {
+            previous.set_size_mode(SizeMode::Apparent);
+            let _ = self.ctx.previous.set(previous.root);
+        }
+        self
+    }
+
+    /// Also reuse the directory entries that earlier scans of any root saved in
+    /// `cache`, as with [`Scanner::with_previous`], so a scan of `/home` builds on
+    /// earlier scans of `/home/alice` and of `/` alike. Only entries saved with the
+    /// same [`TreeSettings`] are used.
+    pub fn with_cache(self, cache: Cache) -> Self {
+        let tree_settings = self.ctx.settings.tree_settings();
+        let _ = self.ctx.cache.set((cache, tree_settings));
+        self
+    }
+
+    /// Handle for pausing, resuming and cancelling this scanner from another task.
+    pub fn control(&self) -> ScanControl {
+        ScanControl {
+            ctx: Arc::clone(&self.ctx),
+        }
+    }
+
+    pub fn progress(&self) -> &Arc<ProgressTracker> {
+        &self.ctx.progress
+    }
+
+    /// Per-directory I/O timings, recorded only when `Settings::profile_output` is set.
+    pub fn io_profile(&self) -> Option<&Arc<IoProfile>> {
+        self.ctx.io_profile.as_ref()
+    }
+
+    /// Scan `root` keeping only running totals and the `n` largest files.
+    ///
+    /// No directory tree is built, so memory use does not grow with the number of
+    /// files; use this for volumes too large to hold as a `Node` tree. Directories
+    /// past a depth limit are summed up as in [`Scanner::scan`], and their files count
+    /// towards the totals without being ranked.
+    pub async fn scan_top_n(&self, root: PathBuf, n: usize) -> anyhow::Result<TopNResult> {
+        let ctx = &self.ctx;
+
+        let root_meta = match tokio::fs::metadata(&root).await {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                anyhow::bail!("path does not exist: {}", root.display())
+            }
+            Err(e) => anyhow::bail!("cannot access {}: {}", root.display(), e),
+        };
+
+        ctx.emit(Event::ScanStarted { path: root.clone() });
+        ctx.set_root_dev(&root_meta);
+
+        let top = Arc::new(Mutex::new(TopFiles::new(n)));
+        if root_meta.is_dir() {
+            let queue = Arc::new(TopNQueue {
+                pending: Mutex::new(vec![(root.clone(), 0, ctx.root_ignore(&root))]),
+                busy: AtomicUsize::new(0),
+                wake: Notify::new(),
+            });
+            let workers: Vec<_> = (0..ctx.settings.max_concurrent_io.max(1))
+                .map(|_| {
+                    let (queue, ctx, top) = (Arc::clone(&queue), Arc::clone(ctx), Arc::clone(&top));
+                    tokio::spawn(async move { queue.work(&ctx, &top).await })
+                })
+                .collect();
+            for worker in workers {
+                worker.await?;
+            }
+        } else if root_meta.is_file() {
+            ctx.progress.increment_files();
+            ctx.progress.add_size(root_meta.len());
+            top.lock().unwrap().offer(root_meta.len(), root.clone());
+        }
+
+        let snapshot = ctx.progress.snapshot();
+        let top = Arc::try_unwrap(top)
+            .map_err(|_| anyhow::anyhow!("scan tasks still running"))?
+            .into_inner()
+            .unwrap();
+
+        let result = TopNResult {
+            scan_path: root,
+            files: top.into_sorted(),
+            total_size: snapshot.total_size,
+            total_files: snapshot.files_scanned,
+            total_dirs: snapshot.dirs_scanned,
+            scan_duration: ctx.progress.elapsed(),
+            errors: ctx.errors.lock().unwrap().clone(),
+        };
+
+        ctx.emit(Event::ScanCompleted {
+            total_files: result.total_files,
+            total_size: result.total_size,
+            duration_ms: result.scan_duration.as_millis() as u64,
+        });
+
+        Ok(result)
+    }
+
+    pub async fn scan(&self, root: PathBuf) -> anyhow::Result<ScanResult> {
+        self.scan_roots(vec![root]).await
+    }
+
+    /// Scan several paths into one result. With more than one, each root becomes a
+    /// child of a virtual root node whose path is empty, and so is `scan_path`.
+    pub async fn scan_roots(&self, roots: Vec<PathBuf>) -> anyhow::Result<ScanResult> {
+        let ctx = &self.ctx;
+
+        let (scan_path, root_node) = match roots.as_slice() {
+            [] => anyhow::bail!("no paths to scan"),
+            [root] => (root.clone(), self.scan_root_node(root.clone()).await?),
+            _ => {
+                let mut children = Vec::with_capacity(roots.len());
+                for root in &roots {
+                    children.push(self.scan_root_node(root.clone()).await?);
+                }
+                (PathBuf::new(), virtual_root(children))
+            }
+        };
+
+        let filesystem = {
+            let roots = roots.clone();
+            tokio::task::spawn_blocking(move || {
+                roots.iter().find_map(|root| detect_filesystem_type(root))
+            })
+            .await?
+        };
+
+        Ok(ctx.finish(scan_path, root_node, filesystem))
+    }
+
+    async fn scan_root_node(&self, root: PathBuf) -> anyhow::Result<Node> {
+        let ctx = &self.ctx;
+
+        let root_meta = match tokio::fs::symlink_metadata(&root).await {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                anyhow::bail!("path does not exist: {}", root.display())
+            }
+            Err(e) => anyhow::bail!("cannot access {}: {}", root.display(), e),
+        };
+
+        ctx.emit(Event::ScanStarted { path: root.clone() });
+
+        let root_node = if root_meta.file_type().is_symlink() && !ctx.settings.follow_symlinks {
+            symlink_node(root.clone(), node_name(&root), &root_meta)
+        } else {
+            let meta = if root_meta.file_type().is_symlink() {
+                match tokio::fs::metadata(&root).await {
+                    Ok(meta) => meta,
+                    Err(e) => anyhow::bail!("cannot resolve symlink {}: {}", root.display(), e),
+                }
+            } else {
+                root_meta
+            };
+
+            if meta.is_dir() {
+                ctx.set_root_dev(&meta);
+                if ctx.settings.throttle_network_fs {
+                    let (path, scan) = (root.clone(), Arc::clone(ctx));
+                    let fs_type =
+                        tokio::task::spawn_blocking(move || detect_network_fs(&path, &scan.mounts))
+                            .await?;
+                    if let Some(fs_type) = fs_type {
+                        ctx.enter_network_fs(&root, fs_type);
+                    }
+                }
+                let ignore = ctx.root_ignore(&root);
+                let mtime = meta.modified().ok();
+                scan_directory(root.clone(), 0, ignore, Arc::clone(ctx), mtime).await?
+            } else if meta.is_file() {
+                ctx.progress.increment_files();
+                ctx.progress.add_size(meta.len());
+                file_node(root.clone(), node_name(&root), &meta)
+            } else {
+                other_node(root.clone(), node_name(&root), &meta)
+            }
+        };
+
+        Ok(root_node)
+    }
+}
+
+/// Pauses, resumes or cancels a running scan; see [`Scanner::control`].
+#[derive(Clone)]
+pub struct ScanControl {
+    ctx: Arc<ScanContext>,
+}
+
+impl ScanControl {
+    pub fn pause(&self) {
+        if self.ctx.paused.send_replace(true) {
+            return;
+        }
+        self.ctx.progress.pause();
+        self.ctx.emit(Event::ScanPaused);
+    }
+
+    pub fn resume(&self) {
+        if !self.ctx.paused.send_replace(false) {
+            return;
+        }
+        self.ctx.progress.resume();
+        self.ctx.emit(Event::ScanResumed);
+    }
+
+    pub fn toggle_pause(&self) {
+        if self.is_paused() {
+            self.resume();
+        } else {
+            self.pause();
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.ctx.paused.borrow()
+    }
+
+    /// Cancel the scan, resuming it first so paused tasks can wind down.
+    pub fn cancel(&self) {
+        self.ctx.cancel.cancel();
+        self.resume();
+    }
+}
+
+/// Collected directory entry from batch I/O.
+struct DirEntryData<M = std::fs::Metadata> {
+    path: PathBuf,
+    name: String,
+    metadata: M,
+    /// Bytes shared with clones, when `detect_clones` is on.
+    shared: u64,
+}
+
+/// Result of reading one directory.
+struct DirBatch<M = std::fs::Metadata> {
+    entries: Vec<DirEntryData<M>>,
+    /// Per-entry `(path, message)` errors.
+    errors: Vec<(PathBuf, String)>,
+    /// The directory's own metadata size, when it was requested.
+    overhead: u64,
+    /// Files left unstatted by sampling, as `(path, name)`.
+    unsampled: Vec<(PathBuf, String)>,
+}
+
+/// What a scan reads from an entry's metadata, so entries statted by other means than
+/// `std::fs` (the io_uring backend's `statx` batches) build the same nodes.
+trait EntryStat {
+    fn is_dir(&self) -> bool;
+    fn is_file(&self) -> bool;
+    /// See [`is_link`].
+    fn is_link(&self) -> bool;
+    /// Apparent length in bytes.
+    fn size(&self) -> u64;
+    fn mtime(&self) -> Option<SystemTime>;
+    /// See [`allocated_size`].
+    fn allocated_size(&self, path: &Path) -> u64;
+    /// See [`is_hidden`].
+    fn is_hidden(&self, name: &str) -> bool;
+    #[cfg(unix)]
+    fn dev(&self) -> u64;
+    #[cfg(unix)]
+    fn ino(&self) -> u64;
+    #[cfg(unix)]
+    fn nlink(&self) -> u64;
+    #[cfg(unix)]
+    fn uid(&self) -> u32;
+    #[cfg(unix)]
+    fn gid(&self) -> u32;
+}
+
+impl EntryStat for std::fs::Metadata {
+    fn is_dir(&self) -> bool {
+        std::fs::Metadata::is_dir(self)
+    }
+
+    fn is_file(&self) -> bool {
+        std::fs::Metadata::is_file(self)
+    }
+
+    fn is_link(&self) -> bool {
+        is_link(self)
     }
 
-    pub fn progress(&self) -> &Arc<ProgressTracker> {
-        &self.progress
+    fn size(&self) -> u64 {
+        self.len()
     }
 
-    pub async fn scan(&self, root: PathBuf) -> anyhow::Result<ScanResult> {
-        let _ = self.event_tx.send(Event::ScanStarted { path: root.clone() });
-
-        let root_node = scan_directory(
-            root.clone(),
-            0,
-            Arc::clone(&self.semaphore),
-            self.event_tx.clone(),
-            Arc::clone(&self.visited),
-            Arc::clone(&self.progress),
-            Arc::clone(&self.settings),
-            Arc::clone(&self.errors),
-            Arc::clone(&self.last_progress_time),
-        )
-        .await?;
+    fn mtime(&self) -> Option<SystemTime> {
+        self.modified().ok()
+    }
 
-        let elapsed = self.progress.elapsed();
-        let errors = self.errors.lock().unwrap().clone();
+    fn allocated_size(&self, path: &Path) -> u64 {
+        allocated_size(path, self)
+    }
 
-        let result = ScanResult {
-            total_size: root_node.size,
-            total_files: root_node.file_count,
-            total_dirs: root_node.dir_count,
-            scan_duration: elapsed,
-            errors,
-            timestamp: SystemTime::now(),
-            scan_path: root,
-            root: root_node,
-        };
+    fn is_hidden(&self, name: &str) -> bool {
+        is_hidden(name, self)
+    }
 
-        let _ = self.event_tx.send(Event::ScanCompleted {
-            total_files: result.total_files,
-            total_size: result.total_size,
-            duration_ms: result.scan_duration.as_millis() as u64,
-        });
+    #[cfg(unix)]
+    fn dev(&self) -> u64 {
+        std::os::unix::fs::MetadataExt::dev(self)
+    }
 
-        Ok(result)
+    #[cfg(unix)]
+    fn ino(&self) -> u64 {
+        std::os::unix::fs::MetadataExt::ino(self)
     }
-}
 
-/// Collected directory entry from batch I/O.
-struct DirEntryData {
-    path: PathBuf,
-    name: String,
-    metadata: std::fs::Metadata,
+    #[cfg(unix)]
+    fn nlink(&self) -> u64 {
+        std::os::unix::fs::MetadataExt::nlink(self)
+    }
+
+    #[cfg(unix)]
+    fn uid(&self) -> u32 {
+        std::os::unix::fs::MetadataExt::uid(self)
+    }
+
+    #[cfg(unix)]
+    fn gid(&self) -> u32 {
+        std::os::unix::fs::MetadataExt::gid(self)
+    }
 }
 
+/// Directories with at most this many files are always statted in full, even when sampling.
+pub const SAMPLE_MIN_FILES: usize = 100;
+
 /// Read all entries and their metadata from a directory in one blocking call.
-/// Returns (entries, entry_errors) or an error if the directory itself can't be read.
+/// Returns an error if the directory itself can't be read.
+///
+/// With `sample` set, directories holding more than `SAMPLE_MIN_FILES` files only
+/// stat roughly that fraction of them; the rest are returned in `unsampled`.
 fn read_dir_batch(
     dir_path: &std::path::Path,
-) -> std::io::Result<(Vec<DirEntryData>, Vec<(PathBuf, String)>)> {
-    let mut entries = Vec::new();
-    let mut errors = Vec::new();
+    stat_self: bool,
+    sample: Option<f64>,
+) -> std::io::Result<DirBatch> {
+    let mut batch = DirBatch {
+        entries: Vec::new(),
+        errors: Vec::new(),
+        overhead: 0,
+        unsampled: Vec::new(),
+    };
+
+    if stat_self {
+        batch.overhead = std::fs::metadata(dir_path)?.len();
+    }
+
+    // Regular files whose stat is deferred until we know how many there are
+    let mut files = Vec::new();
 
     for entry_result in std::fs::read_dir(dir_path)? {
         match entry_result {
             Ok(entry) => {
                 let entry_path = entry.path();
                 let entry_name = entry.file_name().to_string_lossy().to_string();
-                match std::fs::symlink_metadata(&entry_path) {
-                    Ok(meta) => entries.push(DirEntryData {
-                        path: entry_path,
-                        name: entry_name,
-                        metadata: meta,
-                    }),
-                    Err(e) => errors.push((entry_path, e.to_string())),
+                let is_plain_file = entry.file_type().is_ok_and(|t| t.is_file());
+                if sample.is_some() && is_plain_file && entry_name != IGNORE_FILE_NAME {
+                    files.push((entry_path, entry_name));
+                    continue;
                 }
+                stat_entry(&mut batch, entry_path, entry_name);
             }
             Err(e) => {
-                errors.push((dir_path.to_path_buf(), e.to_string()));
+                batch.errors.push((dir_path.to_path_buf(), e.to_string()));
+            }
+        }
+    }
+
+    let ratio = sample.unwrap_or(1.0);
+    if files.len() > SAMPLE_MIN_FILES {
+        let (mut picked, rest): (Vec<_>, Vec<_>) = files
+            .into_iter()
+            .partition(|(_, name)| sample_fraction(name) < ratio);
+        if picked.is_empty() {
+            // Always stat at least one file to have something to extrapolate from
+            picked = rest.iter().take(1).cloned().collect();
+            batch.unsampled = rest.into_iter().skip(1).collect();
+        } else {
+            batch.unsampled = rest;
+        }
+        files = picked;
+    }
+    for (path, name) in files {
+        stat_entry(&mut batch, path, name);
+    }
+
+    Ok(batch)
+}
+
+/// [`read_dir_batch`], plus each file's bytes shared with clones when `detect_clones`
+/// is set.
+fn read_dir_with_clones(
+    dir_path: &Path,
+    stat_self: bool,
+    sample: Option<f64>,
+    detect_clones: bool,
+) -> std::io::Result<DirBatch> {
+    let mut batch = read_dir_batch(dir_path, stat_self, sample)?;
+    if detect_clones {
+        add_shared_bytes(&mut batch);
+    }
+    Ok(batch)
+}
+
+/// Fill in the bytes each file of `batch` shares with clones.
+fn add_shared_bytes<M: EntryStat>(batch: &mut DirBatch<M>) {
+    for entry in batch.entries.iter_mut().filter(|e| e.metadata.is_file()) {
+        entry.shared = clones::shared_bytes(&entry.path).unwrap_or(0);
+    }
+}
+
+fn stat_entry(batch: &mut DirBatch, path: PathBuf, name: String) {
+    match std::fs::symlink_metadata(&path) {
+        Ok(metadata) => batch.entries.push(DirEntryData {
+            path,
+            name,
+            metadata,
+            shared: 0,
+        }),
+        Err(e) => batch.errors.push((path, e.to_string())),
+    }
+}
+
+/// Stable pseudo-random position in `[0, 1)` for a file name, so repeated sampled
+/// scans pick the same files.
+fn sample_fraction(name: &str) -> f64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    name.hash(&mut hasher);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Stable pseudo-random position in `[0, 1)` for draw number `draw` among the
+/// subdirectories of `dir`, so repeated estimates of a tree descend the same way.
+fn draw_fraction(dir: &Path, draw: usize) -> f64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    dir.hash(&mut hasher);
+    draw.hash(&mut hasher);
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// The node holding the roots of a multi-root scan, with an empty path.
+fn virtual_root(children: Vec<Node>) -> Node {
+    let name = format!("{} roots", children.len());
+    let mut node = Node::from_directory(PathBuf::new(), name, children);
+    // The virtual root isn't a directory on disk
+    node.dir_count -= 1;
+    node
+}
+
+fn node_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+fn file_node(path: PathBuf, name: String, metadata: &impl EntryStat) -> Node {
+    #[cfg(unix)]
+    let inode = Some(metadata.ino());
+    #[cfg(not(unix))]
+    let inode = None;
+    let size_on_disk = metadata.allocated_size(&path);
+    let category = FileCategory::from_path(&path);
+    let mut node = Node::from_file(path, name, metadata.size(), metadata.mtime(), inode);
+    node.size_on_disk = size_on_disk;
+    node.category = Some(category);
+    #[cfg(unix)]
+    {
+        node.nlink = Some(metadata.nlink());
+        node.uid = Some(metadata.uid());
+        node.gid = Some(metadata.gid());
+    }
+    node
+}
+
+/// Dotfiles on unix; on Windows, entries with the hidden attribute.
+fn is_hidden(name: &str, metadata: &std::fs::Metadata) -> bool {
+    #[cfg(windows)]
+    {
+        let _ = name;
+        super::attributes::is_hidden(metadata)
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = metadata;
+        name.starts_with('.')
+    }
+}
+
+/// Bytes actually allocated for an entry: `st_blocks` is always in 512-byte units.
+/// On Windows, compressed and sparse files ask NTFS for their on-disk size. Elsewhere,
+/// and for other Windows files, the apparent length is the best available approximation.
+fn allocated_size(path: &Path, metadata: &std::fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        let _ = path;
+        std::os::unix::fs::MetadataExt::blocks(metadata) * 512
+    }
+    #[cfg(windows)]
+    {
+        // Checking the attributes first saves a system call for every ordinary file
+        if super::attributes::is_compressed_or_sparse(metadata) {
+            if let Some(size) = super::attributes::compressed_size(path) {
+                return size;
             }
         }
+        metadata.len()
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = path;
+        metadata.len()
+    }
+}
+
+/// Symlinks, and on Windows also directory reparse points such as junctions, which are
+/// treated like symlinks: listed, and only descended into with `follow_symlinks`.
+fn is_link(metadata: &std::fs::Metadata) -> bool {
+    #[cfg(windows)]
+    if metadata.is_dir() && super::attributes::is_reparse_point(metadata) {
+        return true;
     }
+    metadata.file_type().is_symlink()
+}
+
+/// An unfollowed symlink, sized by the link itself rather than its target.
+fn symlink_node(path: PathBuf, name: String, metadata: &impl EntryStat) -> Node {
+    let size_on_disk = metadata.allocated_size(&path);
+    let symlink_target = std::fs::read_link(&path).ok();
+    Node {
+        path,
+        name,
+        size: metadata.size(),
+        size_on_disk,
+        node_type: NodeType::Symlink,
+        children: Vec::new(),
+        file_count: 0,
+        dir_count: 0,
+        modified: metadata.mtime(),
+        newest_modified: None,
+        #[cfg(unix)]
+        inode: Some(metadata.ino()),
+        #[cfg(unix)]
+        nlink: Some(metadata.nlink()),
+        #[cfg(unix)]
+        uid: Some(metadata.uid()),
+        #[cfg(unix)]
+        gid: Some(metadata.gid()),
+        symlink_target,
+        gitignored: false,
+        shared_size: 0,
+        uncompressed_size: None,
+        category: None,
+        estimated: false,
+        junk: None,
+        error_count: 0,
+    }
+}
 
-    Ok((entries, errors))
+/// Sockets, FIFOs, devices and the like: listed but never sized.
+fn other_node(path: PathBuf, name: String, metadata: &impl EntryStat) -> Node {
+    Node {
+        path,
+        name,
+        size: 0,
+        size_on_disk: 0,
+        node_type: NodeType::Other,
+        children: Vec::new(),
+        file_count: 0,
+        dir_count: 0,
+        modified: metadata.mtime(),
+        newest_modified: None,
+        #[cfg(unix)]
+        inode: Some(metadata.ino()),
+        #[cfg(unix)]
+        nlink: Some(metadata.nlink()),
+        #[cfg(unix)]
+        uid: Some(metadata.uid()),
+        #[cfg(unix)]
+        gid: Some(metadata.gid()),
+        symlink_target: None,
+        gitignored: false,
+        shared_size: 0,
+        uncompressed_size: None,
+        category: None,
+        estimated: false,
+        junk: None,
+        error_count: 0,
+    }
 }
 
 fn scan_directory(
     path: PathBuf,
     depth: usize,
-    semaphore: Arc<Semaphore>,
-    event_tx: EventSender,
-    visited: Arc<DashSet<PathBuf>>,
-    progress: Arc<ProgressTracker>,
-    settings: Arc<Settings>,
-    errors: Arc<std::sync::Mutex<Vec<ScanError>>>,
-    last_progress_time: Arc<AtomicU64>,
+    ignore: Arc<IgnoreStack>,
+    ctx: Arc<ScanContext>,
+    mtime: Option<SystemTime>,
 ) -> Pin<Box<dyn Future<Output = anyhow::Result<Node>> + Send>> {
     Box::pin(async move {
+        let settings = &ctx.settings;
+        let progress = &ctx.progress;
+
         progress.increment_dirs();
+        progress.set_current_path(&path);
 
-        if let Some(max_depth) = settings.max_depth {
-            if depth >= max_depth {
-                let name = path
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_else(|| path.to_string_lossy().to_string());
-                return Ok(Node::from_directory(path, name, Vec::new()));
-            }
+        // A directory left unread still sorts by when it last changed
+        let unread_dir = |path: PathBuf| {
+            let name = node_name(&path);
+            let mut node = Node::from_directory(path, name, Vec::new());
+            node.modified = mtime;
+            node
+        };
+
+        if ctx.cancel.is_cancelled() {
+            return Ok(unread_dir(path));
         }
 
-        // Batch I/O: read directory and all entry metadata in a single spawn_blocking.
-        // Semaphore permit is held only during I/O, then released before processing.
-        let io_result = {
-            let _permit = semaphore.acquire().await?;
-            let path_clone = path.clone();
-            tokio::task::spawn_blocking(move || read_dir_batch(&path_clone)).await?
-            // _permit drops here — released before processing entries or waiting for children
-        };
+        if let Some(fs_type) = ctx.mounts.network_fs_type(&path) {
+            ctx.enter_network_fs(&path, fs_type.to_string());
+        }
+
+        let past_max_depth = settings.max_depth.is_some_and(|max_depth| depth >= max_depth);
+        if past_max_depth || ctx.beyond_profile_depth(&path) {
+            return aggregate_directory(path, mtime, ignore, &ctx).await;
+        }
+
+        if let Some(previous) = ctx.unchanged_dir(&path, mtime).await {
+            return reuse_directory(path, &previous, mtime, depth, ignore, &ctx).await;
+        }
+
+        let io_result = ctx.read_dir(&path, settings.sample_ratio).await?;
 
-        let (entries, entry_errors) = match io_result {
-            Ok(result) => result,
+        let DirBatch {
+            entries,
+            errors: entry_errors,
+            overhead,
+            unsampled,
+        } = match io_result {
+            Ok(batch) => batch,
             Err(e) => {
-                let error_type = match e.kind() {
-                    std::io::ErrorKind::PermissionDenied => ScanErrorType::PermissionDenied,
-                    std::io::ErrorKind::NotFound => ScanErrorType::NotFound,
-                    _ => ScanErrorType::IoError,
-                };
-                errors.lock().unwrap().push(ScanError {
-                    path: path.clone(),
-                    error_type,
-                    message: e.to_string(),
-                });
-                progress.increment_errors();
-                let _ = event_tx.send(Event::ScanError {
-                    path: path.clone(),
-                    error: e.to_string(),
-                });
-                let name = path
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_else(|| path.to_string_lossy().to_string());
-                return Ok(Node::from_directory(path, name, Vec::new()));
+                ctx.record_io_error(path.clone(), &e);
+                return Ok(unread_dir(path));
             }
         };
 
-        // Record entry-level I/O errors
-        for (err_path, err_msg) in entry_errors {
-            errors.lock().unwrap().push(ScanError {
-                path: err_path.clone(),
-                error_type: ScanErrorType::IoError,
-                message: err_msg.clone(),
-            });
-            progress.increment_errors();
-            let _ = event_tx.send(Event::ScanError {
-                path: err_path,
-                error: err_msg,
-            });
-        }
+        progress.add_size(overhead);
+        ctx.record_entry_errors(entry_errors);
+
+        // Ignore files here apply to this directory and everything below it
+        let has_entry = |name: &str, is_dir: bool| {
+            entries
+                .iter()
+                .any(|e| e.name == name && e.metadata.is_dir() == is_dir)
+        };
+        let ignore = ctx.push_ignore_files(&path, has_entry, ignore).await;
 
         let mut handles = Vec::new();
         let mut file_nodes = Vec::new();
+        let mut subdirs = Vec::new();
+        let mut sampled_sizes = Vec::new();
+        let mut small_files = SmallFiles::default();
 
         for entry_data in entries {
+            if ctx.cancel.is_cancelled() {
+                break;
+            }
+            if ctx.skip_hidden(&entry_data) {
+                continue;
+            }
             let entry_path = entry_data.path;
             let entry_name = entry_data.name;
             let metadata = entry_data.metadata;
+            let shared = entry_data.shared;
             let file_type = metadata.file_type();
 
-            if file_type.is_symlink() {
+            if ctx.excluded(&ignore, &entry_path, file_type.is_dir()) {
+                continue;
+            }
+
+            if is_link(&metadata) {
                 if !settings.follow_symlinks {
-                    let size = metadata.len();
-                    let modified = metadata.modified().ok();
-                    #[cfg(unix)]
-                    let inode = Some(std::os::unix::fs::MetadataExt::ino(&metadata));
-                    let node = Node {
-                        path: entry_path,
-                        name: entry_name,
-                        size,
-                        size_on_disk: size,
-                        node_type: NodeType::Symlink,
-                        children: Vec::new(),
-                        file_count: 0,
-                        dir_count: 0,
-                        modified,
-                        #[cfg(unix)]
-                        inode,
-                    };
-                    file_nodes.push(node);
+                    file_nodes.push(symlink_node(entry_path, entry_name, &metadata));
                     continue;
                 }
                 // Follow symlink - resolve and check for cycles
                 match tokio::fs::canonicalize(&entry_path).await {
                     Ok(real_path) => {
-                        if !visited.insert(real_path.clone()) {
-                            errors.lock().unwrap().push(ScanError {
-                                path: entry_path.clone(),
-                                error_type: ScanErrorType::SymlinkCycle,
-                                message: format!("Symlink cycle detected: {:?}", entry_path),
-                            });
-                            progress.increment_errors();
+                        if !ctx.visited.insert(real_path.clone()) {
+                            ctx.record_error(
+                                entry_path.clone(),
+                                ScanErrorType::SymlinkCycle,
+                                format!("Symlink cycle detected: {:?}", entry_path),
+                            );
                             continue;
                         }
                         match tokio::fs::metadata(&real_path).await {
                             Ok(resolved_meta) => {
                                 if resolved_meta.is_dir() {
-                                    let handle = tokio::spawn(scan_directory(
-                                        real_path,
-                                        depth + 1,
-                                        Arc::clone(&semaphore),
-                                        event_tx.clone(),
-                                        Arc::clone(&visited),
-                                        Arc::clone(&progress),
-                                        Arc::clone(&settings),
-                                        Arc::clone(&errors),
-                                        Arc::clone(&last_progress_time),
-                                    ));
-                                    handles.push(handle);
+                                    if ctx.skip_other_fs(&real_path, &resolved_meta)
+                                        || ctx.skip_pseudo_fs(&real_path)
+                                    {
+                                        continue;
+                                    }
+                                    if ctx.out_of_time() {
+                                        let mtime = resolved_meta.modified().ok();
+                                        let mut node =
+                                            ctx.unscanned_dir(entry_path, entry_name, mtime);
+                                        node.symlink_target = Some(real_path);
+                                        file_nodes.push(node);
+                                        continue;
+                                    }
+                                    subdirs.push(Subdir {
+                                        path: real_path,
+                                        mtime: resolved_meta.modified().ok(),
+                                        link: Some((entry_path, entry_name)),
+                                        weight: resolved_meta.len(),
+                                    });
                                 } else {
-                                    let size = resolved_meta.len();
-                                    let modified = resolved_meta.modified().ok();
-                                    #[cfg(unix)]
-                                    let inode =
-                                        Some(std::os::unix::fs::MetadataExt::ino(&resolved_meta));
-                                    #[cfg(not(unix))]
-                                    let inode = None;
-                                    let node =
-                                        Node::from_file(entry_path, entry_name, size, modified, inode);
-                                    progress.increment_files();
-                                    progress.add_size(size);
+                                    let mut node =
+                                        ctx.file_node(entry_path, entry_name, &resolved_meta);
+                                    node.symlink_target = Some(real_path);
                                     file_nodes.push(node);
                                 }
                             }
                             Err(e) => {
-                                errors.lock().unwrap().push(ScanError {
-                                    path: entry_path,
-                                    error_type: ScanErrorType::IoError,
-                                    message: e.to_string(),
-                                });
-                                progress.increment_errors();
+                                ctx.record_error(entry_path, ScanErrorType::IoError, e.to_string());
                             }
                         }
                     }
                     Err(e) => {
-                        errors.lock().unwrap().push(ScanError {
-                            path: entry_path,
-                            error_type: ScanErrorType::IoError,
-                            message: e.to_string(),
-                        });
-                        progress.increment_errors();
+                        ctx.record_error(entry_path, ScanErrorType::IoError, e.to_string());
                     }
                 }
                 continue;
             }
 
             if file_type.is_dir() {
-                if !visited.insert(entry_path.clone()) {
+                if ctx.skip_other_fs(&entry_path, &metadata)
+                    || ctx.skip_pseudo_fs(&entry_path)
+                    || !ctx.visited.insert(entry_path.clone())
+                {
+                    continue;
+                }
+                if ctx.out_of_time() {
+                    let mtime = metadata.modified().ok();
+                    file_nodes.push(ctx.unscanned_dir(entry_path, entry_name, mtime));
                     continue;
                 }
 
-                let handle = tokio::spawn(scan_directory(
-                    entry_path,
-                    depth + 1,
-                    Arc::clone(&semaphore),
-                    event_tx.clone(),
-                    Arc::clone(&visited),
-                    Arc::clone(&progress),
-                    Arc::clone(&settings),
-                    Arc::clone(&errors),
-                    Arc::clone(&last_progress_time),
-                ));
-                handles.push(handle);
+                subdirs.push(Subdir {
+                    path: entry_path,
+                    mtime: metadata.modified().ok(),
+                    link: None,
+                    weight: metadata.len(),
+                });
             } else if file_type.is_file() {
-                let size = metadata.len();
-                let modified = metadata.modified().ok();
-                #[cfg(unix)]
-                let inode = Some(std::os::unix::fs::MetadataExt::ino(&metadata));
-                #[cfg(not(unix))]
-                let inode = None;
-
-                let node = Node::from_file(entry_path, entry_name, size, modified, inode);
-                progress.increment_files();
-                progress.add_size(size);
-                file_nodes.push(node);
+                if !unsampled.is_empty() {
+                    sampled_sizes.push(metadata.len());
+                }
+                let mut node = ctx.file_node(entry_path, entry_name, &metadata);
+                node.shared_size = shared.min(node.size_on_disk);
+                match settings.min_file_size {
+                    Some(min_size) if node.size < min_size => small_files.add(&node),
+                    _ => match ctx.archive_kind(&node) {
+                        Some(kind) => {
+                            let ctx = Arc::clone(&ctx);
+                            handles.push(tokio::spawn(async move {
+                                ctx.peek_archive(node, kind).await
+                            }));
+                        }
+                        None => file_nodes.push(node),
+                    },
+                }
             } else {
-                let node = Node {
-                    path: entry_path,
-                    name: entry_name,
-                    size: 0,
-                    size_on_disk: 0,
-                    node_type: NodeType::Other,
-                    children: Vec::new(),
-                    file_count: 0,
-                    dir_count: 0,
-                    modified: metadata.modified().ok(),
-                    #[cfg(unix)]
-                    inode: Some(std::os::unix::fs::MetadataExt::ino(&metadata)),
-                };
-                file_nodes.push(node);
+                file_nodes.push(other_node(entry_path, entry_name, &metadata));
             }
         }
 
+        if let Some(min_size) = settings.min_file_size {
+            file_nodes.extend(small_files.into_node(&path, min_size));
+        }
+
+        file_nodes.extend(ctx.unsampled_node(&path, &ignore, &unsampled, &sampled_sizes));
+
+        let sample = ctx.draw_subdirs(&path, &subdirs);
+        if let Some(sample) = &sample {
+            subdirs = sample.pick(subdirs);
+        }
+        let subdir_handles: Vec<_> = subdirs
+            .into_iter()
+            .map(|subdir| {
+                let scan = scan_directory(
+                    subdir.path.clone(),
+                    depth + 1,
+                    Arc::clone(&ignore),
+                    Arc::clone(&ctx),
+                    subdir.mtime,
+                );
+                tokio::spawn(async move {
+                    let mut node = scan.await?;
+                    subdir.keep_link(&mut node);
+                    Ok(node)
+                })
+            })
+            .collect();
+
         // Wait for all spawned directory scans (permit already released)
-        for handle in handles {
-            match handle.await {
-                Ok(Ok(node)) => file_nodes.push(node),
-                Ok(Err(e)) => {
-                    errors.lock().unwrap().push(ScanError {
-                        path: path.clone(),
-                        error_type: ScanErrorType::IoError,
-                        message: e.to_string(),
-                    });
-                    progress.increment_errors();
+        let mut subdir_nodes = Vec::new();
+        join_children(&path, subdir_handles, &ctx, &mut subdir_nodes).await;
+        if let Some(sample) = &sample {
+            file_nodes.extend(ctx.estimate_skipped(&path, sample, &subdir_nodes));
+        }
+        file_nodes.extend(subdir_nodes);
+        join_children(&path, handles, &ctx, &mut file_nodes).await;
+        ctx.tag_gitignored(&ignore, &mut file_nodes);
+
+        let name = node_name(&path);
+        let mut dir_node =
+            Node::from_directory_with_overhead(path.clone(), name, overhead, file_nodes);
+        dir_node.modified = mtime;
+
+        ctx.emit_subtree(depth, &dir_node);
+        ctx.maybe_emit_progress(path);
+
+        Ok(dir_node)
+    })
+}
+
+/// Files of one directory below `Settings::min_file_size`, kept only as totals.
+#[derive(Default)]
+struct SmallFiles {
+    count: usize,
+    size: u64,
+    size_on_disk: u64,
+    shared_size: u64,
+}
+
+impl SmallFiles {
+    fn add(&mut self, node: &Node) {
+        self.count += 1;
+        self.size += node.size;
+        self.size_on_disk += node.size_on_disk;
+        self.shared_size += node.shared_size;
+    }
+
+    /// Stand-in node carrying the folded totals, or `None` when no file was folded.
+    fn into_node(self, dir: &Path, min_size: u64) -> Option<Node> {
+        if self.count == 0 {
+            return None;
+        }
+        let noun = if self.count == 1 { "file" } else { "files" };
+        let name = format!("{} {} under {}", self.count, noun, human_readable_size(min_size));
+        let mut node = Node::summary(dir, name, self.size, self.count);
+        node.size_on_disk = self.size_on_disk;
+        node.shared_size = self.shared_size;
+        Some(node)
+    }
+}
+
+/// Running sums of everything below a directory past a depth limit.
+#[derive(Default)]
+struct DirTotals {
+    size: u64,
+    size_on_disk: u64,
+    files: usize,
+    /// Subdirectories, not counting the directory itself.
+    dirs: usize,
+}
+
+impl DirTotals {
+    /// Childless node for directory `path` that carries the sums.
+    fn into_node(self, path: PathBuf, mtime: Option<SystemTime>) -> Node {
+        let name = node_name(&path);
+        let mut node = Node::from_directory(path, name, Vec::new());
+        node.size = self.size;
+        node.size_on_disk = self.size_on_disk;
+        node.file_count = self.files;
+        node.dir_count += self.dirs;
+        node.modified = mtime;
+        node
+    }
+}
+
+/// Sum up directory `path`, which is past a depth limit, and everything below it
+/// without building nodes for its contents, so depth-limited trees stay small but
+/// their sizes stay right. Directories are read one after another within the task.
+async fn aggregate_directory(
+    path: PathBuf,
+    mtime: Option<SystemTime>,
+    ignore: Arc<IgnoreStack>,
+    ctx: &Arc<ScanContext>,
+) -> anyhow::Result<Node> {
+    let mut totals = DirTotals::default();
+    let mut pending = vec![(path.clone(), ignore)];
+    while let Some((dir, ignore)) = pending.pop() {
+        if ctx.cancel.is_cancelled() {
+            break;
+        }
+        let batch = match ctx.read_dir(&dir, None).await? {
+            Ok(batch) => batch,
+            Err(e) => {
+                ctx.record_io_error(dir, &e);
+                continue;
+            }
+        };
+        let has_entry = |name: &str, is_dir: bool| {
+            batch
+                .entries
+                .iter()
+                .any(|e| e.name == name && e.metadata.is_dir() == is_dir)
+        };
+        let ignore = ctx.push_ignore_files(&dir, has_entry, ignore).await;
+        ctx.tally_entries(batch, &ignore, &mut totals, &mut pending);
+    }
+    Ok(totals.into_node(path, mtime))
+}
+
+/// Rebuild a directory unchanged since the previous scan: its files are copied from
+/// `previous` and only its subdirectories are visited again.
+async fn reuse_directory(
+    path: PathBuf,
+    previous: &Node,
+    mtime: Option<SystemTime>,
+    depth: usize,
+    ignore: Arc<IgnoreStack>,
+    ctx: &Arc<ScanContext>,
+) -> anyhow::Result<Node> {
+    let has_entry = |name: &str, is_dir: bool| {
+        previous
+            .children
+            .iter()
+            .any(|c| c.name == name && (c.node_type == NodeType::Directory) == is_dir)
+    };
+    let ignore = ctx.push_ignore_files(&path, has_entry, ignore).await;
+    // The files share the directory's device, which tells their inodes apart
+    #[cfg(unix)]
+    let dev = tokio::fs::symlink_metadata(&path)
+        .await
+        .ok()
+        .map(|meta| std::os::unix::fs::MetadataExt::dev(&meta));
+    #[cfg(not(unix))]
+    let dev = None;
+
+    let mut handles = Vec::new();
+    let mut nodes = Vec::new();
+    for child in &previous.children {
+        if ctx.cancel.is_cancelled() {
+            break;
+        }
+        let is_dir = child.node_type == NodeType::Directory;
+        // The ignore rules may have changed since the previous scan
+        if ctx.excluded(&ignore, &child.path, is_dir)
+            || (ctx.settings.skip_hidden && child.is_hidden())
+        {
+            continue;
+        }
+        if is_dir {
+            // Mounting a filesystem on a subdirectory leaves this one's mtime alone, so
+            // each is checked again as `scan_directory` checks the entries it reads
+            let meta = tokio::fs::symlink_metadata(&child.path).await.ok();
+            if meta.as_ref().is_some_and(|meta| ctx.skip_other_fs(&child.path, meta))
+                || ctx.skip_pseudo_fs(&child.path)
+                || !ctx.visited.insert(child.path.clone())
+            {
+                continue;
+            }
+            if ctx.out_of_time() {
+                let (path, name) = (child.path.clone(), child.name.clone());
+                nodes.push(ctx.unscanned_dir(path, name, child.modified));
+                continue;
+            }
+            // Each subdirectory's own mtime decides whether it is reused in turn
+            let child_mtime = meta.and_then(|meta| meta.modified().ok());
+            handles.push(tokio::spawn(scan_directory(
+                child.path.clone(),
+                depth + 1,
+                Arc::clone(&ignore),
+                Arc::clone(ctx),
+                child_mtime,
+            )));
+        } else {
+            let mut node = child.clone();
+            if !ctx.reused_file_counts(&node, dev) {
+                node.size = 0;
+                node.size_on_disk = 0;
+            }
+            if node.node_type == NodeType::File {
+                ctx.progress.increment_files();
+            }
+            ctx.progress.add_size(node.size);
+            node.gitignored = false;
+            // Keep an archive's previous listing, but follow `peek_archives` as it is now
+            match ctx.archive_kind(&node) {
+                Some(kind) if node.children.is_empty() => {
+                    let ctx = Arc::clone(ctx);
+                    handles.push(tokio::spawn(async move { ctx.peek_archive(node, kind).await }));
+                    continue;
+                }
+                Some(_) => {}
+                None if node.node_type == NodeType::File => node.children.clear(),
+                None => {}
+            }
+            nodes.push(node);
+        }
+    }
+
+    join_children(&path, handles, ctx, &mut nodes).await;
+    ctx.tag_gitignored(&ignore, &mut nodes);
+
+    let children_size: u64 = previous.children.iter().map(|c| c.size).sum();
+    let overhead = previous.size.saturating_sub(children_size);
+    ctx.progress.add_size(overhead);
+
+    let name = node_name(&path);
+    let mut dir_node = Node::from_directory_with_overhead(path.clone(), name, overhead, nodes);
+    dir_node.modified = mtime;
+
+    ctx.emit_subtree(depth, &dir_node);
+    ctx.maybe_emit_progress(path);
+
+    Ok(dir_node)
+}
+
+/// Collect the nodes of `path` from their tasks (subdirectories and listed archives),
+/// recording failures.
+async fn join_children(
+    path: &Path,
+    handles: Vec<tokio::task::JoinHandle<anyhow::Result<Node>>>,
+    ctx: &ScanContext,
+    nodes: &mut Vec<Node>,
+) {
+    for handle in handles {
+        match handle.await {
+            Ok(Ok(node)) => nodes.push(node),
+            Ok(Err(e)) => {
+                ctx.record_error(path.to_path_buf(), ScanErrorType::IoError, e.to_string());
+            }
+            Err(e) => {
+                ctx.record_error(
+                    path.to_path_buf(),
+                    ScanErrorType::Other,
+                    format!("Task join error: {}", e),
+                );
+            }
+        }
+    }
+}
+
+/// Bounded min-heap of the largest files seen so far.
+struct TopFiles {
+    limit: usize,
+    heap: BinaryHeap<Reverse<(u64, PathBuf)>>,
+}
+
+impl TopFiles {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            heap: BinaryHeap::with_capacity(limit + 1),
+        }
+    }
+
+    /// Smallest size that would currently be admitted.
+    fn threshold(&self) -> Option<u64> {
+        if self.heap.len() < self.limit {
+            None
+        } else {
+            self.heap.peek().map(|Reverse((size, _))| *size)
+        }
+    }
+
+    fn offer(&mut self, size: u64, path: PathBuf) {
+        if self.limit == 0 {
+            return;
+        }
+        self.heap.push(Reverse((size, path)));
+        if self.heap.len() > self.limit {
+            self.heap.pop();
+        }
+    }
+
+    fn into_sorted(self) -> Vec<TopFile> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((size, path))| TopFile { path, size })
+            .collect()
+    }
+}
+
+/// Directories waiting to be read by [`Scanner::scan_top_n`], shared by a fixed set
+/// of workers, so memory grows with the directories queued rather than with tasks.
+struct TopNQueue {
+    /// Each with its depth and the ignore rules that apply inside it.
+    pending: Mutex<Vec<(PathBuf, usize, Arc<IgnoreStack>)>>,
+    /// Workers reading a directory, which may still queue more.
+    busy: AtomicUsize,
+    /// Woken whenever a worker finishes a directory.
+    wake: Notify,
+}
+
+impl TopNQueue {
+    /// Read queued directories until none are left and no other worker can add more.
+    async fn work(&self, ctx: &Arc<ScanContext>, top: &Mutex<TopFiles>) {
+        loop {
+            // Registered before checking, so a wake-up in between isn't lost
+            let woken = self.wake.notified();
+            let job = {
+                let mut pending = self.pending.lock().unwrap();
+                let job = pending.pop();
+                if job.is_some() {
+                    self.busy.fetch_add(1, Ordering::SeqCst);
+                } else if self.busy.load(Ordering::SeqCst) == 0 {
+                    return;
                 }
+                job
+            };
+            let Some((path, depth, ignore)) = job else {
+                woken.await;
+                continue;
+            };
+
+            let subdirs = match read_top_n_dir(path.clone(), depth, ignore, ctx, top).await {
+                Ok(subdirs) => subdirs,
                 Err(e) => {
-                    errors.lock().unwrap().push(ScanError {
-                        path: path.clone(),
-                        error_type: ScanErrorType::Other,
-                        message: format!("Task join error: {}", e),
-                    });
-                    progress.increment_errors();
+                    ctx.record_error(path, ScanErrorType::IoError, e.to_string());
+                    Vec::new()
                 }
+            };
+            {
+                let mut pending = self.pending.lock().unwrap();
+                pending.extend(subdirs.into_iter().map(|(dir, ignore)| (dir, depth + 1, ignore)));
+                self.busy.fetch_sub(1, Ordering::SeqCst);
             }
+            self.wake.notify_waiters();
         }
+    }
+}
 
-        let name = path
-            .file_name()
-            .map(|n| n.to_string_lossy().to_string())
-            .unwrap_or_else(|| path.to_string_lossy().to_string());
+/// Like `scan_directory`, but only feeds file sizes into `top` and the progress
+/// totals, and returns the subdirectories to read next with their ignore rules
+/// instead of reading them.
+async fn read_top_n_dir(
+    path: PathBuf,
+    depth: usize,
+    ignore: Arc<IgnoreStack>,
+    ctx: &Arc<ScanContext>,
+    top: &Mutex<TopFiles>,
+) -> anyhow::Result<Vec<(PathBuf, Arc<IgnoreStack>)>> {
+    let settings = &ctx.settings;
+    let progress = &ctx.progress;
 
-        let dir_node = Node::from_directory(path.clone(), name, file_nodes);
+    progress.increment_dirs();
+    progress.set_current_path(&path);
 
-        // Throttle progress events: only send if 100ms+ since last send
-        let now_ms = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-        let last = last_progress_time.load(Ordering::Relaxed);
-        if now_ms.saturating_sub(last) >= 100 {
-            last_progress_time.store(now_ms, Ordering::Relaxed);
-            let snapshot = progress.snapshot();
-            let _ = event_tx.send(Event::Progress {
-                scanned: snapshot.files_scanned,
-                total_size: snapshot.total_size,
-                current_path: path,
-            });
+    if ctx.cancel.is_cancelled() {
+        return Ok(Vec::new());
+    }
+    if settings.max_depth.is_some_and(|max_depth| depth >= max_depth)
+        || ctx.beyond_profile_depth(&path)
+    {
+        aggregate_directory(path, None, ignore, ctx).await?;
+        return Ok(Vec::new());
+    }
+
+    let DirBatch {
+        entries,
+        errors: entry_errors,
+        overhead,
+        ..
+    } = match ctx.read_dir(&path, None).await? {
+        Ok(batch) => batch,
+        Err(e) => {
+            ctx.record_io_error(path, &e);
+            return Ok(Vec::new());
         }
+    };
+    progress.add_size(overhead);
 
-        Ok(dir_node)
-    })
+    for (err_path, err_msg) in entry_errors {
+        ctx.record_error(err_path.clone(), ScanErrorType::IoError, err_msg.clone());
+        ctx.emit(Event::ScanError {
+            path: err_path,
+            error: err_msg,
+        });
+    }
+
+    let has_entry = |name: &str, is_dir: bool| {
+        entries
+            .iter()
+            .any(|e| e.name == name && e.metadata.is_dir() == is_dir)
+    };
+    let ignore = ctx.push_ignore_files(&path, has_entry, ignore).await;
+
+    let mut subdirs = Vec::new();
+    let mut files = Vec::new();
+
+    for entry in entries {
+        if ctx.cancel.is_cancelled() {
+            break;
+        }
+        if ctx.skip_hidden(&entry) {
+            continue;
+        }
+        let file_type = entry.metadata.file_type();
+        if ctx.excluded(&ignore, &entry.path, file_type.is_dir()) {
+            continue;
+        }
+
+        if is_link(&entry.metadata) {
+            if !settings.follow_symlinks {
+                progress.add_size(entry.metadata.len());
+                continue;
+            }
+            let real_path = match tokio::fs::canonicalize(&entry.path).await {
+                Ok(real_path) => real_path,
+                Err(e) => {
+                    ctx.record_error(entry.path, ScanErrorType::IoError, e.to_string());
+                    continue;
+                }
+            };
+            if !ctx.visited.insert(real_path.clone()) {
+                ctx.record_error(
+                    entry.path.clone(),
+                    ScanErrorType::SymlinkCycle,
+                    format!("Symlink cycle detected: {:?}", entry.path),
+                );
+                continue;
+            }
+            match tokio::fs::metadata(&real_path).await {
+                Ok(meta) if meta.is_dir() => {
+                    if !ctx.skip_other_fs(&real_path, &meta) {
+                        subdirs.push(real_path);
+                    }
+                }
+                Ok(meta) => files.push((ctx.counted_size(&meta), entry.path)),
+                Err(e) => ctx.record_error(entry.path, ScanErrorType::IoError, e.to_string()),
+            }
+        } else if file_type.is_dir() {
+            // Only needed to catch symlinks back into the tree; skipping it otherwise
+            // keeps memory flat on huge trees
+            if settings.follow_symlinks && !ctx.visited.insert(entry.path.clone()) {
+                continue;
+            }
+            if ctx.skip_other_fs(&entry.path, &entry.metadata) {
+                continue;
+            }
+            subdirs.push(entry.path);
+        } else if file_type.is_file() {
+            files.push((ctx.counted_size(&entry.metadata), entry.path));
+        }
+    }
+
+    if !files.is_empty() {
+        let mut top = top.lock().unwrap();
+        for (size, file_path) in files {
+            progress.increment_files();
+            progress.add_size(size);
+            if top.threshold().is_none_or(|min| size > min) {
+                top.offer(size, file_path);
+            }
+        }
+    }
+
+    ctx.maybe_emit_progress(path);
+
+    Ok(subdirs
+        .into_iter()
+        .filter(|dir| !ctx.skip_pseudo_fs(dir))
+        .map(|dir| (dir, Arc::clone(&ignore)))
+        .collect())
 }
@@ -0,0 +1,172 @@
+//! Directory reads for `--backend mft`: the volume's whole MFT is read once up front,
+//! and each directory's entries are then listed from it without touching the disk.
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use dashmap::DashMap;
+
+use crate::core::mft::{MftFile, MftIndex};
+
+use super::{add_shared_bytes, DirBatch, DirEntryData, EntryStat};
+
+/// The MFT of the volume mounted at `root`.
+pub struct MftVolume {
+    root: PathBuf,
+    index: MftIndex,
+    /// Records of the subdirectories listed so far, by the path they will be read at,
+    /// so reading one doesn't have to resolve its path from the root again.
+    dirs: DashMap<PathBuf, u64>,
+}
+
+impl MftVolume {
+    pub fn new(root: PathBuf, index: MftIndex) -> Self {
+        Self {
+            root,
+            index,
+            dirs: DashMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// The record of the entry at `path`, when it is on this volume.
+    fn record(&self, path: &Path) -> Option<u64> {
+        if let Some((_, record)) = self.dirs.remove(path) {
+            return Some(record);
+        }
+        let absolute = std::path::absolute(path).ok()?;
+        self.index.lookup(absolute.strip_prefix(&self.root).ok()?)
+    }
+
+    /// The metadata of the entry at `path`, when it is on this volume.
+    pub fn stat(&self, path: &Path) -> Option<MftStat> {
+        let record = self.record(path)?;
+        Some(MftStat::new(record, self.index.file(record)?))
+    }
+
+    /// Like `read_dir_with_clones` without sampling, with the entries listed from the
+    /// MFT. `None` when `dir_path` isn't a directory on this volume.
+    pub fn read_dir_batch(
+        &self,
+        dir_path: &Path,
+        stat_self: bool,
+        detect_clones: bool,
+    ) -> Option<DirBatch<MftStat>> {
+        let record = self.record(dir_path)?;
+        let dir = self.index.file(record).filter(|dir| dir.is_dir)?;
+        let mut batch = DirBatch {
+            entries: Vec::new(),
+            errors: Vec::new(),
+            overhead: if stat_self { dir.size } else { 0 },
+            unsampled: Vec::new(),
+        };
+        for (entry, name) in self.index.entries(record) {
+            let Some(file) = self.index.file(entry) else {
+                continue;
+            };
+            let path = dir_path.join(name);
+            let metadata = MftStat::new(entry, file);
+            if metadata.is_dir && !metadata.is_link {
+                self.dirs.insert(path.clone(), entry);
+            }
+            batch.entries.push(DirEntryData {
+                path,
+                name: name.to_string(),
+                metadata,
+                shared: 0,
+            });
+        }
+        if detect_clones {
+            add_shared_bytes(&mut batch);
+        }
+        Some(batch)
+    }
+}
+
+/// An entry's metadata as its MFT records give it.
+pub struct MftStat {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    record: u64,
+    is_dir: bool,
+    is_link: bool,
+    is_hidden: bool,
+    size: u64,
+    allocated_size: u64,
+    modified: Option<SystemTime>,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    links: u64,
+}
+
+impl MftStat {
+    fn new(record: u64, file: &MftFile) -> Self {
+        Self {
+            record,
+            is_dir: file.is_dir,
+            is_link: file.is_link(),
+            is_hidden: file.is_hidden(),
+            size: file.size,
+            allocated_size: file.allocated_size,
+            modified: file.modified,
+            links: file.links.len() as u64,
+        }
+    }
+}
+
+impl EntryStat for MftStat {
+    fn is_dir(&self) -> bool {
+        self.is_dir
+    }
+
+    fn is_file(&self) -> bool {
+        !self.is_dir && !self.is_link
+    }
+
+    fn is_link(&self) -> bool {
+        self.is_link
+    }
+
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn mtime(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    fn allocated_size(&self, _path: &Path) -> u64 {
+        self.allocated_size
+    }
+
+    fn is_hidden(&self, _name: &str) -> bool {
+        self.is_hidden
+    }
+
+    /// Entries of one volume share a device; the record number is their inode.
+    #[cfg(unix)]
+    fn dev(&self) -> u64 {
+        0
+    }
+
+    #[cfg(unix)]
+    fn ino(&self) -> u64 {
+        self.record
+    }
+
+    #[cfg(unix)]
+    fn nlink(&self) -> u64 {
+        self.links
+    }
+
+    #[cfg(unix)]
+    fn uid(&self) -> u32 {
+        0
+    }
+
+    #[cfg(unix)]
+    fn gid(&self) -> u32 {
+        0
+    }
+}
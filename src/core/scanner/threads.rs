@@ -0,0 +1,539 @@
+//! A scanner backend that walks the tree on a rayon work-stealing pool with plain
+//! blocking I/O, instead of a tokio task and a `spawn_blocking` hop per directory.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use rayon::prelude::*;
+
+use crate::config::settings::{detect_filesystem_type, ScanBackend, Settings};
+use crate::core::archive;
+use crate::core::cancel::CancelToken;
+use crate::core::events::{Event, EventSender};
+use crate::core::ignore::IgnoreStack;
+use crate::core::mft::MftIndex;
+use crate::core::mounts::MountTable;
+use crate::core::profile::IoProfile;
+use crate::core::progress::ProgressTracker;
+use crate::models::node::Node;
+use crate::models::scan_result::{ScanErrorType, ScanResult};
+
+use super::mft::{MftStat, MftVolume};
+use super::{
+    file_node, node_name, other_node, read_dir_with_clones, symlink_node, virtual_root, DirBatch,
+    DirEntryData, DirTotals, EntryStat, ScanContext, ScanControl, SmallFiles, Subdir,
+};
+
+/// How often a paused scan checks whether it was resumed.
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Scans with a pool of `Settings::max_concurrent_io` threads, each reading whole
+/// directories with blocking calls and handing subdirectories to idle threads. On
+/// fast local disks this avoids most of the scheduling overhead of [`Scanner`].
+///
+/// Results, events and controls are the same as [`Scanner`]'s, except that network
+/// filesystems and per-path profiles get no concurrency limits of their own, and
+/// there is no incremental rescan against a previous result.
+///
+/// [`Scanner`]: super::Scanner
+pub struct ScannerSync {
+    ctx: Arc<ScanContext>,
+}
+
+impl ScannerSync {
+    pub fn new(settings: Settings, event_tx: EventSender) -> Self {
+        Self::with_mounts(settings, event_tx, MountTable::load())
+    }
+
+    /// Like [`ScannerSync::new`], but consults `mounts` instead of the system mount table.
+    pub fn with_mounts(settings: Settings, event_tx: EventSender, mounts: MountTable) -> Self {
+        Self {
+            ctx: Arc::new(ScanContext::new(settings, event_tx, mounts)),
+        }
+    }
+
+    /// Send `Event::SubtreeCompleted` as directories in the top levels of the tree
+    /// finish, as [`Scanner::with_subtree_events`] does.
+    ///
+    /// [`Scanner::with_subtree_events`]: super::Scanner::with_subtree_events
+    pub fn with_subtree_events(self) -> Self {
+        self.ctx.subtree_events.store(true, Ordering::Relaxed);
+        self
+    }
+
+    /// List directories on the volume mounted at `volume_root` from `index`, its MFT,
+    /// instead of reading them from the disk. `--backend mft` reads the MFT of every
+    /// root's volume itself.
+    pub fn with_mft(self, volume_root: PathBuf, index: MftIndex) -> Self {
+        self.add_mft_volume(volume_root, index);
+        self
+    }
+
+    fn add_mft_volume(&self, volume_root: PathBuf, index: MftIndex) {
+        let mut volumes = self.ctx.mft_volumes.write().unwrap();
+        volumes.retain(|volume| volume.root() != volume_root);
+        volumes.push(MftVolume::new(volume_root, index));
+    }
+
+    /// Read the MFT of the volume `root` is on, unless it was read already.
+    #[cfg(windows)]
+    fn open_mft(&self, root: &Path) -> anyhow::Result<()> {
+        let absolute = std::path::absolute(root)?;
+        // The drive prefix and the root directory after it
+        let volume_root: PathBuf = absolute.components().take(2).collect();
+        let opened = self.ctx.mft_volumes.read().unwrap();
+        if opened.iter().any(|volume| volume.root() == volume_root) {
+            return Ok(());
+        }
+        drop(opened);
+        let index = MftIndex::open(&volume_root).map_err(|e| {
+            anyhow::anyhow!("cannot read the MFT of {}: {}", volume_root.display(), e)
+        })?;
+        self.add_mft_volume(volume_root, index);
+        Ok(())
+    }
+
+    /// Token that stops the scan early; it then returns a partial result flagged as
+    /// `cancelled`.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.ctx.cancel.clone()
+    }
+
+    /// Handle for pausing, resuming and cancelling this scanner from another thread.
+    pub fn control(&self) -> ScanControl {
+        ScanControl {
+            ctx: Arc::clone(&self.ctx),
+        }
+    }
+
+    pub fn progress(&self) -> &Arc<ProgressTracker> {
+        &self.ctx.progress
+    }
+
+    /// Per-directory I/O timings, recorded only when `Settings::profile_output` is set.
+    pub fn io_profile(&self) -> Option<&Arc<IoProfile>> {
+        self.ctx.io_profile.as_ref()
+    }
+
+    pub fn scan(&self, root: PathBuf) -> anyhow::Result<ScanResult> {
+        self.scan_roots(vec![root])
+    }
+
+    /// Scan several paths into one result, as [`Scanner::scan_roots`] does. Blocks
+    /// until the scan is done, so run it on `spawn_blocking` from async code.
+    ///
+    /// [`Scanner::scan_roots`]: super::Scanner::scan_roots
+    pub fn scan_roots(&self, roots: Vec<PathBuf>) -> anyhow::Result<ScanResult> {
+        if self.ctx.settings.backend == ScanBackend::Uring {
+            #[cfg(all(target_os = "linux", feature = "uring"))]
+            super::uring::check_support()
+                .map_err(|e| anyhow::anyhow!("io_uring is unavailable: {}", e))?;
+            #[cfg(not(all(target_os = "linux", feature = "uring")))]
+            anyhow::bail!("the uring backend needs a Linux build with `--features uring`");
+        }
+        if self.ctx.settings.backend == ScanBackend::Mft {
+            #[cfg(windows)]
+            for root in &roots {
+                self.open_mft(root)?;
+            }
+            #[cfg(not(windows))]
+            anyhow::bail!("the mft backend needs Windows");
+        }
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(self.ctx.settings.max_concurrent_io.max(1))
+            .thread_name(|index| format!("disklens-scan-{}", index))
+            .build()?;
+
+        let (scan_path, root_node) = match roots.as_slice() {
+            [] => anyhow::bail!("no paths to scan"),
+            [root] => (root.clone(), pool.install(|| self.scan_root_node(root))?),
+            _ => {
+                let mut children = Vec::with_capacity(roots.len());
+                for root in &roots {
+                    children.push(pool.install(|| self.scan_root_node(root))?);
+                }
+                (PathBuf::new(), virtual_root(children))
+            }
+        };
+
+        let filesystem = roots.iter().find_map(|root| detect_filesystem_type(root));
+        Ok(self.ctx.finish(scan_path, root_node, filesystem))
+    }
+
+    fn scan_root_node(&self, root: &Path) -> anyhow::Result<Node> {
+        let ctx = &self.ctx;
+
+        // A root the MFT lists as a directory needs nothing from the disk
+        if let Some(meta) = mft_stat(ctx, root).filter(|meta| meta.is_dir() && !meta.is_link()) {
+            ctx.emit(Event::ScanStarted {
+                path: root.to_path_buf(),
+            });
+            ctx.set_root_dev(&meta);
+            let ignore = ctx.root_ignore(root);
+            return Ok(walk_directory(ctx, root.to_path_buf(), 0, ignore, meta.mtime()));
+        }
+
+        let root_meta = match std::fs::symlink_metadata(root) {
+            Ok(meta) => meta,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                anyhow::bail!("path does not exist: {}", root.display())
+            }
+            Err(e) => anyhow::bail!("cannot access {}: {}", root.display(), e),
+        };
+
+        ctx.emit(Event::ScanStarted {
+            path: root.to_path_buf(),
+        });
+
+        if root_meta.file_type().is_symlink() && !ctx.settings.follow_symlinks {
+            return Ok(symlink_node(root.to_path_buf(), node_name(root), &root_meta));
+        }
+        let meta = if root_meta.file_type().is_symlink() {
+            match std::fs::metadata(root) {
+                Ok(meta) => meta,
+                Err(e) => anyhow::bail!("cannot resolve symlink {}: {}", root.display(), e),
+            }
+        } else {
+            root_meta
+        };
+
+        let root_node = if meta.is_dir() {
+            ctx.set_root_dev(&meta);
+            let ignore = ctx.root_ignore(root);
+            walk_directory(ctx, root.to_path_buf(), 0, ignore, meta.modified().ok())
+        } else if meta.is_file() {
+            ctx.progress.increment_files();
+            ctx.progress.add_size(meta.len());
+            file_node(root.to_path_buf(), node_name(root), &meta)
+        } else {
+            other_node(root.to_path_buf(), node_name(root), &meta)
+        };
+        Ok(root_node)
+    }
+}
+
+/// Block while the scan is paused; reads already running finish first.
+fn wait_while_paused(ctx: &ScanContext) {
+    while *ctx.paused.borrow() && !ctx.cancel.is_cancelled() {
+        std::thread::sleep(PAUSE_POLL_INTERVAL);
+    }
+}
+
+/// A directory left unread, which still sorts by when it last changed.
+fn unread_dir(path: PathBuf, mtime: Option<SystemTime>) -> Node {
+    let name = node_name(&path);
+    let mut node = Node::from_directory(path, name, Vec::new());
+    node.modified = mtime;
+    node
+}
+
+/// The metadata of `path` from the MFT of a volume it is on, if one was read.
+fn mft_stat(ctx: &ScanContext, path: &Path) -> Option<MftStat> {
+    let volumes = ctx.mft_volumes.read().unwrap();
+    volumes.iter().find_map(|volume| volume.stat(path))
+}
+
+/// Directory `path` listed from the MFT of a volume it is on, if one was read.
+fn read_mft_dir(ctx: &ScanContext, path: &Path) -> Option<DirBatch<MftStat>> {
+    let settings = &ctx.settings;
+    let volumes = ctx.mft_volumes.read().unwrap();
+    volumes.iter().find_map(|volume| {
+        volume.read_dir_batch(path, settings.count_dir_overhead, settings.detect_clones)
+    })
+}
+
+/// Run `read` on directory `path`, recording how long it took when profiling.
+fn timed_read<M>(
+    ctx: &ScanContext,
+    path: &Path,
+    read: impl FnOnce() -> std::io::Result<DirBatch<M>>,
+) -> std::io::Result<DirBatch<M>> {
+    let io_start = Instant::now();
+    let batch = read();
+    if let Some(profile) = &ctx.io_profile {
+        profile.record(path.to_path_buf(), io_start.elapsed());
+    }
+    batch
+}
+
+/// Stack the rules of `dir`'s profile and ignore files, found among its `entries`, on
+/// top of `ignore`.
+fn push_ignore_files<M: EntryStat>(
+    ctx: &ScanContext,
+    dir: &Path,
+    entries: &[DirEntryData<M>],
+    ignore: Arc<IgnoreStack>,
+) -> Arc<IgnoreStack> {
+    let has_entry = |name: &str, is_dir: bool| {
+        entries
+            .iter()
+            .any(|e| e.name == name && e.metadata.is_dir() == is_dir)
+    };
+    let mut ignore = ctx.push_profile_patterns(dir, ignore);
+    for (file, git) in ctx.ignore_files(dir, has_entry) {
+        let contents = std::fs::read_to_string(&file);
+        ignore = ctx.push_ignore_file(dir, ignore, &file, git, contents);
+    }
+    ignore
+}
+
+/// Sum up directory `path`, which is past a depth limit, and everything below it on
+/// this thread, without building nodes for its contents.
+fn aggregate_directory(
+    ctx: &ScanContext,
+    path: PathBuf,
+    ignore: Arc<IgnoreStack>,
+    mtime: Option<SystemTime>,
+) -> Node {
+    let mut totals = DirTotals::default();
+    let mut pending = vec![(path.clone(), ignore)];
+    while let Some((dir, ignore)) = pending.pop() {
+        wait_while_paused(ctx);
+        if ctx.cancel.is_cancelled() {
+            break;
+        }
+        if let Some(batch) = read_mft_dir(ctx, &dir) {
+            let ignore = push_ignore_files(ctx, &dir, &batch.entries, ignore);
+            ctx.tally_entries(batch, &ignore, &mut totals, &mut pending);
+            continue;
+        }
+        let batch = timed_read(ctx, &dir, || {
+            read_dir_with_clones(&dir, ctx.settings.count_dir_overhead, None, false)
+        });
+        let batch = match batch {
+            Ok(batch) => batch,
+            Err(e) => {
+                ctx.record_io_error(dir, &e);
+                continue;
+            }
+        };
+        let ignore = push_ignore_files(ctx, &dir, &batch.entries, ignore);
+        ctx.tally_entries(batch, &ignore, &mut totals, &mut pending);
+    }
+    totals.into_node(path, mtime)
+}
+
+/// Read directory `path` and, in parallel, everything below it.
+fn walk_directory(
+    ctx: &ScanContext,
+    path: PathBuf,
+    depth: usize,
+    ignore: Arc<IgnoreStack>,
+    mtime: Option<SystemTime>,
+) -> Node {
+    let settings = &ctx.settings;
+    ctx.progress.increment_dirs();
+    ctx.progress.set_current_path(&path);
+
+    wait_while_paused(ctx);
+    if ctx.cancel.is_cancelled() {
+        return unread_dir(path, mtime);
+    }
+    if settings.max_depth.is_some_and(|max_depth| depth >= max_depth)
+        || ctx.beyond_profile_depth(&path)
+    {
+        return aggregate_directory(ctx, path, ignore, mtime);
+    }
+
+    // The MFT already holds every entry's metadata, so nothing is left to sample
+    if let Some(batch) = read_mft_dir(ctx, &path) {
+        return build_directory(ctx, path, depth, ignore, mtime, Ok(batch));
+    }
+
+    // Sampling decides which files to stat one by one, so it stays on `std::fs`
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    if settings.backend == ScanBackend::Uring && settings.sample_ratio.is_none() {
+        let batch = timed_read(ctx, &path, || {
+            super::uring::read_dir_batch(&path, settings.count_dir_overhead, settings.detect_clones)
+        });
+        return build_directory(ctx, path, depth, ignore, mtime, batch);
+    }
+
+    let batch = timed_read(ctx, &path, || {
+        read_dir_with_clones(
+            &path,
+            settings.count_dir_overhead,
+            settings.sample_ratio,
+            settings.detect_clones,
+        )
+    });
+    build_directory(ctx, path, depth, ignore, mtime, batch)
+}
+
+/// Turn the entries `batch` read from directory `path` into its node, walking its
+/// subdirectories in parallel.
+fn build_directory<M: EntryStat>(
+    ctx: &ScanContext,
+    path: PathBuf,
+    depth: usize,
+    ignore: Arc<IgnoreStack>,
+    mtime: Option<SystemTime>,
+    batch: std::io::Result<DirBatch<M>>,
+) -> Node {
+    let settings = &ctx.settings;
+    let DirBatch {
+        entries,
+        errors: entry_errors,
+        overhead,
+        unsampled,
+    } = match batch {
+        Ok(batch) => batch,
+        Err(e) => {
+            ctx.record_io_error(path.clone(), &e);
+            return unread_dir(path, mtime);
+        }
+    };
+    ctx.progress.add_size(overhead);
+    ctx.record_entry_errors(entry_errors);
+
+    // Ignore files here apply to this directory and everything below it
+    let ignore = push_ignore_files(ctx, &path, &entries, ignore);
+
+    let mut nodes = Vec::new();
+    let mut subdirs = Vec::new();
+    let mut sampled_sizes = Vec::new();
+    let mut small_files = SmallFiles::default();
+
+    for entry in entries {
+        if ctx.cancel.is_cancelled() {
+            break;
+        }
+        if ctx.skip_hidden(&entry) {
+            continue;
+        }
+        let DirEntryData {
+            path: entry_path,
+            name: entry_name,
+            metadata,
+            shared,
+        } = entry;
+        if ctx.excluded(&ignore, &entry_path, metadata.is_dir()) {
+            continue;
+        }
+
+        if metadata.is_link() {
+            if !settings.follow_symlinks {
+                nodes.push(symlink_node(entry_path, entry_name, &metadata));
+                continue;
+            }
+            let real_path = match std::fs::canonicalize(&entry_path) {
+                Ok(real_path) => real_path,
+                Err(e) => {
+                    ctx.record_error(entry_path, ScanErrorType::IoError, e.to_string());
+                    continue;
+                }
+            };
+            if !ctx.visited.insert(real_path.clone()) {
+                ctx.record_error(
+                    entry_path.clone(),
+                    ScanErrorType::SymlinkCycle,
+                    format!("Symlink cycle detected: {:?}", entry_path),
+                );
+                continue;
+            }
+            let resolved_meta = match std::fs::metadata(&real_path) {
+                Ok(meta) => meta,
+                Err(e) => {
+                    ctx.record_error(entry_path, ScanErrorType::IoError, e.to_string());
+                    continue;
+                }
+            };
+            if !resolved_meta.is_dir() {
+                let mut node = ctx.file_node(entry_path, entry_name, &resolved_meta);
+                node.symlink_target = Some(real_path);
+                nodes.push(node);
+            } else if ctx.skip_other_fs(&real_path, &resolved_meta)
+                || ctx.skip_pseudo_fs(&real_path)
+            {
+                continue;
+            } else if ctx.out_of_time() {
+                let mtime = resolved_meta.modified().ok();
+                let mut node = ctx.unscanned_dir(entry_path, entry_name, mtime);
+                node.symlink_target = Some(real_path);
+                nodes.push(node);
+            } else {
+                subdirs.push(Subdir {
+                    path: real_path,
+                    mtime: resolved_meta.modified().ok(),
+                    link: Some((entry_path, entry_name)),
+                    weight: resolved_meta.len(),
+                });
+            }
+            continue;
+        }
+
+        if metadata.is_dir() {
+            if ctx.skip_other_fs(&entry_path, &metadata)
+                || ctx.skip_pseudo_fs(&entry_path)
+                || !ctx.visited.insert(entry_path.clone())
+            {
+                continue;
+            }
+            if ctx.out_of_time() {
+                let mtime = metadata.mtime();
+                nodes.push(ctx.unscanned_dir(entry_path, entry_name, mtime));
+                continue;
+            }
+            subdirs.push(Subdir {
+                path: entry_path,
+                mtime: metadata.mtime(),
+                link: None,
+                weight: metadata.size(),
+            });
+        } else if metadata.is_file() {
+            if !unsampled.is_empty() {
+                sampled_sizes.push(metadata.size());
+            }
+            let mut node = ctx.file_node(entry_path, entry_name, &metadata);
+            node.shared_size = shared.min(node.size_on_disk);
+            match settings.min_file_size {
+                Some(min_size) if node.size < min_size => small_files.add(&node),
+                _ => {
+                    if let Some(kind) = ctx.archive_kind(&node) {
+                        let listing = archive::list_entries(&node.path, kind);
+                        ctx.attach_archive_entries(&mut node, listing);
+                    }
+                    nodes.push(node);
+                }
+            }
+        } else {
+            nodes.push(other_node(entry_path, entry_name, &metadata));
+        }
+    }
+
+    if let Some(min_size) = settings.min_file_size {
+        nodes.extend(small_files.into_node(&path, min_size));
+    }
+    nodes.extend(ctx.unsampled_node(&path, &ignore, &unsampled, &sampled_sizes));
+
+    let sample = ctx.draw_subdirs(&path, &subdirs);
+    if let Some(sample) = &sample {
+        subdirs = sample.pick(subdirs);
+    }
+    let subdir_nodes: Vec<Node> = subdirs
+        .into_par_iter()
+        .map(|subdir| {
+            let (path, ignore) = (subdir.path.clone(), Arc::clone(&ignore));
+            let mut node = walk_directory(ctx, path, depth + 1, ignore, subdir.mtime);
+            subdir.keep_link(&mut node);
+            node
+        })
+        .collect();
+    if let Some(sample) = &sample {
+        nodes.extend(ctx.estimate_skipped(&path, sample, &subdir_nodes));
+    }
+    nodes.extend(subdir_nodes);
+    ctx.tag_gitignored(&ignore, &mut nodes);
+
+    let name = node_name(&path);
+    let mut dir_node = Node::from_directory_with_overhead(path.clone(), name, overhead, nodes);
+    dir_node.modified = mtime;
+
+    ctx.emit_subtree(depth, &dir_node);
+    ctx.maybe_emit_progress(path);
+
+    dir_node
+}
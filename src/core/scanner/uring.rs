@@ -0,0 +1,244 @@
+//! Directory reads for `--backend uring`: entries are listed with `getdents` as
+//! usual, which io_uring has no operation for, and then all of their `statx` calls
+//! are submitted to the kernel as one batch instead of one system call each.
+
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::fs::File;
+use std::io;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use io_uring::{opcode, types, IoUring, Probe};
+
+use super::{add_shared_bytes, DirBatch, DirEntryData, EntryStat};
+
+/// Submission queue size, and so the most `statx` calls in flight per thread.
+const QUEUE_DEPTH: u32 = 256;
+
+thread_local! {
+    /// Each scan thread's ring, created on its first directory. `None` once a failed
+    /// submission left it in an unknown state.
+    static RING: RefCell<Option<IoUring>> = RefCell::new(IoUring::new(QUEUE_DEPTH).ok());
+}
+
+/// An entry's metadata as filled in by `statx`.
+pub struct Statx(libc::statx);
+
+impl Statx {
+    fn file_type(&self) -> u32 {
+        u32::from(self.0.stx_mode) & libc::S_IFMT
+    }
+}
+
+impl EntryStat for Statx {
+    fn is_dir(&self) -> bool {
+        self.file_type() == libc::S_IFDIR
+    }
+
+    fn is_file(&self) -> bool {
+        self.file_type() == libc::S_IFREG
+    }
+
+    fn is_link(&self) -> bool {
+        self.file_type() == libc::S_IFLNK
+    }
+
+    fn size(&self) -> u64 {
+        self.0.stx_size
+    }
+
+    fn mtime(&self) -> Option<SystemTime> {
+        if self.0.stx_mask & libc::STATX_MTIME == 0 {
+            return None;
+        }
+        let time = self.0.stx_mtime;
+        let nanos = Duration::from_nanos(u64::from(time.tv_nsec));
+        if time.tv_sec >= 0 {
+            UNIX_EPOCH.checked_add(Duration::from_secs(time.tv_sec as u64) + nanos)
+        } else {
+            UNIX_EPOCH
+                .checked_sub(Duration::from_secs(time.tv_sec.unsigned_abs()))
+                .and_then(|t| t.checked_add(nanos))
+        }
+    }
+
+    fn allocated_size(&self, _path: &Path) -> u64 {
+        self.0.stx_blocks * 512
+    }
+
+    fn is_hidden(&self, name: &str) -> bool {
+        name.starts_with('.')
+    }
+
+    fn dev(&self) -> u64 {
+        libc::makedev(self.0.stx_dev_major, self.0.stx_dev_minor)
+    }
+
+    fn ino(&self) -> u64 {
+        self.0.stx_ino
+    }
+
+    fn nlink(&self) -> u64 {
+        u64::from(self.0.stx_nlink)
+    }
+
+    fn uid(&self) -> u32 {
+        self.0.stx_uid
+    }
+
+    fn gid(&self) -> u32 {
+        self.0.stx_gid
+    }
+}
+
+/// Check that this kernel can create a ring and run `statx` on it, so a scan fails
+/// up front rather than on every directory.
+pub fn check_support() -> io::Result<()> {
+    let ring = IoUring::new(QUEUE_DEPTH)?;
+    let mut probe = Probe::new();
+    ring.submitter().register_probe(&mut probe)?;
+    if !probe.is_supported(opcode::Statx::CODE) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the kernel's io_uring has no statx operation",
+        ));
+    }
+    Ok(())
+}
+
+/// Like `read_dir_with_clones` without sampling, with the entries statted through
+/// this thread's ring.
+pub fn read_dir_batch(
+    dir_path: &Path,
+    stat_self: bool,
+    detect_clones: bool,
+) -> io::Result<DirBatch<Statx>> {
+    let mut batch = DirBatch {
+        entries: Vec::new(),
+        errors: Vec::new(),
+        overhead: 0,
+        unsampled: Vec::new(),
+    };
+
+    if stat_self {
+        batch.overhead = std::fs::metadata(dir_path)?.len();
+    }
+
+    // Entry names are looked up relative to the directory, which saves the kernel
+    // walking the full path again for each of them
+    let dir = File::open(dir_path)?;
+    let mut listed = Vec::new();
+    for entry_result in std::fs::read_dir(dir_path)? {
+        match entry_result {
+            Ok(entry) => {
+                let entry_path = entry.path();
+                let entry_name = entry.file_name().to_string_lossy().to_string();
+                match CString::new(entry.file_name().into_vec()) {
+                    Ok(c_name) => listed.push((entry_path, entry_name, c_name)),
+                    Err(e) => batch.errors.push((entry_path, e.to_string())),
+                }
+            }
+            Err(e) => {
+                batch.errors.push((dir_path.to_path_buf(), e.to_string()));
+            }
+        }
+    }
+
+    RING.with(|ring| {
+        let mut ring = ring.borrow_mut();
+        let Some(uring) = ring.as_mut() else {
+            return Err(io::Error::other("no io_uring available on this thread"));
+        };
+        let mut listed = listed.into_iter().peekable();
+        while listed.peek().is_some() {
+            let chunk: Vec<_> = listed.by_ref().take(QUEUE_DEPTH as usize).collect();
+            let names: Vec<&CString> = chunk.iter().map(|(_, _, c_name)| c_name).collect();
+            let stats = match stat_chunk(uring, &dir, &names) {
+                Ok(stats) => stats,
+                Err(e) => {
+                    // The kernel may still write to the names and buffers of requests
+                    // it already took, so they are leaked along with the ring
+                    std::mem::forget(chunk);
+                    *ring = None;
+                    return Err(e);
+                }
+            };
+            for ((path, name, _), stat) in chunk.into_iter().zip(stats) {
+                match stat {
+                    Ok(stat) => batch.entries.push(DirEntryData {
+                        path,
+                        name,
+                        metadata: Statx(stat),
+                        shared: 0,
+                    }),
+                    Err(e) => batch.errors.push((path, e.to_string())),
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    if detect_clones {
+        add_shared_bytes(&mut batch);
+    }
+    Ok(batch)
+}
+
+/// `statx` each of `names` in `dir` through `ring`, which must have room for all of
+/// them. Returns one result per name, in order.
+fn stat_chunk(
+    ring: &mut IoUring,
+    dir: &File,
+    names: &[&CString],
+) -> io::Result<Vec<io::Result<libc::statx>>> {
+    // SAFETY: `statx` is plain data, for which all zeroes is a valid value
+    let mut bufs: Vec<libc::statx> =
+        names.iter().map(|_| unsafe { std::mem::zeroed() }).collect();
+    for (index, (name, buf)) in names.iter().zip(bufs.iter_mut()).enumerate() {
+        let sqe = opcode::Statx::new(
+            types::Fd(dir.as_raw_fd()),
+            name.as_ptr(),
+            (buf as *mut libc::statx).cast(),
+        )
+        .flags(libc::AT_SYMLINK_NOFOLLOW)
+        .mask(libc::STATX_BASIC_STATS)
+        .build()
+        .user_data(index as u64);
+        // SAFETY: the name and buffer outlive the request: this function waits for
+        // every completion, and on failure the caller leaks them
+        unsafe { ring.submission().push(&sqe) }
+            .map_err(|_| io::Error::other("io_uring submission queue is full"))?;
+    }
+
+    let mut results = vec![0; names.len()];
+    let mut done = 0;
+    while done < names.len() {
+        match ring.submit_and_wait(names.len() - done) {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                std::mem::forget(bufs);
+                return Err(e);
+            }
+        }
+        for cqe in ring.completion() {
+            results[cqe.user_data() as usize] = cqe.result();
+            done += 1;
+        }
+    }
+
+    Ok(results
+        .into_iter()
+        .zip(bufs)
+        .map(|(result, buf)| {
+            if result < 0 {
+                Err(io::Error::from_raw_os_error(-result))
+            } else {
+                Ok(buf)
+            }
+        })
+        .collect())
+}
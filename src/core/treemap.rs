@@ -0,0 +1,153 @@
+//! Squarified treemap layout: the geometry only, in unit-square coordinates, for the
+//! TUI and HTML export to scale to whatever they draw on.
+
+use std::path::PathBuf;
+
+use crate::models::node::Node;
+
+/// An axis-aligned rectangle with its origin at the top left.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TreemapRect {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+}
+
+impl TreemapRect {
+    pub const UNIT: TreemapRect = TreemapRect { x: 0.0, y: 0.0, w: 1.0, h: 1.0 };
+
+    pub fn area(&self) -> f64 {
+        self.w * self.h
+    }
+}
+
+/// One entry's place in the treemap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreemapTile {
+    pub path: PathBuf,
+    pub name: String,
+    pub size: u64,
+    /// 0 for the laid-out node's children, 1 for theirs, and so on.
+    pub depth: usize,
+    pub is_container: bool,
+    pub rect: TreemapRect,
+}
+
+/// Tiles for everything below `node` down to `max_depth` levels (1 lays out its
+/// children only), parents before their children so drawing in order paints each
+/// level over the one containing it. A directory's entries fill its whole tile, and
+/// empty entries get none.
+pub fn layout(node: &Node, max_depth: usize) -> Vec<TreemapTile> {
+    let mut tiles = Vec::new();
+    layout_into(node, TreemapRect::UNIT, 0, max_depth, &mut tiles);
+    tiles
+}
+
+fn layout_into(
+    node: &Node,
+    bounds: TreemapRect,
+    depth: usize,
+    max_depth: usize,
+    tiles: &mut Vec<TreemapTile>,
+) {
+    if depth >= max_depth {
+        return;
+    }
+    let children: Vec<&Node> = node.children.iter().filter(|c| c.size > 0).collect();
+    let sizes: Vec<u64> = children.iter().map(|c| c.size).collect();
+    for (child, rect) in children.into_iter().zip(squarify(&sizes, bounds)) {
+        tiles.push(TreemapTile {
+            path: child.path.clone(),
+            name: child.name.clone(),
+            size: child.size,
+            depth,
+            is_container: child.is_container(),
+            rect,
+        });
+        if child.is_container() {
+            layout_into(child, rect, depth + 1, max_depth, tiles);
+        }
+    }
+}
+
+/// Split `bounds` into one rectangle per size, in the order given, with areas in
+/// proportion to the sizes. Rows are built largest first and each is closed once
+/// adding another entry would make its worst aspect ratio worse (Bruls, Huizing and
+/// van Wijk's squarified algorithm), which keeps tiles close to square.
+pub fn squarify(sizes: &[u64], bounds: TreemapRect) -> Vec<TreemapRect> {
+    let total: u64 = sizes.iter().sum();
+    let empty = TreemapRect { w: 0.0, h: 0.0, ..bounds };
+    let mut rects = vec![empty; sizes.len()];
+    if total == 0 || bounds.area() <= 0.0 {
+        return rects;
+    }
+
+    let scale = bounds.area() / total as f64;
+    let mut order: Vec<usize> = (0..sizes.len()).filter(|&i| sizes[i] > 0).collect();
+    order.sort_by(|&a, &b| sizes[b].cmp(&sizes[a]));
+    let area = |i: usize| sizes[i] as f64 * scale;
+
+    let mut remaining = bounds;
+    let mut row: Vec<usize> = Vec::new();
+    let mut next = 0;
+    while next < order.len() {
+        let side = remaining.w.min(remaining.h);
+        let current = worst_ratio(row.iter().map(|&i| area(i)), side);
+        let extended = worst_ratio(row.iter().chain([&order[next]]).map(|&i| area(i)), side);
+        if row.is_empty() || extended <= current {
+            row.push(order[next]);
+            next += 1;
+        } else {
+            remaining = place_row(&row, &area, remaining, &mut rects);
+            row.clear();
+        }
+    }
+    if !row.is_empty() {
+        place_row(&row, &area, remaining, &mut rects);
+    }
+    rects
+}
+
+/// The largest aspect ratio among tiles of these areas laid side by side along `side`.
+fn worst_ratio(areas: impl Iterator<Item = f64> + Clone, side: f64) -> f64 {
+    let sum: f64 = areas.clone().sum();
+    let (min, max) = areas.fold((f64::INFINITY, 0.0_f64), |(lo, hi), a| (lo.min(a), hi.max(a)));
+    if sum <= 0.0 || min <= 0.0 {
+        return f64::INFINITY;
+    }
+    let side2 = side * side;
+    let sum2 = sum * sum;
+    (side2 * max / sum2).max(sum2 / (side2 * min))
+}
+
+/// Lay `row` along the shorter side of `bounds` and return the space left beside it.
+fn place_row(
+    row: &[usize],
+    area: &impl Fn(usize) -> f64,
+    bounds: TreemapRect,
+    rects: &mut [TreemapRect],
+) -> TreemapRect {
+    let sum: f64 = row.iter().map(|&i| area(i)).sum();
+    if bounds.w >= bounds.h {
+        // A column down the left edge
+        let thickness = (sum / bounds.h).min(bounds.w);
+        let mut y = bounds.y;
+        for &i in row {
+            let h = area(i) / thickness;
+            rects[i] = TreemapRect { x: bounds.x, y, w: thickness, h };
+            y += h;
+        }
+        TreemapRect { x: bounds.x + thickness, w: bounds.w - thickness, ..bounds }
+    } else {
+        // A row along the top edge
+        let thickness = (sum / bounds.w).min(bounds.h);
+        let mut x = bounds.x;
+        for &i in row {
+            let w = area(i) / thickness;
+            rects[i] = TreemapRect { x, y: bounds.y, w, h: thickness };
+            x += w;
+        }
+        TreemapRect { y: bounds.y + thickness, h: bounds.h - thickness, ..bounds }
+    }
+}
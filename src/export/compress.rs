@@ -0,0 +1,107 @@
+//! Compressed export files, chosen by file name: `.gz` for gzip and `.zst` for zstd,
+//! so a full-tree export of a large volume doesn't take hundreds of megabytes.
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+/// The level zstd picks when asked for its default.
+const ZSTD_LEVEL: i32 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The compression a file's extension asks for, if any.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Compression::Gzip),
+            "zst" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// `path` with `.zst` appended unless its extension already asks for compression.
+pub fn compressed_path(path: &Path) -> PathBuf {
+    if Compression::from_path(path).is_some() {
+        return path.to_path_buf();
+    }
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".zst");
+    PathBuf::from(name)
+}
+
+/// An export file being written, compressed when its name asks for it. Call
+/// [`OutputFile::finish`] once done: a compressed stream isn't complete until then.
+pub struct OutputFile {
+    inner: Output,
+}
+
+enum Output {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl OutputFile {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        let inner = match Compression::from_path(path) {
+            None => Output::Plain(file),
+            Some(Compression::Gzip) => {
+                Output::Gzip(GzEncoder::new(file, flate2::Compression::default()))
+            }
+            Some(Compression::Zstd) => Output::Zstd(zstd::Encoder::new(file, ZSTD_LEVEL)?),
+        };
+        Ok(Self { inner })
+    }
+
+    /// End the compressed stream, if any, and flush everything to the file.
+    pub fn finish(self) -> io::Result<()> {
+        let mut file = match self.inner {
+            Output::Plain(file) => file,
+            Output::Gzip(encoder) => encoder.finish()?,
+            Output::Zstd(encoder) => encoder.finish()?,
+        };
+        file.flush()
+    }
+}
+
+impl Write for OutputFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Output::Plain(file) => file.write(buf),
+            Output::Gzip(encoder) => encoder.write(buf),
+            Output::Zstd(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Output::Plain(file) => file.flush(),
+            Output::Gzip(encoder) => encoder.flush(),
+            Output::Zstd(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Read a whole file, decompressing it when its name says it's compressed.
+pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+    let file = BufReader::new(File::open(path)?);
+    let mut reader: Box<dyn Read> = match Compression::from_path(path) {
+        None => Box::new(file),
+        Some(Compression::Gzip) => Box::new(MultiGzDecoder::new(file)),
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::with_buffer(file)?),
+    };
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
@@ -0,0 +1,135 @@
+//! Comma-separated exports, for getting results into spreadsheets.
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::core::analyzer::OwnerUsage;
+use crate::export::compress::OutputFile;
+use crate::models::node::{Node, SizeMode};
+use crate::models::scan_result::ScanResult;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CsvOptions {
+    /// Deepest level to write, counting the scanned path as 0; all levels when `None`.
+    pub max_depth: Option<usize>,
+    /// Separates fields: a comma by default, or e.g. `;` or a tab for spreadsheets in
+    /// locales that use the comma as decimal separator.
+    pub delimiter: char,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self { max_depth: None, delimiter: ',' }
+    }
+}
+
+/// Write every node of the scan to `output_path`, one row each.
+pub fn export_tree_csv(
+    result: &ScanResult,
+    output_path: &Path,
+    options: &CsvOptions,
+) -> anyhow::Result<()> {
+    let mut out = OutputFile::create(output_path)?;
+    write_tree_csv(result, &mut out, options)?;
+    out.finish()?;
+    Ok(())
+}
+
+/// One row per node, parents before their children: path, type, apparent size and
+/// size on disk in bytes, files below it, modification time (RFC 3339, UTC; empty
+/// when unknown) and depth. With several roots each is written at depth 0.
+pub fn write_tree_csv(
+    result: &ScanResult,
+    out: &mut dyn Write,
+    options: &CsvOptions,
+) -> io::Result<()> {
+    let d = options.delimiter;
+    writeln!(out, "path{d}type{d}size{d}size_on_disk{d}file_count{d}mtime{d}depth")?;
+    let roots = if result.is_multi_root() {
+        result.root.children.iter().collect()
+    } else {
+        vec![&result.root]
+    };
+    for root in roots {
+        write_node_rows(root, result.size_mode, 0, out, options)?;
+    }
+    Ok(())
+}
+
+fn write_node_rows(
+    node: &Node,
+    size_mode: SizeMode,
+    depth: usize,
+    out: &mut dyn Write,
+    options: &CsvOptions,
+) -> io::Result<()> {
+    if options.max_depth.is_some_and(|max| depth > max) {
+        return Ok(());
+    }
+    // `size` holds whichever measure the scan reports; the other is in `size_on_disk`
+    let (size, on_disk) = match size_mode {
+        SizeMode::Apparent => (node.size, node.size_on_disk),
+        SizeMode::DiskUsage => (node.size_on_disk, node.size),
+    };
+    let mtime = node
+        .modified
+        .map(|time| {
+            let time = chrono::DateTime::<chrono::Utc>::from(time);
+            time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        })
+        .unwrap_or_default();
+    let d = options.delimiter;
+    writeln!(
+        out,
+        "{}{d}{}{d}{}{d}{}{d}{}{d}{}{d}{}",
+        field(&node.path.to_string_lossy(), d),
+        node.node_type.label(),
+        size,
+        on_disk,
+        node.file_count,
+        mtime,
+        depth,
+    )?;
+    for child in &node.children {
+        write_node_rows(child, size_mode, depth + 1, out, options)?;
+    }
+    Ok(())
+}
+
+/// Write the per-user table from `Analyzer::usage_by_owner` to `output_path`.
+pub fn export_owners_csv(usage: &[OwnerUsage], output_path: &Path) -> anyhow::Result<()> {
+    let mut out = OutputFile::create(output_path)?;
+    write_owners_csv(usage, &mut out)?;
+    out.finish()?;
+    Ok(())
+}
+
+/// One row per owner: login name (empty when unknown), numeric ID (empty when none
+/// was recorded), bytes, file count and share of the listed total.
+pub fn write_owners_csv(usage: &[OwnerUsage], out: &mut dyn Write) -> io::Result<()> {
+    let total: u64 = usage.iter().map(|u| u.size).sum();
+    writeln!(out, "user,uid,size_bytes,files,percent")?;
+    for owner in usage {
+        writeln!(
+            out,
+            "{},{},{},{},{:.2}",
+            field(owner.name.as_deref().unwrap_or(""), ','),
+            owner.uid.map(|uid| uid.to_string()).unwrap_or_default(),
+            owner.size,
+            owner.file_count,
+            owner.size as f64 / total.max(1) as f64 * 100.0,
+        )?;
+    }
+    Ok(())
+}
+
+/// `value` quoted as RFC 4180 requires when it holds the delimiter, a quote or a line
+/// break.
+fn field(value: &str, delimiter: char) -> Cow<'_, str> {
+    if value.contains([delimiter, '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
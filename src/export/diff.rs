@@ -0,0 +1,196 @@
+//! Reports comparing two scans of the same tree, in Markdown or HTML: the biggest
+//! growers and shrinkers, and the directories that appeared or disappeared.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::core::diff::{ChangeKind, DiffResult, NodeChange};
+use crate::export::compress::OutputFile;
+use crate::export::html::escape_html;
+use crate::models::node::{human_readable_delta, human_readable_size};
+use crate::models::scan_result::ScanResult;
+
+/// A report section: a title and the changes it lists, largest first.
+struct Section<'a> {
+    title: &'static str,
+    changes: Vec<&'a NodeChange>,
+}
+
+/// Up to `limit` changes per section. The root's own change is the summary's.
+fn sections<'a>(diff: &'a DiffResult, new: &ScanResult, limit: usize) -> Vec<Section<'a>> {
+    let pick = |keep: fn(&NodeChange) -> bool| {
+        diff.changes
+            .iter()
+            .filter(|c| c.path != new.root.path && keep(c))
+            .take(limit)
+            .collect()
+    };
+    vec![
+        Section {
+            title: "Biggest Growers",
+            changes: pick(|c| c.kind == ChangeKind::Grown),
+        },
+        Section {
+            title: "New Directories",
+            changes: pick(|c| c.kind == ChangeKind::Added && c.is_dir),
+        },
+        Section {
+            title: "Removed Directories",
+            changes: pick(|c| c.kind == ChangeKind::Removed && c.is_dir),
+        },
+        Section {
+            title: "Biggest Shrinkers",
+            changes: pick(|c| c.kind == ChangeKind::Shrunk),
+        },
+    ]
+}
+
+fn date(time: std::time::SystemTime) -> String {
+    chrono::DateTime::<chrono::Local>::from(time).format("%Y-%m-%d %H:%M").to_string()
+}
+
+pub fn export_diff_markdown(
+    old: &ScanResult,
+    new: &ScanResult,
+    output_path: &Path,
+    limit: usize,
+) -> anyhow::Result<()> {
+    let mut out = OutputFile::create(output_path)?;
+    write_diff_markdown(old, new, &mut out, limit)?;
+    out.finish()?;
+    Ok(())
+}
+
+/// The comparison of `old` with `new` as Markdown, with at most `limit` rows a section.
+pub fn write_diff_markdown(
+    old: &ScanResult,
+    new: &ScanResult,
+    md: &mut dyn Write,
+    limit: usize,
+) -> io::Result<()> {
+    let diff = DiffResult::compare(old, new);
+    writeln!(md, "# DiskLens Diff Report")?;
+    writeln!(md)?;
+    writeln!(md, "- **Path:** {}", new.display_path())?;
+    let before = human_readable_size(diff.old_total);
+    let after = human_readable_size(diff.new_total);
+    writeln!(md, "- **Before:** {} ({})", before, date(diff.old_timestamp))?;
+    writeln!(md, "- **After:** {} ({})", after, date(diff.new_timestamp))?;
+    writeln!(md, "- **Change:** {}", human_readable_delta(diff.delta()))?;
+
+    for section in sections(&diff, new, limit) {
+        if section.changes.is_empty() {
+            continue;
+        }
+        writeln!(md)?;
+        writeln!(md, "## {}", section.title)?;
+        writeln!(md)?;
+        writeln!(md, "| Path | Before | After | Change |")?;
+        writeln!(md, "|------|--------|-------|--------|")?;
+        for change in section.changes {
+            writeln!(
+                md,
+                "| {}{} | {} | {} | {} |",
+                change.path.display().to_string().replace('|', "\\|"),
+                if change.is_dir { "/" } else { "" },
+                human_readable_size(change.old_size),
+                human_readable_size(change.new_size),
+                human_readable_delta(change.delta()),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+pub fn export_diff_html(
+    old: &ScanResult,
+    new: &ScanResult,
+    output_path: &Path,
+    limit: usize,
+) -> anyhow::Result<()> {
+    let mut out = OutputFile::create(output_path)?;
+    write_diff_html(old, new, &mut out, limit)?;
+    out.finish()?;
+    Ok(())
+}
+
+/// The comparison of `old` with `new` as a standalone HTML page, styled like the scan
+/// report, with at most `limit` rows a section.
+pub fn write_diff_html(
+    old: &ScanResult,
+    new: &ScanResult,
+    html: &mut dyn Write,
+    limit: usize,
+) -> io::Result<()> {
+    let diff = DiffResult::compare(old, new);
+    write!(html, r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="UTF-8">
+<title>DiskLens Diff Report</title>
+<style>
+    body {{ font-family: -apple-system, system-ui, sans-serif; margin: 20px; background: #1a1a2e; color: #e0e0e0; }}
+    h1 {{ color: #00d4ff; }}
+    h2 {{ color: #5dade2; margin-top: 30px; }}
+    .summary {{ background: #16213e; padding: 15px; border-radius: 8px; margin-bottom: 20px; }}
+    .summary p {{ margin: 6px 0; }}
+    .summary strong {{ color: #00d4ff; }}
+    table {{ border-collapse: collapse; }}
+    th, td {{ padding: 4px 12px; text-align: left; border-bottom: 1px solid #16213e; }}
+    td.size {{ text-align: right; color: #aaa; }}
+    .grown {{ color: #e74c3c; }}
+    .shrunk {{ color: #2ecc71; }}
+</style>
+</head>
+<body>
+"#)?;
+    writeln!(html, "<h1>DiskLens Diff Report</h1>")?;
+    writeln!(html, "<div class=\"summary\">")?;
+    writeln!(html, "<p><strong>Path:</strong> {}</p>", escape_html(&new.display_path()))?;
+    let before = human_readable_size(diff.old_total);
+    let after = human_readable_size(diff.new_total);
+    writeln!(html, "<p><strong>Before:</strong> {} ({})</p>", before, date(diff.old_timestamp))?;
+    writeln!(html, "<p><strong>After:</strong> {} ({})</p>", after, date(diff.new_timestamp))?;
+    writeln!(
+        html,
+        "<p><strong>Change:</strong> <span class=\"{}\">{}</span></p>",
+        delta_class(diff.delta()),
+        human_readable_delta(diff.delta()),
+    )?;
+    writeln!(html, "</div>")?;
+
+    for section in sections(&diff, new, limit) {
+        if section.changes.is_empty() {
+            continue;
+        }
+        writeln!(html, "<h2>{}</h2>", section.title)?;
+        writeln!(html, "<table>")?;
+        writeln!(html, "<tr><th>Path</th><th>Before</th><th>After</th><th>Change</th></tr>")?;
+        for change in section.changes {
+            writeln!(
+                html,
+                "<tr><td>{}{}</td><td class=\"size\">{}</td><td class=\"size\">{}</td>\
+                 <td class=\"size {}\">{}</td></tr>",
+                escape_html(&change.path.display().to_string()),
+                if change.is_dir { "/" } else { "" },
+                human_readable_size(change.old_size),
+                human_readable_size(change.new_size),
+                delta_class(change.delta()),
+                human_readable_delta(change.delta()),
+            )?;
+        }
+        writeln!(html, "</table>")?;
+    }
+
+    write!(html, "</body>\n</html>")?;
+    Ok(())
+}
+
+/// Growth shows in red and shrinkage in green, as space lost and space won.
+fn delta_class(delta: i64) -> &'static str {
+    match delta.cmp(&0) {
+        std::cmp::Ordering::Greater => "grown",
+        std::cmp::Ordering::Less => "shrunk",
+        std::cmp::Ordering::Equal => "",
+    }
+}
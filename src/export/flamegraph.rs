@@ -0,0 +1,58 @@
+//! Folded-stack export (`root;dir;file 1234`, in bytes), the input `inferno-flamegraph`
+//! and `flamegraph.pl` draw flame graphs from, so disk usage can sit beside profiles.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::core::profile::fold_frame;
+use crate::export::compress::OutputFile;
+use crate::models::node::Node;
+use crate::models::scan_result::ScanResult;
+
+/// Write the scan to `output_path` as folded stacks; see [`write_folded`].
+pub fn export_folded(result: &ScanResult, output_path: &Path) -> anyhow::Result<()> {
+    let mut out = OutputFile::create(output_path)?;
+    write_folded(result, &mut out)?;
+    out.finish()?;
+    Ok(())
+}
+
+/// One line per entry holding bytes of its own: each file, and each directory whose size
+/// exceeds its entries' (its own blocks, folded small files). Frames are entry names from
+/// the scanned path down, so a frame's width is the entry's size. With several roots each
+/// starts its own stack, named by its full path.
+pub fn write_folded(result: &ScanResult, out: &mut dyn Write) -> io::Result<()> {
+    let mut stack = String::new();
+    if result.is_multi_root() {
+        for root in &result.root.children {
+            write_stacks(root, &root.path.to_string_lossy(), &mut stack, out)?;
+        }
+        Ok(())
+    } else {
+        write_stacks(&result.root, &result.root.name, &mut stack, out)
+    }
+}
+
+fn write_stacks(
+    node: &Node,
+    name: &str,
+    stack: &mut String,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let len = stack.len();
+    if len > 0 {
+        stack.push(';');
+    }
+    stack.push_str(&fold_frame(name));
+
+    let children: u64 = node.children.iter().map(|c| c.size).sum();
+    let own = node.size.saturating_sub(children);
+    if own > 0 {
+        writeln!(out, "{} {}", stack, own)?;
+    }
+    for child in &node.children {
+        write_stacks(child, &child.name, stack, out)?;
+    }
+    stack.truncate(len);
+    Ok(())
+}
@@ -1,12 +1,209 @@
-use std::fmt::Write;
+use std::io::{self, Write};
 use std::path::Path;
+use std::time::SystemTime;
 
+use crate::core::analyzer::Analyzer;
+use crate::export::compress::OutputFile;
+use crate::models::category::{category_totals, FileCategory};
+use crate::models::index::SizeIndex;
 use crate::models::node::{human_readable_size, Node, NodeType};
 use crate::models::scan_result::ScanResult;
 
-pub fn export_html(result: &ScanResult, output_path: &Path) -> anyhow::Result<()> {
-    let mut html = String::new();
+/// Directories with more children than this list the largest ones and summarize the rest,
+/// so huge trees still produce a report a browser can open.
+pub const MAX_CHILDREN_PER_DIR: usize = 200;
 
+/// Most extensions listed in the file type section, the largest first.
+pub const MAX_EXTENSIONS: usize = 30;
+
+/// Files listed in the largest files section when no `--top` is given.
+pub const DEFAULT_TOP_FILES: usize = 20;
+
+/// Most cleanup suggestions listed, the highest ranked first.
+pub const MAX_SUGGESTIONS: usize = 50;
+
+/// Most sparse files listed in their section, those with the largest holes first.
+pub const MAX_SPARSE_FILES: usize = 100;
+
+/// Depth below the root down to which directories are expanded.
+const MAX_DEPTH: usize = 4;
+
+/// Levels of the tree embedded in the interactive treemap.
+const TREEMAP_DEPTH: usize = 8;
+
+/// Entries under this share of the whole tree are left out of the treemap's data and
+/// shown within their directory's "(other)" tile, which keeps large reports small.
+const TREEMAP_MIN_SHARE: f64 = 0.0001;
+
+/// Lays out and draws the treemap from the embedded data, zooming on click.
+const TREEMAP_SCRIPT: &str = r#"(() => {
+    const data = JSON.parse(document.getElementById('treemap-data').textContent);
+    const box = document.getElementById('treemap');
+    const crumbs = document.getElementById('treemap-path');
+    const units = ['B', 'KB', 'MB', 'GB', 'TB'];
+    const human = (bytes) => {
+        let i = 0;
+        let n = bytes;
+        while (n >= 1024 && i < units.length - 1) {
+            n /= 1024;
+            i++;
+        }
+        return i === 0 ? bytes + ' B' : n.toFixed(2) + ' ' + units[i];
+    };
+    const entries = (node) => (node[3] || []).filter((n) => n[1] > 0).sort((a, b) => b[1] - a[1]);
+    const sum = (values) => values.reduce((a, b) => a + b, 0);
+    // The largest aspect ratio among tiles of these areas laid along `side`
+    const worst = (areas, side) => {
+        const total = sum(areas);
+        return Math.max(
+            side * side * Math.max(...areas) / (total * total),
+            total * total / (side * side * Math.min(...areas)),
+        );
+    };
+    // Squarified layout, the same as the TUI's: rows are closed once another entry
+    // would make their worst aspect ratio worse
+    const squarify = (nodes, x, y, w, h) => {
+        const scale = w * h / sum(nodes.map((n) => n[1]));
+        const rects = [];
+        let row = [];
+        let areas = [];
+        const place = () => {
+            const column = w >= h;
+            const t = column ? Math.min(sum(areas) / h, w) : Math.min(sum(areas) / w, h);
+            let offset = 0;
+            row.forEach((node, i) => {
+                const length = areas[i] / t;
+                rects.push(column
+                    ? [node, x, y + offset, t, length]
+                    : [node, x + offset, y, length, t]);
+                offset += length;
+            });
+            if (column) {
+                x += t;
+                w -= t;
+            } else {
+                y += t;
+                h -= t;
+            }
+            row = [];
+            areas = [];
+        };
+        for (const node of nodes) {
+            const area = node[1] * scale;
+            const side = Math.min(w, h);
+            if (row.length && worst(areas.concat(area), side) > worst(areas, side)) {
+                place();
+            }
+            row.push(node);
+            areas.push(area);
+        }
+        if (row.length) {
+            place();
+        }
+        return rects;
+    };
+    let stack = [data];
+    const pathOf = (names) => names.join('/').replace(/\/\/+/g, '/');
+    const tile = (parent, node, names, x, y, w, h, nested) => {
+        const el = document.createElement('div');
+        el.className = 'tile' + (nested ? ' nested' : '');
+        el.style.left = x + 'px';
+        el.style.top = y + 'px';
+        el.style.width = w + 'px';
+        el.style.height = h + 'px';
+        el.style.background = node[2] === 'dir'
+            ? (nested ? '#1f4e79' : '#0f3460')
+            : (TREEMAP_COLORS[node[2]] || '#444');
+        el.title = pathOf(names.concat(node[0])) + '\n' + human(node[1]);
+        if (w > 40 && h > 14) {
+            el.textContent = node[0] + ' ' + human(node[1]);
+        }
+        // Clicking zooms into the directory, or for a file inside one, into that
+        const zoom = node[2] === 'dir' ? [parent, node] : [parent];
+        if (zoom.some((n) => n)) {
+            el.onclick = () => {
+                stack = stack.concat(zoom.filter((n) => n));
+                render();
+            };
+        }
+        box.appendChild(el);
+        return el;
+    };
+    const render = () => {
+        box.replaceChildren();
+        crumbs.replaceChildren();
+        stack.forEach((node, i) => {
+            const link = document.createElement('a');
+            link.textContent = node[0];
+            link.onclick = () => {
+                stack = stack.slice(0, i + 1);
+                render();
+            };
+            crumbs.append(i ? ' / ' : '', link);
+        });
+        const current = stack[stack.length - 1];
+        const names = stack.map((n) => n[0]);
+        const rects = squarify(entries(current), 0, 0, box.clientWidth, box.clientHeight);
+        for (const [node, x, y, w, h] of rects) {
+            tile(null, node, names, x, y, w, h, false);
+            // A second level inside each directory large enough to show it
+            if (node[2] === 'dir' && w > 60 && h > 40) {
+                const inner = squarify(entries(node), x + 2, y + 16, w - 4, h - 18);
+                for (const [child, cx, cy, cw, ch] of inner) {
+                    tile(node, child, names.concat(node[0]), cx, cy, cw, ch, true);
+                }
+            }
+        }
+    };
+    window.addEventListener('resize', render);
+    render();
+})();
+"#;
+
+/// Levels of the tree that start expanded when no `--expand-depth` is given: just the root.
+pub const DEFAULT_EXPAND_DEPTH: usize = 1;
+
+#[derive(Debug, Clone, Copy)]
+pub struct HtmlOptions {
+    /// Directories less than this many levels deep start expanded.
+    pub expand_depth: usize,
+    /// Color file rows by category and add a category breakdown with a legend.
+    pub categories: bool,
+    /// How many of the largest files to list; none when zero.
+    pub top_files: usize,
+    /// Embed the tree and a script drawing it as a zoomable treemap, so the report can
+    /// be explored without DiskLens.
+    pub treemap: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            expand_depth: DEFAULT_EXPAND_DEPTH,
+            categories: false,
+            top_files: DEFAULT_TOP_FILES,
+            treemap: false,
+        }
+    }
+}
+
+pub fn export_html(
+    result: &ScanResult,
+    output_path: &Path,
+    options: &HtmlOptions,
+) -> anyhow::Result<()> {
+    let mut out = OutputFile::create(output_path)?;
+    write_html(result, &mut out, options)?;
+    out.finish()?;
+    Ok(())
+}
+
+/// Stream the report to `html` as it is generated rather than building it in memory.
+pub fn write_html(
+    result: &ScanResult,
+    html: &mut dyn Write,
+    options: &HtmlOptions,
+) -> io::Result<()> {
     write!(html, r#"<!DOCTYPE html>
 <html lang="en">
 <head>
@@ -22,12 +219,20 @@ pub fn export_html(result: &ScanResult, output_path: &Path) -> anyhow::Result<()
     .node {{ display: flex; align-items: center; padding: 4px 0; }}
     .name {{ min-width: 300px; }}
     .size {{ min-width: 100px; text-align: right; color: #aaa; margin-right: 10px; }}
+    .unique {{ min-width: 120px; text-align: right; color: #7a9; margin-right: 10px; }}
     .pct {{ min-width: 50px; text-align: right; color: #888; margin-right: 10px; }}
     .bar {{ width: 200px; height: 16px; background: #0f3460; border-radius: 3px; overflow: hidden; }}
     .bar-fill {{ height: 100%; border-radius: 3px; background: linear-gradient(90deg, #00d4ff, #0f3460); }}
     .dir {{ color: #5dade2; }}
     .file {{ color: #aaa; }}
     .error {{ color: #e74c3c; }}
+    .error-summary {{ border-collapse: collapse; margin-bottom: 10px; }}
+    .error-summary th, .error-summary td {{ padding: 4px 12px; text-align: left; border-bottom: 1px solid #2c1a1a; }}
+    .sparse-files, .file-types, .largest-files, .suggestions {{ border-collapse: collapse; }}
+    .sparse-files th, .sparse-files td, .file-types th, .file-types td, .largest-files th, .largest-files td, .suggestions th, .suggestions td {{ padding: 4px 12px; text-align: left; border-bottom: 1px solid #16213e; }}
+    .risk-low {{ color: #2ecc71; }}
+    .risk-medium {{ color: #f1c40f; }}
+    .risk-high {{ color: #e74c3c; }}
     .error-list {{ background: #2c1a1a; padding: 15px; border-radius: 8px; border-left: 3px solid #e74c3c; }}
     details {{ margin-left: 20px; }}
     summary {{ cursor: pointer; padding: 4px; }}
@@ -38,47 +243,76 @@ pub fn export_html(result: &ScanResult, output_path: &Path) -> anyhow::Result<()
 "#)?;
 
     // Summary section
-    write!(html, "<h1>DiskLens Report</h1>\n")?;
-    write!(html, "<div class=\"summary\">\n")?;
-    write!(html, "<p><strong>Path:</strong> {}</p>\n", escape_html(&result.scan_path.display().to_string()))?;
-    write!(html, "<p><strong>Total Size:</strong> {}</p>\n", human_readable_size(result.total_size))?;
-    write!(html, "<p><strong>Files:</strong> {}</p>\n", result.total_files)?;
-    write!(html, "<p><strong>Directories:</strong> {}</p>\n", result.total_dirs)?;
-    write!(html, "<p><strong>Scan Duration:</strong> {:.2}s</p>\n", result.scan_duration.as_secs_f64())?;
-    write!(html, "</div>\n")?;
+    writeln!(html, "<h1>DiskLens Report</h1>")?;
+    writeln!(html, "<div class=\"summary\">")?;
+    writeln!(html, "<p><strong>Path:</strong> {}</p>", escape_html(&result.display_path()))?;
+    writeln!(html, "<p><strong>Total Size:</strong> {}</p>", human_readable_size(result.total_size))?;
+    writeln!(html, "<p><strong>Files:</strong> {}</p>", result.total_files)?;
+    writeln!(html, "<p><strong>Directories:</strong> {}</p>", result.total_dirs)?;
+    if result.root.shared_size > 0 {
+        writeln!(html, "<p><strong>Shared with clones:</strong> {}</p>", human_readable_size(result.root.shared_size))?;
+    }
+    let gitignored = result.root.gitignored_size();
+    if gitignored > 0 {
+        writeln!(html, "<p><strong>Ignored by git:</strong> {}</p>", human_readable_size(gitignored))?;
+    }
+    writeln!(html, "<p><strong>Scan Duration:</strong> {:.2}s</p>", result.scan_duration.as_secs_f64())?;
+    for note in result.clone_note().into_iter().chain(result.estimate_note()).chain(result.truncation_note()) {
+        writeln!(html, "<p><strong>Note:</strong> {}</p>", escape_html(&note))?;
+    }
+    writeln!(html, "</div>")?;
+
+    if options.categories {
+        write_categories_html(html, &result.root)?;
+    }
+    write_file_types_html(html, &result.root)?;
+    write_largest_files_html(html, &result.root, options.top_files)?;
+    write_suggestions_html(html, &result.root)?;
+    if options.treemap {
+        write_treemap_html(html, result)?;
+    }
 
     // Directory tree
-    write!(html, "<h2>Directory Tree</h2>\n")?;
-    write_node_html(&mut html, &result.root, result.total_size, 0, 4)?;
+    writeln!(html, "<h2>Directory Tree</h2>")?;
+    // Clone detection ran and found shared extents
+    let unique = result.root.shared_size > 0;
+    write_node_html(html, &result.root, result.total_size, 0, MAX_DEPTH, unique, options)?;
+
+    write_sparse_html(html, result)?;
 
     // Error list
     if !result.errors.is_empty() {
-        write!(html, "<h2>Errors ({} total)</h2>\n", result.errors.len())?;
-        write!(html, "<div class=\"error-list\">\n<ul>\n")?;
+        writeln!(html, "<h2>Errors ({} total)</h2>", result.errors.len())?;
+        writeln!(html, "<table class=\"error-summary\">\n<tr><th>Type</th><th>Count</th></tr>")?;
+        for (error_type, count) in result.error_summary().counts() {
+            writeln!(html, "<tr><td>{:?}</td><td>{}</td></tr>", error_type, count)?;
+        }
+        writeln!(html, "</table>")?;
+        writeln!(html, "<div class=\"error-list\">\n<ul>")?;
         for err in &result.errors {
-            write!(
+            writeln!(
                 html,
-                "<li class=\"error\"><strong>{:?}</strong>: {}</li>\n",
+                "<li class=\"error\"><strong>{:?}</strong>: {}</li>",
                 err.error_type,
                 escape_html(&err.path.display().to_string()),
             )?;
         }
-        write!(html, "</ul>\n</div>\n")?;
+        writeln!(html, "</ul>\n</div>")?;
     }
 
     write!(html, "</body>\n</html>")?;
-
-    std::fs::write(output_path, html)?;
     Ok(())
 }
 
 fn write_node_html(
-    html: &mut String,
+    html: &mut dyn Write,
     node: &Node,
     total_size: u64,
     depth: usize,
     max_depth: usize,
-) -> std::fmt::Result {
+    unique: bool,
+    options: &HtmlOptions,
+) -> io::Result<()> {
     if depth > max_depth {
         return Ok(());
     }
@@ -86,52 +320,320 @@ fn write_node_html(
     let pct = node.percentage(total_size);
     let bar_width = (pct * 2.0).min(200.0);
     let name_class = match node.node_type {
-        NodeType::Directory => "dir",
-        _ => "file",
+        NodeType::Directory => "dir".to_string(),
+        NodeType::File if options.categories => {
+            format!("file cat-{}", node.file_category().name().to_lowercase())
+        }
+        _ => "file".to_string(),
     };
     let icon = match node.node_type {
         NodeType::Directory => "📁",
         NodeType::File => "📄",
         NodeType::Symlink => "🔗",
         NodeType::Other => "❓",
+        NodeType::ArchiveEntry => "📦",
+        NodeType::Summary => "📊",
     };
 
-    let has_children = node.node_type == NodeType::Directory && !node.children.is_empty() && depth < max_depth;
+    let has_children = !node.children.is_empty() && depth < max_depth;
 
     if has_children {
-        write!(html, "<details{}>\n", if depth == 0 { " open" } else { "" })?;
+        writeln!(html, "<details{}>", if depth < options.expand_depth { " open" } else { "" })?;
         write!(html, "<summary>")?;
         write!(html, "<span class=\"node\">")?;
-        write!(html, "<span class=\"name {name_class}\">{icon} {}</span>", escape_html(&node.name))?;
-        write!(html, "<span class=\"size\">{}</span>", human_readable_size(node.size))?;
+        write!(html, "<span class=\"name {name_class}\">{icon} {}</span>", escape_html(&node.name_with_target()))?;
+        write!(html, "<span class=\"size\">{}</span>", node.size_label())?;
+        if unique {
+            write!(html, "<span class=\"unique\">{} unique</span>", human_readable_size(node.unique_size()))?;
+        }
         write!(html, "<span class=\"pct\">{pct:.1}%</span>")?;
         write!(html, "<span class=\"bar\"><span class=\"bar-fill\" style=\"width:{bar_width:.0}px\"></span></span>")?;
         write!(html, "</span>")?;
-        write!(html, "</summary>\n")?;
+        writeln!(html, "</summary>")?;
 
         let mut children: Vec<&Node> = node.children.iter().collect();
-        children.sort_by(|a, b| b.size.cmp(&a.size));
+        children.sort_by_key(|c| std::cmp::Reverse(c.size));
 
-        for child in children {
-            write_node_html(html, child, total_size, depth + 1, max_depth)?;
+        for child in children.iter().take(MAX_CHILDREN_PER_DIR) {
+            write_node_html(html, child, total_size, depth + 1, max_depth, unique, options)?;
+        }
+        if children.len() > MAX_CHILDREN_PER_DIR {
+            let rest = &children[MAX_CHILDREN_PER_DIR..];
+            let rest_size: u64 = rest.iter().map(|c| c.size).sum();
+            writeln!(
+                html,
+                "<div style=\"margin-left:20px\" class=\"file\">… {} smaller items ({})</div>",
+                rest.len(),
+                human_readable_size(rest_size),
+            )?;
         }
 
-        write!(html, "</details>\n")?;
+        writeln!(html, "</details>")?;
     } else {
         write!(html, "<div style=\"margin-left:20px\">")?;
         write!(html, "<span class=\"node\">")?;
-        write!(html, "<span class=\"name {name_class}\">{icon} {}</span>", escape_html(&node.name))?;
-        write!(html, "<span class=\"size\">{}</span>", human_readable_size(node.size))?;
+        write!(html, "<span class=\"name {name_class}\">{icon} {}</span>", escape_html(&node.name_with_target()))?;
+        write!(html, "<span class=\"size\">{}</span>", node.size_label())?;
+        if unique {
+            write!(html, "<span class=\"unique\">{} unique</span>", human_readable_size(node.unique_size()))?;
+        }
         write!(html, "<span class=\"pct\">{pct:.1}%</span>")?;
         write!(html, "<span class=\"bar\"><span class=\"bar-fill\" style=\"width:{bar_width:.0}px\"></span></span>")?;
         write!(html, "</span>")?;
-        write!(html, "</div>\n")?;
+        writeln!(html, "</div>")?;
     }
 
     Ok(())
 }
 
-fn escape_html(s: &str) -> String {
+/// The treemap's container, its data as JSON and the script drawing it.
+fn write_treemap_html(html: &mut dyn Write, result: &ScanResult) -> io::Result<()> {
+    writeln!(html, "<style>")?;
+    writeln!(html, "    .treemap {{ position: relative; height: 70vh; background: #16213e; border-radius: 4px; overflow: hidden; }}")?;
+    writeln!(html, "    .treemap-path {{ margin: 10px 0; }}")?;
+    writeln!(html, "    .treemap-path a {{ color: #00d4ff; cursor: pointer; }}")?;
+    writeln!(html, "    .tile {{ position: absolute; box-sizing: border-box; border: 1px solid #1a1a2e; overflow: hidden; white-space: nowrap; font-size: 12px; padding: 1px 3px; color: #fff; cursor: pointer; }}")?;
+    writeln!(html, "    .tile.nested {{ font-size: 11px; opacity: 0.85; }}")?;
+    writeln!(html, "</style>")?;
+    writeln!(html, "<h2>Treemap</h2>")?;
+    writeln!(html, "<div id=\"treemap-path\" class=\"treemap-path\"></div>")?;
+    writeln!(html, "<div id=\"treemap\" class=\"treemap\"></div>")?;
+
+    write!(html, "<script type=\"application/json\" id=\"treemap-data\">")?;
+    let min_size = ((result.root.size as f64 * TREEMAP_MIN_SHARE) as u64).max(1);
+    write_treemap_node(html, &result.root, &result.display_path(), 0, min_size)?;
+    writeln!(html, "</script>")?;
+
+    writeln!(html, "<script>")?;
+    write!(html, "const TREEMAP_COLORS = {{")?;
+    for category in FileCategory::ALL {
+        write!(html, "'{}': '{}', ", category.name().to_lowercase(), category.css_color())?;
+    }
+    writeln!(html, "}};")?;
+    writeln!(html, "{}", TREEMAP_SCRIPT)?;
+    writeln!(html, "</script>")?;
+    Ok(())
+}
+
+/// `node` as `[name, size, kind, children]`, where `kind` is `dir` or a file's
+/// category. Entries too small or too deep to include are summed into an `other` tile,
+/// so a directory's tiles always fill it.
+fn write_treemap_node(
+    html: &mut dyn Write,
+    node: &Node,
+    name: &str,
+    depth: usize,
+    min_size: u64,
+) -> io::Result<()> {
+    write!(html, "[{},{},", json_string(name), node.size)?;
+    if !node.is_container() {
+        return write!(html, "\"{}\"]", node.file_category().name().to_lowercase());
+    }
+    write!(html, "\"dir\",[")?;
+    let mut listed = 0;
+    if depth < TREEMAP_DEPTH {
+        let mut children: Vec<&Node> =
+            node.children.iter().filter(|c| c.size >= min_size).collect();
+        children.sort_by_key(|c| std::cmp::Reverse(c.size));
+        for (i, child) in children.iter().take(MAX_CHILDREN_PER_DIR).enumerate() {
+            if i > 0 {
+                write!(html, ",")?;
+            }
+            write_treemap_node(html, child, &child.name_with_target(), depth + 1, min_size)?;
+            listed += child.size;
+        }
+    }
+    let other = node.size.saturating_sub(listed);
+    if other > 0 {
+        let separator = if listed > 0 { "," } else { "" };
+        write!(html, "{separator}[\"(other)\",{other},\"other\"]")?;
+    }
+    write!(html, "]]")
+}
+
+/// A stacked bar of file size by category across the whole tree, with a legend, plus
+/// the classes that color file rows to match.
+/// Size and file count per extension, largest first, each in its category's color.
+fn write_file_types_html(html: &mut dyn Write, root: &Node) -> io::Result<()> {
+    let extensions = Analyzer::by_extension(root);
+    if extensions.is_empty() {
+        return Ok(());
+    }
+    let total: u64 = extensions.iter().map(|e| e.size).sum();
+
+    writeln!(html, "<h2>File Types</h2>")?;
+    writeln!(
+        html,
+        "<table class=\"file-types\">\n<tr><th>Extension</th><th>Category</th><th>Size</th><th>%</th><th>Files</th></tr>"
+    )?;
+    for usage in extensions.iter().take(MAX_EXTENSIONS) {
+        let pct = usage.size as f64 / total.max(1) as f64 * 100.0;
+        writeln!(
+            html,
+            "<tr><td style=\"color:{}\">{}</td><td>{}</td><td>{}</td><td>{:.1}%</td><td>{}</td></tr>",
+            usage.category.css_color(),
+            escape_html(&usage.label()),
+            usage.category.name(),
+            human_readable_size(usage.size),
+            pct,
+            usage.file_count,
+        )?;
+    }
+    writeln!(html, "</table>")?;
+    if extensions.len() > MAX_EXTENSIONS {
+        let rest = &extensions[MAX_EXTENSIONS..];
+        let rest_size: u64 = rest.iter().map(|e| e.size).sum();
+        writeln!(
+            html,
+            "<p class=\"file\">… {} more extensions ({})</p>",
+            rest.len(),
+            human_readable_size(rest_size),
+        )?;
+    }
+    Ok(())
+}
+
+fn write_largest_files_html(html: &mut dyn Write, root: &Node, n: usize) -> io::Result<()> {
+    let index = SizeIndex::build_files(root);
+    let files = index.top_n(n);
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(html, "<h2>Largest Files</h2>")?;
+    writeln!(
+        html,
+        "<table class=\"largest-files\">\n<tr><th>#</th><th>Path</th><th>Size</th></tr>"
+    )?;
+    for (rank, (path, size)) in files.iter().enumerate() {
+        writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            rank + 1,
+            escape_html(&path.display().to_string()),
+            human_readable_size(*size),
+        )?;
+    }
+    writeln!(html, "</table>")?;
+    Ok(())
+}
+
+/// Ways to free space, if there are any. Regenerable directories are only suggested
+/// once `Analyzer::tag_junk` has run on the tree.
+fn write_suggestions_html(html: &mut dyn Write, root: &Node) -> io::Result<()> {
+    let suggestions = Analyzer::suggestions(root, SystemTime::now());
+    if suggestions.is_empty() {
+        return Ok(());
+    }
+    let total: u64 = suggestions.iter().map(|s| s.reclaimable).sum();
+
+    writeln!(html, "<h2>Cleanup Suggestions ({} reclaimable)</h2>", human_readable_size(total))?;
+    writeln!(html, "<table class=\"suggestions\">")?;
+    writeln!(html, "<tr><th>Risk</th><th>Kind</th><th>Path</th><th>Reason</th><th>Size</th></tr>")?;
+    for suggestion in suggestions.iter().take(MAX_SUGGESTIONS) {
+        writeln!(
+            html,
+            "<tr><td class=\"risk-{0}\">{0}</td>\
+             <td>{1}</td><td>{2}</td><td>{3}</td><td>{4}</td></tr>",
+            suggestion.risk.label(),
+            suggestion.kind.label(),
+            escape_html(&suggestion.path.display().to_string()),
+            escape_html(&suggestion.reason),
+            human_readable_size(suggestion.reclaimable),
+        )?;
+    }
+    writeln!(html, "</table>")?;
+    if suggestions.len() > MAX_SUGGESTIONS {
+        writeln!(html, "<p class=\"file\">… {} more</p>", suggestions.len() - MAX_SUGGESTIONS)?;
+    }
+    Ok(())
+}
+
+/// List files whose length overstates the space they take, if there are any.
+fn write_sparse_html(html: &mut dyn Write, result: &ScanResult) -> io::Result<()> {
+    let sparse = Analyzer::sparse_files(&result.root, result.size_mode);
+    if sparse.is_empty() {
+        return Ok(());
+    }
+
+    writeln!(html, "<h2>Sparse Files ({} total)</h2>", sparse.len())?;
+    writeln!(
+        html,
+        "<table class=\"sparse-files\">\n<tr><th>Path</th><th>Length</th><th>On disk</th></tr>"
+    )?;
+    for file in sparse.iter().take(MAX_SPARSE_FILES) {
+        writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape_html(&file.path.display().to_string()),
+            human_readable_size(file.apparent_size),
+            human_readable_size(file.allocated_size),
+        )?;
+    }
+    writeln!(html, "</table>")?;
+    if sparse.len() > MAX_SPARSE_FILES {
+        writeln!(html, "<p class=\"file\">… {} more</p>", sparse.len() - MAX_SPARSE_FILES)?;
+    }
+    Ok(())
+}
+
+fn write_categories_html(html: &mut dyn Write, root: &Node) -> io::Result<()> {
+    let totals = category_totals(root);
+    let total: u64 = totals.iter().map(|(_, size)| size).sum();
+
+    writeln!(html, "<style>")?;
+    writeln!(html, "    .category-bar {{ display: flex; height: 20px; border-radius: 4px; overflow: hidden; margin: 10px 0; }}")?;
+    writeln!(html, "    .category-legend {{ list-style: none; padding: 0; display: flex; flex-wrap: wrap; gap: 16px; }}")?;
+    writeln!(html, "    .swatch {{ display: inline-block; width: 12px; height: 12px; border-radius: 2px; margin-right: 6px; }}")?;
+    for category in FileCategory::ALL {
+        writeln!(
+            html,
+            "    .cat-{} {{ color: {}; }}",
+            category.name().to_lowercase(),
+            category.css_color()
+        )?;
+    }
+    writeln!(html, "</style>")?;
+
+    writeln!(html, "<h2>File Categories</h2>")?;
+    if total == 0 {
+        writeln!(html, "<p>No file data.</p>")?;
+        return Ok(());
+    }
+    write!(html, "<div class=\"category-bar\">")?;
+    for (category, size) in &totals {
+        let pct = *size as f64 / total as f64 * 100.0;
+        write!(
+            html,
+            "<span style=\"width:{pct:.2}%;background:{}\" title=\"{} {pct:.1}%\"></span>",
+            category.css_color(),
+            category.name(),
+        )?;
+    }
+    writeln!(html, "</div>")?;
+    writeln!(html, "<ul class=\"category-legend\">")?;
+    for (category, size) in &totals {
+        writeln!(
+            html,
+            "<li><span class=\"swatch\" style=\"background:{}\"></span>{} {} ({:.1}%)</li>",
+            category.css_color(),
+            category.name(),
+            human_readable_size(*size),
+            *size as f64 / total as f64 * 100.0,
+        )?;
+    }
+    writeln!(html, "</ul>")?;
+    Ok(())
+}
+
+/// A JSON string safe inside a `<script>` element, which would end at `</script>`.
+fn json_string(s: &str) -> String {
+    serde_json::to_string(s)
+        .unwrap_or_default()
+        .replace('<', "\\u003c")
+}
+
+pub fn escape_html(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -1,9 +1,139 @@
+use std::io::Write;
 use std::path::Path;
+use std::time::{Duration, SystemTime};
 
-use crate::models::scan_result::ScanResult;
+use serde::Serialize;
+
+use crate::export::compress::{self, OutputFile};
+use crate::models::node::{Node, NodeType, SizeMode};
+use crate::models::scan_result::{ErrorSummary, ScanError, ScanResult};
+
+/// The exported document: the scan result plus derived rollups.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    #[serde(flatten)]
+    result: &'a ScanResult,
+    error_summary: ErrorSummary,
+}
 
 pub fn export_json(result: &ScanResult, output_path: &Path) -> anyhow::Result<()> {
-    let json = serde_json::to_string_pretty(result)?;
-    std::fs::write(output_path, json)?;
+    let report = JsonReport {
+        result,
+        error_summary: result.error_summary(),
+    };
+    let mut out = OutputFile::create(output_path)?;
+    serde_json::to_writer_pretty(&mut out, &report)?;
+    out.finish()?;
+    Ok(())
+}
+
+/// One line of a newline-delimited export, tagged by `record`.
+#[derive(Serialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+enum NdjsonRecord<'a> {
+    /// The first line: the scan as a whole.
+    Scan {
+        scan_path: &'a Path,
+        timestamp: SystemTime,
+        scan_duration: Duration,
+        total_size: u64,
+        total_files: usize,
+        total_dirs: usize,
+        size_mode: SizeMode,
+        cancelled: bool,
+        error_count: usize,
+    },
+    /// An entry, after the directory holding it.
+    Node {
+        id: u64,
+        /// The `id` of the directory holding it; `None` for the root.
+        parent: Option<u64>,
+        depth: usize,
+        path: &'a Path,
+        name: &'a str,
+        node_type: NodeType,
+        size: u64,
+        size_on_disk: u64,
+        file_count: usize,
+        dir_count: usize,
+        modified: Option<SystemTime>,
+    },
+    Error(&'a ScanError),
+}
+
+/// Write the scan to `output_path` as newline-delimited JSON; see [`write_ndjson`].
+pub fn export_ndjson(result: &ScanResult, output_path: &Path) -> anyhow::Result<()> {
+    let mut out = OutputFile::create(output_path)?;
+    write_ndjson(result, &mut out)?;
+    out.finish()?;
     Ok(())
 }
+
+/// Write the scan one object per line: a `scan` record, then a `node` record for each
+/// entry, parents first, pointing at its directory by `id`, then an `error` record for
+/// each error. Each line is written as it is produced, so memory use doesn't grow with
+/// the tree.
+pub fn write_ndjson(result: &ScanResult, out: &mut dyn Write) -> anyhow::Result<()> {
+    write_record(
+        out,
+        &NdjsonRecord::Scan {
+            scan_path: &result.scan_path,
+            timestamp: result.timestamp,
+            scan_duration: result.scan_duration,
+            total_size: result.total_size,
+            total_files: result.total_files,
+            total_dirs: result.total_dirs,
+            size_mode: result.size_mode,
+            cancelled: result.cancelled,
+            error_count: result.errors.len(),
+        },
+    )?;
+    let mut next_id = 0;
+    write_node_records(out, &result.root, None, 0, &mut next_id)?;
+    for error in &result.errors {
+        write_record(out, &NdjsonRecord::Error(error))?;
+    }
+    Ok(())
+}
+
+fn write_node_records(
+    out: &mut dyn Write,
+    node: &Node,
+    parent: Option<u64>,
+    depth: usize,
+    next_id: &mut u64,
+) -> anyhow::Result<()> {
+    let id = *next_id;
+    *next_id += 1;
+    write_record(
+        out,
+        &NdjsonRecord::Node {
+            id,
+            parent,
+            depth,
+            path: &node.path,
+            name: &node.name,
+            node_type: node.node_type,
+            size: node.size,
+            size_on_disk: node.size_on_disk,
+            file_count: node.file_count,
+            dir_count: node.dir_count,
+            modified: node.modified,
+        },
+    )?;
+    for child in &node.children {
+        write_node_records(out, child, Some(id), depth + 1, next_id)?;
+    }
+    Ok(())
+}
+
+fn write_record(out: &mut dyn Write, record: &NdjsonRecord) -> anyhow::Result<()> {
+    serde_json::to_writer(&mut *out, record)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Read back a scan exported with [`export_json`], compressed or not.
+pub fn import_json(path: &Path) -> anyhow::Result<ScanResult> {
+    Ok(serde_json::from_slice(&compress::read(path)?)?)
+}
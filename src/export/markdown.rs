@@ -1,6 +1,11 @@
 use std::fmt::Write;
 use std::path::Path;
+use std::time::SystemTime;
 
+use crate::core::analyzer::Analyzer;
+use crate::export::compress::OutputFile;
+use crate::export::html::{DEFAULT_TOP_FILES, MAX_EXTENSIONS, MAX_SUGGESTIONS};
+use crate::models::index::SizeIndex;
 use crate::models::node::{human_readable_size, Node, NodeType};
 use crate::models::scan_result::ScanResult;
 
@@ -9,29 +14,144 @@ pub fn export_markdown(result: &ScanResult, output_path: &Path) -> anyhow::Resul
 
     writeln!(md, "# DiskLens Report")?;
     writeln!(md)?;
-    writeln!(md, "- **Path:** {}", result.scan_path.display())?;
+    writeln!(md, "- **Path:** {}", result.display_path())?;
     writeln!(md, "- **Total Size:** {}", human_readable_size(result.total_size))?;
     writeln!(md, "- **Files:** {}", result.total_files)?;
     writeln!(md, "- **Directories:** {}", result.total_dirs)?;
+    let gitignored = result.root.gitignored_size();
+    if gitignored > 0 {
+        writeln!(md, "- **Ignored by git:** {}", human_readable_size(gitignored))?;
+    }
+    // Clone detection ran and found shared extents
+    let unique = result.root.shared_size > 0;
+    if unique {
+        writeln!(md, "- **Shared with clones:** {}", human_readable_size(result.root.shared_size))?;
+    }
     writeln!(md, "- **Scan Duration:** {:.2}s", result.scan_duration.as_secs_f64())?;
+    let notes = result.clone_note().into_iter().chain(result.estimate_note());
+    for note in notes.chain(result.truncation_note()) {
+        writeln!(md, "- **Note:** {}", note)?;
+    }
     writeln!(md)?;
 
+    let extensions = Analyzer::by_extension(&result.root);
+    if !extensions.is_empty() {
+        let total: u64 = extensions.iter().map(|e| e.size).sum();
+        writeln!(md, "## File Types")?;
+        writeln!(md)?;
+        writeln!(md, "| Extension | Category | Size | % | Files |")?;
+        writeln!(md, "|-----------|----------|------|---|-------|")?;
+        for usage in extensions.iter().take(MAX_EXTENSIONS) {
+            writeln!(
+                md,
+                "| {} | {} | {} | {:.1}% | {} |",
+                usage.label(),
+                usage.category.name(),
+                human_readable_size(usage.size),
+                usage.size as f64 / total.max(1) as f64 * 100.0,
+                usage.file_count,
+            )?;
+        }
+        writeln!(md)?;
+    }
+
+    let index = SizeIndex::build_files(&result.root);
+    let largest = index.top_n(DEFAULT_TOP_FILES);
+    if !largest.is_empty() {
+        writeln!(md, "## Largest Files")?;
+        writeln!(md)?;
+        writeln!(md, "| # | Path | Size |")?;
+        writeln!(md, "|---|------|------|")?;
+        for (rank, (path, size)) in largest.iter().enumerate() {
+            writeln!(md, "| {} | {} | {} |", rank + 1, path.display(), human_readable_size(*size))?;
+        }
+        writeln!(md)?;
+    }
+
+    let suggestions = Analyzer::suggestions(&result.root, SystemTime::now());
+    if !suggestions.is_empty() {
+        let total: u64 = suggestions.iter().map(|s| s.reclaimable).sum();
+        writeln!(md, "## Cleanup Suggestions ({} reclaimable)", human_readable_size(total))?;
+        writeln!(md)?;
+        writeln!(md, "| Risk | Kind | Path | Reason | Size |")?;
+        writeln!(md, "|------|------|------|--------|------|")?;
+        for suggestion in suggestions.iter().take(MAX_SUGGESTIONS) {
+            writeln!(
+                md,
+                "| {} | {} | {} | {} | {} |",
+                suggestion.risk.label(),
+                suggestion.kind.label(),
+                suggestion.path.display(),
+                suggestion.reason,
+                human_readable_size(suggestion.reclaimable),
+            )?;
+        }
+        writeln!(md)?;
+    }
+
     writeln!(md, "## Directory Tree")?;
     writeln!(md)?;
-    writeln!(md, "| Name | Size | % |")?;
-    writeln!(md, "|------|------|---|")?;
+    if unique {
+        writeln!(md, "| Name | Size | Unique | % |")?;
+        writeln!(md, "|------|------|--------|---|")?;
+    } else {
+        writeln!(md, "| Name | Size | % |")?;
+        writeln!(md, "|------|------|---|")?;
+    }
 
-    write_node_markdown(&mut md, &result.root, result.total_size, 0, 3)?;
+    write_node_markdown(&mut md, &result.root, result.total_size, 0, 3, unique)?;
 
     if !result.errors.is_empty() {
         writeln!(md)?;
         writeln!(md, "## Errors ({} total)", result.errors.len())?;
         writeln!(md)?;
+        writeln!(md, "| Type | Count |")?;
+        writeln!(md, "|------|-------|")?;
+        for (error_type, count) in result.error_summary().counts() {
+            writeln!(md, "| {:?} | {} |", error_type, count)?;
+        }
+        writeln!(md)?;
         for err in &result.errors {
             writeln!(md, "- **{:?}**: {}", err.error_type, err.path.display())?;
         }
     }
 
+    let mut out = OutputFile::create(output_path)?;
+    std::io::Write::write_all(&mut out, md.as_bytes())?;
+    out.finish()?;
+    Ok(())
+}
+
+/// Write one directory's entries exactly as listed in the TUI: already sorted and
+/// filtered, with percentages against `total_size`.
+pub fn export_view_markdown(
+    dir: &Path,
+    view: &str,
+    nodes: &[&Node],
+    total_size: u64,
+    output_path: &Path,
+) -> anyhow::Result<()> {
+    let mut md = String::new();
+
+    writeln!(md, "# {}", dir.display())?;
+    writeln!(md)?;
+    writeln!(md, "- **Total Size:** {}", human_readable_size(total_size))?;
+    writeln!(md, "- **View:** {}", view)?;
+    writeln!(md)?;
+    writeln!(md, "| Name | Size | % |")?;
+    writeln!(md, "|------|------|---|")?;
+    for node in nodes {
+        let suffix = if node.node_type == NodeType::Directory { "/" } else { "" };
+        writeln!(
+            md,
+            "| {}{} | {} | {:.1}% |",
+            node.name_with_target(),
+            suffix,
+            node.size_label(),
+            node.percentage(total_size),
+        )?;
+    }
+
     std::fs::write(output_path, md)?;
     Ok(())
 }
@@ -42,6 +162,7 @@ fn write_node_markdown(
     total_size: u64,
     depth: usize,
     max_depth: usize,
+    unique: bool,
 ) -> std::fmt::Result {
     if depth > max_depth {
         return Ok(());
@@ -53,25 +174,33 @@ fn write_node_markdown(
         NodeType::File => "📄 ",
         NodeType::Symlink => "🔗 ",
         NodeType::Other => "❓ ",
+        NodeType::ArchiveEntry => "📦 ",
+        NodeType::Summary => "📊 ",
     };
     let pct = node.percentage(total_size);
+    let unique_column = if unique {
+        format!(" {} |", human_readable_size(node.unique_size()))
+    } else {
+        String::new()
+    };
 
     writeln!(
         md,
-        "| {}{}{} | {} | {:.1}% |",
+        "| {}{}{} | {} |{} {:.1}% |",
         indent,
         icon,
-        node.name,
-        human_readable_size(node.size),
+        node.name_with_target(),
+        node.size_label(),
+        unique_column,
         pct,
     )?;
 
-    if node.node_type == NodeType::Directory && depth < max_depth {
+    if node.is_container() && depth < max_depth {
         let mut children: Vec<&Node> = node.children.iter().collect();
-        children.sort_by(|a, b| b.size.cmp(&a.size));
+        children.sort_by_key(|c| std::cmp::Reverse(c.size));
 
         for child in children {
-            write_node_markdown(md, child, total_size, depth + 1, max_depth)?;
+            write_node_markdown(md, child, total_size, depth + 1, max_depth, unique)?;
         }
     }
 
@@ -1,3 +1,54 @@
 pub mod json;
 pub mod markdown;
 pub mod html;
+pub mod csv;
+pub mod sqlite;
+pub mod compress;
+pub mod flamegraph;
+pub mod diff;
+
+use std::path::Path;
+
+use crate::models::scan_result::ScanResult;
+
+/// The formats offered by the TUI's export dialog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Html,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] =
+        [ExportFormat::Json, ExportFormat::Csv, ExportFormat::Html, ExportFormat::Markdown];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "JSON",
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Html => "HTML",
+            ExportFormat::Markdown => "Markdown",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Html => "html",
+            ExportFormat::Markdown => "md",
+        }
+    }
+
+    /// Write `result` to `output_path` in this format, with the exporter's default options.
+    pub fn export(self, result: &ScanResult, output_path: &Path) -> anyhow::Result<()> {
+        match self {
+            ExportFormat::Json => json::export_json(result, output_path),
+            ExportFormat::Csv => csv::export_tree_csv(result, output_path, &Default::default()),
+            ExportFormat::Html => html::export_html(result, output_path, &Default::default()),
+            ExportFormat::Markdown => markdown::export_markdown(result, output_path),
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! SQLite export, for ad-hoc SQL over trees too large to explore comfortably as JSON.
+//!
+//! The database holds three tables:
+//! - `scan`: one row describing the scan as a whole.
+//! - `nodes`: one row per entry, linked to its directory by `parent_id`, indexed by
+//!   path, parent and size.
+//! - `errors`: the scan's errors, indexed by path.
+
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use rusqlite::{params, Connection, Statement};
+
+use crate::export::compress::{Compression, OutputFile};
+use crate::models::node::{Node, SizeMode};
+use crate::models::scan_result::ScanResult;
+
+const SCHEMA: &str = "
+CREATE TABLE scan (
+    scan_path TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    total_size INTEGER NOT NULL,
+    total_files INTEGER NOT NULL,
+    total_dirs INTEGER NOT NULL,
+    size_mode TEXT NOT NULL,
+    cancelled INTEGER NOT NULL,
+    estimated INTEGER NOT NULL
+);
+CREATE TABLE nodes (
+    id INTEGER PRIMARY KEY,
+    parent_id INTEGER REFERENCES nodes(id),
+    path TEXT NOT NULL,
+    name TEXT NOT NULL,
+    type TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    size_on_disk INTEGER NOT NULL,
+    file_count INTEGER NOT NULL,
+    dir_count INTEGER NOT NULL,
+    modified INTEGER,
+    depth INTEGER NOT NULL,
+    uid INTEGER,
+    gid INTEGER,
+    error_count INTEGER NOT NULL
+);
+CREATE TABLE errors (
+    path TEXT NOT NULL,
+    type TEXT NOT NULL,
+    message TEXT NOT NULL
+);
+";
+
+/// Created after the rows are in, which is faster than keeping them up to date.
+const INDEXES: &str = "
+CREATE INDEX nodes_path ON nodes(path);
+CREATE INDEX nodes_parent ON nodes(parent_id);
+CREATE INDEX nodes_size ON nodes(size);
+CREATE INDEX errors_path ON errors(path);
+";
+
+/// Write the scan to a new SQLite database at `output_path`, replacing any file there.
+/// A `.gz` or `.zst` name gets the database compressed, which SQLite itself can't open.
+pub fn export_sqlite(result: &ScanResult, output_path: &Path) -> anyhow::Result<()> {
+    if Compression::from_path(output_path).is_none() {
+        return write_database(result, output_path);
+    }
+    // SQLite needs a real file to build the database in
+    let mut database = output_path.as_os_str().to_owned();
+    database.push(".tmp");
+    let database = PathBuf::from(database);
+    let written = write_database(result, &database).and_then(|()| {
+        let mut out = OutputFile::create(output_path)?;
+        std::io::copy(&mut File::open(&database)?, &mut out)?;
+        out.finish()?;
+        Ok(())
+    });
+    let _ = std::fs::remove_file(&database);
+    written
+}
+
+fn write_database(result: &ScanResult, path: &Path) -> anyhow::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+    let mut conn = Connection::open(path)?;
+    write_sqlite(result, &mut conn)?;
+    Ok(())
+}
+
+/// Create the tables in `conn` and fill them from `result` in one transaction. Sizes
+/// are apparent sizes and sizes on disk whichever the scan reported; times are Unix
+/// seconds.
+pub fn write_sqlite(result: &ScanResult, conn: &mut Connection) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+    tx.execute(
+        "INSERT INTO scan VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            result.scan_path.to_string_lossy(),
+            unix_seconds(result.timestamp),
+            result.scan_duration.as_millis() as i64,
+            result.total_size as i64,
+            result.total_files as i64,
+            result.total_dirs as i64,
+            result.size_mode.label(),
+            result.cancelled,
+            result.estimate.is_some(),
+        ],
+    )?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO nodes (parent_id, path, name, type, size, size_on_disk, file_count,
+             dir_count, modified, depth, uid, gid, error_count)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        )?;
+        insert_node(&mut insert, &result.root, None, 0, result.size_mode)?;
+
+        let mut insert = tx.prepare("INSERT INTO errors VALUES (?1, ?2, ?3)")?;
+        for error in &result.errors {
+            insert.execute(params![
+                error.path.to_string_lossy(),
+                format!("{:?}", error.error_type),
+                error.message,
+            ])?;
+        }
+    }
+    tx.execute_batch(INDEXES)?;
+    tx.commit()
+}
+
+/// Insert `node` and everything below it, parents first so children can refer to them.
+fn insert_node(
+    insert: &mut Statement,
+    node: &Node,
+    parent_id: Option<i64>,
+    depth: usize,
+    size_mode: SizeMode,
+) -> rusqlite::Result<()> {
+    // `size` holds whichever measure the scan reports; the other is in `size_on_disk`
+    let (size, on_disk) = match size_mode {
+        SizeMode::Apparent => (node.size, node.size_on_disk),
+        SizeMode::DiskUsage => (node.size_on_disk, node.size),
+    };
+    #[cfg(unix)]
+    let (uid, gid) = (node.uid, node.gid);
+    #[cfg(not(unix))]
+    let (uid, gid): (Option<u32>, Option<u32>) = (None, None);
+    let id = insert.insert(params![
+        parent_id,
+        node.path.to_string_lossy(),
+        node.name,
+        node.node_type.label(),
+        size as i64,
+        on_disk as i64,
+        node.file_count as i64,
+        node.dir_count as i64,
+        node.modified.map(unix_seconds),
+        depth as i64,
+        uid,
+        gid,
+        node.error_count as i64,
+    ])?;
+    for child in &node.children {
+        insert_node(insert, child, Some(id), depth + 1, size_mode)?;
+    }
+    Ok(())
+}
+
+/// Seconds since the Unix epoch, negative for earlier times.
+fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
@@ -1,15 +1,36 @@
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use disklens::config::settings::{GitignoreMode, ListDensity, ScanBackend};
+use disklens::core::profile::IoProfile;
+use disklens::models::node::human_readable_size;
+use disklens::models::scan_result::ScanResult;
 
 #[derive(Parser, Debug)]
-#[command(name = "disklens", version, about = "High-performance disk space analyzer")]
+#[command(
+    name = "disklens",
+    version,
+    about = "High-performance disk space analyzer",
+    args_conflicts_with_subcommands = true
+)]
 struct Cli {
-    /// Path to analyze (default: current directory)
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Paths to analyze (default: current directory)
     #[arg(default_value = ".")]
-    path: PathBuf,
+    paths: Vec<PathBuf>,
+
+    /// Start from an overview of mounted filesystems with their capacity and free space,
+    /// and pick the ones to scan (with an export, all of them are); each is scanned
+    /// without crossing into the others
+    #[arg(long, conflicts_with_all = ["paths", "streaming"])]
+    all_mounts: bool,
 
-    /// Maximum scan depth
+    /// Maximum tree depth; directories at the limit are still summed up, just not
+    /// listed
     #[arg(short = 'd', long)]
     max_depth: Option<usize>,
 
@@ -17,17 +38,399 @@ struct Cli {
     #[arg(short = 'c', long)]
     concurrency: Option<usize>,
 
+    /// Scanner engine: tasks (async, the default), threads (a work-stealing thread
+    /// pool, often faster on local SSDs), uring (threads with stat calls batched
+    /// through io_uring; Linux builds with the `uring` feature) or mft (threads
+    /// listing directories from the NTFS Master File Table; Windows, elevated)
+    #[arg(long, value_name = "BACKEND", value_parser = parse_backend)]
+    backend: Option<ScanBackend>,
+
+    /// JSON file of per-path overrides, e.g. {"/mnt/nas": {"max_concurrent_io": 8}};
+    /// each directory uses the entry with the longest path containing it
+    #[arg(long, value_name = "FILE")]
+    profiles: Option<PathBuf>,
+
+    /// Read network filesystems (NFS, SMB, sshfs, ...) at full concurrency instead of
+    /// throttling them
+    #[arg(long)]
+    no_network_throttle: bool,
+
     /// Follow symbolic links
     #[arg(long)]
     follow_symlinks: bool,
 
+    /// Skip hidden files and directories (dotfiles; the hidden attribute on Windows)
+    #[arg(long)]
+    skip_hidden: bool,
+
+    /// Include each directory's own metadata size in totals (closer to `du`)
+    #[arg(long)]
+    count_dir_overhead: bool,
+
+    /// Detect data shared by cloned or reflinked files (Linux, e.g. btrfs and XFS) and
+    /// report each entry's unique size beside its total
+    #[arg(long)]
+    detect_clones: bool,
+
+    /// List what .zip, .tar and .tar.gz archives contain, so they can be browsed like
+    /// directories with each entry's packed and unpacked size
+    #[arg(long)]
+    peek_archives: bool,
+
+    /// Skip entries matching a gitignore-style glob such as `**/node_modules` or `*.iso`
+    /// (repeatable)
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Report disk usage (allocated blocks) instead of apparent file sizes
+    #[arg(long)]
+    disk_usage: bool,
+
+    /// Count every hard link to a file at full size instead of only the first one found
+    #[arg(long)]
+    count_hardlinks: bool,
+
+    /// Don't cross into other filesystems (mount points on other devices are skipped)
+    #[arg(short = 'x', long)]
+    one_file_system: bool,
+
+    /// Descend into pseudo filesystems such as /proc and /sys, which are skipped by default
+    #[arg(long)]
+    scan_pseudo_fs: bool,
+
+    /// Only export files modified at or after this time (YYYY-MM-DD, RFC 3339, or an age like 7d)
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    modified_after: Option<SystemTime>,
+
+    /// Only export files modified before this time (YYYY-MM-DD, RFC 3339, or an age like 7d)
+    #[arg(long, value_name = "TIME", value_parser = parse_time)]
+    modified_before: Option<SystemTime>,
+
+    /// Only export the entry at this path (as reached from the scanned path) and what's
+    /// below it
+    #[arg(long, value_name = "PATH")]
+    export_root: Option<PathBuf>,
+
+    /// Only export this many levels below the exported root, which is 0; deeper
+    /// entries still count toward their directory's size
+    #[arg(long, value_name = "N")]
+    export_depth: Option<usize>,
+
+    /// Leave entries smaller than this out of exports (e.g. 10M); they still count
+    /// toward their directory's size
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    export_min_size: Option<u64>,
+
+    /// Read .gitignore and .git/info/exclude files; matched entries are skipped, or with
+    /// `=tag` kept and flagged so build output can be told apart
+    #[arg(
+        long,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "skip",
+        value_parser = parse_gitignore_mode
+    )]
+    respect_gitignore: Option<GitignoreMode>,
+
+    /// File list layout: compact (one line per entry) or comfortable (adds date and path)
+    #[arg(long, value_name = "DENSITY", value_parser = parse_density)]
+    density: Option<ListDensity>,
+
+    /// Render inline instead of on the alternate screen, keeping logs and output in scrollback
+    #[arg(long)]
+    no_alternate_screen: bool,
+
+    /// Neither start from nor save scans in the cache
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Also save each directory in the cache, and reuse those whose mtime hasn't
+    /// changed in scans of any root. Files rewritten in place inside such directories
+    /// keep their cached sizes
+    #[arg(long)]
+    cache_dirs: bool,
+
+    /// Keep only files of at least this size (e.g. 10M) as entries; smaller files are
+    /// summed into one entry per directory, so totals stay exact with far less memory
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    min_size: Option<u64>,
+
+    /// Approximate scan: in large directories stat only this fraction of files (0 < RATIO <= 1)
+    /// and extrapolate the rest; results are marked as estimated
+    #[arg(long, value_name = "RATIO", value_parser = parse_ratio, conflicts_with = "streaming")]
+    sample: Option<f64>,
+
+    /// Instant estimate: in directories with many subdirectories, descend into only this
+    /// fraction of them (default 0.1), drawn at random weighted by their directory entry
+    /// sizes, and extrapolate the rest; results are marked as estimated
+    #[arg(
+        long,
+        value_name = "RATIO",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0.1",
+        value_parser = parse_ratio,
+        conflicts_with_all = ["sample", "streaming"]
+    )]
+    estimate: Option<f64>,
+
+    /// Stop starting new directories once the scan has run this long (e.g. 30s or 5m);
+    /// work in progress finishes and the result lists the directories left unscanned
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration)]
+    max_duration: Option<Duration>,
+
     /// Export result as JSON to file (non-interactive mode)
     #[arg(long)]
     export_json: Option<PathBuf>,
+
+    /// Export as newline-delimited JSON to file, one object per entry pointing at its
+    /// directory, written as it goes so huge trees fit in memory (non-interactive mode)
+    #[arg(long, value_name = "FILE")]
+    export_ndjson: Option<PathBuf>,
+
+    /// Export sizes as folded stacks (`root;dir;file bytes`) to file, for drawing a
+    /// flame graph with inferno-flamegraph or flamegraph.pl (non-interactive mode)
+    #[arg(long, value_name = "FILE")]
+    export_flamegraph: Option<PathBuf>,
+
+    /// Export result as a Markdown report to file (non-interactive mode)
+    #[arg(long, value_name = "FILE")]
+    export_markdown: Option<PathBuf>,
+
+    /// Export result as an HTML report to file (non-interactive mode)
+    #[arg(long, value_name = "FILE")]
+    export_html: Option<PathBuf>,
+
+    /// Export every entry as a CSV row (path, type, sizes, file count, modification
+    /// time, depth) to file (non-interactive mode)
+    #[arg(long, value_name = "FILE")]
+    export_csv: Option<PathBuf>,
+
+    /// Deepest level of the tree to write to the CSV export, the scanned path being 0
+    #[arg(long, value_name = "N", requires = "export_csv")]
+    csv_depth: Option<usize>,
+
+    /// Field separator of the CSV export: one character, or `tab`
+    #[arg(long, value_name = "CHAR", value_parser = parse_delimiter, requires = "export_csv")]
+    csv_delimiter: Option<char>,
+
+    /// Export entries, errors and scan details as a SQLite database to file, for
+    /// querying with SQL (non-interactive mode)
+    #[arg(long, value_name = "FILE")]
+    export_sqlite: Option<PathBuf>,
+
+    /// Export bytes and files per owning user as CSV to file (non-interactive mode)
+    #[arg(long, value_name = "FILE")]
+    owners_csv: Option<PathBuf>,
+
+    /// Compress exports with zstd, adding `.zst` to their names. Files already named
+    /// `.gz` or `.zst` are compressed with gzip or zstd either way
+    #[arg(long)]
+    compress: bool,
+
+    /// After exporting, scan directories that couldn't be read again through sudo and
+    /// merge them in, so root-owned trees aren't reported as empty
+    #[arg(long)]
+    sudo_rescan: bool,
+
+    /// Directory levels that start expanded in the HTML report
+    #[arg(long, value_name = "N", default_value_t = disklens::export::html::DEFAULT_EXPAND_DEPTH)]
+    expand_depth: usize,
+
+    /// Color the HTML report's files by category and add a category breakdown
+    #[arg(long, requires = "export_html")]
+    categories: bool,
+
+    /// Embed an interactive treemap in the HTML report: click a directory to zoom in,
+    /// so the report can be explored in any browser
+    #[arg(long, requires = "export_html")]
+    treemap: bool,
+
+    /// Print how many bytes were last modified within 30 days, 180 days, a year or
+    /// longer ago, in total and per entry of the scanned path (non-interactive mode)
+    #[arg(long, conflicts_with = "streaming")]
+    age_report: bool,
+
+    /// Estimate how much zstd compression would save, in total and per entry of the
+    /// scanned path, by compressing samples of file contents (non-interactive mode)
+    #[arg(long, conflicts_with = "streaming")]
+    compression_report: bool,
+
+    /// Write per-directory I/O time as flamegraph folded stacks to file
+    #[arg(long, value_name = "FILE")]
+    profile: Option<PathBuf>,
+
+    /// Have the empty directory finder (E) also list directories holding at most this
+    /// many bytes (e.g. 16K); by default it lists only directories without files
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    near_empty: Option<u64>,
+
+    /// Also list directories with this name as cleanup candidates (C), besides the
+    /// built-in ones such as node_modules and target (repeatable)
+    #[arg(long, value_name = "NAME")]
+    junk: Vec<String>,
+
+    /// Print the N largest files in the whole tree with their full paths, and list that
+    /// many in the HTML report (non-interactive mode)
+    #[arg(long, value_name = "N")]
+    top: Option<usize>,
+
+    /// Print results without building the full tree, so memory stays bounded (requires --top)
+    #[arg(long, requires = "top")]
+    streaming: bool,
+
+    /// Maximum threads in the blocking I/O pool (default: tokio's 512)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    threads_io: Option<u16>,
+
+    /// Async worker threads (default: one per CPU core)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    threads_cpu: Option<u16>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two JSON exports of the same tree and list what was added or removed
+    /// and what grew or shrank, largest change first
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+        /// Changes to list
+        #[arg(short = 'n', long, value_name = "N", default_value_t = 20)]
+        limit: usize,
+        #[command(flatten)]
+        report: DiffReport,
+    },
+    /// Inspect and manage the saved scans that speed up rescans
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+    /// Keep named scans to compare later, e.g. before and after a cleanup
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SnapshotAction {
+    /// Scan a path and keep the result under a name
+    Save {
+        name: String,
+        /// Path to scan
+        #[arg(default_value = ".")]
+        path: PathBuf,
+        /// Replace an existing snapshot with the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// List the snapshots, oldest first
+    List,
+    /// Compare two snapshots: what was added, removed, grew or shrank
+    Diff {
+        old: String,
+        new: String,
+        /// Changes to list
+        #[arg(short = 'n', long, value_name = "N", default_value_t = 20)]
+        limit: usize,
+        #[command(flatten)]
+        report: DiffReport,
+    },
+    /// Delete a snapshot
+    Delete { name: String },
+}
+
+/// Files to write a comparison to instead of printing it; `-n` sets the rows per
+/// section.
+#[derive(clap::Args, Debug)]
+struct DiffReport {
+    /// Write the biggest growers and shrinkers and the new and removed directories as
+    /// an HTML report to file
+    #[arg(long, value_name = "FILE")]
+    export_html: Option<PathBuf>,
+    /// Write the same report as Markdown to file
+    #[arg(long, value_name = "FILE")]
+    export_markdown: Option<PathBuf>,
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// List the saved scans with their path, age and size, most recently used first
+    List,
+    /// Show where the cache is, how much space it takes and its limits
+    Info,
+    /// Delete every saved scan and size history
+    Clear,
+    /// Delete saved scans past an age, then the least recently used ones until the
+    /// cache fits a size; without options, the configured limits apply
+    Prune {
+        /// Delete scans taken longer ago than this, e.g. 7d
+        #[arg(long, value_name = "AGE", value_parser = parse_duration)]
+        older_than: Option<Duration>,
+        /// Shrink the cache to at most this size, e.g. 200M
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_size: Option<u64>,
+    },
+}
+
+fn parse_ratio(s: &str) -> anyhow::Result<f64> {
+    let ratio: f64 = s.parse()?;
+    if !(ratio > 0.0 && ratio <= 1.0) {
+        anyhow::bail!("ratio must be greater than 0 and at most 1");
+    }
+    Ok(ratio)
+}
+
+fn parse_density(s: &str) -> anyhow::Result<ListDensity> {
+    match s {
+        "compact" => Ok(ListDensity::Compact),
+        "comfortable" => Ok(ListDensity::Comfortable),
+        _ => anyhow::bail!("density must be compact or comfortable"),
+    }
+}
+
+fn parse_backend(s: &str) -> anyhow::Result<ScanBackend> {
+    match s {
+        "tasks" => Ok(ScanBackend::Tasks),
+        "threads" => Ok(ScanBackend::Threads),
+        "uring" => Ok(ScanBackend::Uring),
+        "mft" => Ok(ScanBackend::Mft),
+        _ => anyhow::bail!("backend must be tasks, threads, uring or mft"),
+    }
+}
+
+fn parse_gitignore_mode(s: &str) -> anyhow::Result<GitignoreMode> {
+    match s {
+        "skip" => Ok(GitignoreMode::Skip),
+        "tag" => Ok(GitignoreMode::Tag),
+        _ => anyhow::bail!("mode must be skip or tag"),
+    }
+}
+
+fn parse_delimiter(s: &str) -> anyhow::Result<char> {
+    let mut chars = s.chars();
+    match (s, chars.next(), chars.next()) {
+        ("tab", _, _) => Ok('\t'),
+        (_, Some(c), None) if !matches!(c, '"' | '\n' | '\r') => Ok(c),
+        _ => anyhow::bail!("delimiter must be a single character other than a quote, or tab"),
+    }
+}
+
+fn parse_size(spec: &str) -> anyhow::Result<u64> {
+    disklens::config::size_spec::parse_size_spec(spec)
+}
+
+fn parse_duration(spec: &str) -> anyhow::Result<Duration> {
+    disklens::config::time_spec::parse_duration_spec(spec)
+}
+
+fn parse_time(spec: &str) -> anyhow::Result<SystemTime> {
+    disklens::config::time_spec::parse_time_spec(spec, SystemTime::now())
+}
+
+fn main() -> anyhow::Result<()> {
     // Initialize tracing (logs to stderr)
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
@@ -36,6 +439,47 @@ async fn main() -> anyhow::Result<()> {
 
     // Parse CLI arguments
     let cli = Cli::parse();
+    if let Some(Command::Diff { old, new, limit, report }) = &cli.command {
+        let old = disklens::export::json::import_json(old)?;
+        let new = disklens::export::json::import_json(new)?;
+        return report_diff(&old, &new, *limit, report);
+    }
+
+    // Build the runtime by hand so the thread pools can be sized from the CLI
+    let mut runtime = tokio::runtime::Builder::new_multi_thread();
+    runtime.enable_all();
+    if let Some(threads) = cli.threads_cpu {
+        runtime.worker_threads(threads as usize);
+    }
+    if let Some(threads) = cli.threads_io {
+        runtime.max_blocking_threads(threads as usize);
+    }
+    runtime.build()?.block_on(run(cli))
+}
+
+/// Environment variable holding the passphrase saved scans are encrypted with. Not a
+/// flag, so it stays out of shell history and process listings.
+const CACHE_PASSPHRASE_VAR: &str = "DISKLENS_CACHE_PASSPHRASE";
+
+async fn run(mut cli: Cli) -> anyhow::Result<()> {
+    if let Some(Command::Cache { action }) = &cli.command {
+        return run_cache_command(action).await;
+    }
+    if cli.compress {
+        let exports = [
+            &mut cli.export_json,
+            &mut cli.export_ndjson,
+            &mut cli.export_html,
+            &mut cli.export_markdown,
+            &mut cli.export_flamegraph,
+            &mut cli.export_csv,
+            &mut cli.export_sqlite,
+            &mut cli.owners_csv,
+        ];
+        for path in exports.into_iter().flatten() {
+            *path = disklens::export::compress::compressed_path(path);
+        }
+    }
 
     // Build settings
     let mut settings = disklens::config::settings::Settings::default();
@@ -45,22 +489,529 @@ async fn main() -> anyhow::Result<()> {
     if let Some(conc) = cli.concurrency {
         settings.max_concurrent_io = conc;
     }
+    if let Some(backend) = cli.backend {
+        settings.backend = backend;
+    }
+    settings.throttle_network_fs = !cli.no_network_throttle;
     settings.follow_symlinks = cli.follow_symlinks;
+    settings.skip_hidden = cli.skip_hidden;
+    settings.count_dir_overhead = cli.count_dir_overhead;
+    settings.detect_clones = cli.detect_clones;
+    settings.peek_archives = cli.peek_archives;
+    settings.dedupe_hardlinks = !cli.count_hardlinks;
+    if cli.disk_usage {
+        settings.size_mode = disklens::models::node::SizeMode::DiskUsage;
+    }
+    settings.one_file_system = cli.one_file_system;
+    if cli.scan_pseudo_fs {
+        settings.pseudo_fs_types.clear();
+    }
+    settings.sample_ratio = cli.sample;
+    settings.estimate_ratio = cli.estimate;
+    settings.max_duration = cli.max_duration;
+    settings.min_file_size = cli.min_size;
+    settings.modified_after = cli.modified_after;
+    settings.modified_before = cli.modified_before;
+    settings.export_root = cli.export_root.clone();
+    settings.export_depth = cli.export_depth;
+    settings.export_min_size = cli.export_min_size;
+    settings.alternate_screen = !cli.no_alternate_screen;
+    settings.use_cache = !cli.no_cache;
+    settings.cache_dirs = cli.cache_dirs;
+    settings.cache_passphrase = std::env::var(CACHE_PASSPHRASE_VAR).ok();
+    settings.ignore_patterns.extend(cli.exclude);
+    if let Some(mode) = cli.respect_gitignore {
+        settings.gitignore = mode;
+    }
+    if let Some(density) = cli.density {
+        settings.list_density = density;
+    }
+    if let Some(size) = cli.near_empty {
+        settings.near_empty_size = size;
+    }
+    settings.junk_dirs.extend(cli.junk);
+    settings.profile_output = cli.profile.clone();
+    if let Some(ref file) = cli.profiles {
+        settings.profiles = disklens::config::profiles::load_profiles(file)?;
+    }
+
+    let mounts = if cli.all_mounts {
+        let mounts = disklens::core::mounts::list_mounts(&settings.pseudo_fs_types);
+        if mounts.is_empty() {
+            anyhow::bail!("no mounted filesystems found");
+        }
+        settings.one_file_system = true;
+        Some(mounts)
+    } else {
+        None
+    };
 
-    // Resolve path
-    let path = std::fs::canonicalize(&cli.path)?;
+    // Resolve paths
+    let paths = match &mounts {
+        Some(mounts) => mounts.iter().map(|m| m.mount_point.clone()).collect(),
+        None => cli
+            .paths
+            .iter()
+            .map(|path| disklens::config::path::resolve_scan_path(path))
+            .collect::<anyhow::Result<Vec<_>>>()?,
+    };
 
-    // Non-interactive mode: scan and export JSON
-    if let Some(ref export_path) = cli.export_json {
+    if let Some(Command::Snapshot { action }) = &cli.command {
+        return run_snapshot_command(action, &settings).await;
+    }
+
+    // Streaming mode: totals and the largest files, no tree
+    if let (true, Some(n)) = (cli.streaming, cli.top) {
+        let [path] = <[PathBuf; 1]>::try_from(paths)
+            .map_err(|_| anyhow::anyhow!("--streaming scans a single path"))?;
         let (event_tx, _rx) = disklens::core::events::create_event_channel();
         let scanner = disklens::core::scanner::Scanner::new(settings, event_tx);
-        let result = scanner.scan(path).await?;
-        disklens::export::json::export_json(&result, export_path)?;
-        println!("Exported to: {}", export_path.display());
+        let result = scanner.scan_top_n(path, n).await?;
+        if let Some(ref export_path) = cli.export_json {
+            let mut out = disklens::export::compress::OutputFile::create(export_path)?;
+            serde_json::to_writer_pretty(&mut out, &result)?;
+            out.finish()?;
+            println!("Exported to: {}", export_path.display());
+            return Ok(());
+        }
+        println!(
+            "{}: {} in {} files, {} directories",
+            result.scan_path.display(),
+            human_readable_size(result.total_size),
+            result.total_files,
+            result.total_dirs
+        );
+        for file in &result.files {
+            println!("{:>10}  {}", human_readable_size(file.size), file.path.display());
+        }
+        if !result.errors.is_empty() {
+            eprintln!("{} errors during scan", result.errors.len());
+        }
+        return Ok(());
+    }
+
+    // Non-interactive mode: scan and export JSON and/or HTML, or print reports
+    let report = cli.age_report || cli.compression_report || cli.top.is_some();
+    let export = cli.export_json.is_some()
+        || cli.export_ndjson.is_some()
+        || cli.export_html.is_some()
+        || cli.export_markdown.is_some()
+        || cli.export_flamegraph.is_some()
+        || cli.export_csv.is_some()
+        || cli.export_sqlite.is_some()
+        || cli.owners_csv.is_some();
+    if export || report {
+        if let Some(mounts) = &mounts {
+            print_mounts(mounts);
+        }
+        let (mut result, io_profile) = scan_roots(&settings, paths).await?;
+        if cli.sudo_rescan {
+            let denied = disklens::core::elevated::permission_denied_dirs(&result);
+            if !denied.is_empty() {
+                eprintln!("Rescanning {} unreadable directories with sudo", denied.len());
+                let helper_settings = settings.clone();
+                let rescan = tokio::task::spawn_blocking(move || {
+                    disklens::core::elevated::rescan_with_sudo(&denied, &helper_settings)
+                })
+                .await??;
+                result.merge_rescan(rescan);
+            }
+        }
+        save_to_cache(&settings, &result).await;
+        let mut result = settings.export_view(&result)?;
+        disklens::core::analyzer::Analyzer::tag_junk(&mut result.root, &settings.junk_dirs);
+        if let Some(ref export_path) = cli.export_json {
+            disklens::export::json::export_json(&result, export_path)?;
+            println!("Exported to: {}", export_path.display());
+        }
+        if let Some(ref export_path) = cli.export_ndjson {
+            disklens::export::json::export_ndjson(&result, export_path)?;
+            println!("Exported to: {}", export_path.display());
+        }
+        if let Some(ref export_path) = cli.export_html {
+            let options = disklens::export::html::HtmlOptions {
+                expand_depth: cli.expand_depth,
+                categories: cli.categories,
+                top_files: cli.top.unwrap_or(disklens::export::html::DEFAULT_TOP_FILES),
+                treemap: cli.treemap,
+            };
+            disklens::export::html::export_html(&result, export_path, &options)?;
+            println!("Exported to: {}", export_path.display());
+        }
+        if let Some(ref export_path) = cli.export_markdown {
+            disklens::export::markdown::export_markdown(&result, export_path)?;
+            println!("Exported to: {}", export_path.display());
+        }
+        if let Some(ref export_path) = cli.export_flamegraph {
+            disklens::export::flamegraph::export_folded(&result, export_path)?;
+            println!("Exported to: {}", export_path.display());
+        }
+        if let Some(ref export_path) = cli.export_csv {
+            let options = disklens::export::csv::CsvOptions {
+                max_depth: cli.csv_depth,
+                delimiter: cli.csv_delimiter.unwrap_or(','),
+            };
+            disklens::export::csv::export_tree_csv(&result, export_path, &options)?;
+            println!("Exported to: {}", export_path.display());
+        }
+        if let Some(ref export_path) = cli.export_sqlite {
+            disklens::export::sqlite::export_sqlite(&result, export_path)?;
+            println!("Exported to: {}", export_path.display());
+        }
+        if let Some(ref export_path) = cli.owners_csv {
+            let usage = disklens::core::analyzer::Analyzer::usage_by_owner(&result.root);
+            disklens::export::csv::export_owners_csv(&usage, export_path)?;
+            println!("Exported to: {}", export_path.display());
+        }
+        if let Some(n) = cli.top {
+            print_largest_files(&result, n);
+        }
+        if cli.age_report {
+            print_age_report(&result);
+        }
+        if cli.compression_report {
+            print_compression_report(&result);
+        }
+        if let (Some(profile), Some(profile_path)) = (io_profile, &cli.profile) {
+            profile.write_folded(&result.scan_path, profile_path)?;
+            println!("Profile written to: {}", profile_path.display());
+        }
         return Ok(());
     }
 
+    if cli.sudo_rescan {
+        anyhow::bail!("--sudo-rescan requires an export, --age-report or --top");
+    }
+
     // Interactive mode: launch TUI
-    let mut app = disklens::app::App::new(path, settings);
+    let mut app = disklens::app::App::new(paths, settings);
+    if let Some(mounts) = mounts {
+        app = app.with_mount_picker(mounts);
+    }
     app.run().await
 }
+
+/// Print the `--all-mounts` overview: each filesystem's type, usage and free space.
+fn print_mounts(mounts: &[disklens::core::mounts::MountInfo]) {
+    let width = mounts
+        .iter()
+        .map(|m| m.mount_point.display().to_string().len())
+        .max()
+        .unwrap_or(0);
+    for mount in mounts {
+        println!(
+            "{:<width$}  {:<8} {:>10} used of {:>10}, {:>10} free",
+            mount.mount_point.display(),
+            mount.fs_type,
+            human_readable_size(mount.used()),
+            human_readable_size(mount.total),
+            human_readable_size(mount.available),
+        );
+    }
+}
+
+/// Scan `paths` with the backend `settings` ask for, without a TUI to report to,
+/// reusing unchanged directories saved in the cache when `cache_dirs` is on.
+async fn scan_roots(
+    settings: &disklens::config::settings::Settings,
+    paths: Vec<PathBuf>,
+) -> anyhow::Result<(ScanResult, Option<Arc<IoProfile>>)> {
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    Ok(match settings.backend {
+        ScanBackend::Tasks => {
+            let mut scanner = disklens::core::scanner::Scanner::new(settings.clone(), event_tx);
+            if settings.use_cache && settings.cache_dirs {
+                let cache = disklens::core::cache::Cache::from_settings(settings);
+                scanner = scanner.with_cache(cache);
+            }
+            let result = scanner.scan_roots(paths).await?;
+            (result, scanner.io_profile().cloned())
+        }
+        ScanBackend::Threads | ScanBackend::Uring | ScanBackend::Mft => {
+            let scanner = disklens::core::scanner::ScannerSync::new(settings.clone(), event_tx);
+            let io_profile = scanner.io_profile().cloned();
+            let result = tokio::task::spawn_blocking(move || scanner.scan_roots(paths)).await??;
+            (result, io_profile)
+        }
+    })
+}
+
+/// Save a finished scan for later runs to start from and compare against, as the TUI
+/// does. Sampled, estimated, depth-limited and `--min-size` scans are not saved.
+async fn save_to_cache(settings: &disklens::config::settings::Settings, result: &ScanResult) {
+    if settings.use_cache && result.is_cacheable() {
+        let cache = disklens::core::cache::Cache::from_settings(settings);
+        if let Err(e) = cache.save(result).await {
+            eprintln!("warning: failed to save the scan to the cache: {}", e);
+        }
+        if settings.cache_dirs {
+            let saved = async { anyhow::Ok(cache.save_dirs(result)?.await??) };
+            if let Err(e) = saved.await {
+                eprintln!("warning: failed to save directories to the cache: {}", e);
+            }
+        }
+    }
+}
+
+/// Write the comparison to the report files asked for, or print it when there are none.
+fn report_diff(
+    old: &ScanResult,
+    new: &ScanResult,
+    limit: usize,
+    report: &DiffReport,
+) -> anyhow::Result<()> {
+    if report.export_html.is_none() && report.export_markdown.is_none() {
+        print_diff(old, new, limit);
+        return Ok(());
+    }
+    if let Some(ref export_path) = report.export_html {
+        disklens::export::diff::export_diff_html(old, new, export_path, limit)?;
+        println!("Exported to: {}", export_path.display());
+    }
+    if let Some(ref export_path) = report.export_markdown {
+        disklens::export::diff::export_diff_markdown(old, new, export_path, limit)?;
+        println!("Exported to: {}", export_path.display());
+    }
+    Ok(())
+}
+
+/// Print a comparison of two scans of a tree: the change in total, then the largest
+/// changes.
+fn print_diff(old: &ScanResult, new: &ScanResult, limit: usize) {
+    use disklens::core::diff::DiffResult;
+    use disklens::models::node::human_readable_delta;
+
+    if old.scan_path != new.scan_path {
+        eprintln!(
+            "warning: comparing scans of different paths ({} and {})",
+            old.display_path(),
+            new.display_path()
+        );
+    }
+    let diff = DiffResult::compare(old, new);
+    let date = |time| chrono::DateTime::<chrono::Local>::from(time).format("%Y-%m-%d %H:%M");
+    println!(
+        "{}: {} \u{2192} {} ({}) between {} and {}",
+        new.display_path(),
+        human_readable_size(diff.old_total),
+        human_readable_size(diff.new_total),
+        human_readable_delta(diff.delta()),
+        date(diff.old_timestamp),
+        date(diff.new_timestamp),
+    );
+    // The roots' own change is the total above
+    let changes = diff.changes.iter().filter(|c| c.path != new.root.path).take(limit);
+    for change in changes {
+        let suffix = if change.is_dir { "/" } else { "" };
+        println!(
+            "{:>11}  {:<8} {}{}",
+            human_readable_delta(change.delta()),
+            change.kind.label(),
+            change.path.display(),
+            suffix
+        );
+    }
+}
+
+/// Carry out a `snapshot` subcommand, scanning with `settings` for `save`.
+async fn run_snapshot_command(
+    action: &SnapshotAction,
+    settings: &disklens::config::settings::Settings,
+) -> anyhow::Result<()> {
+    let cache = disklens::core::cache::Cache::from_settings(settings);
+    match action {
+        SnapshotAction::Save { name, path, force } => {
+            // Checked before the scan rather than after it
+            if !force && cache.has_snapshot(name)? {
+                anyhow::bail!("snapshot {:?} already exists; pass --force to replace it", name);
+            }
+            let path = disklens::config::path::resolve_scan_path(path)?;
+            let (result, _) = scan_roots(settings, vec![path]).await?;
+            save_to_cache(settings, &result).await;
+            cache.save_snapshot(name, &result, *force).await?;
+            println!(
+                "Saved snapshot {}: {}, {} in {} files",
+                name,
+                result.scan_path.display(),
+                human_readable_size(result.total_size),
+                result.total_files
+            );
+        }
+        SnapshotAction::List => {
+            let snapshots = cache.list_snapshots().await?;
+            if snapshots.is_empty() {
+                println!("No snapshots; take one with `disklens snapshot save <name> <path>`");
+                return Ok(());
+            }
+            let width = snapshots.iter().map(|s| s.name.len()).max().unwrap_or(0);
+            for snapshot in snapshots {
+                let taken = chrono::DateTime::<chrono::Local>::from(snapshot.taken);
+                println!(
+                    "{:<width$}  {}  {:>10} {:>10} files  {}",
+                    snapshot.name,
+                    taken.format("%Y-%m-%d %H:%M"),
+                    human_readable_size(snapshot.total_size),
+                    snapshot.total_files,
+                    snapshot.path.display()
+                );
+            }
+        }
+        SnapshotAction::Diff { old, new, limit, report } => {
+            let old = cache.load_snapshot(old).await?;
+            let new = cache.load_snapshot(new).await?;
+            report_diff(&old, &new, *limit, report)?;
+        }
+        SnapshotAction::Delete { name } => {
+            cache.remove_snapshot(name).await?;
+            println!("Deleted snapshot {}", name);
+        }
+    }
+    Ok(())
+}
+
+/// Carry out a `cache` subcommand on the cache in the configured directory.
+async fn run_cache_command(action: &CacheAction) -> anyhow::Result<()> {
+    use disklens::config::time_spec::format_age;
+    use disklens::core::cache::Cache;
+
+    let settings = disklens::config::settings::Settings::default();
+    let cache = Cache::from_settings(&settings);
+    let now = SystemTime::now();
+    let age = |time: SystemTime| now.duration_since(time).map_or("-".to_string(), format_age);
+    match action {
+        CacheAction::List => {
+            let scans = cache.list().await?;
+            if scans.is_empty() {
+                println!("No saved scans in {}", cache.dir().display());
+                return Ok(());
+            }
+            println!("{:>8} {:>9} {:>10} {:>10}  Path", "Scanned", "Last used", "Tree", "Cached");
+            for scan in scans {
+                let path = match (&scan.path, scan.has_scan) {
+                    (Some(path), _) => path.display().to_string(),
+                    (None, true) => "(unreadable metadata)".to_string(),
+                    (None, false) => "(size history only)".to_string(),
+                };
+                println!(
+                    "{:>8} {:>9} {:>10} {:>10}  {}",
+                    scan.scanned_at.map_or("-".to_string(), age),
+                    age(scan.last_used),
+                    scan.tree_size.map_or("-".to_string(), human_readable_size),
+                    human_readable_size(scan.size),
+                    path
+                );
+            }
+        }
+        CacheAction::Info => {
+            let scans = cache.list().await?;
+            let saved = scans.iter().filter(|s| s.has_scan).count();
+            let (dirs, dirs_size) = cache.dir_entry_usage().await?;
+            let size = scans.iter().map(|s| s.size).sum::<u64>() + dirs_size;
+            let limit = |enabled: bool, value: String| if enabled { value } else { "none".into() };
+            println!("Directory:    {}", cache.dir().display());
+            println!("Used:         {}", human_readable_size(size));
+            println!("Saved scans:  {}", saved);
+            println!("History only: {}", scans.len() - saved);
+            println!("Directories:  {} ({})", dirs, human_readable_size(dirs_size));
+            println!(
+                "Size limit:   {}",
+                limit(cache.max_size() > 0, human_readable_size(cache.max_size()))
+            );
+            println!(
+                "Age limit:    {}",
+                limit(!cache.max_age().is_zero(), format_age(cache.max_age()))
+            );
+        }
+        CacheAction::Clear => {
+            cache.clear().await?;
+            println!("Cleared {}", cache.dir().display());
+        }
+        CacheAction::Prune { older_than, max_size } => {
+            let cache = if older_than.is_some() || max_size.is_some() {
+                Cache::new(settings.cache_dir.clone())
+                    .with_max_age(older_than.unwrap_or_default())
+                    .with_max_size(max_size.unwrap_or_default())
+            } else {
+                cache
+            };
+            let expired = cache.remove_expired().await?;
+            let evicted = cache.shrink_to_limit().await?;
+            println!(
+                "Removed {} expired and {} least recently used scans from {}",
+                expired,
+                evicted,
+                cache.dir().display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Print the `--top` list: the `n` largest files anywhere in the scan.
+fn print_largest_files(result: &disklens::models::scan_result::ScanResult, n: usize) {
+    let index = disklens::models::index::SizeIndex::build_files(&result.root);
+    for (path, size) in index.top_n(n) {
+        println!("{:>10}  {}", human_readable_size(*size), path.display());
+    }
+}
+
+/// Print the `--compression-report` table: the estimated compressed size and savings
+/// of each entry directly in the scan, the largest savings first, after the total.
+fn print_compression_report(result: &disklens::models::scan_result::ScanResult) {
+    let estimates = disklens::core::compression::estimate_children(&result.root);
+    let width = estimates.iter().map(|e| e.name.chars().count() + 1).max().unwrap_or(0).max(5);
+    let print_row = |name: &str, size: u64, ratio: Option<f64>, savings: u64| {
+        let ratio = ratio.map_or("-".to_string(), |r| format!("{:.0}%", r * 100.0));
+        println!(
+            "{:<width$}  {:>10} {:>10} {:>10}",
+            name,
+            human_readable_size(size),
+            ratio,
+            human_readable_size(savings)
+        );
+    };
+
+    println!("{}", result.scan_path.display());
+    println!("{:<width$}  {:>10} {:>10} {:>10}", "", "Size", "Compressed", "Saves");
+    let size: u64 = estimates.iter().map(|e| e.size).sum();
+    let savings: u64 = estimates.iter().map(|e| e.savings()).sum();
+    let sampled = estimates.iter().any(|e| e.ratio.is_some());
+    let ratio = (size > 0 && sampled).then(|| 1.0 - savings as f64 / size as f64);
+    print_row("Total", size, ratio, savings);
+    for estimate in &estimates {
+        let name = if estimate.is_container {
+            format!("{}/", estimate.name)
+        } else {
+            estimate.name.clone()
+        };
+        print_row(&name, estimate.size, estimate.ratio, estimate.savings());
+    }
+}
+
+/// Print the `--age-report` table: bytes per age bucket for the whole scan, then for
+/// each entry directly in it, the ones holding the most old data first.
+fn print_age_report(result: &disklens::models::scan_result::ScanResult) {
+    use disklens::core::analyzer::{AgeBreakdown, Analyzer, AGE_BUCKET_LABELS};
+
+    let now = SystemTime::now();
+    let entries = Analyzer::age_report(&result.root, now);
+    let width = entries.iter().map(|e| e.name.chars().count() + 1).max().unwrap_or(0).max(5);
+    let print_row = |name: &str, ages: &AgeBreakdown| {
+        let sizes: Vec<String> =
+            ages.buckets.iter().map(|&size| format!("{:>10}", human_readable_size(size))).collect();
+        let mut line = format!("{:<width$}  {}", name, sizes.join(" "));
+        if ages.unknown > 0 {
+            line.push_str(&format!("  +{} undated", human_readable_size(ages.unknown)));
+        }
+        println!("{}", line.trim_end());
+    };
+
+    println!("{}", result.scan_path.display());
+    let labels: Vec<String> = AGE_BUCKET_LABELS.iter().map(|l| format!("{:>10}", l)).collect();
+    println!("{:<width$}  {}", "", labels.join(" "));
+    print_row("Total", &Analyzer::age_breakdown(&result.root, now));
+    for entry in &entries {
+        let name = if entry.is_container { format!("{}/", entry.name) } else { entry.name.clone() };
+        print_row(&name, &entry.ages);
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::node::{Node, NodeType};
+
+/// Coarse file type, decided by extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum FileCategory {
+    Code,
+    Media,
+    Archive,
+    Document,
+    Binary,
+    Other,
+}
+
+impl FileCategory {
+    pub const ALL: [FileCategory; 6] = [
+        FileCategory::Code,
+        FileCategory::Media,
+        FileCategory::Archive,
+        FileCategory::Document,
+        FileCategory::Binary,
+        FileCategory::Other,
+    ];
+
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) => Self::from_extension(ext),
+            None => FileCategory::Other,
+        }
+    }
+
+    /// The category of files ending in `.ext`, in any case.
+    pub fn from_extension(ext: &str) -> Self {
+        match ext.to_ascii_lowercase().as_str() {
+            "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "go" | "py" | "js" | "jsx" | "ts"
+            | "tsx" | "java" | "kt" | "swift" | "rb" | "php" | "cs" | "sh" | "lua" | "sql"
+            | "html" | "css" | "scss" | "json" | "toml" | "yaml" | "yml" | "xml" => {
+                FileCategory::Code
+            }
+            "mp4" | "mkv" | "mov" | "avi" | "webm" | "m4v" | "mp3" | "flac" | "wav" | "aac"
+            | "ogg" | "m4a" | "jpg" | "jpeg" | "png" | "gif" | "webp" | "heic" | "tiff"
+            | "bmp" | "svg" | "raw" | "psd" => FileCategory::Media,
+            "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "dmg" | "iso"
+            | "jar" | "deb" | "rpm" => FileCategory::Archive,
+            "pdf" | "doc" | "docx" | "xls" | "xlsx" | "ppt" | "pptx" | "odt" | "ods" | "txt"
+            | "md" | "rtf" | "csv" | "epub" => FileCategory::Document,
+            "exe" | "dll" | "so" | "dylib" | "a" | "o" | "bin" | "class" | "wasm" | "pyc"
+            | "rlib" | "db" | "sqlite" => FileCategory::Binary,
+            _ => FileCategory::Other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FileCategory::Code => "Code",
+            FileCategory::Media => "Media",
+            FileCategory::Archive => "Archive",
+            FileCategory::Document => "Document",
+            FileCategory::Binary => "Binary",
+            FileCategory::Other => "Other",
+        }
+    }
+
+    /// Color used for this category in the TUI and in reports.
+    pub fn rgb(self) -> (u8, u8, u8) {
+        match self {
+            FileCategory::Code => (0x5d, 0xad, 0xe2),
+            FileCategory::Media => (0xe7, 0x4c, 0x3c),
+            FileCategory::Archive => (0xf3, 0x9c, 0x12),
+            FileCategory::Document => (0x2e, 0xcc, 0x71),
+            FileCategory::Binary => (0x9b, 0x59, 0xb6),
+            FileCategory::Other => (0x95, 0xa5, 0xa6),
+        }
+    }
+
+    /// `rgb` as a CSS hex color.
+    pub fn css_color(self) -> String {
+        let (r, g, b) = self.rgb();
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    }
+}
+
+/// Total file size per category under `root`, largest first. Empty categories are left out.
+pub fn category_totals(root: &Node) -> Vec<(FileCategory, u64)> {
+    fn walk(node: &Node, totals: &mut [u64; FileCategory::ALL.len()]) {
+        if matches!(node.node_type, NodeType::File | NodeType::Summary) {
+            totals[node.file_category() as usize] += node.size;
+        }
+        for child in &node.children {
+            walk(child, totals);
+        }
+    }
+
+    let mut totals = [0; FileCategory::ALL.len()];
+    walk(root, &mut totals);
+    let mut totals: Vec<_> = FileCategory::ALL
+        .into_iter()
+        .zip(totals)
+        .filter(|(_, size)| *size > 0)
+        .collect();
+    totals.sort_by_key(|(_, size)| std::cmp::Reverse(*size));
+    totals
+}
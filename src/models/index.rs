@@ -1,8 +1,9 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
 
-use super::node::Node;
+use super::node::{Node, NodeType};
 
+#[derive(Default)]
 pub struct PathIndex {
     map: HashMap<PathBuf, usize>,
 }
@@ -50,6 +51,7 @@ impl PathIndex {
     }
 }
 
+#[derive(Default)]
 pub struct SizeIndex {
     sorted: Vec<(PathBuf, u64)>,
 }
@@ -62,10 +64,28 @@ impl SizeIndex {
     pub fn build(root: &Node) -> Self {
         let mut index = Self::new();
         Self::collect_recursive(root, &mut index.sorted);
-        index.sorted.sort_by(|a, b| b.1.cmp(&a.1));
+        index.sorted.sort_by_key(|e| std::cmp::Reverse(e.1));
         index
     }
 
+    /// Like `build`, but indexing only files, not the directories containing them or
+    /// stand-ins that total several files.
+    pub fn build_files(root: &Node) -> Self {
+        let mut index = Self::new();
+        Self::collect_files(root, &mut index.sorted);
+        index.sorted.sort_by_key(|e| std::cmp::Reverse(e.1));
+        index
+    }
+
+    fn collect_files(node: &Node, entries: &mut Vec<(PathBuf, u64)>) {
+        if node.node_type == NodeType::File && node.file_count == 1 && !node.estimated {
+            entries.push((node.path.clone(), node.size));
+        }
+        for child in &node.children {
+            Self::collect_files(child, entries);
+        }
+    }
+
     fn collect_recursive(node: &Node, entries: &mut Vec<(PathBuf, u64)>) {
         entries.push((node.path.clone(), node.size));
         for child in &node.children {
@@ -77,4 +97,17 @@ impl SizeIndex {
         let end = n.min(self.sorted.len());
         &self.sorted[..end]
     }
+
+    /// The entry ranked `rank` (0 = largest).
+    pub fn get(&self, rank: usize) -> Option<&(PathBuf, u64)> {
+        self.sorted.get(rank)
+    }
+
+    pub fn len(&self) -> usize {
+        self.sorted.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
 }
@@ -1,3 +1,4 @@
+pub mod category;
 pub mod index;
 pub mod node;
 pub mod scan_result;
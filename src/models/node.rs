@@ -1,16 +1,70 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 use serde::{Deserialize, Serialize};
 
+use super::category::FileCategory;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NodeType {
     File,
     Directory,
     Symlink,
     Other,
+    /// A file or folder inside an archive that was listed with `peek_archives`.
+    ArchiveEntry,
+    /// A stand-in totalling entries the scan didn't list one by one: files folded by
+    /// `min_file_size`, or what sampling extrapolated. Its path doesn't exist on disk.
+    Summary,
+}
+
+impl NodeType {
+    /// Lowercase name for tabular exports.
+    pub fn label(self) -> &'static str {
+        match self {
+            NodeType::File => "file",
+            NodeType::Directory => "directory",
+            NodeType::Symlink => "symlink",
+            NodeType::Other => "other",
+            NodeType::ArchiveEntry => "archive_entry",
+            NodeType::Summary => "summary",
+        }
+    }
+}
+
+/// Which measure a node's `size` holds; the other one is kept in `size_on_disk`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SizeMode {
+    /// File lengths, as `ls` reports them.
+    #[default]
+    Apparent,
+    /// Blocks actually allocated, as `du` reports them.
+    DiskUsage,
+}
+
+impl SizeMode {
+    pub fn toggle(self) -> Self {
+        match self {
+            SizeMode::Apparent => SizeMode::DiskUsage,
+            SizeMode::DiskUsage => SizeMode::Apparent,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SizeMode::Apparent => "apparent size",
+            SizeMode::DiskUsage => "disk usage",
+        }
+    }
 }
 
+/// A file is reported as sparse when its allocated blocks cover at most this share of
+/// its length...
+pub const SPARSE_MAX_RATIO: f64 = 0.5;
+/// ...and its holes add up to at least this many bytes, so small files whose data
+/// fits inline in the inode don't count.
+pub const SPARSE_MIN_HOLES: u64 = 1024 * 1024;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
     pub path: PathBuf,
@@ -22,8 +76,53 @@ pub struct Node {
     pub file_count: usize,
     pub dir_count: usize,
     pub modified: Option<SystemTime>,
+    /// Latest `modified` of anything below a directory, filled in by
+    /// `Analyzer::compute_newest_modified`.
+    #[serde(default)]
+    pub newest_modified: Option<SystemTime>,
     #[cfg(unix)]
     pub inode: Option<u64>,
+    /// Hard-link count; more than one means the data is shared with another path.
+    #[cfg(unix)]
+    #[serde(default)]
+    pub nlink: Option<u64>,
+    /// Owning user and group IDs; only files, symlinks and special files record them.
+    #[cfg(unix)]
+    #[serde(default)]
+    pub uid: Option<u32>,
+    #[cfg(unix)]
+    #[serde(default)]
+    pub gid: Option<u32>,
+    /// Set when this entry was reached through a followed symlink: the resolved path
+    /// it was scanned at, while `path` stays the link's own path. On a symlink that
+    /// wasn't followed, what the link points to, as stored in it (possibly relative).
+    #[serde(default)]
+    pub symlink_target: Option<PathBuf>,
+    /// Set when git's ignore files match this entry or one of its ancestors.
+    #[serde(default)]
+    pub gitignored: bool,
+    /// Bytes in extents shared with cloned or reflinked files, summed over a directory.
+    /// Only filled when clone detection is enabled.
+    #[serde(default)]
+    pub shared_size: u64,
+    /// Extracted size of an archive entry, whose `size` is what it takes up in the archive.
+    #[serde(default)]
+    pub uncompressed_size: Option<u64>,
+    /// File type by extension, recorded when the scanner creates a file node.
+    #[serde(default)]
+    pub category: Option<FileCategory>,
+    /// Set on stand-ins whose size was extrapolated from a sample rather than
+    /// measured, and on every directory holding one.
+    #[serde(default)]
+    pub estimated: bool,
+    /// What kind of regenerable directory this is (package install, build output,
+    /// cache), set by `Analyzer::tag_junk`.
+    #[serde(default)]
+    pub junk: Option<String>,
+    /// Scan errors at or below this node, set by `ScanResult::attribute_errors`. When
+    /// there are any, its size is incomplete.
+    #[serde(default)]
+    pub error_count: usize,
 }
 
 impl Node {
@@ -51,16 +150,43 @@ impl Node {
             file_count: 1,
             dir_count: 0,
             modified,
+            newest_modified: None,
             #[cfg(unix)]
             inode,
+            #[cfg(unix)]
+            nlink: None,
+            #[cfg(unix)]
+            uid: None,
+            #[cfg(unix)]
+            gid: None,
+            symlink_target: None,
+            gitignored: false,
+            shared_size: 0,
+            uncompressed_size: None,
+            category: None,
+            estimated: false,
+            junk: None,
+            error_count: 0,
         }
     }
 
     pub fn from_directory(path: PathBuf, name: String, children: Vec<Node>) -> Self {
-        let size = children.iter().map(|c| c.size).sum();
-        let size_on_disk = children.iter().map(|c| c.size_on_disk).sum();
+        Self::from_directory_with_overhead(path, name, 0, children)
+    }
+
+    /// Like `from_directory`, but starts from `overhead` bytes for the directory entry itself.
+    pub fn from_directory_with_overhead(
+        path: PathBuf,
+        name: String,
+        overhead: u64,
+        children: Vec<Node>,
+    ) -> Self {
+        let size = overhead + children.iter().map(|c| c.size).sum::<u64>();
+        let size_on_disk = overhead + children.iter().map(|c| c.size_on_disk).sum::<u64>();
+        let shared_size = children.iter().map(|c| c.shared_size).sum();
         let file_count = children.iter().map(|c| c.file_count).sum();
         let dir_count: usize = children.iter().map(|c| c.dir_count).sum::<usize>() + 1;
+        let estimated = children.iter().any(|c| c.estimated);
 
         Self {
             path,
@@ -72,8 +198,242 @@ impl Node {
             file_count,
             dir_count,
             modified: None,
+            newest_modified: None,
             #[cfg(unix)]
             inode: None,
+            #[cfg(unix)]
+            nlink: None,
+            #[cfg(unix)]
+            uid: None,
+            #[cfg(unix)]
+            gid: None,
+            symlink_target: None,
+            gitignored: false,
+            shared_size,
+            uncompressed_size: None,
+            category: None,
+            estimated,
+            junk: None,
+            error_count: 0,
+        }
+    }
+
+    /// A [`NodeType::Summary`] in directory `dir` standing for `file_count` files
+    /// totalling `size` bytes, named after what it stands for.
+    pub fn summary(dir: &Path, name: String, size: u64, file_count: usize) -> Self {
+        let mut node = Self::from_file(dir.join(&name), name, size, None, None);
+        node.node_type = NodeType::Summary;
+        node.file_count = file_count;
+        node
+    }
+
+    /// An entry listed inside an archive, sized by its `compressed` bytes in the archive.
+    /// Folders pass their entries as `children`.
+    pub fn archive_entry(
+        path: PathBuf,
+        name: String,
+        compressed: u64,
+        uncompressed: u64,
+        children: Vec<Node>,
+    ) -> Self {
+        Self {
+            path,
+            name,
+            size: compressed,
+            size_on_disk: compressed,
+            node_type: NodeType::ArchiveEntry,
+            children,
+            file_count: 0,
+            dir_count: 0,
+            modified: None,
+            newest_modified: None,
+            #[cfg(unix)]
+            inode: None,
+            #[cfg(unix)]
+            nlink: None,
+            #[cfg(unix)]
+            uid: None,
+            #[cfg(unix)]
+            gid: None,
+            symlink_target: None,
+            gitignored: false,
+            shared_size: 0,
+            uncompressed_size: Some(uncompressed),
+            category: None,
+            estimated: false,
+            junk: None,
+            error_count: 0,
+        }
+    }
+
+    /// The recorded category, or one derived from the path for nodes built elsewhere.
+    pub fn file_category(&self) -> FileCategory {
+        self.category.unwrap_or_else(|| FileCategory::from_path(&self.path))
+    }
+
+    /// Whether this node has entries to browse into: a directory, or an archive that
+    /// was listed.
+    pub fn is_container(&self) -> bool {
+        self.node_type == NodeType::Directory || !self.children.is_empty()
+    }
+
+    /// Whether `path` names this node, either directly or as the resolved target of
+    /// the symlink it was reached through.
+    pub fn matches_path(&self, path: &std::path::Path) -> bool {
+        let followed = self.node_type != NodeType::Symlink;
+        self.path == path || (followed && self.symlink_target.as_deref() == Some(path))
+    }
+
+    /// The name, followed by where it points for a symlink.
+    pub fn name_with_target(&self) -> String {
+        match &self.symlink_target {
+            Some(target) => format!("{} \u{2192} {}", self.name, target.display()),
+            None => self.name.clone(),
+        }
+    }
+
+    /// The node at `path` in this subtree, found by following the directories that
+    /// lead to it.
+    pub fn find(&self, path: &std::path::Path) -> Option<&Node> {
+        if self.path == path {
+            return Some(self);
+        }
+        self.children
+            .iter()
+            .filter(|child| path.starts_with(&child.path))
+            .find_map(|child| child.find(path))
+    }
+
+    /// Size not shared with any clone: what deleting this entry would actually free.
+    pub fn unique_size(&self) -> u64 {
+        self.size.saturating_sub(self.shared_size)
+    }
+
+    /// Dotfiles and dot-directories.
+    pub fn is_hidden(&self) -> bool {
+        self.name.starts_with('.')
+    }
+
+    /// Flag this node and everything below it as ignored by git.
+    pub fn mark_gitignored(&mut self) {
+        self.gitignored = true;
+        for child in &mut self.children {
+            child.mark_gitignored();
+        }
+    }
+
+    /// Bytes in this subtree that git ignores.
+    pub fn gitignored_size(&self) -> u64 {
+        if self.gitignored {
+            self.size
+        } else {
+            self.children.iter().map(Node::gitignored_size).sum()
+        }
+    }
+
+    /// Bytes in this subtree held by directories tagged as regenerable.
+    pub fn junk_size(&self) -> u64 {
+        if self.junk.is_some() {
+            self.size
+        } else {
+            self.children.iter().map(Node::junk_size).sum()
+        }
+    }
+
+    /// ID of the user owning this entry, where the platform and scan recorded one.
+    pub fn owner(&self) -> Option<u32> {
+        #[cfg(unix)]
+        {
+            self.uid
+        }
+        #[cfg(not(unix))]
+        {
+            None
+        }
+    }
+
+    /// Whether this file has other hard links pointing at the same data.
+    pub fn is_hardlinked(&self) -> bool {
+        #[cfg(unix)]
+        {
+            self.node_type == NodeType::File && self.nlink.is_some_and(|n| n > 1)
+        }
+        #[cfg(not(unix))]
+        {
+            false
+        }
+    }
+
+    /// Length and allocated bytes, for a node whose `size` holds `mode`.
+    pub fn apparent_and_allocated(&self, mode: SizeMode) -> (u64, u64) {
+        match mode {
+            SizeMode::Apparent => (self.size, self.size_on_disk),
+            SizeMode::DiskUsage => (self.size_on_disk, self.size),
+        }
+    }
+
+    /// Whether this file takes up much less disk space than its length, as VM images
+    /// and core dumps with holes do. Files on a compressing filesystem look the same.
+    pub fn is_sparse(&self, mode: SizeMode) -> bool {
+        if self.node_type != NodeType::File || self.file_count != 1 {
+            return false;
+        }
+        let (apparent, allocated) = self.apparent_and_allocated(mode);
+        apparent.saturating_sub(allocated) >= SPARSE_MIN_HOLES
+            && allocated as f64 <= apparent as f64 * SPARSE_MAX_RATIO
+    }
+
+    /// Exchange `size` and `size_on_disk` throughout the subtree.
+    pub fn swap_sizes(&mut self) {
+        std::mem::swap(&mut self.size, &mut self.size_on_disk);
+        for child in &mut self.children {
+            child.swap_sizes();
+        }
+    }
+
+    /// A copy of this directory one level deep: files keep their children (archive
+    /// listings), subdirectories are copied without theirs.
+    pub fn shallow_copy(&self) -> Node {
+        let children = self
+            .children
+            .iter()
+            .map(|c| match c.node_type {
+                NodeType::Directory => c.childless_copy(),
+                _ => c.clone(),
+            })
+            .collect();
+        Node { children, ..self.childless_copy() }
+    }
+
+    /// A copy of this node without its children, made without copying them first.
+    fn childless_copy(&self) -> Node {
+        Node {
+            path: self.path.clone(),
+            name: self.name.clone(),
+            size: self.size,
+            size_on_disk: self.size_on_disk,
+            node_type: self.node_type,
+            children: Vec::new(),
+            file_count: self.file_count,
+            dir_count: self.dir_count,
+            modified: self.modified,
+            newest_modified: self.newest_modified,
+            #[cfg(unix)]
+            inode: self.inode,
+            #[cfg(unix)]
+            nlink: self.nlink,
+            #[cfg(unix)]
+            uid: self.uid,
+            #[cfg(unix)]
+            gid: self.gid,
+            symlink_target: self.symlink_target.clone(),
+            gitignored: self.gitignored,
+            shared_size: self.shared_size,
+            uncompressed_size: self.uncompressed_size,
+            category: self.category,
+            estimated: self.estimated,
+            junk: self.junk.clone(),
+            error_count: self.error_count,
         }
     }
 
@@ -84,6 +444,15 @@ impl Node {
     pub fn human_readable_size(&self) -> String {
         human_readable_size(self.size)
     }
+
+    /// The size for reports, marked with `≈` when it was extrapolated.
+    pub fn size_label(&self) -> String {
+        if self.estimated {
+            format!("\u{2248} {}", human_readable_size(self.size))
+        } else {
+            human_readable_size(self.size)
+        }
+    }
 }
 
 pub fn human_readable_size(bytes: u64) -> String {
@@ -104,3 +473,9 @@ pub fn human_readable_size(bytes: u64) -> String {
         format!("{} B", bytes)
     }
 }
+
+/// A size change with its sign, e.g. `+1.20 GB` or `-512 B`.
+pub fn human_readable_delta(delta: i64) -> String {
+    let sign = if delta < 0 { '-' } else { '+' };
+    format!("{}{}", sign, human_readable_size(delta.unsigned_abs()))
+}
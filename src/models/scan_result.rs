@@ -1,9 +1,11 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
 use serde::{Deserialize, Serialize};
 
-use super::node::Node;
+use super::node::{Node, NodeType, SizeMode};
+use crate::config::settings::{FilesystemType, TreeSettings};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScanResult {
@@ -14,7 +16,549 @@ pub struct ScanResult {
     pub scan_duration: Duration,
     pub errors: Vec<ScanError>,
     pub timestamp: SystemTime,
+    /// The scanned path, or empty when several roots were scanned under a virtual root.
     pub scan_path: PathBuf,
+    /// Set when the scan root is on a copy-on-write filesystem.
+    #[serde(default)]
+    pub filesystem: Option<FilesystemType>,
+    /// Set when the scan sampled large directories instead of statting every file.
+    #[serde(default)]
+    pub estimate: Option<SampleEstimate>,
+    /// Directories the scanner deliberately did not descend into.
+    #[serde(default)]
+    pub skipped: Vec<SkippedEntry>,
+    /// What `size` measures on every node; `size_on_disk` holds the other measure.
+    #[serde(default)]
+    pub size_mode: SizeMode,
+    /// Set when the scan was stopped early; the tree holds only what was reached.
+    #[serde(default)]
+    pub cancelled: bool,
+    /// Directories left unread because the `max_duration` budget ran out. They appear
+    /// in the tree as empty directories.
+    #[serde(default)]
+    pub unscanned: Vec<PathBuf>,
+    /// The settings the tree was built with.
+    #[serde(default)]
+    pub tree_settings: TreeSettings,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedEntry {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+/// How much of a sampled scan was extrapolated rather than measured.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SampleEstimate {
+    /// Fraction of `unit` looked at where there were enough of them to sample.
+    pub ratio: f64,
+    /// What was sampled: files with `--sample`, subdirectories with `--estimate`.
+    #[serde(default)]
+    pub unit: SampleUnit,
+    pub estimated_files: usize,
+    /// Directories never read, whose contents were extrapolated.
+    #[serde(default)]
+    pub estimated_dirs: usize,
+    pub estimated_bytes: u64,
+    /// Half-width of the 95% confidence interval on the total size.
+    pub margin_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SampleUnit {
+    /// Only some files of large directories were statted.
+    #[default]
+    Files,
+    /// Only some subdirectories of directories with many were descended into.
+    Directories,
+}
+
+impl SampleUnit {
+    pub fn label(self) -> &'static str {
+        match self {
+            SampleUnit::Files => "files",
+            SampleUnit::Directories => "directories",
+        }
+    }
+}
+
+/// Number of errors copied into `ErrorSummary::sample`.
+pub const ERROR_SAMPLE_SIZE: usize = 5;
+
+/// Errors rolled up by type, with the first few kept as examples.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ErrorSummary {
+    pub by_type: HashMap<ScanErrorType, usize>,
+    pub total: usize,
+    pub sample: Vec<ScanError>,
+}
+
+impl ErrorSummary {
+    /// Counts per type, most frequent first.
+    pub fn counts(&self) -> Vec<(ScanErrorType, usize)> {
+        let mut counts: Vec<_> = self.by_type.iter().map(|(&t, &n)| (t, n)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts
+    }
+}
+
+impl ScanResult {
+    pub fn error_summary(&self) -> ErrorSummary {
+        let mut by_type = HashMap::new();
+        for err in &self.errors {
+            *by_type.entry(err.error_type).or_insert(0) += 1;
+        }
+        ErrorSummary {
+            by_type,
+            total: self.errors.len(),
+            sample: self.errors.iter().take(ERROR_SAMPLE_SIZE).cloned().collect(),
+        }
+    }
+
+    /// A copy of this result keeping only the non-directory entries that satisfy `predicate`.
+    ///
+    /// Directories survive when anything below them does (the root always does), and
+    /// sizes and counts are recomputed from what remains.
+    pub fn filter_files(&self, predicate: impl Fn(&Node) -> bool) -> ScanResult {
+        let root = filter_node(&self.root, &predicate).unwrap_or_else(|| empty_dir(&self.root));
+        self.with_root(root)
+    }
+
+    /// A copy containing only the nodes at `paths` (each with everything below it) and
+    /// the directories leading to them. Totals cover the selected nodes alone.
+    pub fn select_paths(&self, paths: &[PathBuf]) -> ScanResult {
+        let root = select_node(&self.root, paths).unwrap_or_else(|| {
+            Node::from_directory(self.root.path.clone(), self.root.name.clone(), Vec::new())
+        });
+        self.with_root(root)
+    }
+
+    /// This result's metadata around a different tree, with totals taken from `root`.
+    fn with_root(&self, root: Node) -> ScanResult {
+        let mut result = ScanResult {
+            total_size: root.size,
+            total_files: root.file_count,
+            total_dirs: root.dir_count,
+            root,
+            scan_duration: self.scan_duration,
+            errors: self.errors.clone(),
+            timestamp: self.timestamp,
+            scan_path: self.scan_path.clone(),
+            filesystem: self.filesystem,
+            estimate: self.estimate,
+            skipped: self.skipped.clone(),
+            size_mode: self.size_mode,
+            cancelled: self.cancelled,
+            unscanned: self.unscanned.clone(),
+            tree_settings: self.tree_settings.clone(),
+        };
+        result.attribute_errors();
+        result
+    }
+
+    /// Set `Node::error_count` across the tree from `errors`. An error counts on the
+    /// node at its path, or on the directory that was listing it when the entry itself
+    /// couldn't be read, and on every directory above. Symlink cycles leave nothing
+    /// out, so they don't count.
+    pub fn attribute_errors(&mut self) {
+        fn apply(node: &mut Node, counts: &HashMap<&Path, usize>) {
+            node.error_count = counts.get(node.path.as_path()).copied().unwrap_or(0);
+            for child in &mut node.children {
+                apply(child, counts);
+            }
+        }
+
+        let mut counts: HashMap<&Path, usize> = HashMap::new();
+        for err in self.errors.iter().filter(|e| e.error_type != ScanErrorType::SymlinkCycle) {
+            for ancestor in err.path.ancestors() {
+                *counts.entry(ancestor).or_default() += 1;
+            }
+        }
+        apply(&mut self.root, &counts);
+        if self.is_multi_root() {
+            self.root.error_count = self.root.children.iter().map(|c| c.error_count).sum();
+        }
+    }
+
+    /// Whether the time budget ran out before every directory was read.
+    pub fn truncated(&self) -> bool {
+        !self.unscanned.is_empty()
+    }
+
+    /// Whether the tree is missing parts of what is on disk, because the scan was
+    /// cancelled or ran out of time.
+    pub fn is_partial(&self) -> bool {
+        self.cancelled || self.truncated()
+    }
+
+    /// Whether this result may be saved as the scan of its path, for later runs to open
+    /// and build on: a complete tree of a single root whose sizes are all measured.
+    pub fn is_cacheable(&self) -> bool {
+        !self.is_partial() && !self.is_multi_root() && self.tree_settings.is_exact()
+    }
+
+    /// Merge `other`, a rescan of directories inside this tree, into this result. Each
+    /// of its roots replaces the node at the same path, the directories above are
+    /// re-totalled, and errors recorded inside the rescanned directories give way to
+    /// `other`'s. Returns how many roots were merged; the others had no place here.
+    pub fn merge_rescan(&mut self, mut other: ScanResult) -> usize {
+        other.set_size_mode(self.size_mode);
+        let subtrees = if other.is_multi_root() {
+            other.root.children
+        } else {
+            vec![other.root]
+        };
+        let mut merged = 0;
+        for subtree in subtrees {
+            let path = subtree.path.clone();
+            if path == self.root.path || self.root.find(&path).is_none() {
+                continue;
+            }
+            graft(&mut self.root, subtree);
+            self.errors.retain(|e| !e.path.starts_with(&path));
+            self.errors.extend(other.errors.iter().filter(|e| e.path.starts_with(&path)).cloned());
+            merged += 1;
+        }
+        if merged > 0 && self.is_multi_root() {
+            // Rebuilding counted the virtual root as a directory
+            self.root.dir_count -= 1;
+        }
+        self.attribute_errors();
+        self.total_size = self.root.size;
+        self.total_files = self.root.file_count;
+        self.total_dirs = self.root.dir_count;
+        merged
+    }
+
+    /// Drop the nodes at `paths`, which were deleted from disk, re-total the directories
+    /// above them and forget the errors recorded inside them. The roots stay.
+    pub fn remove_paths(&mut self, paths: &[PathBuf]) {
+        if paths.is_empty() {
+            return;
+        }
+        self.root = prune(&self.root, paths);
+        if self.is_multi_root() {
+            // Rebuilding counted the virtual root as a directory
+            self.root.dir_count -= 1;
+        }
+        self.errors.retain(|e| !paths.iter().any(|path| e.path.starts_with(path)));
+        self.attribute_errors();
+        self.total_size = self.root.size;
+        self.total_files = self.root.file_count;
+        self.total_dirs = self.root.dir_count;
+    }
+
+    /// An empty result for a scan of `roots` that is still running, to be filled in with
+    /// [`ScanResult::insert_subtree`] as its directories finish.
+    pub fn provisional(roots: &[PathBuf]) -> ScanResult {
+        let (scan_path, root) = match roots {
+            [root] => (root.clone(), placeholder_dir(root)),
+            _ => {
+                let children = roots.iter().map(|root| placeholder_dir(root)).collect();
+                let name = format!("{} roots", roots.len());
+                let mut root = Node::from_directory(PathBuf::new(), name, children);
+                root.dir_count -= 1;
+                (PathBuf::new(), root)
+            }
+        };
+        ScanResult {
+            total_size: 0,
+            total_files: 0,
+            total_dirs: root.dir_count,
+            root,
+            scan_duration: Duration::ZERO,
+            errors: Vec::new(),
+            timestamp: SystemTime::now(),
+            scan_path,
+            filesystem: None,
+            estimate: None,
+            skipped: Vec::new(),
+            size_mode: SizeMode::Apparent,
+            cancelled: false,
+            unscanned: Vec::new(),
+            tree_settings: TreeSettings::default(),
+        }
+    }
+
+    /// Put `subtree`, a finished directory of the scan still building this result, in
+    /// place at its path and re-total the directories above it. Ancestors not seen yet
+    /// are added as empty directories until they finish too. `subtree` holds apparent
+    /// sizes, as scanners send them; subtrees outside the scanned roots are ignored.
+    pub fn insert_subtree(&mut self, mut subtree: Node) {
+        let inside = self
+            .roots()
+            .iter()
+            .any(|root| subtree.path != *root && subtree.path.starts_with(root));
+        if !inside {
+            return;
+        }
+        if self.size_mode != SizeMode::Apparent {
+            subtree.swap_sizes();
+        }
+        graft(&mut self.root, subtree);
+        if self.is_multi_root() {
+            self.root.dir_count -= 1;
+        }
+        self.total_size = self.root.size;
+        self.total_files = self.root.file_count;
+        self.total_dirs = self.root.dir_count;
+    }
+
+    /// Whether `root` is a virtual node holding several scanned roots as children.
+    pub fn is_multi_root(&self) -> bool {
+        self.scan_path.as_os_str().is_empty()
+    }
+
+    /// The paths that were scanned.
+    pub fn roots(&self) -> Vec<PathBuf> {
+        if self.is_multi_root() {
+            self.root.children.iter().map(|child| child.path.clone()).collect()
+        } else {
+            vec![self.scan_path.clone()]
+        }
+    }
+
+    /// The scanned paths for display in reports, comma separated.
+    pub fn display_path(&self) -> String {
+        let roots: Vec<String> = self.roots().iter().map(|p| p.display().to_string()).collect();
+        roots.join(", ")
+    }
+
+    /// Make `size` and `total_size` report `mode`, swapping the two measures if needed.
+    pub fn set_size_mode(&mut self, mode: SizeMode) {
+        if self.size_mode == mode {
+            return;
+        }
+        self.root.swap_sizes();
+        self.total_size = self.root.size;
+        self.size_mode = mode;
+    }
+
+    /// A caveat for reports when cloned files may share blocks on this filesystem.
+    pub fn clone_note(&self) -> Option<String> {
+        self.filesystem.map(|fs| {
+            format!(
+                "{} is copy-on-write: cloned files share blocks, so on-disk totals may overstate real usage",
+                fs.name()
+            )
+        })
+    }
+
+    /// A caveat for reports when sizes were extrapolated from a sample.
+    pub fn estimate_note(&self) -> Option<String> {
+        self.estimate.map(|e| {
+            let count = match e.unit {
+                SampleUnit::Files => e.estimated_files,
+                SampleUnit::Directories => e.estimated_dirs,
+            };
+            format!(
+                "Estimated: {} {} ({}) extrapolated from a {:.0}% sample, total \u{00b1}{} at 95% confidence",
+                count,
+                e.unit.label(),
+                super::node::human_readable_size(e.estimated_bytes),
+                e.ratio * 100.0,
+                super::node::human_readable_size(e.margin_bytes)
+            )
+        })
+    }
+
+    /// A caveat for reports when the time budget left directories unscanned.
+    pub fn truncation_note(&self) -> Option<String> {
+        self.truncated().then(|| {
+            format!(
+                "Truncated: the time budget ran out with {} directories unscanned; their sizes are missing",
+                self.unscanned.len()
+            )
+        })
+    }
+
+    /// Keep files modified at or after `after` and strictly before `before`.
+    /// Files with an unknown modification time are dropped.
+    pub fn filter_modified(&self, after: Option<SystemTime>, before: Option<SystemTime>) -> ScanResult {
+        self.filter_files(|node| match node.modified {
+            Some(modified) => {
+                after.is_none_or(|after| modified >= after)
+                    && before.is_none_or(|before| modified < before)
+            }
+            None => false,
+        })
+    }
+
+    /// A copy rooted at the entry at `path`, with totals and errors covering it alone;
+    /// `None` when the scan holds nothing there.
+    pub fn subtree(&self, path: &Path) -> Option<ScanResult> {
+        let node = self.root.find(path)?;
+        let mut result = self.with_root(node.clone());
+        result.scan_path = path.to_path_buf();
+        result.errors.retain(|error| error.path.starts_with(path));
+        result.skipped.retain(|skipped| skipped.path.starts_with(path));
+        result.unscanned.retain(|unscanned| unscanned.starts_with(path));
+        Some(result)
+    }
+
+    /// Drop the entries deeper than `max_depth` (the root being 0) and those smaller
+    /// than `min_size`, so reports on huge trees stay readable. Directories keep their
+    /// scanned sizes and counts, which still include what was left out.
+    pub fn trim(&mut self, max_depth: Option<usize>, min_size: u64) {
+        trim_node(&mut self.root, max_depth, min_size);
+    }
+}
+
+fn trim_node(node: &mut Node, depth_left: Option<usize>, min_size: u64) {
+    if depth_left == Some(0) {
+        node.children = Vec::new();
+        return;
+    }
+    node.children.retain(|child| child.size >= min_size);
+    for child in &mut node.children {
+        trim_node(child, depth_left.map(|depth| depth - 1), min_size);
+    }
+}
+
+fn filter_node(node: &Node, predicate: &impl Fn(&Node) -> bool) -> Option<Node> {
+    if node.node_type != NodeType::Directory {
+        return predicate(node).then(|| node.clone());
+    }
+
+    let children: Vec<Node> = node
+        .children
+        .iter()
+        .filter_map(|child| filter_node(child, predicate))
+        .collect();
+    if children.is_empty() {
+        return None;
+    }
+    Some(rebuild_dir(node, children))
+}
+
+fn select_node(node: &Node, paths: &[PathBuf]) -> Option<Node> {
+    if paths.contains(&node.path) {
+        return Some(node.clone());
+    }
+    if node.node_type != NodeType::Directory || !paths.iter().any(|p| p.starts_with(&node.path)) {
+        return None;
+    }
+
+    let children: Vec<Node> = node
+        .children
+        .iter()
+        .filter_map(|child| select_node(child, paths))
+        .collect();
+    if children.is_empty() {
+        return None;
+    }
+    // Ancestors only lead to the selection, so their own overhead isn't counted
+    let mut dir = Node::from_directory(node.path.clone(), node.name.clone(), children);
+    dir.modified = node.modified;
+    dir.symlink_target = node.symlink_target.clone();
+    Some(dir)
+}
+
+/// `node` without the descendants at `paths`, re-totalled along the way down to them.
+fn prune(node: &Node, paths: &[PathBuf]) -> Node {
+    let children = node
+        .children
+        .iter()
+        .filter(|child| !paths.contains(&child.path))
+        .map(|child| {
+            if paths.iter().any(|p| p.starts_with(&child.path)) {
+                prune(child, paths)
+            } else {
+                child.clone()
+            }
+        })
+        .collect();
+    rebuild_dir(node, children)
+}
+
+/// An empty directory standing in for `path` until its contents are known.
+fn placeholder_dir(path: &Path) -> Node {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+    Node::from_directory(path.to_path_buf(), name, Vec::new())
+}
+
+fn empty_dir(node: &Node) -> Node {
+    rebuild_dir(node, Vec::new())
+}
+
+/// Re-aggregate `node` over a new set of children, keeping its own overhead and metadata.
+fn rebuild_dir(node: &Node, children: Vec<Node>) -> Node {
+    rebuild_dir_with_overhead(node, dir_overhead(node), children)
+}
+
+/// Bytes of `node` that don't come from its children: the directory entry itself.
+fn dir_overhead(node: &Node) -> u64 {
+    node.size.saturating_sub(node.children.iter().map(|c| c.size).sum::<u64>())
+}
+
+fn rebuild_dir_with_overhead(node: &Node, overhead: u64, children: Vec<Node>) -> Node {
+    let mut dir =
+        Node::from_directory_with_overhead(node.path.clone(), node.name.clone(), overhead, children);
+    dir.modified = node.modified;
+    dir.symlink_target = node.symlink_target.clone();
+    dir.gitignored = node.gitignored;
+    #[cfg(unix)]
+    {
+        dir.inode = node.inode;
+        dir.nlink = node.nlink;
+        dir.uid = node.uid;
+        dir.gid = node.gid;
+    }
+    dir
+}
+
+/// Put `subtree` in place of the descendant of `node` with the same path, adding empty
+/// directories for any missing on the way down, and re-total the directories leading
+/// to it.
+fn graft(node: &mut Node, subtree: Node) {
+    let overhead = dir_overhead(node);
+    let index = match node.children.iter().position(|c| subtree.path.starts_with(&c.path)) {
+        Some(index) => index,
+        None => {
+            let Some(next) = subtree
+                .path
+                .strip_prefix(&node.path)
+                .ok()
+                .and_then(|rest| rest.components().next())
+            else {
+                return;
+            };
+            node.children.push(placeholder_dir(&node.path.join(next)));
+            node.children.len() - 1
+        }
+    };
+    let child = &mut node.children[index];
+    if child.path == subtree.path {
+        *child = subtree;
+    } else {
+        graft(child, subtree);
+    }
+    let children = std::mem::take(&mut node.children);
+    *node = rebuild_dir_with_overhead(node, overhead, children);
+}
+
+/// Output of [`Scanner::scan_top_n`](crate::core::scanner::Scanner::scan_top_n):
+/// running totals and the largest files, without the directory tree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopNResult {
+    pub scan_path: PathBuf,
+    /// Largest files first.
+    pub files: Vec<TopFile>,
+    pub total_size: u64,
+    pub total_files: usize,
+    pub total_dirs: usize,
+    pub scan_duration: Duration,
+    pub errors: Vec<ScanError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopFile {
+    pub path: PathBuf,
+    pub size: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,7 +568,7 @@ pub struct ScanError {
     pub message: String,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ScanErrorType {
     PermissionDenied,
     NotFound,
@@ -1,7 +1,27 @@
-use std::path::PathBuf;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-use crate::models::node::Node;
-use crate::models::scan_result::ScanResult;
+use crate::config::notes::NotesStore;
+use crate::config::settings::ListDensity;
+use crate::config::time_spec::format_age;
+use crate::core::analyzer::{
+    Analyzer, EmptyDir, JunkDir, OwnerUsage, RecentFile, Suggestion, TreeStats,
+};
+use crate::core::cache::SizeHistory;
+use crate::core::compression::CompressionEstimate;
+use crate::core::diff::DiffResult;
+use crate::core::mounts::MountInfo;
+use crate::export::ExportFormat;
+use crate::models::index::SizeIndex;
+use crate::models::node::{Node, NodeType, SizeMode};
+use crate::models::scan_result::{ScanError, ScanResult};
+use crate::ui::widgets::ring_chart::LegendPlacement;
+
+/// Most files listed in `ViewMode::LargestFiles`.
+pub const LARGEST_FILES_SHOWN: usize = 100;
+/// Most files listed in `ViewMode::RecentFiles`.
+pub const RECENT_FILES_SHOWN: usize = 100;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ViewMode {
@@ -9,7 +29,33 @@ pub enum ViewMode {
     Normal,
     Help,
     ErrorList,
+    /// Choosing the format, scope and file of an export.
     Export,
+    NoteInput,
+    /// Choosing which mounted filesystems to scan, before any scan starts.
+    MountPicker,
+    /// Size per file extension and category below the current directory.
+    FileTypes,
+    /// The largest files anywhere in the scan, with their full paths.
+    LargestFiles,
+    /// Empty and near-empty directories, to pick and delete.
+    EmptyDirs,
+    /// Bytes below the current directory by time since last modification.
+    AgeReport,
+    /// How the scanned root's top-level entries grew over past scans.
+    History,
+    /// Regenerable directories (package installs, build output, caches) in the scan.
+    Cleanup,
+    /// Ranked ways to free space across the scan.
+    Suggestions,
+    /// Bytes below the current directory per owning user.
+    Owners,
+    /// How well the entries of a directory would compress, from sampled contents.
+    Compression,
+    /// How deep and how wide the tree below the current directory is.
+    TreeStats,
+    /// The most recently modified files below the current directory.
+    RecentFiles,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -23,6 +69,8 @@ pub enum SortMode {
     Size,
     Name,
     Modified,
+    /// Newest modification time of anything inside; files sort by their own.
+    NewestContent,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -31,6 +79,58 @@ pub enum SortOrder {
     Descending,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeTypeFilter {
+    All,
+    DirsOnly,
+    FilesOnly,
+}
+
+impl NodeTypeFilter {
+    pub fn matches(self, node: &Node) -> bool {
+        match self {
+            NodeTypeFilter::All => true,
+            NodeTypeFilter::DirsOnly => node.node_type == NodeType::Directory,
+            NodeTypeFilter::FilesOnly => node.node_type != NodeType::Directory,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NodeTypeFilter::All => "All",
+            NodeTypeFilter::DirsOnly => "Dirs only",
+            NodeTypeFilter::FilesOnly => "Files only",
+        }
+    }
+}
+
+/// What the export dialog writes out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportScope {
+    Scan,
+    CurrentDirectory,
+    /// The marked entries and the directories leading to them.
+    Marked,
+}
+
+impl ExportScope {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExportScope::Scan => "Whole scan",
+            ExportScope::CurrentDirectory => "Current directory",
+            ExportScope::Marked => "Marked entries",
+        }
+    }
+}
+
+/// Rows of the export dialog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportField {
+    Format,
+    Scope,
+    Path,
+}
+
 pub struct AppState {
     pub view_mode: ViewMode,
     pub focus: FocusPanel,
@@ -41,14 +141,112 @@ pub struct AppState {
     pub sort_mode: SortMode,
     pub sort_order: SortOrder,
     pub merge_threshold: f64,
+    pub node_type_filter: NodeTypeFilter,
+    /// List dotfiles and dot-directories; when off they are filtered out like `node_type_filter`.
+    pub show_hidden: bool,
+    /// When filtering, compute percentages against the visible entries instead of the whole directory.
+    pub percent_of_visible: bool,
+    pub show_legend: bool,
+    pub legend_placement: LegendPlacement,
+    /// Show file and directory counts under the total in the ring chart's center.
+    pub show_center_stats: bool,
+    /// Drop dominant children from the ring chart so smaller slices stay legible.
+    pub ring_zoom: bool,
     pub scan_result: Option<ScanResult>,
+    /// A scan is running. `scan_result`, when set meanwhile, is a provisional tree of
+    /// the directories it has finished so far.
+    pub scanning: bool,
+    /// When the tree shown is a saved scan from the cache, when that scan ran.
+    pub cached_scan: Option<SystemTime>,
+    /// A scan is running in the background to replace the cached tree shown, which
+    /// stays until it finishes.
+    pub refreshing: bool,
+    /// Sizes from previous scans of this root, for the file list's trend column.
+    pub size_history: Option<SizeHistory>,
+    /// How much each entry changed since the previous scan of this root, and when that
+    /// scan ran; empty when there was none.
+    pub size_deltas: HashMap<PathBuf, i64>,
+    pub previous_scan: Option<SystemTime>,
     pub should_quit: bool,
     pub files_scanned: usize,
     pub total_size_scanned: u64,
     pub scan_speed: f64,
     pub current_scanning_path: String,
+    /// The running scan is paused.
+    pub scan_paused: bool,
+    /// Type of a network filesystem the running scan reached and is reading throttled.
+    pub network_fs: Option<String>,
     pub error_count: usize,
+    pub error_query: String,
+    pub error_scroll: usize,
+    /// First extension shown in `ViewMode::FileTypes`.
+    pub file_types_scroll: usize,
+    /// First entry shown in `ViewMode::AgeReport`.
+    pub age_report_scroll: usize,
+    /// First entry shown in `ViewMode::History`.
+    pub history_scroll: usize,
     pub pending_g: bool,
+    /// One-off notice shown in the status bar until the next key press.
+    pub status_message: Option<String>,
+    pub notes: NotesStore,
+    /// Text being edited in `ViewMode::NoteInput`, and the path it will be attached to.
+    pub note_input: String,
+    pub note_target: Option<PathBuf>,
+    /// Entries marked for a subset export.
+    pub marked: BTreeSet<PathBuf>,
+    /// Choices in `ViewMode::Export`: the format, what to export, the file to write,
+    /// and the row under the cursor. The format is kept from one export to the next.
+    pub export_format: ExportFormat,
+    pub export_scope: ExportScope,
+    pub export_path: String,
+    pub export_field: ExportField,
+    pub list_density: ListDensity,
+    /// Filesystems offered in `ViewMode::MountPicker`, the one under the cursor, and
+    /// the mount points picked so far.
+    pub mounts: Vec<MountInfo>,
+    pub mount_cursor: usize,
+    pub picked_mounts: BTreeSet<PathBuf>,
+    /// Every file in the scan by size, built the first time it's needed.
+    largest_files: Option<SizeIndex>,
+    /// Rank of the file the last "go to largest" landed on.
+    largest_rank: Option<usize>,
+    /// Row under the cursor in `ViewMode::LargestFiles`.
+    pub largest_files_cursor: usize,
+    /// Directories listed in `ViewMode::EmptyDirs`, the one under the cursor, the ones
+    /// picked for deletion, and whether a delete is waiting for confirmation.
+    pub empty_dirs: Vec<EmptyDir>,
+    pub empty_dirs_cursor: usize,
+    pub picked_empty_dirs: BTreeSet<PathBuf>,
+    pub confirm_delete: bool,
+    /// Directories holding at most this many bytes count as near-empty; 0 lists only
+    /// directories without files.
+    pub near_empty_size: u64,
+    /// Directory names tagged as regenerable besides the built-in catalogue.
+    pub junk_dirs: Vec<String>,
+    /// Directories listed in `ViewMode::Cleanup`, and the one under the cursor.
+    pub cleanup_candidates: Vec<JunkDir>,
+    pub cleanup_cursor: usize,
+    /// Entries listed in `ViewMode::Suggestions`, and the one under the cursor.
+    pub suggestions: Vec<Suggestion>,
+    pub suggestions_cursor: usize,
+    /// Users listed in `ViewMode::Owners`, for the directory it was opened on, and
+    /// the first one shown.
+    pub owners: Vec<OwnerUsage>,
+    pub owners_scroll: usize,
+    /// Estimates listed in `ViewMode::Compression` for the directory at
+    /// `compression_path`, `None` while they are being worked out, and the first one
+    /// shown.
+    pub compression: Option<Vec<CompressionEstimate>>,
+    pub compression_path: PathBuf,
+    pub compression_scroll: usize,
+    /// Statistics shown in `ViewMode::TreeStats`, for the directory it was opened on,
+    /// and the first line shown.
+    pub tree_stats: Option<TreeStats>,
+    pub tree_stats_scroll: usize,
+    /// Files listed in `ViewMode::RecentFiles`, for the directory it was opened on, and
+    /// the one under the cursor.
+    pub recent_files: Vec<RecentFile>,
+    pub recent_files_cursor: usize,
 }
 
 impl AppState {
@@ -63,14 +261,69 @@ impl AppState {
             sort_mode: SortMode::Size,
             sort_order: SortOrder::Descending,
             merge_threshold: 0.01,
+            node_type_filter: NodeTypeFilter::All,
+            show_hidden: true,
+            percent_of_visible: false,
+            show_legend: true,
+            legend_placement: LegendPlacement::Right,
+            show_center_stats: false,
+            ring_zoom: false,
             scan_result: None,
+            scanning: true,
+            cached_scan: None,
+            refreshing: false,
+            size_history: None,
+            size_deltas: HashMap::new(),
+            previous_scan: None,
             should_quit: false,
             files_scanned: 0,
             total_size_scanned: 0,
             scan_speed: 0.0,
             current_scanning_path: String::new(),
+            scan_paused: false,
+            network_fs: None,
             error_count: 0,
+            error_query: String::new(),
+            error_scroll: 0,
+            file_types_scroll: 0,
+            age_report_scroll: 0,
+            history_scroll: 0,
             pending_g: false,
+            status_message: None,
+            notes: NotesStore::default(),
+            note_input: String::new(),
+            note_target: None,
+            marked: BTreeSet::new(),
+            export_format: ExportFormat::Json,
+            export_scope: ExportScope::Scan,
+            export_path: String::new(),
+            export_field: ExportField::Format,
+            list_density: ListDensity::Compact,
+            mounts: Vec::new(),
+            mount_cursor: 0,
+            picked_mounts: BTreeSet::new(),
+            largest_files: None,
+            largest_rank: None,
+            largest_files_cursor: 0,
+            empty_dirs: Vec::new(),
+            empty_dirs_cursor: 0,
+            picked_empty_dirs: BTreeSet::new(),
+            confirm_delete: false,
+            near_empty_size: 0,
+            junk_dirs: Vec::new(),
+            cleanup_candidates: Vec::new(),
+            cleanup_cursor: 0,
+            suggestions: Vec::new(),
+            suggestions_cursor: 0,
+            owners: Vec::new(),
+            owners_scroll: 0,
+            compression: None,
+            compression_path: PathBuf::new(),
+            compression_scroll: 0,
+            tree_stats: None,
+            tree_stats_scroll: 0,
+            recent_files: Vec::new(),
+            recent_files_cursor: 0,
         }
     }
 
@@ -93,7 +346,7 @@ impl AppState {
     pub fn enter_directory(&mut self) {
         let children = self.sorted_children();
         if let Some(child) = children.get(self.selected_index) {
-            if child.node_type == crate::models::node::NodeType::Directory {
+            if child.is_container() {
                 let child_path = child.path.clone();
                 self.path_stack.push(self.current_path.clone());
                 self.current_path = child_path;
@@ -111,6 +364,147 @@ impl AppState {
         }
     }
 
+    /// Jump straight to `path_stack[index]`, discarding everything visited after it.
+    pub fn jump_to_ancestor(&mut self, index: usize) {
+        if index >= self.path_stack.len() {
+            return;
+        }
+        self.path_stack.truncate(index + 1);
+        if let Some(ancestor) = self.path_stack.pop() {
+            self.current_path = ancestor;
+            self.selected_index = 0;
+            self.list_offset = 0;
+        }
+    }
+
+    /// Reveal the largest file in the whole scan: open its parent directory with the
+    /// breadcrumb trail from the root, and select it. Pressing again while it is still
+    /// selected moves on to the next largest.
+    pub fn jump_to_largest_file(&mut self) {
+        let selected = self
+            .sorted_children()
+            .get(self.selected_index)
+            .map(|c| c.path.clone());
+        let last_rank = self.largest_rank;
+        let Some(index) = self.largest_files_index() else {
+            return;
+        };
+        if index.is_empty() {
+            return;
+        }
+        let rank = match last_rank {
+            Some(rank) if index.get(rank).map(|(p, _)| p) == selected.as_ref() => {
+                (rank + 1) % index.len()
+            }
+            _ => 0,
+        };
+        self.reveal_largest_file(rank);
+    }
+
+    /// Every file in the scan by size, building the index on first use.
+    fn largest_files_index(&mut self) -> Option<&SizeIndex> {
+        let result = self.scan_result.as_ref()?;
+        Some(self.largest_files.get_or_insert_with(|| SizeIndex::build_files(&result.root)))
+    }
+
+    /// The files listed in `ViewMode::LargestFiles`, once it has been opened.
+    pub fn largest_files(&self) -> &[(PathBuf, u64)] {
+        self.largest_files.as_ref().map_or(&[], |index| index.top_n(LARGEST_FILES_SHOWN))
+    }
+
+    pub fn toggle_largest_files(&mut self) {
+        self.view_mode = if self.view_mode == ViewMode::LargestFiles {
+            ViewMode::Normal
+        } else {
+            self.largest_files_index();
+            ViewMode::LargestFiles
+        };
+        self.largest_files_cursor = 0;
+    }
+
+    pub fn move_largest_files_cursor(&mut self, delta: isize) {
+        let max = self.largest_files().len().saturating_sub(1);
+        self.largest_files_cursor =
+            self.largest_files_cursor.saturating_add_signed(delta).min(max);
+    }
+
+    /// Close `ViewMode::LargestFiles` and reveal the file under its cursor.
+    pub fn open_largest_file(&mut self) {
+        let rank = self.largest_files_cursor;
+        self.toggle_largest_files();
+        self.reveal_largest_file(rank);
+    }
+
+    /// Open the parent directory of the file ranked `rank` by size, with the breadcrumb
+    /// trail from the root, and select it.
+    pub fn reveal_largest_file(&mut self, rank: usize) {
+        let Some((path, size)) = self.largest_files_index().and_then(|i| i.get(rank)).cloned()
+        else {
+            return;
+        };
+        if !self.reveal(&path) {
+            return;
+        }
+        self.largest_rank = Some(rank);
+        self.status_message = Some(format!(
+            "#{} largest file: {} ({})",
+            rank + 1,
+            path.display(),
+            crate::models::node::human_readable_size(size)
+        ));
+    }
+
+    /// Open the directory holding `path`, with the breadcrumb trail from the root, and
+    /// select it. False when `path` isn't below a scanned root.
+    fn reveal(&mut self, path: &Path) -> bool {
+        let Some(result) = &self.scan_result else {
+            return false;
+        };
+        let roots = result.roots();
+        let multi_root = result.is_multi_root();
+        let Some(root) = roots.into_iter().find(|root| path.starts_with(root)) else {
+            return false;
+        };
+        // A root that is itself a file has no directory to open
+        let Some(parent) = path
+            .parent()
+            .filter(|p| p.starts_with(&root))
+            .map(PathBuf::from)
+        else {
+            return false;
+        };
+
+        let mut stack: Vec<PathBuf> = parent
+            .ancestors()
+            .skip(1)
+            .take_while(|a| a.starts_with(&root))
+            .map(PathBuf::from)
+            .collect();
+        // Several roots hang off a virtual root with an empty path
+        if multi_root {
+            stack.push(PathBuf::new());
+        }
+        stack.reverse();
+        self.path_stack = stack;
+        self.current_path = parent;
+        self.list_offset = 0;
+        if self.node_type_filter == NodeTypeFilter::DirsOnly {
+            self.node_type_filter = NodeTypeFilter::All;
+        }
+        let under_hidden = path
+            .strip_prefix(&root)
+            .is_ok_and(|rel| rel.iter().any(|name| name.to_string_lossy().starts_with('.')));
+        if under_hidden {
+            self.show_hidden = true;
+        }
+        self.selected_index = self
+            .sorted_children()
+            .iter()
+            .position(|c| c.path == path)
+            .unwrap_or(0);
+        true
+    }
+
     pub fn go_to_first(&mut self) {
         self.selected_index = 0;
         self.list_offset = 0;
@@ -137,6 +531,9 @@ impl AppState {
 
     pub fn sorted_children(&self) -> Vec<&Node> {
         let mut children = self.current_children();
+        children.retain(|c| {
+            self.node_type_filter.matches(c) && (self.show_hidden || !c.is_hidden())
+        });
         match self.sort_mode {
             SortMode::Size => {
                 children.sort_by(|a, b| {
@@ -167,6 +564,19 @@ impl AppState {
                     }
                 });
             }
+            SortMode::NewestContent => {
+                // Empty directories fall back to their own time
+                let newest = |node: &Node| {
+                    node.newest_modified.or(node.modified).unwrap_or(std::time::UNIX_EPOCH)
+                };
+                children.sort_by(|a, b| {
+                    if self.sort_order == SortOrder::Descending {
+                        newest(b).cmp(&newest(a))
+                    } else {
+                        newest(a).cmp(&newest(b))
+                    }
+                });
+            }
         }
         children
     }
@@ -175,16 +585,291 @@ impl AppState {
         self.sorted_children().len()
     }
 
+    /// The size that child percentages are computed against: the current directory's
+    /// total, or only the visible entries when a type filter is active or hidden entries
+    /// are not shown, and `percent_of_visible` is set.
+    pub fn percentage_base(&self) -> u64 {
+        let filtered = self.node_type_filter != NodeTypeFilter::All || !self.show_hidden;
+        if filtered && self.percent_of_visible {
+            self.sorted_children().iter().map(|c| c.size).sum()
+        } else {
+            self.current_node().map(|n| n.size).unwrap_or(0)
+        }
+    }
+
+    pub fn cycle_node_type_filter(&mut self) {
+        self.node_type_filter = match self.node_type_filter {
+            NodeTypeFilter::All => NodeTypeFilter::DirsOnly,
+            NodeTypeFilter::DirsOnly => NodeTypeFilter::FilesOnly,
+            NodeTypeFilter::FilesOnly => NodeTypeFilter::All,
+        };
+        self.selected_index = 0;
+        self.list_offset = 0;
+    }
+
+    pub fn toggle_legend(&mut self) {
+        self.show_legend = !self.show_legend;
+    }
+
+    pub fn cycle_legend_placement(&mut self) {
+        self.legend_placement = match self.legend_placement {
+            LegendPlacement::Right => LegendPlacement::Bottom,
+            _ => LegendPlacement::Right,
+        };
+        self.show_legend = true;
+    }
+
+    /// Legend placement to hand to the ring chart, honoring the visibility toggle.
+    pub fn effective_legend(&self) -> LegendPlacement {
+        if self.show_legend {
+            self.legend_placement
+        } else {
+            LegendPlacement::Off
+        }
+    }
+
+    pub fn toggle_center_stats(&mut self) {
+        self.show_center_stats = !self.show_center_stats;
+    }
+
+    pub fn toggle_ring_zoom(&mut self) {
+        self.ring_zoom = !self.ring_zoom;
+    }
+
+    /// Largest child size kept in the ring chart while zoomed, or `None` when nothing is
+    /// excluded. Children are peeled off largest-first while each one outweighs everything
+    /// smaller than it combined; the selected child and the last two slices are always kept.
+    pub fn ring_zoom_cap(&self) -> Option<u64> {
+        if !self.ring_zoom {
+            return None;
+        }
+        let children = self.sorted_children();
+        let selected_size = children.get(self.selected_index).map(|c| c.size)?;
+
+        let mut sizes: Vec<u64> = children.iter().map(|c| c.size).collect();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+
+        let mut remaining: u64 = sizes.iter().sum();
+        let mut cap = None;
+        for (i, &size) in sizes.iter().enumerate() {
+            if size <= selected_size || sizes.len() - i <= 2 || size <= remaining - size {
+                break;
+            }
+            remaining -= size;
+            cap = Some(sizes[i + 1]);
+        }
+        cap
+    }
+
+    /// Children drawn in the ring chart, paired with their index in `sorted_children`.
+    pub fn ring_children(&self) -> Vec<(usize, &Node)> {
+        let cap = self.ring_zoom_cap();
+        self.sorted_children()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, c)| cap.is_none_or(|cap| c.size <= cap))
+            .collect()
+    }
+
+    /// Recorded sizes of a directory across past scans, oldest first, or `None` when
+    /// there is no history for it to show a trend from.
+    pub fn size_trend(&self, node: &Node) -> Option<Vec<u64>> {
+        if node.node_type != NodeType::Directory {
+            return None;
+        }
+        let history = self.size_history.as_ref()?;
+        if history.snapshots.len() < 2 {
+            return None;
+        }
+        let trend = history.trend(&node.path);
+        trend.iter().any(|&size| size > 0).then_some(trend)
+    }
+
+    pub fn toggle_percent_basis(&mut self) {
+        self.percent_of_visible = !self.percent_of_visible;
+    }
+
+    /// Short summary of the active sort and filters, for snapshots of the view.
+    pub fn view_description(&self) -> String {
+        let sort = match self.sort_mode {
+            SortMode::Size => "size",
+            SortMode::Name => "name",
+            SortMode::Modified => "modified time",
+            SortMode::NewestContent => "newest contents",
+        };
+        let order = match self.sort_order {
+            SortOrder::Ascending => "ascending",
+            SortOrder::Descending => "descending",
+        };
+        let mut description = format!("sorted by {} ({})", sort, order);
+        if self.node_type_filter != NodeTypeFilter::All {
+            description.push_str(&format!(", {}", self.node_type_filter.label().to_lowercase()));
+            if self.percent_of_visible {
+                description.push_str(", % of visible");
+            }
+        }
+        description
+    }
+
+    /// Mark or unmark the selected entry, then move to the next one.
+    pub fn toggle_mark(&mut self) {
+        let Some(path) = self
+            .sorted_children()
+            .get(self.selected_index)
+            .map(|c| c.path.clone())
+        else {
+            return;
+        };
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+        self.move_down();
+    }
+
+    /// Switch every size shown between apparent size and disk usage.
+    pub fn toggle_size_mode(&mut self) {
+        let Some(result) = self.scan_result.as_mut() else {
+            return;
+        };
+        let mode = result.size_mode.toggle();
+        result.set_size_mode(mode);
+        // The ranking of largest files depends on which size is shown
+        self.largest_files = None;
+        self.largest_rank = None;
+        self.status_message = Some(format!("Showing {}", mode.label()));
+    }
+
+    pub fn toggle_list_density(&mut self) {
+        self.list_density = self.list_density.toggle();
+    }
+
+    /// Show or hide dotfiles in the file list without rescanning.
+    pub fn toggle_hidden(&mut self) {
+        self.show_hidden = !self.show_hidden;
+        self.selected_index = 0;
+        self.list_offset = 0;
+        self.status_message = Some(if self.show_hidden {
+            "Showing hidden entries".to_string()
+        } else {
+            "Hiding hidden entries".to_string()
+        });
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// Start editing the note on the selected entry, prefilled with its current note.
+    pub fn begin_note_input(&mut self) {
+        let Some(path) = self
+            .sorted_children()
+            .get(self.selected_index)
+            .map(|c| c.path.clone())
+        else {
+            return;
+        };
+        self.note_input = self.notes.get(&path).unwrap_or_default().to_string();
+        self.note_target = Some(path);
+        self.view_mode = ViewMode::NoteInput;
+    }
+
+    /// Store the edited note (a blank note removes it) and persist the store.
+    pub fn commit_note_input(&mut self) {
+        if let Some(path) = self.note_target.take() {
+            self.notes.set(path, &self.note_input);
+            if let Err(e) = self.notes.save() {
+                self.status_message = Some(format!("Failed to save notes: {}", e));
+            }
+        }
+        self.note_input.clear();
+        self.view_mode = ViewMode::Normal;
+    }
+
+    pub fn cancel_note_input(&mut self) {
+        self.note_target = None;
+        self.note_input.clear();
+        self.view_mode = ViewMode::Normal;
+    }
+
+    /// Open the export dialog on a fresh file name, scoped to the marked entries when
+    /// anything is marked.
+    pub fn open_export_dialog(&mut self) {
+        if self.scan_result.is_none() {
+            return;
+        }
+        if self.scanning {
+            self.status_message = Some("Export is available once the scan finishes".to_string());
+            return;
+        }
+        self.export_scope =
+            if self.marked.is_empty() { ExportScope::Scan } else { ExportScope::Marked };
+        self.export_path = format!(
+            "disklens_report_{}.{}",
+            chrono::Local::now().format("%Y%m%d_%H%M%S"),
+            self.export_format.extension()
+        );
+        self.export_field = ExportField::Format;
+        self.view_mode = ViewMode::Export;
+    }
+
+    pub fn close_export_dialog(&mut self) {
+        self.view_mode = ViewMode::Normal;
+    }
+
+    /// The scopes the export dialog offers; marked entries only when there are some.
+    pub fn export_scopes(&self) -> Vec<ExportScope> {
+        let mut scopes = vec![ExportScope::Scan, ExportScope::CurrentDirectory];
+        if !self.marked.is_empty() {
+            scopes.push(ExportScope::Marked);
+        }
+        scopes
+    }
+
+    /// Move the cursor between the dialog's rows, wrapping around.
+    pub fn move_export_field(&mut self, delta: isize) {
+        const FIELDS: [ExportField; 3] =
+            [ExportField::Format, ExportField::Scope, ExportField::Path];
+        let index = FIELDS.iter().position(|f| *f == self.export_field).unwrap_or(0);
+        self.export_field = FIELDS[wrap_index(index, delta, FIELDS.len())];
+    }
+
+    /// Step the format or scope under the cursor to the next or previous choice. A
+    /// new format also changes the file's extension, unless the user renamed it to
+    /// something else.
+    pub fn cycle_export_choice(&mut self, delta: isize) {
+        match self.export_field {
+            ExportField::Format => {
+                let all = ExportFormat::ALL;
+                let index = all.iter().position(|f| *f == self.export_format).unwrap_or(0);
+                let format = all[wrap_index(index, delta, all.len())];
+                let path = Path::new(&self.export_path);
+                let extension = path.extension().and_then(|e| e.to_str());
+                if extension == Some(self.export_format.extension()) {
+                    self.export_path =
+                        path.with_extension(format.extension()).to_string_lossy().to_string();
+                }
+                self.export_format = format;
+            }
+            ExportField::Scope => {
+                let scopes = self.export_scopes();
+                let index = scopes.iter().position(|s| *s == self.export_scope).unwrap_or(0);
+                self.export_scope = scopes[wrap_index(index, delta, scopes.len())];
+            }
+            ExportField::Path => {}
+        }
+    }
+
     pub fn toggle_sort(&mut self) {
         self.sort_mode = match self.sort_mode {
             SortMode::Size => SortMode::Name,
             SortMode::Name => SortMode::Modified,
-            SortMode::Modified => SortMode::Size,
+            SortMode::Modified => SortMode::NewestContent,
+            SortMode::NewestContent => SortMode::Size,
         };
         self.sort_order = match self.sort_mode {
             SortMode::Size => SortOrder::Descending,
             SortMode::Name => SortOrder::Ascending,
-            SortMode::Modified => SortOrder::Descending,
+            SortMode::Modified | SortMode::NewestContent => SortOrder::Descending,
         };
         self.selected_index = 0;
         self.list_offset = 0;
@@ -204,6 +889,351 @@ impl AppState {
         } else {
             ViewMode::ErrorList
         };
+        self.error_query.clear();
+        self.error_scroll = 0;
+    }
+
+    pub fn toggle_file_types(&mut self) {
+        self.view_mode = if self.view_mode == ViewMode::FileTypes {
+            ViewMode::Normal
+        } else {
+            ViewMode::FileTypes
+        };
+        self.file_types_scroll = 0;
+    }
+
+    pub fn scroll_file_types(&mut self, delta: isize) {
+        let count = self.current_node().map_or(0, |n| Analyzer::by_extension(n).len());
+        let max = count.saturating_sub(1);
+        self.file_types_scroll = self.file_types_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// Open the empty directory finder on the whole scan, or close it. Directories
+    /// with scan errors or left unscanned below them only look empty and aren't listed.
+    pub fn toggle_empty_dirs(&mut self) {
+        if self.view_mode == ViewMode::EmptyDirs {
+            self.view_mode = ViewMode::Normal;
+            self.empty_dirs.clear();
+            return;
+        }
+        let Some(result) = &self.scan_result else {
+            return;
+        };
+        if self.scanning {
+            self.status_message =
+                Some("Empty directories are listed once the scan finishes".to_string());
+            return;
+        }
+        let incomplete = |dir: &EmptyDir| {
+            result.errors.iter().any(|e| e.path.starts_with(&dir.path))
+                || result.unscanned.iter().any(|path| path.starts_with(&dir.path))
+        };
+        self.empty_dirs = Analyzer::find_empty_dirs(&result.root, self.near_empty_size)
+            .into_iter()
+            .filter(|dir| !incomplete(dir))
+            .collect();
+        self.empty_dirs_cursor = 0;
+        self.picked_empty_dirs.clear();
+        self.confirm_delete = false;
+        self.view_mode = ViewMode::EmptyDirs;
+    }
+
+    pub fn move_empty_dirs_cursor(&mut self, delta: isize) {
+        let last = self.empty_dirs.len().saturating_sub(1);
+        self.empty_dirs_cursor = self.empty_dirs_cursor.saturating_add_signed(delta).min(last);
+    }
+
+    /// Pick or unpick the directory under the cursor.
+    pub fn toggle_empty_dir(&mut self) {
+        if let Some(dir) = self.empty_dirs.get(self.empty_dirs_cursor) {
+            if !self.picked_empty_dirs.remove(&dir.path) {
+                self.picked_empty_dirs.insert(dir.path.clone());
+            }
+        }
+    }
+
+    /// Pick every listed directory, or none if they all are already.
+    pub fn toggle_all_empty_dirs(&mut self) {
+        if self.picked_empty_dirs.len() == self.empty_dirs.len() {
+            self.picked_empty_dirs.clear();
+        } else {
+            self.picked_empty_dirs = self.empty_dirs.iter().map(|d| d.path.clone()).collect();
+        }
+    }
+
+    /// The picked directories, or the one under the cursor when none are picked.
+    pub fn dirs_to_delete(&self) -> Vec<&EmptyDir> {
+        if self.picked_empty_dirs.is_empty() {
+            return self.empty_dirs.get(self.empty_dirs_cursor).into_iter().collect();
+        }
+        self.empty_dirs
+            .iter()
+            .filter(|dir| self.picked_empty_dirs.contains(&dir.path))
+            .collect()
+    }
+
+    /// Ask for confirmation before deleting `dirs_to_delete`.
+    pub fn request_delete(&mut self) {
+        self.confirm_delete = !self.dirs_to_delete().is_empty();
+    }
+
+    /// Take directories that were deleted from disk out of the tree and the list.
+    pub fn remove_deleted(&mut self, deleted: &[PathBuf]) {
+        let Some(result) = self.scan_result.as_mut() else {
+            return;
+        };
+        result.remove_paths(deleted);
+        self.error_count = result.errors.len();
+        self.empty_dirs.retain(|dir| !deleted.contains(&dir.path));
+        self.picked_empty_dirs.retain(|path| !deleted.contains(path));
+        self.move_empty_dirs_cursor(0);
+        self.largest_files = None;
+        self.largest_rank = None;
+        // Leave a directory that is gone for the closest one still there
+        while self.current_node().is_none() && !self.path_stack.is_empty() {
+            self.go_back();
+        }
+        let count = self.visible_children_count();
+        self.selected_index = self.selected_index.min(count.saturating_sub(1));
+    }
+
+    pub fn toggle_age_report(&mut self) {
+        self.view_mode = if self.view_mode == ViewMode::AgeReport {
+            ViewMode::Normal
+        } else {
+            ViewMode::AgeReport
+        };
+        self.age_report_scroll = 0;
+    }
+
+    pub fn scroll_age_report(&mut self, delta: isize) {
+        let max = self.current_node().map_or(0, |n| n.children.len()).saturating_sub(1);
+        self.age_report_scroll = self.age_report_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    pub fn toggle_cleanup(&mut self) {
+        self.view_mode = if self.view_mode == ViewMode::Cleanup {
+            ViewMode::Normal
+        } else {
+            self.cleanup_candidates =
+                self.scan_result.as_ref().map(|r| Analyzer::junk_dirs(&r.root)).unwrap_or_default();
+            ViewMode::Cleanup
+        };
+        self.cleanup_cursor = 0;
+    }
+
+    pub fn move_cleanup_cursor(&mut self, delta: isize) {
+        let last = self.cleanup_candidates.len().saturating_sub(1);
+        self.cleanup_cursor = self.cleanup_cursor.saturating_add_signed(delta).min(last);
+    }
+
+    /// Close `ViewMode::Cleanup` and reveal the directory under its cursor.
+    pub fn open_cleanup_candidate(&mut self) {
+        let Some(dir) = self.cleanup_candidates.get(self.cleanup_cursor).cloned() else {
+            return;
+        };
+        self.toggle_cleanup();
+        if self.reveal(&dir.path) {
+            self.status_message = Some(format!(
+                "{}: {} ({})",
+                dir.label,
+                dir.path.display(),
+                crate::models::node::human_readable_size(dir.size)
+            ));
+        }
+    }
+
+    /// Open the cleanup suggestions for the whole scan, or close them. They need the
+    /// finished scan, where regenerable directories have been tagged.
+    pub fn toggle_suggestions(&mut self) {
+        if self.view_mode == ViewMode::Suggestions {
+            self.view_mode = ViewMode::Normal;
+            self.suggestions.clear();
+            return;
+        }
+        let Some(result) = &self.scan_result else {
+            return;
+        };
+        if self.scanning {
+            self.status_message =
+                Some("Suggestions are made once the scan finishes".to_string());
+            return;
+        }
+        self.suggestions = Analyzer::suggestions(&result.root, SystemTime::now());
+        self.suggestions_cursor = 0;
+        self.view_mode = ViewMode::Suggestions;
+    }
+
+    pub fn move_suggestions_cursor(&mut self, delta: isize) {
+        let last = self.suggestions.len().saturating_sub(1);
+        self.suggestions_cursor = self.suggestions_cursor.saturating_add_signed(delta).min(last);
+    }
+
+    /// Close `ViewMode::Suggestions` and reveal the path under its cursor.
+    pub fn open_suggestion(&mut self) {
+        let Some(suggestion) = self.suggestions.get(self.suggestions_cursor).cloned() else {
+            return;
+        };
+        self.toggle_suggestions();
+        if self.reveal(&suggestion.path) {
+            self.status_message = Some(format!(
+                "{}: {}",
+                suggestion.path.display(),
+                suggestion.reason
+            ));
+        }
+    }
+
+    /// Open the per-user table for the current directory, or close it. User names are
+    /// looked up once here rather than on every frame.
+    pub fn toggle_owners(&mut self) {
+        self.view_mode = if self.view_mode == ViewMode::Owners {
+            self.owners.clear();
+            ViewMode::Normal
+        } else {
+            self.owners = self.current_node().map(Analyzer::usage_by_owner).unwrap_or_default();
+            ViewMode::Owners
+        };
+        self.owners_scroll = 0;
+    }
+
+    pub fn scroll_owners(&mut self, delta: isize) {
+        let max = self.owners.len().saturating_sub(1);
+        self.owners_scroll = self.owners_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// Open the compression estimate for the current directory, or close it. Returns
+    /// whether it opened, in which case the estimates still have to be made: that
+    /// reads file contents, so it happens in the background.
+    pub fn toggle_compression(&mut self) -> bool {
+        self.compression = None;
+        self.compression_scroll = 0;
+        if self.view_mode == ViewMode::Compression {
+            self.view_mode = ViewMode::Normal;
+            return false;
+        }
+        if self.scanning {
+            self.status_message =
+                Some("Compression is estimated once the scan finishes".to_string());
+            return false;
+        }
+        let Some(path) = self.current_node().map(|n| n.path.clone()) else {
+            return false;
+        };
+        self.compression_path = path;
+        self.view_mode = ViewMode::Compression;
+        true
+    }
+
+    /// Show the estimates made for `path`, unless the view was closed or moved on.
+    pub fn set_compression(&mut self, path: &Path, estimates: Vec<CompressionEstimate>) {
+        if self.view_mode == ViewMode::Compression && self.compression_path == path {
+            self.compression = Some(estimates);
+        }
+    }
+
+    pub fn scroll_compression(&mut self, delta: isize) {
+        let max = self.compression.as_ref().map_or(0, Vec::len).saturating_sub(1);
+        self.compression_scroll = self.compression_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    pub fn toggle_recent_files(&mut self) {
+        self.view_mode = if self.view_mode == ViewMode::RecentFiles {
+            self.recent_files.clear();
+            ViewMode::Normal
+        } else {
+            self.recent_files = self
+                .current_node()
+                .map(|node| Analyzer::recently_modified(node, RECENT_FILES_SHOWN))
+                .unwrap_or_default();
+            ViewMode::RecentFiles
+        };
+        self.recent_files_cursor = 0;
+    }
+
+    pub fn move_recent_files_cursor(&mut self, delta: isize) {
+        let max = self.recent_files.len().saturating_sub(1);
+        self.recent_files_cursor = self.recent_files_cursor.saturating_add_signed(delta).min(max);
+    }
+
+    /// Close `ViewMode::RecentFiles` and reveal the file under its cursor.
+    pub fn open_recent_file(&mut self) {
+        let Some(file) = self.recent_files.get(self.recent_files_cursor).cloned() else {
+            return;
+        };
+        self.toggle_recent_files();
+        if self.reveal(&file.path) {
+            self.status_message = Some(format!(
+                "{} ({})",
+                file.path.display(),
+                crate::models::node::human_readable_size(file.size)
+            ));
+        }
+    }
+
+    pub fn toggle_tree_stats(&mut self) {
+        self.view_mode = if self.view_mode == ViewMode::TreeStats {
+            self.tree_stats = None;
+            ViewMode::Normal
+        } else {
+            self.tree_stats = self.current_node().map(Analyzer::tree_stats);
+            ViewMode::TreeStats
+        };
+        self.tree_stats_scroll = 0;
+    }
+
+    pub fn scroll_tree_stats(&mut self, delta: isize) {
+        let rows = self.tree_stats.as_ref().map_or(0, |s| s.deepest.len() + s.widest.len());
+        self.tree_stats_scroll = self.tree_stats_scroll.saturating_add_signed(delta).min(rows);
+    }
+
+    pub fn toggle_history(&mut self) {
+        self.view_mode = if self.view_mode == ViewMode::History {
+            ViewMode::Normal
+        } else {
+            ViewMode::History
+        };
+        self.history_scroll = 0;
+    }
+
+    pub fn scroll_history(&mut self, delta: isize) {
+        let entries = self.size_history.as_ref().map_or(0, |history| {
+            history.snapshots.iter().flat_map(|s| s.children.keys()).collect::<BTreeSet<_>>().len()
+        });
+        let max = entries.saturating_sub(1);
+        self.history_scroll = self.history_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// Scan errors whose path or message contains `error_query` (case-insensitive).
+    pub fn filtered_errors(&self) -> Vec<&ScanError> {
+        let Some(result) = self.scan_result.as_ref() else {
+            return Vec::new();
+        };
+        let query = self.error_query.to_lowercase();
+        result
+            .errors
+            .iter()
+            .filter(|e| {
+                query.is_empty()
+                    || e.path.to_string_lossy().to_lowercase().contains(&query)
+                    || e.message.to_lowercase().contains(&query)
+            })
+            .collect()
+    }
+
+    pub fn push_error_query(&mut self, c: char) {
+        self.error_query.push(c);
+        self.error_scroll = 0;
+    }
+
+    pub fn pop_error_query(&mut self) {
+        self.error_query.pop();
+        self.error_scroll = 0;
+    }
+
+    pub fn scroll_errors(&mut self, delta: isize) {
+        let max = self.filtered_errors().len().saturating_sub(1);
+        self.error_scroll = self.error_scroll.saturating_add_signed(delta).min(max);
     }
 
     pub fn toggle_focus(&mut self) {
@@ -229,18 +1259,201 @@ impl AppState {
         self.current_scanning_path = path;
     }
 
-    pub fn set_scan_result(&mut self, result: ScanResult) {
-        self.error_count = result.errors.len();
-        self.view_mode = ViewMode::Normal;
-        self.current_path = result.scan_path.clone();
-        self.scan_result = Some(result);
+    /// The directory the current (or pending) scan started from.
+    pub fn scan_root(&self) -> PathBuf {
+        match &self.scan_result {
+            Some(result) => result.scan_path.clone(),
+            None => self.path_stack.first().unwrap_or(&self.current_path).clone(),
+        }
+    }
+
+    /// Drop the current result and show scan progress again for a rescan of the root.
+    pub fn begin_rescan(&mut self) {
+        self.marked.clear();
+        self.current_path = self.scan_root();
+        self.path_stack.clear();
+        self.scan_result = None;
+        self.scanning = true;
+        self.cached_scan = None;
+        self.refreshing = false;
+        self.view_mode = ViewMode::Scanning;
+        self.scan_paused = false;
+        self.network_fs = None;
+        self.update_progress(0, 0, 0.0, String::new());
+        self.error_count = 0;
         self.selected_index = 0;
         self.list_offset = 0;
     }
+
+    /// Show the finished scan. Someone browsing the provisional tree stays in the
+    /// directory they were looking at, as long as it is still there.
+    pub fn set_scan_result(&mut self, mut result: ScanResult) {
+        Analyzer::compute_newest_modified(&mut result.root);
+        Analyzer::tag_junk(&mut result.root, &self.junk_dirs);
+        let browsing = (self.scanning || self.refreshing)
+            && self.view_mode != ViewMode::Scanning
+            && find_node(&result.root, &self.current_path).is_some();
+        self.error_count = result.errors.len();
+        self.scan_paused = false;
+        self.scanning = false;
+        self.cached_scan = None;
+        self.refreshing = false;
+        self.largest_files = None;
+        self.largest_rank = None;
+        if browsing {
+            let selected = self.selected_path();
+            self.scan_result = Some(result);
+            self.reselect(selected);
+        } else {
+            self.view_mode = ViewMode::Normal;
+            self.current_path = result.scan_path.clone();
+            self.path_stack.clear();
+            self.scan_result = Some(result);
+            self.selected_index = 0;
+            self.list_offset = 0;
+        }
+    }
+
+    /// Show a scan loaded from the cache right away, while `refreshing` says whether a
+    /// background rescan is on its way to replace it.
+    pub fn show_cached_scan(&mut self, result: ScanResult, refreshing: bool) {
+        let scanned = result.timestamp;
+        self.set_scan_result(result);
+        self.cached_scan = Some(scanned);
+        self.refreshing = refreshing;
+    }
+
+    /// The notice shown while the tree comes from the cache, e.g. `age: 2h, press r
+    /// to rescan`.
+    pub fn cache_banner(&self, now: SystemTime) -> Option<String> {
+        let scanned = self.cached_scan?;
+        let age = format_age(now.duration_since(scanned).unwrap_or_default());
+        Some(if self.refreshing {
+            format!("age: {}, rescanning\u{2026} {} files", age, self.files_scanned)
+        } else {
+            format!("age: {}, press r to rescan", age)
+        })
+    }
+
+    /// Show the changes since the previous scan in the file list, or none.
+    pub fn set_scan_diff(&mut self, diff: Option<&DiffResult>) {
+        self.size_deltas = diff.map(DiffResult::deltas).unwrap_or_default();
+        self.previous_scan = diff.map(|diff| diff.old_timestamp);
+    }
+
+    /// Add `node`, a directory the running scan just finished, to the provisional
+    /// tree, which starts out as the empty `roots` shown in `size_mode`. A cached tree
+    /// being refreshed is left as it is.
+    pub fn add_subtree(&mut self, roots: &[PathBuf], size_mode: SizeMode, node: Node) {
+        if self.refreshing {
+            return;
+        }
+        let selected = self.selected_path();
+        let result = self.scan_result.get_or_insert_with(|| {
+            let mut result = ScanResult::provisional(roots);
+            result.set_size_mode(size_mode);
+            result
+        });
+        result.insert_subtree(node);
+        self.largest_files = None;
+        self.largest_rank = None;
+        self.reselect(selected);
+    }
+
+    /// Switch from the progress screen to browsing what the running scan has finished.
+    pub fn browse_partial_scan(&mut self) {
+        if self.scan_result.is_some() {
+            self.view_mode = ViewMode::Normal;
+        } else {
+            self.status_message = Some("Nothing has finished scanning yet".to_string());
+        }
+    }
+
+    /// Go back to the progress screen while a scan is running.
+    pub fn show_scan_progress(&mut self) {
+        if self.scanning {
+            self.view_mode = ViewMode::Scanning;
+        }
+    }
+
+    /// Start on the mount picker instead of a scan.
+    pub fn open_mount_picker(&mut self, mounts: Vec<MountInfo>) {
+        self.mounts = mounts;
+        self.mount_cursor = 0;
+        self.picked_mounts.clear();
+        self.scanning = false;
+        self.view_mode = ViewMode::MountPicker;
+    }
+
+    pub fn move_mount_cursor(&mut self, delta: isize) {
+        let last = self.mounts.len().saturating_sub(1);
+        self.mount_cursor = self.mount_cursor.saturating_add_signed(delta).min(last);
+    }
+
+    /// Pick or unpick the mount under the cursor.
+    pub fn toggle_mount(&mut self) {
+        if let Some(mount) = self.mounts.get(self.mount_cursor) {
+            if !self.picked_mounts.remove(&mount.mount_point) {
+                self.picked_mounts.insert(mount.mount_point.clone());
+            }
+        }
+    }
+
+    /// Pick every mount, or none if they all are already.
+    pub fn toggle_all_mounts(&mut self) {
+        if self.picked_mounts.len() == self.mounts.len() {
+            self.picked_mounts.clear();
+        } else {
+            self.picked_mounts = self.mounts.iter().map(|m| m.mount_point.clone()).collect();
+        }
+    }
+
+    /// Leave the picker for the scan of the picked mounts, in table order, or of the
+    /// one under the cursor when none are picked. Returns the roots to scan.
+    pub fn confirm_mounts(&mut self) -> Vec<PathBuf> {
+        let mut roots: Vec<PathBuf> = self
+            .mounts
+            .iter()
+            .map(|m| m.mount_point.clone())
+            .filter(|path| self.picked_mounts.contains(path))
+            .collect();
+        if roots.is_empty() {
+            roots.extend(self.mounts.get(self.mount_cursor).map(|m| m.mount_point.clone()));
+        }
+        if roots.is_empty() {
+            return roots;
+        }
+        self.current_path = match roots.as_slice() {
+            [root] => root.clone(),
+            _ => PathBuf::new(),
+        };
+        self.scanning = true;
+        self.view_mode = ViewMode::Scanning;
+        roots
+    }
+
+    fn selected_path(&self) -> Option<PathBuf> {
+        self.sorted_children()
+            .get(self.selected_index)
+            .map(|c| c.path.clone())
+    }
+
+    /// Keep `selected` selected after the list changed under it, or stay in range.
+    fn reselect(&mut self, selected: Option<PathBuf>) {
+        let children = self.sorted_children();
+        let index = selected.and_then(|path| children.iter().position(|c| c.path == path));
+        let last = children.len().saturating_sub(1);
+        self.selected_index = index.unwrap_or(self.selected_index.min(last));
+    }
+}
+
+/// `index` moved by `delta` in a list of `len`, wrapping past either end.
+fn wrap_index(index: usize, delta: isize, len: usize) -> usize {
+    (index as isize + delta).rem_euclid(len as isize) as usize
 }
 
 fn find_node<'a>(node: &'a Node, path: &PathBuf) -> Option<&'a Node> {
-    if &node.path == path {
+    if node.matches_path(path) {
         return Some(node);
     }
     for child in &node.children {
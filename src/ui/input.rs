@@ -1,27 +1,123 @@
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use std::time::Duration;
 
-use crate::ui::app_state::{AppState, ViewMode};
+use crate::ui::app_state::{AppState, ExportField, ViewMode};
+use crate::ui::renderer;
 
 pub enum InputAction {
     None,
     Quit,
+    /// Rescan, reusing directories unchanged since the current result.
     Refresh,
+    /// Rescan everything from scratch.
+    FullRefresh,
+    /// Suspend or resume the running scan.
+    TogglePause,
+    /// Stop the running scan and show what it has found so far.
+    CancelScan,
+    /// Flip `follow_symlinks` and rescan.
+    ToggleSymlinks,
     Export,
+    WriteView,
     CopyPath,
     OpenFile,
+    /// Scan the mounts chosen in the mount picker.
+    ScanMounts,
+    /// Delete the directories picked in the empty directory finder.
+    DeleteEmptyDirs,
+    /// Write the per-user table as CSV.
+    ExportOwners,
+    /// Sample the current directory's files to estimate how well they compress.
+    EstimateCompression,
 }
 
 pub fn handle_key_event(key: KeyEvent, state: &mut AppState) -> InputAction {
+    state.status_message = None;
     match state.view_mode {
         ViewMode::Normal => handle_normal_mode(key, state),
         ViewMode::Help => handle_help_mode(key, state),
         ViewMode::ErrorList => handle_error_list_mode(key, state),
         ViewMode::Scanning => handle_scanning_mode(key, state),
-        ViewMode::Export => InputAction::None,
+        ViewMode::NoteInput => handle_note_input_mode(key, state),
+        ViewMode::MountPicker => handle_mount_picker_mode(key, state),
+        ViewMode::FileTypes => handle_file_types_mode(key, state),
+        ViewMode::LargestFiles => handle_largest_files_mode(key, state),
+        ViewMode::EmptyDirs => handle_empty_dirs_mode(key, state),
+        ViewMode::AgeReport => handle_age_report_mode(key, state),
+        ViewMode::History => handle_history_mode(key, state),
+        ViewMode::Cleanup => handle_cleanup_mode(key, state),
+        ViewMode::Suggestions => handle_suggestions_mode(key, state),
+        ViewMode::Owners => handle_owners_mode(key, state),
+        ViewMode::Compression => handle_compression_mode(key, state),
+        ViewMode::TreeStats => handle_tree_stats_mode(key, state),
+        ViewMode::RecentFiles => handle_recent_files_mode(key, state),
+        ViewMode::Export => handle_export_mode(key, state),
     }
 }
 
+/// Clicking a numbered breadcrumb segment jumps to that ancestor.
+pub fn handle_mouse_event(mouse: MouseEvent, state: &mut AppState) -> InputAction {
+    if state.view_mode != ViewMode::Normal {
+        return InputAction::None;
+    }
+    if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+        if let Some(index) = renderer::breadcrumb_target(state, mouse.column, mouse.row) {
+            state.jump_to_ancestor(index);
+        }
+    }
+    InputAction::None
+}
+
+fn handle_note_input_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+
+    match key.code {
+        KeyCode::Enter => state.commit_note_input(),
+        KeyCode::Esc => state.cancel_note_input(),
+        KeyCode::Backspace => {
+            state.note_input.pop();
+        }
+        KeyCode::Char(c) => state.note_input.push(c),
+        _ => {}
+    }
+    InputAction::None
+}
+
+/// The export dialog: arrows move between rows and change the format or scope, and
+/// typing edits the file name while it is under the cursor.
+fn handle_export_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+
+    let editing = state.export_field == ExportField::Path;
+    match key.code {
+        KeyCode::Enter => return InputAction::Export,
+        KeyCode::Esc => state.close_export_dialog(),
+        KeyCode::Down | KeyCode::Tab => state.move_export_field(1),
+        KeyCode::Up | KeyCode::BackTab => state.move_export_field(-1),
+        KeyCode::Right => state.cycle_export_choice(1),
+        KeyCode::Left => state.cycle_export_choice(-1),
+        KeyCode::Backspace if editing => {
+            state.export_path.pop();
+        }
+        KeyCode::Char(c) if editing => state.export_path.push(c),
+        KeyCode::Char('j') => state.move_export_field(1),
+        KeyCode::Char('k') => state.move_export_field(-1),
+        KeyCode::Char('l') | KeyCode::Char(' ') => state.cycle_export_choice(1),
+        KeyCode::Char('h') => state.cycle_export_choice(-1),
+        KeyCode::Char('q') => state.close_export_dialog(),
+        _ => {}
+    }
+    InputAction::None
+}
+
 fn handle_normal_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
     // Handle Ctrl+C globally
     if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
@@ -52,9 +148,9 @@ fn handle_normal_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
             state.move_up();
             InputAction::None
         }
-        KeyCode::Enter | KeyCode::Char('l') | KeyCode::Right
-            if state.focus == crate::ui::app_state::FocusPanel::FileList =>
-        {
+        // Both panels show the same selection, so drilling works whichever has focus;
+        // the arrow keys are reserved for switching focus
+        KeyCode::Enter | KeyCode::Char('l') => {
             state.enter_directory();
             InputAction::None
         }
@@ -70,6 +166,10 @@ fn handle_normal_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
             state.go_to_last();
             InputAction::None
         }
+        KeyCode::Char('B') => {
+            state.jump_to_largest_file();
+            InputAction::None
+        }
         KeyCode::Char('s') => {
             state.toggle_sort();
             InputAction::None
@@ -78,6 +178,46 @@ fn handle_normal_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
             state.cycle_threshold();
             InputAction::None
         }
+        KeyCode::Char('f') => {
+            state.cycle_node_type_filter();
+            InputAction::None
+        }
+        KeyCode::Char('L') => {
+            state.toggle_legend();
+            InputAction::None
+        }
+        KeyCode::Char('b') => {
+            state.cycle_legend_placement();
+            InputAction::None
+        }
+        KeyCode::Char('a') => {
+            state.toggle_size_mode();
+            InputAction::None
+        }
+        KeyCode::Char('D') => {
+            state.toggle_list_density();
+            InputAction::None
+        }
+        KeyCode::Char('.') => {
+            state.toggle_hidden();
+            InputAction::None
+        }
+        KeyCode::Char(c @ '1'..='9') => {
+            state.jump_to_ancestor(c as usize - '1' as usize);
+            InputAction::None
+        }
+        KeyCode::Char('z') => {
+            state.toggle_ring_zoom();
+            InputAction::None
+        }
+        KeyCode::Char('c') => {
+            state.toggle_center_stats();
+            InputAction::None
+        }
+        KeyCode::Char('%') => {
+            state.toggle_percent_basis();
+            InputAction::None
+        }
         KeyCode::Left | KeyCode::Right => {
             state.toggle_focus();
             InputAction::None
@@ -90,14 +230,83 @@ fn handle_normal_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
             state.toggle_error_list();
             InputAction::None
         }
+        KeyCode::Char('T') => {
+            state.toggle_file_types();
+            InputAction::None
+        }
+        KeyCode::Char('A') => {
+            state.toggle_age_report();
+            InputAction::None
+        }
+        KeyCode::Char('H') => {
+            state.toggle_history();
+            InputAction::None
+        }
+        KeyCode::Char('C') => {
+            state.toggle_cleanup();
+            InputAction::None
+        }
+        KeyCode::Char('S') => {
+            state.toggle_suggestions();
+            InputAction::None
+        }
+        KeyCode::Char('O') => {
+            state.toggle_owners();
+            InputAction::None
+        }
+        KeyCode::Char('Z') => {
+            if state.toggle_compression() {
+                InputAction::EstimateCompression
+            } else {
+                InputAction::None
+            }
+        }
+        KeyCode::Char('I') => {
+            state.toggle_tree_stats();
+            InputAction::None
+        }
+        KeyCode::Char('M') => {
+            state.toggle_recent_files();
+            InputAction::None
+        }
+        KeyCode::Char('N') => {
+            state.toggle_largest_files();
+            InputAction::None
+        }
+        KeyCode::Char('E') => {
+            state.toggle_empty_dirs();
+            InputAction::None
+        }
         KeyCode::Char('?') => {
             state.toggle_help();
             InputAction::None
         }
         KeyCode::Char('r') => InputAction::Refresh,
-        KeyCode::Char('x') => InputAction::Export,
+        KeyCode::Char('R') => InputAction::FullRefresh,
+        KeyCode::Char('F') => InputAction::ToggleSymlinks,
+        KeyCode::Char('x') => {
+            state.open_export_dialog();
+            InputAction::None
+        }
+        KeyCode::Char('w') => InputAction::WriteView,
+        KeyCode::Char('n') => {
+            state.begin_note_input();
+            InputAction::None
+        }
+        KeyCode::Char(' ') => {
+            state.toggle_mark();
+            InputAction::None
+        }
+        KeyCode::Char('u') => {
+            state.clear_marks();
+            InputAction::None
+        }
         KeyCode::Char('y') => InputAction::CopyPath,
         KeyCode::Char('o') => InputAction::OpenFile,
+        KeyCode::Esc => {
+            state.show_scan_progress();
+            InputAction::None
+        }
         _ => InputAction::None,
     }
 }
@@ -112,14 +321,27 @@ fn handle_help_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
     }
 }
 
+/// Typing filters the list; Esc clears the filter first, then closes the overlay.
 fn handle_error_list_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
     match key.code {
-        KeyCode::Char('e') | KeyCode::Esc | KeyCode::Char('q') => {
-            state.toggle_error_list();
-            InputAction::None
+        KeyCode::Esc if !state.error_query.is_empty() => {
+            state.error_query.clear();
+            state.error_scroll = 0;
         }
-        _ => InputAction::None,
+        KeyCode::Esc => state.toggle_error_list(),
+        KeyCode::Backspace => state.pop_error_query(),
+        KeyCode::Down => state.scroll_errors(1),
+        KeyCode::Up => state.scroll_errors(-1),
+        KeyCode::PageDown => state.scroll_errors(10),
+        KeyCode::PageUp => state.scroll_errors(-10),
+        KeyCode::Char(c) => state.push_error_query(c),
+        _ => {}
     }
+    InputAction::None
 }
 
 fn handle_scanning_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
@@ -132,6 +354,124 @@ fn handle_scanning_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
             state.should_quit = true;
             InputAction::Quit
         }
+        KeyCode::Esc => InputAction::CancelScan,
+        KeyCode::Char(' ') => InputAction::TogglePause,
+        KeyCode::Enter => {
+            state.browse_partial_scan();
+            InputAction::None
+        }
+        _ => InputAction::None,
+    }
+}
+
+fn handle_file_types_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    match key.code {
+        KeyCode::Char('T') | KeyCode::Esc | KeyCode::Char('q') => state.toggle_file_types(),
+        KeyCode::Down | KeyCode::Char('j') => state.scroll_file_types(1),
+        KeyCode::Up | KeyCode::Char('k') => state.scroll_file_types(-1),
+        KeyCode::PageDown => state.scroll_file_types(10),
+        KeyCode::PageUp => state.scroll_file_types(-10),
+        _ => {}
+    }
+    InputAction::None
+}
+
+fn handle_largest_files_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    match key.code {
+        KeyCode::Char('N') | KeyCode::Esc | KeyCode::Char('q') => state.toggle_largest_files(),
+        KeyCode::Enter | KeyCode::Char('l') => state.open_largest_file(),
+        KeyCode::Down | KeyCode::Char('j') => state.move_largest_files_cursor(1),
+        KeyCode::Up | KeyCode::Char('k') => state.move_largest_files_cursor(-1),
+        KeyCode::PageDown => state.move_largest_files_cursor(10),
+        KeyCode::PageUp => state.move_largest_files_cursor(-10),
+        _ => {}
+    }
+    InputAction::None
+}
+
+fn handle_empty_dirs_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    if state.confirm_delete {
+        // Anything but `y` cancels
+        state.confirm_delete = false;
+        return if key.code == KeyCode::Char('y') {
+            InputAction::DeleteEmptyDirs
+        } else {
+            InputAction::None
+        };
+    }
+    match key.code {
+        KeyCode::Char('E') | KeyCode::Esc | KeyCode::Char('q') => state.toggle_empty_dirs(),
+        KeyCode::Char(' ') => {
+            state.toggle_empty_dir();
+            state.move_empty_dirs_cursor(1);
+        }
+        KeyCode::Char('a') => state.toggle_all_empty_dirs(),
+        KeyCode::Char('d') | KeyCode::Delete => state.request_delete(),
+        KeyCode::Down | KeyCode::Char('j') => state.move_empty_dirs_cursor(1),
+        KeyCode::Up | KeyCode::Char('k') => state.move_empty_dirs_cursor(-1),
+        KeyCode::PageDown => state.move_empty_dirs_cursor(10),
+        KeyCode::PageUp => state.move_empty_dirs_cursor(-10),
+        _ => {}
+    }
+    InputAction::None
+}
+
+fn handle_age_report_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    match key.code {
+        KeyCode::Char('A') | KeyCode::Esc | KeyCode::Char('q') => state.toggle_age_report(),
+        KeyCode::Down | KeyCode::Char('j') => state.scroll_age_report(1),
+        KeyCode::Up | KeyCode::Char('k') => state.scroll_age_report(-1),
+        KeyCode::PageDown => state.scroll_age_report(10),
+        KeyCode::PageUp => state.scroll_age_report(-10),
+        _ => {}
+    }
+    InputAction::None
+}
+
+fn handle_mount_picker_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => {
+            state.should_quit = true;
+            InputAction::Quit
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            state.move_mount_cursor(-1);
+            InputAction::None
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            state.move_mount_cursor(1);
+            InputAction::None
+        }
+        KeyCode::Char(' ') => {
+            state.toggle_mount();
+            state.move_mount_cursor(1);
+            InputAction::None
+        }
+        KeyCode::Char('a') => {
+            state.toggle_all_mounts();
+            InputAction::None
+        }
+        KeyCode::Enter => InputAction::ScanMounts,
         _ => InputAction::None,
     }
 }
@@ -143,3 +483,121 @@ pub fn poll_event(timeout: Duration) -> anyhow::Result<Option<Event>> {
         Ok(None)
     }
 }
+
+fn handle_history_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    match key.code {
+        KeyCode::Char('H') | KeyCode::Esc | KeyCode::Char('q') => state.toggle_history(),
+        KeyCode::Down | KeyCode::Char('j') => state.scroll_history(1),
+        KeyCode::Up | KeyCode::Char('k') => state.scroll_history(-1),
+        KeyCode::PageDown => state.scroll_history(10),
+        KeyCode::PageUp => state.scroll_history(-10),
+        _ => {}
+    }
+    InputAction::None
+}
+
+fn handle_cleanup_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    match key.code {
+        KeyCode::Char('C') | KeyCode::Esc | KeyCode::Char('q') => state.toggle_cleanup(),
+        KeyCode::Enter | KeyCode::Char('l') => state.open_cleanup_candidate(),
+        KeyCode::Down | KeyCode::Char('j') => state.move_cleanup_cursor(1),
+        KeyCode::Up | KeyCode::Char('k') => state.move_cleanup_cursor(-1),
+        KeyCode::PageDown => state.move_cleanup_cursor(10),
+        KeyCode::PageUp => state.move_cleanup_cursor(-10),
+        _ => {}
+    }
+    InputAction::None
+}
+
+fn handle_suggestions_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    match key.code {
+        KeyCode::Char('S') | KeyCode::Esc | KeyCode::Char('q') => state.toggle_suggestions(),
+        KeyCode::Enter | KeyCode::Char('l') => state.open_suggestion(),
+        KeyCode::Down | KeyCode::Char('j') => state.move_suggestions_cursor(1),
+        KeyCode::Up | KeyCode::Char('k') => state.move_suggestions_cursor(-1),
+        KeyCode::PageDown => state.move_suggestions_cursor(10),
+        KeyCode::PageUp => state.move_suggestions_cursor(-10),
+        _ => {}
+    }
+    InputAction::None
+}
+
+fn handle_owners_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    match key.code {
+        KeyCode::Char('O') | KeyCode::Esc | KeyCode::Char('q') => state.toggle_owners(),
+        KeyCode::Char('x') => return InputAction::ExportOwners,
+        KeyCode::Down | KeyCode::Char('j') => state.scroll_owners(1),
+        KeyCode::Up | KeyCode::Char('k') => state.scroll_owners(-1),
+        KeyCode::PageDown => state.scroll_owners(10),
+        KeyCode::PageUp => state.scroll_owners(-10),
+        _ => {}
+    }
+    InputAction::None
+}
+
+fn handle_compression_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    match key.code {
+        KeyCode::Char('Z') | KeyCode::Esc | KeyCode::Char('q') => {
+            state.toggle_compression();
+        }
+        KeyCode::Down | KeyCode::Char('j') => state.scroll_compression(1),
+        KeyCode::Up | KeyCode::Char('k') => state.scroll_compression(-1),
+        KeyCode::PageDown => state.scroll_compression(10),
+        KeyCode::PageUp => state.scroll_compression(-10),
+        _ => {}
+    }
+    InputAction::None
+}
+
+fn handle_tree_stats_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    match key.code {
+        KeyCode::Char('I') | KeyCode::Esc | KeyCode::Char('q') => state.toggle_tree_stats(),
+        KeyCode::Down | KeyCode::Char('j') => state.scroll_tree_stats(1),
+        KeyCode::Up | KeyCode::Char('k') => state.scroll_tree_stats(-1),
+        KeyCode::PageDown => state.scroll_tree_stats(10),
+        KeyCode::PageUp => state.scroll_tree_stats(-10),
+        _ => {}
+    }
+    InputAction::None
+}
+
+fn handle_recent_files_mode(key: KeyEvent, state: &mut AppState) -> InputAction {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        state.should_quit = true;
+        return InputAction::Quit;
+    }
+    match key.code {
+        KeyCode::Char('M') | KeyCode::Esc | KeyCode::Char('q') => state.toggle_recent_files(),
+        KeyCode::Enter | KeyCode::Char('l') => state.open_recent_file(),
+        KeyCode::Down | KeyCode::Char('j') => state.move_recent_files_cursor(1),
+        KeyCode::Up | KeyCode::Char('k') => state.move_recent_files_cursor(-1),
+        KeyCode::PageDown => state.move_recent_files_cursor(10),
+        KeyCode::PageUp => state.move_recent_files_cursor(-10),
+        _ => {}
+    }
+    InputAction::None
+}
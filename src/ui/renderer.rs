@@ -4,12 +4,60 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 use ratatui::Frame;
 
-use crate::ui::app_state::{AppState, FocusPanel, ViewMode};
-use crate::ui::widgets::file_list::{FileList, FileListItem, FileListState, format_size};
+use crate::core::analyzer::{
+    AgeBreakdown, Analyzer, GrowthTrend, RankedPath, Risk, AGE_BUCKET_LABELS, FAN_OUT_PERCENTILES,
+};
+use crate::core::compression::{MAX_SAMPLED_FILES, SAMPLE_BYTES};
+use crate::models::category::FileCategory;
+use crate::models::node::{human_readable_delta, SizeMode};
+use crate::models::scan_result::ScanResult;
+use crate::export::ExportFormat;
+use crate::ui::app_state::{AppState, ExportField, FocusPanel, NodeTypeFilter, ViewMode};
+use crate::ui::widgets::file_list::{FileList, FileListItem, FileListState, format_size, sparkline};
 use crate::ui::widgets::progress_bar::ScanProgressBar;
 use crate::ui::widgets::ring_chart::{RingChart, RingChartItem};
 use crate::ui::widgets::status_bar::StatusBar;
 
+/// Status bar caveats for a finished scan.
+fn result_notices(result: &ScanResult) -> Vec<String> {
+    let mut notices = Vec::new();
+    if let Some(fs) = result.filesystem {
+        notices.push(format!("{} (CoW): on-disk may be overstated", fs.name()));
+    }
+    if let Some(estimate) = result.estimate {
+        notices.push(format!(
+            "ESTIMATED ({:.0}% of {}, \u{00b1}{})",
+            estimate.ratio * 100.0,
+            estimate.unit.label(),
+            format_size(estimate.margin_bytes)
+        ));
+    }
+    if !result.skipped.is_empty() {
+        notices.push(format!("{} skipped", result.skipped.len()));
+    }
+    if result.cancelled {
+        notices.push("CANCELLED (partial)".to_string());
+    }
+    if result.truncated() {
+        notices.push(format!("TRUNCATED ({} dirs unscanned)", result.unscanned.len()));
+    }
+    if result.root.shared_size > 0 {
+        notices.push(format!("{} shared by clones", format_size(result.root.shared_size)));
+    }
+    let gitignored = result.root.gitignored_size();
+    if gitignored > 0 {
+        notices.push(format!("{} gitignored", format_size(gitignored)));
+    }
+    let junk = result.root.junk_size();
+    if junk > 0 {
+        notices.push(format!("{} reclaimable", format_size(junk)));
+    }
+    notices
+}
+
+/// Below this many columns the ring chart is stacked above the file list.
+pub const STACKED_LAYOUT_WIDTH: u16 = 60;
+
 pub fn render(frame: &mut Frame, state: &AppState) {
     match state.view_mode {
         ViewMode::Scanning => render_scanning(frame, state),
@@ -22,10 +70,154 @@ pub fn render(frame: &mut Frame, state: &AppState) {
             render_normal(frame, state);
             render_error_overlay(frame, state);
         }
-        ViewMode::Export => render_normal(frame, state),
+        ViewMode::NoteInput => {
+            render_normal(frame, state);
+            render_note_overlay(frame, state);
+        }
+        ViewMode::Export => {
+            render_normal(frame, state);
+            render_export_overlay(frame, state);
+        }
+        ViewMode::MountPicker => render_mount_picker(frame, state),
+        ViewMode::FileTypes => {
+            render_normal(frame, state);
+            render_file_types_overlay(frame, state);
+        }
+        ViewMode::LargestFiles => {
+            render_normal(frame, state);
+            render_largest_files_overlay(frame, state);
+        }
+        ViewMode::EmptyDirs => {
+            render_normal(frame, state);
+            render_empty_dirs_overlay(frame, state);
+        }
+        ViewMode::AgeReport => {
+            render_normal(frame, state);
+            render_age_report_overlay(frame, state);
+        }
+        ViewMode::History => {
+            render_normal(frame, state);
+            render_history_overlay(frame, state);
+        }
+        ViewMode::Cleanup => {
+            render_normal(frame, state);
+            render_cleanup_overlay(frame, state);
+        }
+        ViewMode::Suggestions => {
+            render_normal(frame, state);
+            render_suggestions_overlay(frame, state);
+        }
+        ViewMode::Owners => {
+            render_normal(frame, state);
+            render_owners_overlay(frame, state);
+        }
+        ViewMode::Compression => {
+            render_normal(frame, state);
+            render_compression_overlay(frame, state);
+        }
+        ViewMode::TreeStats => {
+            render_normal(frame, state);
+            render_tree_stats_overlay(frame, state);
+        }
+        ViewMode::RecentFiles => {
+            render_normal(frame, state);
+            render_recent_files_overlay(frame, state);
+        }
     }
 }
 
+/// Width of the usage bar in the mount picker.
+const MOUNT_BAR_WIDTH: usize = 20;
+
+fn render_mount_picker(frame: &mut Frame, state: &AppState) {
+    let area = frame.area();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3), // title
+            Constraint::Min(3),    // mounts
+            Constraint::Length(1), // hint
+        ])
+        .split(area);
+
+    let title = Paragraph::new(Line::from(vec![
+        Span::styled(" DiskLens ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        Span::styled(" - Mounted filesystems ", Style::default().fg(Color::White)),
+    ]))
+    .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::DarkGray)));
+    frame.render_widget(title, chunks[0]);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(" {} picked ", state.picked_mounts.len()));
+    let inner = block.inner(chunks[1]);
+    frame.render_widget(block, chunks[1]);
+
+    let name_width = state
+        .mounts
+        .iter()
+        .map(|m| m.mount_point.display().to_string().chars().count())
+        .max()
+        .unwrap_or(0);
+    // Keep the cursor row on screen
+    let height = inner.height as usize;
+    let offset = (state.mount_cursor + 1).saturating_sub(height);
+    let lines: Vec<Line> = state
+        .mounts
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(index, mount)| {
+            let picked = state.picked_mounts.contains(&mount.mount_point);
+            let used = mount.used() as f64 / mount.total as f64;
+            let filled = ((used * MOUNT_BAR_WIDTH as f64).round() as usize).min(MOUNT_BAR_WIDTH);
+            let bar_color = if used >= 0.9 { Color::Red } else { Color::Cyan };
+            let base = if index == state.mount_cursor {
+                Style::default().bg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(if picked { " [x] " } else { " [ ] " }, base.fg(Color::Yellow)),
+                Span::styled(
+                    format!("{:<name_width$}  ", mount.mount_point.display()),
+                    base.fg(Color::White),
+                ),
+                Span::styled(format!("{:<8} ", mount.fs_type), base.fg(Color::DarkGray)),
+                Span::styled("\u{2588}".repeat(filled), base.fg(bar_color)),
+                Span::styled(
+                    "\u{2591}".repeat(MOUNT_BAR_WIDTH - filled),
+                    base.fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    format!(
+                        " {:>10} of {:>10}  {:>10} free",
+                        format_size(mount.used()),
+                        format_size(mount.total),
+                        format_size(mount.available),
+                    ),
+                    base.fg(Color::White),
+                ),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), inner);
+
+    let hint = Paragraph::new(Line::from(vec![
+        Span::styled(" Space", Style::default().fg(Color::Yellow)),
+        Span::styled(": Pick  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("a", Style::default().fg(Color::Yellow)),
+        Span::styled(": Pick all  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)),
+        Span::styled(": Scan  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("q", Style::default().fg(Color::Yellow)),
+        Span::styled(": Quit  ", Style::default().fg(Color::DarkGray)),
+    ]));
+    frame.render_widget(hint, chunks[2]);
+}
+
 fn render_scanning(frame: &mut Frame, state: &AppState) {
     let area = frame.area();
     let chunks = Layout::default()
@@ -38,13 +230,31 @@ fn render_scanning(frame: &mut Frame, state: &AppState) {
         .split(area);
 
     // Title
-    let title = Paragraph::new(Line::from(vec![
+    let scanning = if state.current_path.as_os_str().is_empty() {
+        "multiple roots".to_string()
+    } else {
+        state.current_path.display().to_string()
+    };
+    let mut title_spans = vec![
         Span::styled(" DiskLens ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
         Span::styled(
-            format!(" - Scanning: {} ", state.current_path.display()),
+            format!(" - Scanning: {} ", scanning),
             Style::default().fg(Color::White),
         ),
-    ]))
+    ];
+    if state.scan_paused {
+        title_spans.push(Span::styled(
+            " PAUSED ",
+            Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some(fs_type) = &state.network_fs {
+        title_spans.push(Span::styled(
+            format!(" NETWORK ({}, throttled) ", fs_type),
+            Style::default().fg(Color::Black).bg(Color::Magenta),
+        ));
+    }
+    let title = Paragraph::new(Line::from(title_spans))
     .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::DarkGray)));
     frame.render_widget(title, chunks[0]);
 
@@ -61,7 +271,16 @@ fn render_scanning(frame: &mut Frame, state: &AppState) {
 
     // Bottom hint
     let hint = Paragraph::new(Line::from(vec![
-        Span::styled(" q", Style::default().fg(Color::Yellow)),
+        Span::styled(" Space", Style::default().fg(Color::Yellow)),
+        Span::styled(
+            if state.scan_paused { ": Resume  " } else { ": Pause  " },
+            Style::default().fg(Color::DarkGray),
+        ),
+        Span::styled("Esc", Style::default().fg(Color::Yellow)),
+        Span::styled(": Stop and show results  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)),
+        Span::styled(": Browse so far  ", Style::default().fg(Color::DarkGray)),
+        Span::styled("q", Style::default().fg(Color::Yellow)),
         Span::styled(": Quit  ", Style::default().fg(Color::DarkGray)),
     ]));
     frame.render_widget(hint, chunks[2]);
@@ -82,9 +301,15 @@ fn render_normal(frame: &mut Frame, state: &AppState) {
     // Title + breadcrumb
     render_breadcrumb(frame, chunks[0], state);
 
-    // Main content: ring chart (left) | file list (right)
+    // Main content: ring chart (left) | file list (right), or stacked
+    // top-to-bottom when the terminal is too narrow for both side by side
+    let direction = if area.width < STACKED_LAYOUT_WIDTH {
+        Direction::Vertical
+    } else {
+        Direction::Horizontal
+    };
     let main_chunks = Layout::default()
-        .direction(Direction::Horizontal)
+        .direction(direction)
         .constraints([
             Constraint::Percentage(40), // ring chart
             Constraint::Percentage(60), // file list
@@ -97,25 +322,49 @@ fn render_normal(frame: &mut Frame, state: &AppState) {
     } else {
         Style::default().fg(Color::DarkGray)
     };
+    let total_size = state.percentage_base();
+
+    let children = state.sorted_children();
+
+    // While zoomed, slices are re-normalized against the children that remain
+    let ring_children = state.ring_children();
+    let zoom_cap = state.ring_zoom_cap();
+    let ring_total = match zoom_cap {
+        Some(_) => ring_children.iter().map(|(_, node)| node.size).sum(),
+        None => total_size,
+    };
+
+    let ring_title = match zoom_cap {
+        Some(cap) => Line::from(vec![
+            Span::raw(" Ring Chart "),
+            Span::styled(
+                format!(
+                    "[zoomed: {} hidden > {}] ",
+                    children.len() - ring_children.len(),
+                    format_size(cap)
+                ),
+                Style::default().fg(Color::Yellow),
+            ),
+        ]),
+        None => Line::from(" Ring Chart "),
+    };
     let ring_block = Block::default()
-        .title(" Ring Chart ")
+        .title(ring_title)
         .borders(Borders::ALL)
         .border_style(ring_border_style);
     let ring_inner = ring_block.inner(main_chunks[0]);
     frame.render_widget(ring_block, main_chunks[0]);
 
-    let total_size = state
-        .current_node()
-        .map(|n| n.size)
-        .unwrap_or(0);
-
-    let children = state.sorted_children();
+    let ring_selected = ring_children
+        .iter()
+        .position(|(index, _)| *index == state.selected_index)
+        .unwrap_or(usize::MAX);
 
-    let ring_items: Vec<RingChartItem> = children
+    let ring_items: Vec<RingChartItem> = ring_children
         .iter()
-        .map(|node| {
-            let percentage = if total_size > 0 {
-                (node.size as f64 / total_size as f64) * 100.0
+        .map(|(_, node)| {
+            let percentage = if ring_total > 0 {
+                (node.size as f64 / ring_total as f64) * 100.0
             } else {
                 0.0
             };
@@ -127,7 +376,19 @@ fn render_normal(frame: &mut Frame, state: &AppState) {
         })
         .collect();
 
-    let ring_chart = RingChart::new(ring_items, total_size).selected(state.selected_index);
+    let center_lines = match state.current_node() {
+        Some(node) if state.show_center_stats => vec![
+            format_size(ring_total),
+            format!("{} files", node.file_count),
+            format!("{} dirs", node.dir_count.saturating_sub(1)),
+        ],
+        _ => Vec::new(),
+    };
+
+    let ring_chart = RingChart::new(ring_items, ring_total)
+        .selected(ring_selected)
+        .legend(state.effective_legend())
+        .center_lines(center_lines);
     frame.render_widget(ring_chart, ring_inner);
 
     // File list
@@ -137,24 +398,52 @@ fn render_normal(frame: &mut Frame, state: &AppState) {
         Style::default().fg(Color::DarkGray)
     };
 
+    let size_mode = state.scan_result.as_ref().map_or(SizeMode::Apparent, |r| r.size_mode);
     let items: Vec<FileListItem> = children
         .iter()
         .map(|node| FileListItem {
-            name: node.name.clone(),
+            name: node.name_with_target(),
             size: node.size,
             node_type: node.node_type,
             is_merged: false,
             merged_count: 0,
+            trend: state.size_trend(node),
+            hardlinked: node.is_hardlinked(),
+            note: state.notes.get(&node.path).map(str::to_string),
+            marked: state.marked.contains(&node.path),
+            gitignored: node.gitignored,
+            uncompressed: node.uncompressed_size,
+            sparse: node.is_sparse(size_mode),
+            junk: node.junk.clone(),
+            estimated: node.estimated,
+            errors: node.error_count,
+            delta: state.size_deltas.get(&node.path).copied(),
+            path: node.path.clone(),
+            modified: node.modified,
         })
         .collect();
 
-    let threshold_pct = format!("{:.1}%", state.merge_threshold * 100.0);
+    let mut title_parts = vec![format!("threshold: {:.1}%", state.merge_threshold * 100.0)];
+    if let Some(result) = &state.scan_result {
+        if result.size_mode == SizeMode::DiskUsage {
+            title_parts.push(result.size_mode.label().to_string());
+        }
+    }
+    if let Some(previous) = state.previous_scan {
+        let date = chrono::DateTime::<chrono::Local>::from(previous).format("%Y-%m-%d %H:%M");
+        title_parts.push(format!("changes since {}", date));
+    }
+    if !state.marked.is_empty() {
+        title_parts.push(format!("{} marked", state.marked.len()));
+    }
+    let file_title = format!(" Files ({}) ", title_parts.join(", "));
 
     let file_list = FileList::new(items, total_size)
         .sort_mode(state.sort_mode, state.sort_order)
+        .density(state.list_density)
         .block(
             Block::default()
-                .title(format!(" Files (threshold: {}) ", threshold_pct))
+                .title(file_title)
                 .borders(Borders::ALL)
                 .border_style(file_border_style),
         );
@@ -170,7 +459,12 @@ fn render_normal(frame: &mut Frame, state: &AppState) {
         error_count: state.error_count,
         files_scanned: state.files_scanned,
         speed: state.scan_speed,
-        message: None,
+        message: state.status_message.clone(),
+        notices: if state.scanning {
+            vec!["still scanning, Esc for progress".to_string()]
+        } else {
+            state.scan_result.as_ref().map(result_notices).unwrap_or_default()
+        },
     };
     frame.render_widget(status, chunks[2]);
 
@@ -217,7 +511,7 @@ fn render_help_overlay(frame: &mut Frame) {
         ]),
         Line::from(vec![
             Span::styled("    Enter / l   ", Style::default().fg(Color::Green)),
-            Span::raw("Enter directory"),
+            Span::raw("Enter directory (either panel)"),
         ]),
         Line::from(vec![
             Span::styled("    Backspace/h ", Style::default().fg(Color::Green)),
@@ -231,10 +525,22 @@ fn render_help_overlay(frame: &mut Frame) {
             Span::styled("    G           ", Style::default().fg(Color::Green)),
             Span::raw("Go to last item"),
         ]),
+        Line::from(vec![
+            Span::styled("    B           ", Style::default().fg(Color::Green)),
+            Span::raw("Reveal largest file (again: next largest)"),
+        ]),
+        Line::from(vec![
+            Span::styled("    1-9         ", Style::default().fg(Color::Green)),
+            Span::raw("Jump to numbered breadcrumb"),
+        ]),
         Line::from(vec![
             Span::styled("    Tab / Arrow ", Style::default().fg(Color::Green)),
             Span::raw("Switch focus panel"),
         ]),
+        Line::from(vec![
+            Span::styled("    Esc         ", Style::default().fg(Color::Green)),
+            Span::raw("Back to scan progress (while scanning)"),
+        ]),
         Line::from(""),
         Line::from(vec![
             Span::styled("  Actions", Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
@@ -247,13 +553,73 @@ fn render_help_overlay(frame: &mut Frame) {
             Span::styled("    t           ", Style::default().fg(Color::Green)),
             Span::raw("Cycle merge threshold"),
         ]),
+        Line::from(vec![
+            Span::styled("    f           ", Style::default().fg(Color::Green)),
+            Span::raw("Filter: all / dirs only / files only"),
+        ]),
+        Line::from(vec![
+            Span::styled("    .           ", Style::default().fg(Color::Green)),
+            Span::raw("Show / hide hidden entries"),
+        ]),
+        Line::from(vec![
+            Span::styled("    %           ", Style::default().fg(Color::Green)),
+            Span::raw("Percent of directory / of visible"),
+        ]),
+        Line::from(vec![
+            Span::styled("    z           ", Style::default().fg(Color::Green)),
+            Span::raw("Zoom ring past dominant slices"),
+        ]),
+        Line::from(vec![
+            Span::styled("    c           ", Style::default().fg(Color::Green)),
+            Span::raw("Show counts in chart center"),
+        ]),
+        Line::from(vec![
+            Span::styled("    L           ", Style::default().fg(Color::Green)),
+            Span::raw("Show / hide chart legend"),
+        ]),
+        Line::from(vec![
+            Span::styled("    b           ", Style::default().fg(Color::Green)),
+            Span::raw("Legend at right / bottom"),
+        ]),
         Line::from(vec![
             Span::styled("    r           ", Style::default().fg(Color::Green)),
-            Span::raw("Refresh scan"),
+            Span::raw("Refresh scan (unchanged directories reused)"),
+        ]),
+        Line::from(vec![
+            Span::styled("    R           ", Style::default().fg(Color::Green)),
+            Span::raw("Full rescan"),
+        ]),
+        Line::from(vec![
+            Span::styled("    F           ", Style::default().fg(Color::Green)),
+            Span::raw("Toggle following symlinks and rescan"),
+        ]),
+        Line::from(vec![
+            Span::styled("    a           ", Style::default().fg(Color::Green)),
+            Span::raw("Apparent size / disk usage"),
+        ]),
+        Line::from(vec![
+            Span::styled("    D           ", Style::default().fg(Color::Green)),
+            Span::raw("Compact / comfortable file list"),
         ]),
         Line::from(vec![
             Span::styled("    x           ", Style::default().fg(Color::Green)),
-            Span::raw("Export results"),
+            Span::raw("Export: pick format, scope and file"),
+        ]),
+        Line::from(vec![
+            Span::styled("    w           ", Style::default().fg(Color::Green)),
+            Span::raw("Write current view to file"),
+        ]),
+        Line::from(vec![
+            Span::styled("    Space       ", Style::default().fg(Color::Green)),
+            Span::raw("Mark / unmark (x can export only marked)"),
+        ]),
+        Line::from(vec![
+            Span::styled("    u           ", Style::default().fg(Color::Green)),
+            Span::raw("Clear marks"),
+        ]),
+        Line::from(vec![
+            Span::styled("    n           ", Style::default().fg(Color::Green)),
+            Span::raw("Add / edit note on selection"),
         ]),
         Line::from(vec![
             Span::styled("    y           ", Style::default().fg(Color::Green)),
@@ -267,6 +633,50 @@ fn render_help_overlay(frame: &mut Frame) {
             Span::styled("    e           ", Style::default().fg(Color::Green)),
             Span::raw("Show error list"),
         ]),
+        Line::from(vec![
+            Span::styled("    T           ", Style::default().fg(Color::Green)),
+            Span::raw("Show size by file type"),
+        ]),
+        Line::from(vec![
+            Span::styled("    N           ", Style::default().fg(Color::Green)),
+            Span::raw("List the largest files in the scan"),
+        ]),
+        Line::from(vec![
+            Span::styled("    E           ", Style::default().fg(Color::Green)),
+            Span::raw("Find empty directories to delete"),
+        ]),
+        Line::from(vec![
+            Span::styled("    A           ", Style::default().fg(Color::Green)),
+            Span::raw("Show size by last-modified age"),
+        ]),
+        Line::from(vec![
+            Span::styled("    H           ", Style::default().fg(Color::Green)),
+            Span::raw("Show growth over past scans"),
+        ]),
+        Line::from(vec![
+            Span::styled("    C           ", Style::default().fg(Color::Green)),
+            Span::raw("List regenerable directories to clean up"),
+        ]),
+        Line::from(vec![
+            Span::styled("    S           ", Style::default().fg(Color::Green)),
+            Span::raw("Suggest ways to free space, ranked"),
+        ]),
+        Line::from(vec![
+            Span::styled("    O           ", Style::default().fg(Color::Green)),
+            Span::raw("Show size per owning user (x to save as CSV)"),
+        ]),
+        Line::from(vec![
+            Span::styled("    Z           ", Style::default().fg(Color::Green)),
+            Span::raw("Estimate compression savings (reads samples)"),
+        ]),
+        Line::from(vec![
+            Span::styled("    I           ", Style::default().fg(Color::Green)),
+            Span::raw("Show tree depth and fan-out statistics"),
+        ]),
+        Line::from(vec![
+            Span::styled("    M           ", Style::default().fg(Color::Green)),
+            Span::raw("List recently modified files"),
+        ]),
         Line::from(""),
         Line::from(vec![
             Span::styled("    ?           ", Style::default().fg(Color::Green)),
@@ -294,51 +704,160 @@ fn render_help_overlay(frame: &mut Frame) {
     frame.render_widget(help, area);
 }
 
-fn render_error_overlay(frame: &mut Frame, state: &AppState) {
-    let area = centered_rect(70, 60, frame.area());
+fn render_note_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(60, 20, frame.area());
     frame.render_widget(Clear, area);
 
-    let errors = state
-        .scan_result
+    let target = state
+        .note_target
         .as_ref()
-        .map(|r| &r.errors)
-        .cloned()
+        .map(|p| p.display().to_string())
         .unwrap_or_default();
+    let lines = vec![
+        Line::from(Span::styled(format!("  {}", target), Style::default().fg(Color::DarkGray))),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Note: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("{}_", state.note_input), Style::default().fg(Color::Yellow)),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  Enter to save (empty removes), Esc to cancel",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+
+    let panel = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(" Note ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .style(Style::default().bg(Color::Black))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(panel, area);
+}
+
+fn render_export_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(60, 40, frame.area());
+    frame.render_widget(Clear, area);
+
+    // The row under the cursor is marked and its label highlighted
+    let label = |field: ExportField, text: &str| {
+        let focused = state.export_field == field;
+        Span::styled(
+            format!("{} {:<8}", if focused { " >" } else { "  " }, text),
+            Style::default().fg(if focused { Color::Yellow } else { Color::DarkGray }),
+        )
+    };
+    let choice = |text: &str, chosen: bool| {
+        let style = if chosen {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        Span::styled(format!(" {} ", text), style)
+    };
+
+    let mut format_line = vec![label(ExportField::Format, "Format")];
+    format_line.extend(
+        ExportFormat::ALL.iter().map(|f| choice(f.label(), *f == state.export_format)),
+    );
+    let mut scope_line = vec![label(ExportField::Scope, "Scope")];
+    scope_line.extend(
+        state.export_scopes().iter().map(|s| choice(s.label(), *s == state.export_scope)),
+    );
+    let cursor = if state.export_field == ExportField::Path { "_" } else { "" };
+    let lines = vec![
+        Line::from(format_line),
+        Line::from(""),
+        Line::from(scope_line),
+        Line::from(""),
+        Line::from(vec![
+            label(ExportField::Path, "File"),
+            Span::styled(
+                format!(" {}{}", state.export_path, cursor),
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled(
+            "  \u{2191}\u{2193} row  \u{2190}\u{2192} choose  Enter export  Esc cancel",
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+
+    let panel = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(" Export ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        )
+        .style(Style::default().bg(Color::Black))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(panel, area);
+}
+
+fn render_error_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(70, 60, frame.area());
+    frame.render_widget(Clear, area);
+
+    let total = state.scan_result.as_ref().map(|r| r.errors.len()).unwrap_or(0);
+    let errors = state.filtered_errors();
+    let query = state.error_query.as_str();
 
+    let header = if query.is_empty() {
+        format!(" {} errors found ", total)
+    } else {
+        format!(" showing {} of {} errors ", errors.len(), total)
+    };
     let mut lines = vec![
         Line::from(Span::styled(
-            format!(" {} errors found ", errors.len()),
+            header,
             Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
         )),
+        Line::from(vec![
+            Span::styled("  Filter: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(format!("{}_", query), Style::default().fg(Color::Yellow)),
+        ]),
         Line::from(""),
     ];
 
-    for (i, err) in errors.iter().enumerate() {
+    let match_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+    for (i, err) in errors.iter().enumerate().skip(state.error_scroll) {
         let type_str = format!("{:?}", err.error_type);
-        lines.push(Line::from(vec![
+        let mut path_line = vec![
             Span::styled(format!("  {}. ", i + 1), Style::default().fg(Color::DarkGray)),
             Span::styled(format!("[{}] ", type_str), Style::default().fg(Color::Yellow)),
-            Span::styled(
-                err.path.display().to_string(),
-                Style::default().fg(Color::White),
-            ),
-        ]));
-        lines.push(Line::from(vec![
-            Span::styled("     ", Style::default()),
-            Span::styled(&err.message, Style::default().fg(Color::DarkGray)),
-        ]));
+        ];
+        path_line.extend(highlight_matches(
+            &err.path.display().to_string(),
+            query,
+            Style::default().fg(Color::White),
+            match_style,
+        ));
+        lines.push(Line::from(path_line));
+
+        let mut message_line = vec![Span::styled("     ", Style::default())];
+        message_line.extend(highlight_matches(
+            &err.message,
+            query,
+            Style::default().fg(Color::DarkGray),
+            match_style,
+        ));
+        lines.push(Line::from(message_line));
     }
 
     if errors.is_empty() {
-        lines.push(Line::from(Span::styled(
-            "  No errors.",
-            Style::default().fg(Color::Green),
-        )));
+        let msg = if total == 0 { "  No errors." } else { "  No matching errors." };
+        lines.push(Line::from(Span::styled(msg, Style::default().fg(Color::Green))));
     }
 
     lines.push(Line::from(""));
     lines.push(Line::from(Span::styled(
-        "  Press e or Esc to close",
+        "  Type to filter, Up/Down to scroll, Esc to clear / close",
         Style::default().fg(Color::DarkGray),
     )));
 
@@ -354,37 +873,872 @@ fn render_error_overlay(frame: &mut Frame, state: &AppState) {
     frame.render_widget(error_panel, area);
 }
 
-fn render_breadcrumb(frame: &mut Frame, area: Rect, state: &AppState) {
-    let path = &state.current_path;
-    let mut spans = vec![
-        Span::styled(" DiskLens ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
-        Span::styled(" | ", Style::default().fg(Color::DarkGray)),
-    ];
-
-    let components: Vec<&std::ffi::OsStr> = path.components()
-        .filter_map(|c| match c {
-            std::path::Component::Normal(s) => Some(s),
-            std::path::Component::RootDir => None,
-            _ => None,
-        })
-        .collect();
+fn render_file_types_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(70, 70, frame.area());
+    frame.render_widget(Clear, area);
 
-    spans.push(Span::styled("/", Style::default().fg(Color::White)));
+    let extensions = state.current_node().map(Analyzer::by_extension).unwrap_or_default();
+    let total: u64 = extensions.iter().map(|e| e.size).sum();
+    let share = |size: u64| if total > 0 { size as f64 / total as f64 * 100.0 } else { 0.0 };
+    let color = |category: FileCategory| {
+        let (r, g, b) = category.rgb();
+        Color::Rgb(r, g, b)
+    };
 
-    for (i, component) in components.iter().enumerate() {
-        spans.push(Span::styled(" > ", Style::default().fg(Color::DarkGray)));
-        let is_last = i == components.len() - 1;
-        let style = if is_last {
-            Style::default().fg(Color::White).add_modifier(Modifier::BOLD)
-        } else {
-            Style::default().fg(Color::White)
-        };
-        spans.push(Span::styled(
-            component.to_string_lossy().to_string(),
-            style,
-        ));
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!(" {} in {} extensions ", format_size(total), extensions.len()),
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+    ];
+    for usage in Analyzer::by_category(&extensions) {
+        lines.push(Line::from(vec![
+            Span::styled("  \u{25A0} ", Style::default().fg(color(usage.category))),
+            Span::styled(
+                format!("{:<10}", usage.category.name()),
+                Style::default().fg(Color::White),
+            ),
+            Span::styled(
+                format!(
+                    "{:>10} {:5.1}%  {} files",
+                    format_size(usage.size),
+                    share(usage.size),
+                    usage.file_count
+                ),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+    }
+    lines.push(Line::from(""));
+    for usage in extensions.iter().skip(state.file_types_scroll) {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {:<14}", usage.label()),
+                Style::default().fg(color(usage.category)),
+            ),
+            Span::styled(
+                format!(
+                    "{:>10} {:5.1}%  {} files",
+                    format_size(usage.size),
+                    share(usage.size),
+                    usage.file_count
+                ),
+                Style::default().fg(Color::White),
+            ),
+        ]));
+    }
+    if extensions.is_empty() {
+        lines.push(Line::from(Span::styled("  No files.", Style::default().fg(Color::DarkGray))));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Up/Down to scroll, Esc to close",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let panel = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(" File Types ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().bg(Color::Black));
+    frame.render_widget(panel, area);
+}
+
+fn render_largest_files_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let files = state.largest_files();
+    let block = Block::default()
+        .title(format!(" {} Largest Files ", files.len()))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Keep the cursor row on screen, above the hint
+    let height = (inner.height as usize).saturating_sub(2);
+    let offset = (state.largest_files_cursor + 1).saturating_sub(height);
+    let rank_width = files.len().to_string().len();
+    let mut lines: Vec<Line> = files
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(rank, (path, size))| {
+            let base = if rank == state.largest_files_cursor {
+                Style::default().bg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!(" {:>rank_width$}. ", rank + 1), base.fg(Color::DarkGray)),
+                Span::styled(format!("{:>10}  ", format_size(*size)), base.fg(Color::Yellow)),
+                Span::styled(path.display().to_string(), base.fg(Color::White)),
+            ])
+        })
+        .collect();
+    if files.is_empty() {
+        lines.push(Line::from(Span::styled("  No files.", Style::default().fg(Color::DarkGray))));
+    }
+    lines.resize(height, Line::from(""));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Enter to go to the file, Up/Down to move, Esc to close",
+        Style::default().fg(Color::DarkGray),
+    )));
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn render_recent_files_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let files = &state.recent_files;
+    let title = match state.current_node() {
+        Some(node) if !node.name.is_empty() => {
+            format!(" {} Recently Modified Files: {} ", files.len(), node.name)
+        }
+        _ => format!(" {} Recently Modified Files ", files.len()),
+    };
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Keep the cursor row on screen, above the hint
+    let height = (inner.height as usize).saturating_sub(2);
+    let offset = (state.recent_files_cursor + 1).saturating_sub(height);
+    let mut lines: Vec<Line> = files
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(i, file)| {
+            let base = if i == state.recent_files_cursor {
+                Style::default().bg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            let modified = chrono::DateTime::<chrono::Local>::from(file.modified);
+            Line::from(vec![
+                Span::styled(
+                    format!(" {}  ", modified.format("%Y-%m-%d %H:%M")),
+                    base.fg(Color::Cyan),
+                ),
+                Span::styled(format!("{:>10}  ", format_size(file.size)), base.fg(Color::Yellow)),
+                Span::styled(file.path.display().to_string(), base.fg(Color::White)),
+            ])
+        })
+        .collect();
+    if files.is_empty() {
+        lines.push(Line::from(Span::styled("  No files.", Style::default().fg(Color::DarkGray))));
+    }
+    lines.resize(height, Line::from(""));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Enter to go to the file, Up/Down to move, Esc to close",
+        Style::default().fg(Color::DarkGray),
+    )));
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn render_cleanup_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let dirs = &state.cleanup_candidates;
+    let total: u64 = dirs.iter().map(|d| d.size).sum();
+    let block = Block::default()
+        .title(format!(
+            " Cleanup Candidates: {} in {} directories ",
+            format_size(total),
+            dirs.len()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Keep the cursor row on screen, above the hint
+    let height = (inner.height as usize).saturating_sub(2);
+    let offset = (state.cleanup_cursor + 1).saturating_sub(height);
+    let mut lines: Vec<Line> = dirs
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(index, dir)| {
+            let base = if index == state.cleanup_cursor {
+                Style::default().bg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            Line::from(vec![
+                Span::styled(format!(" {:>10}  ", format_size(dir.size)), base.fg(Color::Yellow)),
+                Span::styled(format!("{:<20}", dir.label), base.fg(Color::Magenta)),
+                Span::styled(dir.path.display().to_string(), base.fg(Color::White)),
+            ])
+        })
+        .collect();
+    if dirs.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No regenerable directories found.",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines.resize(height, Line::from(""));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Enter to go to the directory, Up/Down to move, Esc to close",
+        Style::default().fg(Color::DarkGray),
+    )));
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn render_suggestions_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let suggestions = &state.suggestions;
+    let total: u64 = suggestions.iter().map(|s| s.reclaimable).sum();
+    let block = Block::default()
+        .title(format!(" Cleanup Suggestions: {} reclaimable ", format_size(total)))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Two lines per suggestion: what to remove, then why
+    let height = (inner.height as usize).saturating_sub(2);
+    let shown = (height / 2).max(1);
+    let offset = (state.suggestions_cursor + 1).saturating_sub(shown);
+    let mut lines: Vec<Line> = Vec::new();
+    for (index, suggestion) in suggestions.iter().enumerate().skip(offset).take(shown) {
+        let base = if index == state.suggestions_cursor {
+            Style::default().bg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+        let risk_color = match suggestion.risk {
+            Risk::Low => Color::Green,
+            Risk::Medium => Color::Yellow,
+            Risk::High => Color::Red,
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(" {:>10}  ", format_size(suggestion.reclaimable)),
+                base.fg(Color::Yellow),
+            ),
+            Span::styled(format!("{:<7}", suggestion.risk.label()), base.fg(risk_color)),
+            Span::styled(format!("{:<12}", suggestion.kind.label()), base.fg(Color::Magenta)),
+            Span::styled(suggestion.path.display().to_string(), base.fg(Color::White)),
+        ]));
+        lines.push(Line::from(Span::styled(
+            format!("{:32}{}", "", suggestion.reason),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    if suggestions.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  Nothing to suggest.",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines.resize(height, Line::from(""));
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Safest and largest first. Enter to go to it, Up/Down to move, Esc to close",
+        Style::default().fg(Color::DarkGray),
+    )));
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn render_empty_dirs_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let block = Block::default()
+        .title(format!(
+            " Empty Directories ({}, {} picked) ",
+            state.empty_dirs.len(),
+            state.picked_empty_dirs.len()
+        ))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .style(Style::default().bg(Color::Black));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    // Keep the cursor row on screen, above the hint
+    let height = (inner.height as usize).saturating_sub(2);
+    let offset = (state.empty_dirs_cursor + 1).saturating_sub(height);
+    let mut lines: Vec<Line> = state
+        .empty_dirs
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(height)
+        .map(|(index, dir)| {
+            let picked = state.picked_empty_dirs.contains(&dir.path);
+            let base = if index == state.empty_dirs_cursor {
+                Style::default().bg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            let contents = if dir.is_empty() {
+                "empty".to_string()
+            } else {
+                format!("{} files, {}", dir.file_count, format_size(dir.size))
+            };
+            Line::from(vec![
+                Span::styled(if picked { " [x] " } else { " [ ] " }, base.fg(Color::Yellow)),
+                Span::styled(format!("{:<18}", contents), base.fg(Color::DarkGray)),
+                Span::styled(dir.path.display().to_string(), base.fg(Color::Blue)),
+            ])
+        })
+        .collect();
+    if state.empty_dirs.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No empty directories.",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines.resize(height, Line::from(""));
+    lines.push(Line::from(""));
+    let hint = if state.confirm_delete {
+        let dirs = state.dirs_to_delete();
+        let size: u64 = dirs.iter().map(|d| d.size).sum();
+        Span::styled(
+            format!(
+                "  Delete {} directories ({}) and everything in them? y to confirm",
+                dirs.len(),
+                format_size(size)
+            ),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )
+    } else {
+        Span::styled(
+            "  Space to pick, a for all, d to delete, Esc to close",
+            Style::default().fg(Color::DarkGray),
+        )
+    };
+    lines.push(Line::from(hint));
+    frame.render_widget(Paragraph::new(lines), inner);
+}
+
+fn render_owners_overlay(frame: &mut Frame, state: &AppState) {
+    const BAR_WIDTH: usize = 20;
+
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let total: u64 = state.owners.iter().map(|u| u.size).sum();
+    let heading = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::from(Span::styled(
+        format!("  {:<24}{:>10}{:>11}{:>8}{:>10}", "User", "UID", "Size", "%", "Files"),
+        heading,
+    ))];
+    for owner in state.owners.iter().skip(state.owners_scroll) {
+        let share = owner.size as f64 / total.max(1) as f64;
+        let filled = (share * BAR_WIDTH as f64).round() as usize;
+        let mut name = owner.label();
+        if name.chars().count() > 24 {
+            name = format!("{}~", name.chars().take(23).collect::<String>());
+        }
+        let name_color = if owner.uid.is_some() { Color::White } else { Color::DarkGray };
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {:<24}", name), Style::default().fg(name_color)),
+            Span::styled(
+                format!("{:>10}", owner.uid.map(|uid| uid.to_string()).unwrap_or_default()),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(
+                format!("{:>11}", format_size(owner.size)),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::raw(format!("{:>7.1}%", share * 100.0)),
+            Span::raw(format!("{:>10}  ", owner.file_count)),
+            Span::styled("\u{2588}".repeat(filled), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                "\u{2591}".repeat(BAR_WIDTH - filled),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]));
+    }
+    if state.owners.is_empty() {
+        lines.push(Line::from(Span::styled("  No entries.", Style::default().fg(Color::DarkGray))));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  x to save as CSV, Up/Down to scroll, Esc to close",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let title = match state.current_node() {
+        Some(node) if !node.name.is_empty() => format!(" Owners: {} ", node.name),
+        _ => " Owners ".to_string(),
+    };
+    let panel = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().bg(Color::Black));
+    frame.render_widget(panel, area);
+}
+
+fn render_tree_stats_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let heading = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    let label = Style::default().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    if let Some(stats) = &state.tree_stats {
+        let [p50, p90, p99] = stats.fan_out_percentiles;
+        lines.push(Line::from(vec![
+            Span::styled("  Max depth      ", label),
+            Span::styled(stats.max_depth.to_string(), Style::default().fg(Color::Yellow)),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("  Directories    ", label),
+            Span::raw(stats.directories.to_string()),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("  Fan-out        ", label),
+            Span::raw(format!(
+                "avg {:.1}  p{} {}  p{} {}  p{} {}  max {}",
+                stats.average_fan_out,
+                FAN_OUT_PERCENTILES[0],
+                p50,
+                FAN_OUT_PERCENTILES[1],
+                p90,
+                FAN_OUT_PERCENTILES[2],
+                p99,
+                stats.max_fan_out
+            )),
+        ]));
+        let root = state.current_node().map(|node| node.path.clone()).unwrap_or_default();
+        let row = |ranked: &RankedPath| {
+            let path = ranked.path.strip_prefix(&root).unwrap_or(&ranked.path);
+            Line::from(vec![
+                Span::styled(
+                    format!("  {:>8}  ", ranked.value),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::raw(path.display().to_string()),
+            ])
+        };
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(format!("  {:>8}  Deepest", "Depth"), heading)));
+        lines.extend(stats.deepest.iter().map(row));
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(format!("  {:>8}  Widest", "Entries"), heading)));
+        lines.extend(stats.widest.iter().map(row));
+    }
+    let mut lines: Vec<Line> = lines.into_iter().skip(state.tree_stats_scroll).collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled("  Up/Down to scroll, Esc to close", label)));
+
+    let title = match state.current_node() {
+        Some(node) if !node.name.is_empty() => format!(" Tree Shape: {} ", node.name),
+        _ => " Tree Shape ".to_string(),
+    };
+    let panel = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().bg(Color::Black));
+    frame.render_widget(panel, area);
+}
+
+fn render_compression_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let heading = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+    let mut lines = vec![Line::from(Span::styled(
+        format!("  {:<28}{:>11}{:>13}{:>11}{:>9}", "", "Size", "Compressed", "Saves", "Sampled"),
+        heading,
+    ))];
+    let row = |name: String, name_style: Style, size: u64, ratio: Option<f64>, sampled: usize| {
+        let name = if name.chars().count() > 28 {
+            format!("{}~", name.chars().take(27).collect::<String>())
+        } else {
+            name
+        };
+        let savings = ratio.map_or(0, |ratio| (size as f64 * (1.0 - ratio)) as u64);
+        let (ratio_text, color) = match ratio {
+            // Worth compressing below about 80%
+            Some(ratio) if ratio < 0.8 => (format!("{:.0}%", ratio * 100.0), Color::Green),
+            Some(ratio) => (format!("{:.0}%", ratio * 100.0), Color::DarkGray),
+            None => ("-".to_string(), Color::DarkGray),
+        };
+        Line::from(vec![
+            Span::styled(format!("  {:<28}", name), name_style),
+            Span::styled(format!("{:>11}", format_size(size)), Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{:>13}", ratio_text), Style::default().fg(color)),
+            Span::styled(format!("{:>11}", format_size(savings)), Style::default().fg(color)),
+            Span::styled(format!("{:>9}", sampled), Style::default().fg(Color::DarkGray)),
+        ])
+    };
+
+    match &state.compression {
+        None => lines.push(Line::from(Span::styled(
+            "  Sampling file contents\u{2026}",
+            Style::default().fg(Color::DarkGray),
+        ))),
+        Some(estimates) => {
+            let size: u64 = estimates.iter().map(|e| e.size).sum();
+            let savings: u64 = estimates.iter().map(|e| e.savings()).sum();
+            let sampled: usize = estimates.iter().map(|e| e.sampled_files).sum();
+            let ratio = (size > 0 && sampled > 0).then(|| 1.0 - savings as f64 / size as f64);
+            let bold = Style::default().fg(Color::White).add_modifier(Modifier::BOLD);
+            lines.push(row("Total".to_string(), bold, size, ratio, sampled));
+            lines.push(Line::from(""));
+            for estimate in estimates.iter().skip(state.compression_scroll) {
+                let (name, color) = if estimate.is_container {
+                    (format!("{}/", estimate.name), Color::Blue)
+                } else {
+                    (estimate.name.clone(), Color::White)
+                };
+                lines.push(row(
+                    name,
+                    Style::default().fg(color),
+                    estimate.size,
+                    estimate.ratio,
+                    estimate.sampled_files,
+                ));
+            }
+        }
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!(
+            "  zstd level 1 on up to {} files of {} each. Up/Down to scroll, Esc to close",
+            MAX_SAMPLED_FILES,
+            format_size(SAMPLE_BYTES as u64)
+        ),
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let panel = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(format!(" Compression: {} ", state.compression_path.display()))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().bg(Color::Black));
+    frame.render_widget(panel, area);
+}
+
+fn render_age_report_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let now = std::time::SystemTime::now();
+    let node = state.current_node();
+    let total = node.map(|n| Analyzer::age_breakdown(n, now)).unwrap_or_default();
+    let entries = node.map(|n| Analyzer::age_report(n, now)).unwrap_or_default();
+    // Newest in green through oldest in red
+    let colors = [Color::Green, Color::Yellow, Color::LightRed, Color::Red];
+    let row = |name: String, ages: &AgeBreakdown, name_style: Style| {
+        let name = if name.chars().count() > 28 {
+            format!("{}~", name.chars().take(27).collect::<String>())
+        } else {
+            name
+        };
+        let mut spans = vec![Span::styled(format!("  {:<28}", name), name_style)];
+        for (size, color) in ages.buckets.iter().zip(colors) {
+            let style = if *size > 0 {
+                Style::default().fg(color)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            spans.push(Span::styled(format!("{:>11}", format_size(*size)), style));
+        }
+        if ages.unknown > 0 {
+            spans.push(Span::styled(
+                format!("  +{} undated", format_size(ages.unknown)),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        Line::from(spans)
+    };
+
+    let mut heading = vec![Span::raw(format!("  {:<28}", ""))];
+    for label in AGE_BUCKET_LABELS {
+        heading.push(Span::styled(
+            format!("{:>11}", label),
+            Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        ));
+    }
+    let mut lines = vec![
+        Line::from(heading),
+        row(
+            "Total".to_string(),
+            &total,
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+        ),
+        Line::from(""),
+    ];
+    for entry in entries.iter().skip(state.age_report_scroll) {
+        let (name, color) = if entry.is_container {
+            (format!("{}/", entry.name), Color::Blue)
+        } else {
+            (entry.name.clone(), Color::White)
+        };
+        lines.push(row(name, &entry.ages, Style::default().fg(color)));
+    }
+    if entries.is_empty() {
+        lines.push(Line::from(Span::styled("  No entries.", Style::default().fg(Color::DarkGray))));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "  Oldest data first. Up/Down to scroll, Esc to close",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let panel = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(" Age ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().bg(Color::Black));
+    frame.render_widget(panel, area);
+}
+
+fn render_history_overlay(frame: &mut Frame, state: &AppState) {
+    let area = centered_rect(80, 70, frame.area());
+    frame.render_widget(Clear, area);
+
+    let history = state.size_history.clone().unwrap_or_default();
+    let total = Analyzer::total_growth(&history);
+    let trends = Analyzer::growth_trends(&history);
+    let row = |name: String, trend: &GrowthTrend, name_style: Style| {
+        let name = if name.chars().count() > 24 {
+            format!("{}~", name.chars().take(23).collect::<String>())
+        } else {
+            name
+        };
+        let rate = trend.bytes_per_day.round() as i64;
+        // Growing in red, shrinking in green
+        let rate_style = match rate.signum() {
+            1 => Style::default().fg(Color::Red),
+            -1 => Style::default().fg(Color::Green),
+            _ => Style::default().fg(Color::DarkGray),
+        };
+        Line::from(vec![
+            Span::styled(format!("  {:<24}", name), name_style),
+            Span::styled(sparkline(&trend.sizes, 16), Style::default().fg(Color::Cyan)),
+            Span::styled(
+                format!("{:>11}", format_size(trend.current())),
+                Style::default().fg(Color::Yellow),
+            ),
+            Span::raw(format!("{:>12}", human_readable_delta(trend.change()))),
+            Span::styled(format!("{:>12}/day", human_readable_delta(rate)), rate_style),
+        ])
+    };
+
+    let mut lines = Vec::new();
+    match &total {
+        Some(total) => {
+            lines.push(row(
+                "Total".to_string(),
+                total,
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            ));
+            lines.push(Line::from(""));
+            for trend in trends.iter().skip(state.history_scroll) {
+                let name = trend
+                    .path
+                    .file_name()
+                    .map(|n| format!("{}/", n.to_string_lossy()))
+                    .unwrap_or_else(|| trend.path.display().to_string());
+                lines.push(row(name, trend, Style::default().fg(Color::Blue)));
+            }
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "  Fastest growing first. Up/Down to scroll, Esc to close",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        None => lines.push(Line::from(Span::styled(
+            "  Growth shows once this directory has been scanned at least twice.",
+            Style::default().fg(Color::DarkGray),
+        ))),
     }
 
+    let title = match history.snapshots.first() {
+        Some(first) if total.is_some() => {
+            let since = chrono::DateTime::<chrono::Local>::from(first.timestamp);
+            let scans = history.snapshots.len();
+            format!(" Growth over {} scans since {} ", scans, since.format("%Y-%m-%d"))
+        }
+        _ => " Growth ".to_string(),
+    };
+    let panel = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .style(Style::default().bg(Color::Black));
+    frame.render_widget(panel, area);
+}
+
+/// Split `text` into spans, styling case-insensitive occurrences of `query` with `highlight`.
+fn highlight_matches(text: &str, query: &str, base: Style, highlight: Style) -> Vec<Span<'static>> {
+    let lower = text.to_lowercase();
+    // Byte offsets from the lowercased copy are only valid if lowercasing kept lengths
+    if query.is_empty() || lower.len() != text.len() {
+        return vec![Span::styled(text.to_string(), base)];
+    }
+    let query = query.to_lowercase();
+
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    while let Some(found) = lower[pos..].find(&query) {
+        let start = pos + found;
+        let end = start + query.len();
+        if !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+            break;
+        }
+        if start > pos {
+            spans.push(Span::styled(text[pos..start].to_string(), base));
+        }
+        spans.push(Span::styled(text[start..end].to_string(), highlight));
+        pos = end;
+    }
+    if pos < text.len() {
+        spans.push(Span::styled(text[pos..].to_string(), base));
+    }
+    spans
+}
+
+/// A breadcrumb span and, for segments naming a visited ancestor, its `path_stack` index.
+type BreadcrumbSegment = (Span<'static>, Option<usize>);
+
+/// Path segments of the breadcrumb. Ancestors that can be jumped to are numbered
+/// `1`..`9` for the digit keys and are underlined as click targets.
+fn breadcrumb_segments(state: &AppState) -> Vec<BreadcrumbSegment> {
+    let mut segments: Vec<BreadcrumbSegment> = vec![
+        (
+            Span::styled(" DiskLens ", Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+            None,
+        ),
+        (Span::styled(" | ", Style::default().fg(Color::DarkGray)), None),
+    ];
+
+    let path = &state.current_path;
+    // Several roots are scanned under a virtual root whose path is empty
+    let at_virtual_root = path.as_os_str().is_empty();
+    let virtual_ancestor = state.path_stack.iter().position(|p| p.as_os_str().is_empty());
+    if at_virtual_root || virtual_ancestor.is_some() {
+        if let Some(index) = virtual_ancestor.filter(|&i| i < 9) {
+            segments.push((
+                Span::styled(format!("{}:", index + 1), Style::default().fg(Color::DarkGray)),
+                Some(index),
+            ));
+        }
+        let style = if at_virtual_root {
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White).add_modifier(Modifier::UNDERLINED)
+        };
+        segments.push((Span::styled("All roots", style), virtual_ancestor));
+        if at_virtual_root {
+            return segments;
+        }
+        segments.push((Span::styled(" > ", Style::default().fg(Color::DarkGray)), None));
+    }
+
+    let normal_count = path
+        .components()
+        .filter(|c| matches!(c, std::path::Component::Normal(_)))
+        .count();
+    if !path.has_root() {
+        segments.push((Span::styled("/", Style::default().fg(Color::White)), None));
+    }
+
+    let mut prefix = std::path::PathBuf::new();
+    let mut seen_normal = 0;
+    for component in path.components() {
+        prefix.push(component);
+        let label = match component {
+            std::path::Component::RootDir => "/".to_string(),
+            std::path::Component::Normal(name) => {
+                seen_normal += 1;
+                segments.push((Span::styled(" > ", Style::default().fg(Color::DarkGray)), None));
+                name.to_string_lossy().to_string()
+            }
+            _ => continue,
+        };
+        let is_last = seen_normal == normal_count;
+
+        let ancestor = state.path_stack.iter().position(|p| *p == prefix);
+        if let Some(index) = ancestor.filter(|&i| i < 9) {
+            segments.push((
+                Span::styled(format!("{}:", index + 1), Style::default().fg(Color::DarkGray)),
+                Some(index),
+            ));
+        }
+        let style = if is_last {
+            Style::default().fg(Color::White).add_modifier(Modifier::BOLD)
+        } else if ancestor.is_some() {
+            Style::default().fg(Color::White).add_modifier(Modifier::UNDERLINED)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        segments.push((Span::styled(label, style), ancestor));
+    }
+
+    segments
+}
+
+/// The `path_stack` index of the ancestor shown at `column` in the breadcrumb, if any.
+/// The breadcrumb occupies the top rows of the normal view, inside a one-cell border.
+pub fn breadcrumb_target(state: &AppState, column: u16, row: u16) -> Option<usize> {
+    if row != 1 {
+        return None;
+    }
+    let mut x: u16 = 1;
+    for (span, ancestor) in breadcrumb_segments(state) {
+        let width = span.width() as u16;
+        if column >= x && column < x + width {
+            return ancestor;
+        }
+        x += width;
+    }
+    None
+}
+
+fn render_breadcrumb(frame: &mut Frame, area: Rect, state: &AppState) {
+    let mut spans: Vec<Span> = breadcrumb_segments(state)
+        .into_iter()
+        .map(|(span, _)| span)
+        .collect();
+
     // Show total size if scan result is available
     if let Some(node) = state.current_node() {
         spans.push(Span::styled(
@@ -393,6 +1747,22 @@ fn render_breadcrumb(frame: &mut Frame, area: Rect, state: &AppState) {
         ));
     }
 
+    if state.node_type_filter != NodeTypeFilter::All {
+        let basis = if state.percent_of_visible { ", % of visible" } else { "" };
+        spans.push(Span::styled(
+            format!("  [{}{}]", state.node_type_filter.label(), basis),
+            Style::default().fg(Color::Yellow),
+        ));
+    }
+
+    if let Some(banner) = state.cache_banner(std::time::SystemTime::now()) {
+        spans.push(Span::raw("  "));
+        spans.push(Span::styled(
+            format!(" CACHED {} ", banner),
+            Style::default().fg(Color::Black).bg(Color::Yellow),
+        ));
+    }
+
     let breadcrumb = Paragraph::new(Line::from(spans)).block(
         Block::default()
             .borders(Borders::ALL)
@@ -7,7 +7,11 @@ use ratatui::{
 };
 use unicode_width::UnicodeWidthStr;
 
-use crate::models::node::NodeType;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::config::settings::ListDensity;
+use crate::models::node::{human_readable_delta, NodeType};
 use crate::ui::app_state::{SortMode, SortOrder};
 
 pub struct FileListState {
@@ -19,24 +23,56 @@ pub struct FileList<'a> {
     items: Vec<FileListItem>,
     sort_mode: SortMode,
     sort_order: SortOrder,
+    density: ListDensity,
     total_size: u64,
     block: Option<Block<'a>>,
 }
 
 pub struct FileListItem {
+    /// Shown as is; for symlinks, with their target.
     pub name: String,
     pub size: u64,
     pub node_type: NodeType,
     pub is_merged: bool,
     pub merged_count: usize,
+    /// Sizes from past scans, oldest first, drawn as a sparkline before the size.
+    pub trend: Option<Vec<u64>>,
+    /// Flag a file whose data is shared with other hard links.
+    pub hardlinked: bool,
+    /// User note attached to this path, shown after the name.
+    pub note: Option<String>,
+    /// Marked for a subset export.
+    pub marked: bool,
+    /// Matched by git's ignore files; drawn dimmed.
+    pub gitignored: bool,
+    /// Extracted size of an archive entry, shown after the name.
+    pub uncompressed: Option<u64>,
+    /// Flag a file that takes up much less disk space than its length.
+    pub sparse: bool,
+    /// Kind of regenerable directory, shown after the name.
+    pub junk: Option<String>,
+    /// Mark a size that was extrapolated from a sample.
+    pub estimated: bool,
+    /// Scan errors below this entry, flagged with a warning sign since its size is
+    /// incomplete.
+    pub errors: usize,
+    /// Change in size since the previous scan, shown after the size.
+    pub delta: Option<i64>,
+    /// Full path and modification time, shown on the second line in comfortable density.
+    pub path: PathBuf,
+    pub modified: Option<SystemTime>,
 }
 
+/// Number of past scans shown in the trend column.
+pub const SPARKLINE_WIDTH: usize = 8;
+
 impl<'a> FileList<'a> {
     pub fn new(items: Vec<FileListItem>, total_size: u64) -> Self {
         Self {
             items,
             sort_mode: SortMode::Size,
             sort_order: SortOrder::Descending,
+            density: ListDensity::Compact,
             total_size,
             block: None,
         }
@@ -48,6 +84,11 @@ impl<'a> FileList<'a> {
         self
     }
 
+    pub fn density(mut self, density: ListDensity) -> Self {
+        self.density = density;
+        self
+    }
+
     pub fn block(mut self, block: Block<'a>) -> Self {
         self.block = block.into();
         self
@@ -85,6 +126,10 @@ impl StatefulWidget for FileList<'_> {
                 let arrow = if self.sort_order == SortOrder::Descending { "v" } else { "^" };
                 format!(" Modified {} ", arrow)
             }
+            SortMode::NewestContent => {
+                let arrow = if self.sort_order == SortOrder::Descending { "v" } else { "^" };
+                format!(" Newest {} ", arrow)
+            }
         };
 
         let header = Line::from(vec![
@@ -96,8 +141,13 @@ impl StatefulWidget for FileList<'_> {
         ]);
         buf.set_line(inner.x, inner.y, &header, inner.width);
 
-        // Available rows for items (reserve 1 for header, 1 for footer)
-        let list_height = (inner.height as usize).saturating_sub(2);
+        // Available items (reserve 1 row for header, 1 for footer); offsets and selection
+        // count items, not rows
+        let rows_per_item = match self.density {
+            ListDensity::Compact => 1,
+            ListDensity::Comfortable => 2,
+        };
+        let list_height = (inner.height as usize).saturating_sub(2) / rows_per_item;
         if list_height == 0 {
             return;
         }
@@ -110,33 +160,72 @@ impl StatefulWidget for FileList<'_> {
             state.offset = state.selected - list_height + 1;
         }
 
+        let show_trends = self.items.iter().any(|item| item.trend.is_some());
+        let show_deltas = self.items.iter().any(|item| item.delta.is_some());
+
         // Render items
         let end = (state.offset + list_height).min(self.items.len());
         for (i, item) in self.items[state.offset..end].iter().enumerate() {
-            let row_y = inner.y + 1 + i as u16;
+            let row_y = inner.y + 1 + (i * rows_per_item) as u16;
             let idx = state.offset + i;
             let is_selected = idx == state.selected;
 
             let icon = node_icon(&item.node_type);
-            let percentage = if self.total_size > 0 {
-                (item.size as f64 / self.total_size as f64) * 100.0
-            } else {
-                0.0
-            };
 
-            let display_name = if item.is_merged {
+            let mut display_name = if item.is_merged {
                 format!("Others ({} items)", item.merged_count)
+            } else if item.hardlinked {
+                format!("{} \u{21C6}", item.name)
             } else {
                 item.name.clone()
             };
+            if item.sparse {
+                display_name = format!("{} (sparse)", display_name);
+            }
+            if item.errors > 0 {
+                display_name = format!("{} \u{26A0}", display_name);
+            }
+            if let Some(label) = &item.junk {
+                display_name = format!("{} ({})", display_name, label);
+            }
+            if let Some(uncompressed) = item.uncompressed {
+                display_name = format!("{} ({} unpacked)", display_name, format_size(uncompressed));
+            }
+            if let Some(note) = &item.note {
+                display_name = format!("{}  \u{270E} {}", display_name, note);
+            }
+
+            // Keep the trend column aligned by giving sizes a fixed width beside it
+            let size = if item.estimated {
+                format!("\u{2248}{}", format_size(item.size))
+            } else {
+                format_size(item.size)
+            };
+            let size_str = if show_trends { format!("{:>8}", size) } else { size };
+            // An empty directory has no size to share out, so show a dash rather than 0%
+            let pct_str = if self.total_size > 0 {
+                let percentage = (item.size as f64 / self.total_size as f64) * 100.0;
+                format!("{:5.1}%", percentage)
+            } else {
+                format!("{:>6}", "\u{2014}")
+            };
 
-            let size_str = format_size(item.size);
-            let pct_str = format!("{:5.1}%", percentage);
+            let trend_str = match &item.trend {
+                Some(values) => format!("{} ", sparkline(values, SPARKLINE_WIDTH)),
+                None if show_trends => " ".repeat(SPARKLINE_WIDTH + 1),
+                None => String::new(),
+            };
+
+            let delta_str = match item.delta {
+                Some(delta) => format!(" {:>11}", human_readable_delta(delta)),
+                None if show_deltas => " ".repeat(12),
+                None => String::new(),
+            };
 
             // Calculate available width for name
-            // Layout: "  icon name     size  pct%"
-            let right_part = format!("  {}  {}", size_str, pct_str);
-            let right_width = right_part.len();
+            // Layout: "  icon name     trend size delta  pct%"
+            let right_part = format!("  {}{}{}  {}", trend_str, size_str, delta_str, pct_str);
+            let right_width = right_part.width();
             let name_max = (inner.width as usize).saturating_sub(right_width + 4); // 2 for leading space + icon + space
             let display_width = display_name.width();
             let truncated_name = if display_width > name_max {
@@ -159,21 +248,47 @@ impl StatefulWidget for FileList<'_> {
                     .bg(Color::DarkGray)
                     .fg(Color::White)
                     .add_modifier(Modifier::BOLD)
+            } else if item.marked {
+                Style::default().fg(Color::Yellow)
+            } else if item.gitignored {
+                Style::default().fg(Color::DarkGray)
             } else {
                 let fg = match item.node_type {
                     NodeType::Directory => Color::Blue,
                     NodeType::Symlink => Color::Cyan,
+                    NodeType::ArchiveEntry => Color::Magenta,
                     _ => Color::White,
                 };
                 Style::default().fg(fg)
             };
 
-            let name_part = format!(" {} {}", icon, truncated_name);
-            let padding = (inner.width as usize).saturating_sub(name_part.width() + right_part.len());
+            let mark = if item.marked { "*" } else { " " };
+            let name_part = format!("{}{} {}", mark, icon, truncated_name);
+            let padding = (inner.width as usize).saturating_sub(name_part.width() + right_width);
             let line_text = format!("{}{:pad$}{}", name_part, "", right_part, pad = padding);
 
             let line = Line::from(Span::styled(line_text, style));
             buf.set_line(inner.x, row_y, &line, inner.width);
+
+            if self.density == ListDensity::Comfortable {
+                let modified = item
+                    .modified
+                    .map(|t| {
+                        chrono::DateTime::<chrono::Local>::from(t)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_else(|| "-".repeat(16));
+                let detail = format!("     {}  {}", modified, item.path.display());
+                let detail_style = if is_selected {
+                    Style::default().bg(Color::DarkGray).fg(Color::Gray)
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                let padded = format!("{:width$}", detail, width = inner.width as usize);
+                let detail_line = Line::from(Span::styled(padded, detail_style));
+                buf.set_line(inner.x, row_y + 1, &detail_line, inner.width);
+            }
         }
 
         // Footer: Total info
@@ -207,11 +322,36 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
+/// Render the last `width` values as block characters scaled between their min and max,
+/// right-aligned in a `width`-column string.
+pub fn sparkline(values: &[u64], width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let recent = &values[values.len().saturating_sub(width)..];
+    let min = recent.iter().copied().min().unwrap_or(0);
+    let max = recent.iter().copied().max().unwrap_or(0);
+
+    let bars: String = recent
+        .iter()
+        .map(|&v| {
+            if max == min {
+                BARS[BARS.len() / 2]
+            } else {
+                let level = (v - min) as f64 / (max - min) as f64;
+                BARS[(level * (BARS.len() - 1) as f64).round() as usize]
+            }
+        })
+        .collect();
+    format!("{:>width$}", bars, width = width)
+}
+
 fn node_icon(node_type: &NodeType) -> &str {
     match node_type {
         NodeType::Directory => "\u{1F4C1}",
         NodeType::File => "\u{1F4C4}",
         NodeType::Symlink => "\u{1F517}",
         NodeType::Other => " ",
+        NodeType::ArchiveEntry => "\u{1F4E6}",
+        NodeType::Summary => "\u{1F4CA}",
     }
 }
@@ -28,10 +28,12 @@ impl Widget for HelpPanel {
             )),
             help_line("    j / Down    ", "Move down"),
             help_line("    k / Up      ", "Move up"),
-            help_line("    Enter / l   ", "Enter directory"),
+            help_line("    Enter / l   ", "Enter directory (either panel)"),
             help_line("    Backspace/h ", "Go back"),
             help_line("    gg          ", "Go to first item"),
             help_line("    G           ", "Go to last item"),
+            help_line("    B           ", "Reveal largest file (again: next largest)"),
+            help_line("    1-9         ", "Jump to numbered breadcrumb"),
             help_line("    Tab / Arrow ", "Switch focus panel"),
             Line::from(""),
             Line::from(Span::styled(
@@ -42,8 +44,23 @@ impl Widget for HelpPanel {
             )),
             help_line("    s           ", "Cycle sort mode"),
             help_line("    t           ", "Cycle merge threshold"),
-            help_line("    r           ", "Refresh scan"),
+            help_line("    f           ", "Filter: all / dirs only / files only"),
+            help_line("    .           ", "Show / hide hidden entries"),
+            help_line("    %           ", "Percent of directory / of visible"),
+            help_line("    z           ", "Zoom ring past dominant slices"),
+            help_line("    c           ", "Show counts in chart center"),
+            help_line("    L           ", "Show / hide chart legend"),
+            help_line("    b           ", "Legend at right / bottom"),
+            help_line("    r           ", "Refresh scan (unchanged directories reused)"),
+            help_line("    R           ", "Full rescan"),
+            help_line("    F           ", "Toggle following symlinks and rescan"),
+            help_line("    a           ", "Apparent size / disk usage"),
+            help_line("    D           ", "Compact / comfortable file list"),
             help_line("    x           ", "Export results"),
+            help_line("    w           ", "Write current view to file"),
+            help_line("    Space       ", "Mark / unmark (x exports only marked)"),
+            help_line("    u           ", "Clear marks"),
+            help_line("    n           ", "Add / edit note on selection"),
             help_line("    y           ", "Copy current path"),
             help_line("    o           ", "Open in file manager"),
             help_line("    e           ", "Show error list"),
@@ -40,10 +40,21 @@ pub struct RingChartItem {
     pub percentage: f64,
 }
 
+/// Where the ring chart draws its legend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegendPlacement {
+    Right,
+    Bottom,
+    Off,
+}
+
 pub struct RingChart {
     pub items: Vec<RingChartItem>,
     pub selected_index: usize,
     pub total_size: u64,
+    pub legend: LegendPlacement,
+    /// Text stacked in the ring's hole; empty means just the total size.
+    pub center_lines: Vec<String>,
 }
 
 impl RingChart {
@@ -52,15 +63,87 @@ impl RingChart {
             items,
             selected_index: 0,
             total_size,
+            legend: LegendPlacement::Right,
+            center_lines: Vec::new(),
         }
     }
 
+    pub fn center_lines(mut self, lines: Vec<String>) -> Self {
+        self.center_lines = lines;
+        self
+    }
+
     pub fn selected(mut self, index: usize) -> Self {
         self.selected_index = index;
         self
     }
+
+    pub fn legend(mut self, placement: LegendPlacement) -> Self {
+        self.legend = placement;
+        self
+    }
+
+    /// Draw one legend entry (swatch, truncated label, percentage) within `width` columns.
+    fn render_legend_entry(
+        &self,
+        buf: &mut Buffer,
+        x: u16,
+        y: u16,
+        width: u16,
+        index: usize,
+        total: f64,
+    ) {
+        let item = &self.items[index];
+        let is_sel = index == self.selected_index;
+        let color = COLORS[index % COLORS.len()];
+
+        let style = if is_sel {
+            Style::default()
+                .fg(HIGHLIGHT_COLORS[index % HIGHLIGHT_COLORS.len()])
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(color)
+        };
+
+        // Color swatch
+        buf.set_string(x, y, "\u{2588}\u{2588}", style);
+
+        // Label: truncated name + percentage
+        let pct_str = percentage_label(item, total);
+        let avail = width.saturating_sub(3) as usize;
+        let pct_len = pct_str.width();
+        let name_max = avail.saturating_sub(pct_len + 1);
+
+        let truncated = truncate_label(&item.label, name_max);
+        let padding = name_max.saturating_sub(truncated.width());
+
+        let label_style = if is_sel {
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        let pct_style = if is_sel {
+            Style::default()
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+
+        let label_text = format!(" {}{:pad$} ", truncated, "", pad = padding);
+        buf.set_string(x + 2, y, &label_text, label_style);
+        let pct_x = x + 2 + label_text.width() as u16;
+        if pct_x + pct_str.width() as u16 <= x + width {
+            buf.set_string(pct_x, y, &pct_str, pct_style);
+        }
+    }
 }
 
+/// Width of one legend entry: swatch, label and percentage.
+const LEGEND_WIDTH: u16 = 22;
+
 struct Sector {
     start_angle: f64,
     end_angle: f64,
@@ -88,16 +171,21 @@ impl Widget for RingChart {
             return;
         }
 
-        // Reserve right side for legend
-        let legend_width = 22u16;
-        let chart_width = if area.width > legend_width + 12 {
-            area.width - legend_width
+        // Reserve space for the legend: a column on the right, or rows along the bottom
+        // with entries wrapped across the full width
+        let right_legend = self.legend == LegendPlacement::Right && area.width > LEGEND_WIDTH + 12;
+        let bottom_columns = (area.width / LEGEND_WIDTH).max(1) as usize;
+        let bottom_rows = if self.legend == LegendPlacement::Bottom {
+            self.items.len().div_ceil(bottom_columns).min(area.height as usize / 3) as u16
         } else {
-            area.width
+            0
         };
-        let show_legend = area.width > legend_width + 12;
 
-        let chart_area = Rect::new(area.x, area.y, chart_width, area.height);
+        let chart_area = if right_legend {
+            Rect::new(area.x, area.y, area.width - LEGEND_WIDTH, area.height)
+        } else {
+            Rect::new(area.x, area.y, area.width, area.height - bottom_rows)
+        };
 
         // Calculate center and radii
         // Terminal chars are roughly 1:2 aspect ratio (width:height)
@@ -112,15 +200,12 @@ impl Widget for RingChart {
 
         // Build sectors
         let total: f64 = self.items.iter().map(|i| i.size as f64).sum();
-        if total == 0.0 {
-            return;
-        }
 
         let mut sectors = Vec::new();
         let mut angle = -std::f64::consts::FRAC_PI_2; // start from top
 
         for (i, item) in self.items.iter().enumerate() {
-            let fraction = item.size as f64 / total;
+            let fraction = slice_fraction(item, total, self.items.len());
             let sweep = fraction * std::f64::consts::TAU;
             let end = angle + sweep;
             sectors.push(Sector {
@@ -167,95 +252,110 @@ impl Widget for RingChart {
             }
         }
 
-        // Render center text (total size)
-        let center_text = format_size(self.total_size);
-        let text_len = center_text.len() as u16;
-        let text_x = chart_area.x + (chart_area.width.saturating_sub(text_len)) / 2;
-        let text_y = chart_area.y + chart_area.height / 2;
-        buf.set_string(
-            text_x,
-            text_y,
-            &center_text,
-            Style::default()
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD),
-        );
+        // Render center text: the requested stack if every line fits inside the hole,
+        // otherwise just the total size
+        let center_y = chart_area.y + chart_area.height / 2;
+        let first_offset = -((self.center_lines.len() as i32 - 1) / 2);
+        let stack_fits = !self.center_lines.is_empty()
+            && (0..self.center_lines.len() as i32)
+                .all(|i| hole_width(inner_r, first_offset + i) >= MIN_CENTER_WIDTH);
+
+        let center_lines = if stack_fits {
+            self.center_lines
+                .iter()
+                .enumerate()
+                .map(|(i, line)| {
+                    let max = hole_width(inner_r, first_offset + i as i32);
+                    (first_offset + i as i32, truncate_label(line, max))
+                })
+                .collect()
+        } else {
+            vec![(0, format_size(self.total_size))]
+        };
 
-        // Render legend on right side
-        if show_legend {
+        for (i, (offset, text)) in center_lines.iter().enumerate() {
+            let text_len = text.width() as u16;
+            let text_x = chart_area.x + (chart_area.width.saturating_sub(text_len)) / 2;
+            let style = if i == 0 {
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Gray)
+            };
+            buf.set_string(text_x, center_y.saturating_add_signed(*offset as i16), text, style);
+        }
+
+        if right_legend {
             let legend_x = chart_area.x + chart_area.width + 1;
+            let legend_w = (area.x + area.width).saturating_sub(legend_x);
             let max_legend_items = (area.height as usize).saturating_sub(1);
-            let legend_items = self.items.len().min(max_legend_items);
-
-            for (i, item) in self.items.iter().take(legend_items).enumerate() {
-                let y = area.y + i as u16;
-                if y >= area.y + area.height {
-                    break;
-                }
-
-                let color = COLORS[i % COLORS.len()];
-                let is_sel = i == self.selected_index;
-
-                let style = if is_sel {
-                    Style::default()
-                        .fg(HIGHLIGHT_COLORS[i % HIGHLIGHT_COLORS.len()])
-                        .add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(color)
-                };
-
-                // Color swatch
-                buf.set_string(legend_x, y, "\u{2588}\u{2588}", style);
-
-                // Label: truncated name + percentage
-                let pct_str = format!("{:4.1}%", item.percentage);
-                let avail = (area.x + area.width).saturating_sub(legend_x + 3) as usize;
-                let pct_len = pct_str.len();
-                let name_max = avail.saturating_sub(pct_len + 1);
-
-                let label_width = item.label.width();
-                let truncated = if label_width > name_max {
-                    let target = name_max.saturating_sub(1).max(1);
-                    let mut w = 0;
-                    let boundary = item.label.char_indices()
-                        .find(|&(_, c)| {
-                            w += unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
-                            w > target
-                        })
-                        .map(|(i, _)| i)
-                        .unwrap_or(item.label.len());
-                    format!("{}~", &item.label[..boundary])
-                } else {
-                    item.label.clone()
-                };
-                let padding = name_max.saturating_sub(truncated.width());
-
-                let label_style = if is_sel {
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(Color::Gray)
-                };
-                let pct_style = if is_sel {
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(Color::DarkGray)
-                };
-
-                let label_text = format!(" {}{:pad$} ", truncated, "", pad = padding);
-                buf.set_string(legend_x + 2, y, &label_text, label_style);
-                let pct_x = legend_x + 2 + label_text.width() as u16;
-                if pct_x + pct_str.len() as u16 <= area.x + area.width {
-                    buf.set_string(pct_x, y, &pct_str, pct_style);
-                }
+            for i in 0..self.items.len().min(max_legend_items) {
+                self.render_legend_entry(buf, legend_x, area.y + i as u16, legend_w, i, total);
+            }
+        } else if bottom_rows > 0 {
+            let legend_y = chart_area.y + chart_area.height;
+            let cell_w = area.width / bottom_columns as u16;
+            let max_legend_items = bottom_rows as usize * bottom_columns;
+            for i in 0..self.items.len().min(max_legend_items) {
+                let x = area.x + (i % bottom_columns) as u16 * cell_w;
+                let y = legend_y + (i / bottom_columns) as u16;
+                self.render_legend_entry(buf, x, y, cell_w.saturating_sub(1), i, total);
             }
         }
     }
 }
 
+/// Share of the ring given to `item`. When every item is empty the ring is split
+/// equally so the entries stay visible.
+fn slice_fraction(item: &RingChartItem, total: f64, count: usize) -> f64 {
+    if total > 0.0 {
+        item.size as f64 / total
+    } else {
+        1.0 / count as f64
+    }
+}
+
+/// Percentage label for `item`; a dash when there is no size to take a share of.
+fn percentage_label(item: &RingChartItem, total: f64) -> String {
+    if total > 0.0 {
+        format!("{:4.1}%", item.percentage)
+    } else {
+        format!("{:>5}", "\u{2014}")
+    }
+}
+
+/// Center lines narrower than this are not worth drawing; fall back to size only.
+const MIN_CENTER_WIDTH: usize = 5;
+
+/// Usable columns inside the hole on the text row `row_offset` rows from the center.
+fn hole_width(inner_r: f64, row_offset: i32) -> usize {
+    // Measure at the row edge farthest from the center (2 pixels per row)
+    let dy = (row_offset as f64 * 2.0).abs() + 1.0;
+    if dy >= inner_r {
+        return 0;
+    }
+    let half = (inner_r * inner_r - dy * dy).sqrt();
+    // Keep a one-column margin from the ring on each side
+    ((half * 2.0) as usize).saturating_sub(2)
+}
+
+/// Truncate to `max` display columns, marking the cut with `~`.
+fn truncate_label(label: &str, max: usize) -> String {
+    if label.width() <= max {
+        return label.to_string();
+    }
+    let target = max.saturating_sub(1).max(1);
+    let mut w = 0;
+    let boundary = label
+        .char_indices()
+        .find(|&(_, c)| {
+            w += unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
+            w > target
+        })
+        .map(|(i, _)| i)
+        .unwrap_or(label.len());
+    format!("{}~", &label[..boundary])
+}
+
 fn pixel_color(
     px: f64,
     py: f64,
@@ -313,9 +413,6 @@ fn pixel_color(
 
 fn render_bar_chart(chart: &RingChart, area: Rect, buf: &mut Buffer) {
     let total: f64 = chart.items.iter().map(|i| i.size as f64).sum();
-    if total == 0.0 {
-        return;
-    }
 
     // Title
     let title = format_size(chart.total_size);
@@ -339,7 +436,7 @@ fn render_bar_chart(chart: &RingChart, area: Rect, buf: &mut Buffer) {
             break;
         }
 
-        let fraction = item.size as f64 / total;
+        let fraction = slice_fraction(item, total, chart.items.len());
         let filled = (fraction * bar_width as f64).round() as usize;
         let color_idx = i % COLORS.len();
         let is_sel = i == chart.selected_index;
@@ -361,9 +458,9 @@ fn render_bar_chart(chart: &RingChart, area: Rect, buf: &mut Buffer) {
         buf.set_string(area.x + 1, y, &bar, style);
 
         // Label after bar
-        let label = format!(" {:4.1}%", item.percentage);
+        let label = format!(" {}", percentage_label(item, total));
         let label_x = area.x + 1 + filled.max(1) as u16;
-        if label_x + label.len() as u16 <= area.x + area.width {
+        if label_x + label.width() as u16 <= area.x + area.width {
             buf.set_string(
                 label_x,
                 y,
@@ -11,6 +11,8 @@ pub struct StatusBar {
     pub files_scanned: usize,
     pub speed: f64,
     pub message: Option<String>,
+    /// Short caveats about the result, e.g. a copy-on-write filesystem or estimated sizes.
+    pub notices: Vec<String>,
 }
 
 impl Widget for StatusBar {
@@ -40,6 +42,14 @@ impl Widget for StatusBar {
             spans.push(Span::styled(" | ", Style::default().fg(Color::DarkGray)));
         }
 
+        for notice in &self.notices {
+            spans.push(Span::styled(
+                format!(" {} ", notice),
+                Style::default().fg(Color::Yellow),
+            ));
+            spans.push(Span::styled(" | ", Style::default().fg(Color::DarkGray)));
+        }
+
         // Middle: file count
         spans.push(Span::styled(
             format!(" Scanned: {} files", format_number(self.files_scanned)),
@@ -1,12 +1,17 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
 
-use disklens::models::node::{human_readable_size, Node};
-use disklens::models::scan_result::ScanResult;
+use disklens::models::node::{human_readable_size, Node, NodeType, SizeMode};
+use disklens::models::scan_result::{ScanError, ScanErrorType, ScanResult};
 use disklens::models::index::{PathIndex, SizeIndex};
 use disklens::core::analyzer::{Analyzer, MergedItem};
-use disklens::config::settings::Settings;
+use disklens::config::settings::{GitignoreMode, Settings};
 use disklens::export::json::export_json;
+use disklens::ui::app_state::{AppState, NodeTypeFilter};
+use disklens::ui::widgets::ring_chart::{LegendPlacement, RingChart, RingChartItem};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::widgets::Widget;
 
 // ---------------------------------------------------------------------------
 // Helpers
@@ -25,6 +30,29 @@ fn cleanup(dir: &PathBuf) {
     let _ = std::fs::remove_dir_all(dir);
 }
 
+/// Settings for scanner tests: low concurrency and an isolated cache dir.
+fn test_settings() -> Settings {
+    Settings {
+        max_concurrent_io: 4,
+        cache_dir: std::env::temp_dir().join("disklens_cache_test"),
+        ..Settings::default()
+    }
+}
+
+/// Scan `dir` with the given settings, panicking on failure.
+async fn scan_with(dir: &Path, settings: Settings) -> ScanResult {
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(settings, event_tx);
+    scanner.scan(dir.to_path_buf()).await.expect("scan should succeed")
+}
+
+/// Collect the names of a node's direct children, sorted.
+fn child_names(node: &Node) -> Vec<String> {
+    let mut names: Vec<String> = node.children.iter().map(|c| c.name.clone()).collect();
+    names.sort();
+    names
+}
+
 /// Build a simple Node tree for testing (no filesystem needed).
 fn sample_tree() -> Node {
     let file_a = Node::from_file(
@@ -71,6 +99,13 @@ fn make_scan_result(root: Node) -> ScanResult {
         timestamp: SystemTime::now(),
         scan_path: root.path.clone(),
         root,
+        filesystem: None,
+        estimate: None,
+        skipped: Vec::new(),
+        size_mode: SizeMode::Apparent,
+        cancelled: false,
+        unscanned: Vec::new(),
+        tree_settings: Default::default(),
     }
 }
 
@@ -96,6 +131,7 @@ async fn test_scan_basic() {
         cache_dir: std::env::temp_dir().join("disklens_cache_test"),
         cache_max_size_mb: 64,
         cache_max_age_days: 1,
+        ..Settings::default()
     };
 
     let (event_tx, _rx) = disklens::core::events::create_event_channel();
@@ -129,6 +165,7 @@ async fn test_scan_empty_dir() {
         cache_dir: std::env::temp_dir().join("disklens_cache_test"),
         cache_max_size_mb: 64,
         cache_max_age_days: 1,
+        ..Settings::default()
     };
 
     let (event_tx, _rx) = disklens::core::events::create_event_channel();
@@ -198,6 +235,7 @@ fn test_sort_modes() {
     assert_eq!(names, vec!["b.txt", "a.txt", "sub"]);
 
     // Name-based sort (manual)
+    #[allow(clippy::unnecessary_sort_by)]
     root.children.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
     let names: Vec<&str> = root.children.iter().map(|c| c.name.as_str()).collect();
     assert_eq!(names, vec!["a.txt", "b.txt", "sub"]);
@@ -327,3 +365,4973 @@ fn test_settings_default() {
     assert_eq!(s.cache_max_size_mb, 512);
     assert_eq!(s.cache_max_age_days, 7);
 }
+
+// ---------------------------------------------------------------------------
+// 11. test_scan_disklensignore – nested ignore files stack
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_scan_disklensignore() {
+    let dir = make_test_dir("disklensignore");
+
+    std::fs::write(dir.join(".disklensignore"), "# build output\n*.log\n!keep.log\n").unwrap();
+    std::fs::write(dir.join("app.log"), "log").unwrap();
+    std::fs::write(dir.join("keep.log"), "keep").unwrap();
+    std::fs::create_dir_all(dir.join("project/cache/deep")).unwrap();
+    std::fs::create_dir_all(dir.join("project/src")).unwrap();
+    std::fs::write(dir.join("project/.disklensignore"), "cache/\n").unwrap();
+    std::fs::write(dir.join("project/cache/deep/blob.bin"), vec![0u8; 4096]).unwrap();
+    std::fs::write(dir.join("project/src/main.rs"), "fn main() {}").unwrap();
+    std::fs::write(dir.join("project/src/debug.log"), "inherited").unwrap();
+
+    let result = scan_with(&dir, test_settings()).await;
+
+    assert_eq!(child_names(&result.root), vec![".disklensignore", "keep.log", "project"]);
+
+    let project = result.root.children.iter().find(|c| c.name == "project").unwrap();
+    assert_eq!(child_names(project), vec![".disklensignore", "src"]);
+
+    // The root's `*.log` rule is inherited by nested directories
+    let src = project.children.iter().find(|c| c.name == "src").unwrap();
+    assert_eq!(child_names(src), vec!["main.rs"]);
+
+    assert!(result.total_size < 4096, "ignored subtree must not count toward totals");
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 12. test_scan_io_profile – folded stacks keyed by directory
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_scan_io_profile() {
+    let dir = make_test_dir("io_profile");
+    std::fs::create_dir_all(dir.join("usr/lib")).unwrap();
+    std::fs::write(dir.join("usr/lib/libc.so"), "elf").unwrap();
+
+    let out_path = std::env::temp_dir().join("disklens_test_io_profile.folded");
+    let settings = Settings {
+        profile_output: Some(out_path.clone()),
+        ..test_settings()
+    };
+
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(settings, event_tx);
+    let result = scanner.scan(dir.clone()).await.expect("scan should succeed");
+
+    let profile = scanner.io_profile().expect("profiling enabled");
+    profile.write_folded(&result.scan_path, &out_path).expect("write profile");
+
+    let folded = std::fs::read_to_string(&out_path).unwrap();
+    let stacks: Vec<&str> = folded
+        .lines()
+        .map(|line| {
+            let (stack, micros) = line.rsplit_once(' ').expect("stack and count");
+            micros.parse::<u128>().expect("count is an integer");
+            stack
+        })
+        .collect();
+
+    let root = "disklens_test_io_profile";
+    assert!(stacks.iter().all(|s| s.starts_with(root)));
+    for stack in &stacks {
+        assert!(
+            [root.to_string(), format!("{root};usr"), format!("{root};usr;lib")]
+                .contains(&stack.to_string()),
+            "unexpected stack {stack}"
+        );
+    }
+
+    // Profiling is off unless an output path is configured
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(test_settings(), event_tx);
+    assert!(scanner.io_profile().is_none());
+
+    let _ = std::fs::remove_file(&out_path);
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 13. test_node_type_filter – dirs/files view filter and percentage basis
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_node_type_filter() {
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(sample_tree()));
+
+    assert_eq!(state.visible_children_count(), 3);
+    assert_eq!(state.percentage_base(), 3500);
+
+    state.cycle_node_type_filter();
+    assert_eq!(state.node_type_filter, NodeTypeFilter::DirsOnly);
+    let names: Vec<&str> = state.sorted_children().iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["sub"]);
+
+    state.cycle_node_type_filter();
+    assert_eq!(state.node_type_filter, NodeTypeFilter::FilesOnly);
+    let names: Vec<&str> = state.sorted_children().iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["b.txt", "a.txt"]);
+
+    // Percentages default to the whole directory, optionally to the visible subset
+    assert_eq!(state.percentage_base(), 3500);
+    state.toggle_percent_basis();
+    assert_eq!(state.percentage_base(), 3000);
+
+    state.cycle_node_type_filter();
+    assert_eq!(state.node_type_filter, NodeTypeFilter::All);
+    assert_eq!(state.percentage_base(), 3500);
+}
+
+// ---------------------------------------------------------------------------
+// 14. test_scan_root_file / test_scan_missing_root
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_scan_root_file() {
+    let dir = make_test_dir("scan_root_file");
+    let file = dir.join("single.bin");
+    std::fs::write(&file, vec![7u8; 1234]).unwrap();
+
+    let result = scan_with(&file, test_settings()).await;
+
+    assert_eq!(result.root.node_type, NodeType::File);
+    assert_eq!(result.root.name, "single.bin");
+    assert_eq!(result.total_size, 1234);
+    assert_eq!(result.total_files, 1);
+    assert_eq!(result.total_dirs, 0);
+    assert!(result.errors.is_empty());
+
+    cleanup(&dir);
+}
+
+#[tokio::test]
+async fn test_scan_missing_root() {
+    let missing = std::env::temp_dir().join("disklens_test_does_not_exist");
+    let _ = std::fs::remove_dir_all(&missing);
+
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(test_settings(), event_tx);
+    let err = scanner.scan(missing.clone()).await.expect_err("missing root must fail");
+
+    assert!(err.to_string().contains("path does not exist"), "got: {err}");
+}
+
+// ---------------------------------------------------------------------------
+// 15. test_ring_chart_legend_placement
+// ---------------------------------------------------------------------------
+
+/// Render a two-slice ring chart and return each buffer row as a string.
+fn render_ring_rows(chart: RingChart, area: Rect) -> Vec<String> {
+    let mut buf = Buffer::empty(area);
+    chart.render(area, &mut buf);
+    (0..area.height)
+        .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol().to_string()).collect())
+        .collect()
+}
+
+fn two_slice_chart() -> RingChart {
+    let items = vec![
+        RingChartItem { label: "alpha".into(), size: 600, percentage: 60.0 },
+        RingChartItem { label: "beta".into(), size: 400, percentage: 40.0 },
+    ];
+    RingChart::new(items, 1000)
+}
+
+#[test]
+fn test_ring_chart_legend_placement() {
+    let area = Rect::new(0, 0, 60, 24);
+
+    let right = render_ring_rows(two_slice_chart().legend(LegendPlacement::Right), area);
+    assert!(right[0].contains("alpha") && right[1].contains("beta"));
+
+    // Bottom legend wraps both entries onto the last rows, below the ring
+    let bottom = render_ring_rows(two_slice_chart().legend(LegendPlacement::Bottom), area);
+    let last = &bottom[bottom.len() - 1];
+    assert!(last.contains("alpha") && last.contains("beta"), "got: {last}");
+    assert!(!bottom[0].contains("alpha"));
+
+    let off = render_ring_rows(two_slice_chart().legend(LegendPlacement::Off), area);
+    assert!(off.iter().all(|row| !row.contains("alpha")));
+}
+
+#[test]
+fn test_ring_chart_center_lines() {
+    let lines = vec!["1000 B".to_string(), "3 files".to_string(), "1 dirs".to_string()];
+
+    // A large hole fits the whole stack
+    let big = render_ring_rows(
+        two_slice_chart().legend(LegendPlacement::Off).center_lines(lines.clone()),
+        Rect::new(0, 0, 60, 24),
+    );
+    assert!(big.iter().any(|row| row.contains("3 files")));
+    assert!(big.iter().any(|row| row.contains("1 dirs")));
+
+    // A small hole falls back to the total size alone
+    let small = render_ring_rows(
+        two_slice_chart().legend(LegendPlacement::Off).center_lines(lines),
+        Rect::new(0, 0, 22, 10),
+    );
+    assert!(small.iter().any(|row| row.contains("1000 B")));
+    assert!(small.iter().all(|row| !row.contains("files")));
+}
+
+// ---------------------------------------------------------------------------
+// 16. test_error_list_filter – incremental, case-insensitive error search
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_error_list_filter() {
+    let mut result = make_scan_result(sample_tree());
+    result.errors = vec![
+        ScanError {
+            path: PathBuf::from("/test/private"),
+            error_type: ScanErrorType::PermissionDenied,
+            message: "Permission denied (os error 13)".into(),
+        },
+        ScanError {
+            path: PathBuf::from("/test/Cache/tmp"),
+            error_type: ScanErrorType::IoError,
+            message: "Input/output error".into(),
+        },
+    ];
+
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(result);
+    state.toggle_error_list();
+    assert_eq!(state.filtered_errors().len(), 2);
+
+    for c in "CACHE".chars() {
+        state.push_error_query(c);
+    }
+    let matched = state.filtered_errors();
+    assert_eq!(matched.len(), 1);
+    assert_eq!(matched[0].path, PathBuf::from("/test/Cache/tmp"));
+
+    // Messages are searched too
+    state.error_query = "denied".into();
+    assert_eq!(state.filtered_errors()[0].error_type, ScanErrorType::PermissionDenied);
+
+    state.pop_error_query();
+    assert_eq!(state.error_query, "denie");
+
+    // Reopening the overlay starts with an empty filter
+    state.toggle_error_list();
+    state.toggle_error_list();
+    assert!(state.error_query.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 17. test_scan_event_callback – events delivered without a receiver loop
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_scan_event_callback() {
+    use disklens::core::events::Event;
+    use std::sync::{Arc, Mutex};
+
+    let dir = make_test_dir("event_callback");
+    std::fs::write(dir.join("a.txt"), "a").unwrap();
+
+    let seen: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+
+    let (event_tx, rx) = disklens::core::events::create_event_channel();
+    drop(rx);
+    let scanner = disklens::core::scanner::Scanner::new(test_settings(), event_tx).on_event(
+        move |event| {
+            let name = match event {
+                Event::ScanStarted { .. } => "started",
+                Event::ScanCompleted { total_files, .. } => {
+                    assert_eq!(*total_files, 1);
+                    "completed"
+                }
+                _ => return,
+            };
+            sink.lock().unwrap().push(name.to_string());
+        },
+    );
+
+    scanner.scan(dir.clone()).await.expect("scan should succeed");
+
+    assert_eq!(*seen.lock().unwrap(), vec!["started", "completed"]);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 18. test_scan_dir_overhead – directory metadata size counted only when enabled
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_scan_dir_overhead() {
+    let dir = make_test_dir("dir_overhead");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("sub/a.txt"), "hello").unwrap();
+
+    let plain = scan_with(&dir, test_settings()).await;
+    assert_eq!(plain.root.size, 5);
+
+    let settings = Settings {
+        count_dir_overhead: true,
+        ..test_settings()
+    };
+    let counted = scan_with(&dir, settings).await;
+
+    let dir_sizes =
+        std::fs::metadata(&dir).unwrap().len() + std::fs::metadata(dir.join("sub")).unwrap().len();
+    assert_eq!(counted.root.size, 5 + dir_sizes);
+
+    let sub = counted.root.children.iter().find(|c| c.name == "sub").unwrap();
+    assert_eq!(sub.size, 5 + std::fs::metadata(dir.join("sub")).unwrap().len());
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 19. test_ring_zoom – dominant children dropped from the ring when zoomed
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_ring_zoom() {
+    let files: Vec<Node> = [("huge", 9000), ("big", 600), ("a", 200), ("b", 100), ("c", 100)]
+        .iter()
+        .map(|(name, size)| {
+            let path = PathBuf::from(format!("/test/{}", name));
+            Node::from_file(path, name.to_string(), *size, None, None)
+        })
+        .collect();
+    let root = Node::from_directory(PathBuf::from("/test"), "test".into(), files);
+
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(root));
+    state.selected_index = 2; // "a"
+
+    assert_eq!(state.ring_zoom_cap(), None);
+    assert_eq!(state.ring_children().len(), 5);
+
+    state.toggle_ring_zoom();
+    assert_eq!(state.ring_zoom_cap(), Some(200));
+    let names: Vec<&str> = state.ring_children().iter().map(|(_, c)| c.name.as_str()).collect();
+    assert_eq!(names, vec!["a", "b", "c"]);
+    assert_eq!(state.ring_children()[0].0, 2);
+
+    // Selecting a dominant child leaves nothing to exclude
+    state.selected_index = 0;
+    assert_eq!(state.ring_zoom_cap(), None);
+
+    state.toggle_ring_zoom();
+    state.selected_index = 2;
+    assert_eq!(state.ring_children().len(), 5);
+}
+
+// ---------------------------------------------------------------------------
+// 20. test_filter_modified – mtime range keeps ancestors and recomputes sizes
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_filter_modified() {
+    use disklens::config::time_spec::parse_time_spec;
+
+    let day = Duration::from_secs(24 * 60 * 60);
+    let base = SystemTime::UNIX_EPOCH + 1000 * day;
+    let file = |path: &str, size: u64, modified: Option<SystemTime>| {
+        let name = path.rsplit('/').next().unwrap().to_string();
+        Node::from_file(PathBuf::from(path), name, size, modified, None)
+    };
+
+    let sub = Node::from_directory(
+        PathBuf::from("/test/sub"),
+        "sub".into(),
+        vec![
+            file("/test/sub/new.txt", 100, Some(base + 5 * day)),
+            file("/test/sub/old.txt", 200, Some(base - 5 * day)),
+        ],
+    );
+    let stale = Node::from_directory(
+        PathBuf::from("/test/stale"),
+        "stale".into(),
+        vec![file("/test/stale/old.txt", 400, Some(base - 9 * day))],
+    );
+    let root = Node::from_directory(
+        PathBuf::from("/test"),
+        "test".into(),
+        vec![sub, stale, file("/test/unknown.txt", 800, None)],
+    );
+    let result = make_scan_result(root);
+
+    let filtered = result.filter_modified(Some(base), Some(base + 10 * day));
+    assert_eq!(child_names(&filtered.root), vec!["sub"]);
+    assert_eq!(child_names(&filtered.root.children[0]), vec!["new.txt"]);
+    assert_eq!(filtered.total_size, 100);
+    assert_eq!(filtered.total_files, 1);
+    assert_eq!(filtered.total_dirs, 2);
+
+    // Nothing matches: the root survives empty
+    let none = result.filter_files(|_| false);
+    assert!(none.root.children.is_empty());
+    assert_eq!(none.total_size, 0);
+
+    // Time specs: ISO dates, RFC 3339, and relative ages
+    let now = SystemTime::UNIX_EPOCH + 2000 * day;
+    assert_eq!(parse_time_spec("7d", now).unwrap(), now - 7 * day);
+    assert_eq!(parse_time_spec("12h", now).unwrap(), now - day / 2);
+    assert_eq!(
+        parse_time_spec("1970-01-02T00:00:00Z", now).unwrap(),
+        SystemTime::UNIX_EPOCH + day
+    );
+    assert!(parse_time_spec("2024-03-01", now).is_ok());
+    assert!(parse_time_spec("last tuesday", now).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// 21. test_size_history – bounded per-root history and sparkline rendering
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_size_history() {
+    use disklens::core::cache::{Cache, HISTORY_LIMIT};
+    use disklens::ui::widgets::file_list::sparkline;
+
+    let cache_dir = make_test_dir("size_history");
+    let cache = Cache::new(cache_dir.clone());
+    let sub_path = PathBuf::from("/test/sub");
+
+    let mut result = make_scan_result(sample_tree());
+    for i in 0..HISTORY_LIMIT + 2 {
+        result.timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(i as u64 + 1);
+        cache.record_history(&result).await.unwrap();
+    }
+    // Recording the same scan twice doesn't add a snapshot
+    let history = cache.record_history(&result).await.unwrap();
+    assert_eq!(history.snapshots.len(), HISTORY_LIMIT);
+    assert_eq!(
+        history.snapshots[0].timestamp,
+        SystemTime::UNIX_EPOCH + Duration::from_secs(3)
+    );
+
+    let loaded = cache.load_history(&result.scan_path).await;
+    assert_eq!(loaded.snapshots.len(), HISTORY_LIMIT);
+    let sub_size = result.root.children.iter().find(|c| c.path == sub_path).unwrap().size;
+    assert!(loaded.trend(&sub_path).iter().all(|&s| s == sub_size));
+
+    assert_eq!(sparkline(&[0, 50, 100], 4), " ▁▅█");
+    assert_eq!(sparkline(&[7, 7], 2), "▅▅");
+    assert_eq!(sparkline(&[1, 2, 3, 4, 5, 6, 7, 8, 9], 3), "▁▅█");
+
+    cleanup(&cache_dir);
+}
+
+// ---------------------------------------------------------------------------
+// 22. test_breadcrumb_jump – digits and clicks jump to visited ancestors
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_breadcrumb_jump() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::ui::input::handle_key_event;
+    use disklens::ui::renderer::breadcrumb_target;
+
+    let mut state = AppState::new(PathBuf::from("/test/sub/deep"));
+    state.path_stack = vec![PathBuf::from("/test"), PathBuf::from("/test/sub")];
+    state.selected_index = 3;
+
+    // " DiskLens  | / > 1:test > 2:sub > deep", drawn one cell inside the border
+    assert_eq!(breadcrumb_target(&state, 21, 1), Some(0));
+    assert_eq!(breadcrumb_target(&state, 30, 1), Some(1));
+    assert_eq!(breadcrumb_target(&state, 36, 1), None);
+    assert_eq!(breadcrumb_target(&state, 21, 0), None);
+
+    state.jump_to_ancestor(1);
+    assert_eq!(state.current_path, PathBuf::from("/test/sub"));
+    assert_eq!(state.path_stack, vec![PathBuf::from("/test")]);
+    assert_eq!(state.selected_index, 0);
+
+    // Out-of-range digits are ignored
+    state.view_mode = disklens::ui::app_state::ViewMode::Normal;
+    handle_key_event(KeyEvent::from(KeyCode::Char('5')), &mut state);
+    assert_eq!(state.current_path, PathBuf::from("/test/sub"));
+
+    handle_key_event(KeyEvent::from(KeyCode::Char('1')), &mut state);
+    assert_eq!(state.current_path, PathBuf::from("/test"));
+    assert!(state.path_stack.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 23. test_cow_filesystem_note – CoW detection surfaced in the result
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_cow_filesystem_note() {
+    use disklens::config::settings::{detect_filesystem_type, FilesystemType};
+
+    let dir = make_test_dir("cow_fs");
+    std::fs::write(dir.join("a.txt"), "a").unwrap();
+
+    let result = scan_with(&dir, test_settings()).await;
+    assert_eq!(result.filesystem, detect_filesystem_type(&dir));
+    assert_eq!(result.clone_note().is_some(), result.filesystem.is_some());
+
+    let mut result = make_scan_result(sample_tree());
+    assert!(result.clone_note().is_none());
+    result.filesystem = Some(FilesystemType::Btrfs);
+    assert!(result.clone_note().unwrap().starts_with("Btrfs is copy-on-write"));
+
+    let md_path = dir.join("report.md");
+    disklens::export::markdown::export_markdown(&result, &md_path).unwrap();
+    let md = std::fs::read_to_string(&md_path).unwrap();
+    assert!(md.contains("**Note:** Btrfs is copy-on-write"));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 24. test_export_view_markdown – snapshot of the visible list honors filters
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_export_view_markdown() {
+    use disklens::export::markdown::export_view_markdown;
+
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(sample_tree()));
+    state.cycle_node_type_filter();
+    state.cycle_node_type_filter(); // files only
+
+    let dir = make_test_dir("view_markdown");
+    let out = dir.join("view.md");
+    export_view_markdown(
+        &state.current_path,
+        &state.view_description(),
+        &state.sorted_children(),
+        state.percentage_base(),
+        &out,
+    )
+    .unwrap();
+
+    let md = std::fs::read_to_string(&out).unwrap();
+    assert!(md.contains("sorted by size (descending), files only"));
+    let rows: Vec<&str> = md.lines().filter(|l| l.starts_with("| ") && !l.starts_with("| Name")).collect();
+    assert_eq!(rows.len(), 2);
+    assert!(rows[0].starts_with("| b.txt |"));
+    assert!(rows[1].starts_with("| a.txt |"));
+    assert!(!md.contains("sub/"));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 25. test_resolve_scan_path – friendly error for missing paths, relative paths resolved
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_resolve_scan_path() {
+    use disklens::config::path::resolve_scan_path;
+
+    let missing = std::env::temp_dir().join("disklens_test_resolve_missing/nope");
+    let err = resolve_scan_path(&missing).unwrap_err();
+    assert_eq!(err.to_string(), format!("path does not exist: {}", missing.display()));
+
+    let resolved = resolve_scan_path(Path::new("tests")).unwrap();
+    assert!(resolved.is_absolute());
+    assert_eq!(resolved, std::fs::canonicalize("tests").unwrap());
+
+    // A dangling symlink can't be canonicalized but still resolves to an absolute path
+    #[cfg(unix)]
+    {
+        let dir = make_test_dir("resolve_dangling");
+        let link = dir.join("dangling");
+        std::os::unix::fs::symlink(dir.join("gone"), &link).unwrap();
+        let resolved = resolve_scan_path(&link).unwrap();
+        assert!(resolved.is_absolute());
+        assert!(resolved.ends_with("dangling"));
+        cleanup(&dir);
+    }
+}
+
+// ---------------------------------------------------------------------------
+// 26. test_scan_top_n – streaming totals match a full scan
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_scan_top_n() {
+    let dir = make_test_dir("top_n");
+    std::fs::create_dir_all(dir.join("a/b")).unwrap();
+    std::fs::write(dir.join("small.txt"), vec![0u8; 10]).unwrap();
+    std::fs::write(dir.join("a/medium.txt"), vec![0u8; 200]).unwrap();
+    std::fs::write(dir.join("a/b/large.txt"), vec![0u8; 3000]).unwrap();
+    std::fs::write(dir.join("a/b/tiny.txt"), vec![0u8; 1]).unwrap();
+
+    let full = scan_with(&dir, test_settings()).await;
+
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(test_settings(), event_tx);
+    let top = scanner.scan_top_n(dir.clone(), 2).await.unwrap();
+
+    assert_eq!(top.total_size, full.total_size);
+    assert_eq!(top.total_files, full.total_files);
+    assert_eq!(top.total_dirs, full.total_dirs);
+    let sizes: Vec<u64> = top.files.iter().map(|f| f.size).collect();
+    assert_eq!(sizes, vec![3000, 200]);
+    assert_eq!(top.files[0].path, dir.join("a/b/large.txt"));
+
+    // Past a depth limit files still count, but aren't ranked
+    let settings = Settings {
+        max_depth: Some(1),
+        ..test_settings()
+    };
+    let limited = scan_with(&dir, settings.clone()).await;
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(settings, event_tx);
+    let top = scanner.scan_top_n(dir.clone(), 2).await.unwrap();
+    assert_eq!(top.total_size, full.total_size);
+    assert_eq!(top.total_files, full.total_files);
+    assert_eq!(top.total_dirs, limited.total_dirs);
+    let sizes: Vec<u64> = top.files.iter().map(|f| f.size).collect();
+    assert_eq!(sizes, vec![10]);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 27. test_scan_hardlink_nlink – link count recorded and exported
+// ---------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_scan_hardlink_nlink() {
+    let dir = make_test_dir("hardlink_nlink");
+    std::fs::write(dir.join("original.txt"), "shared").unwrap();
+    std::fs::hard_link(dir.join("original.txt"), dir.join("link.txt")).unwrap();
+    std::fs::write(dir.join("single.txt"), "alone").unwrap();
+
+    let result = scan_with(&dir, test_settings()).await;
+    let find = |name: &str| result.root.children.iter().find(|c| c.name == name).unwrap();
+
+    assert_eq!(find("original.txt").nlink, Some(2));
+    assert_eq!(find("link.txt").nlink, Some(2));
+    assert_eq!(find("original.txt").inode, find("link.txt").inode);
+    assert!(find("link.txt").is_hardlinked());
+    assert_eq!(find("single.txt").nlink, Some(1));
+    assert!(!find("single.txt").is_hardlinked());
+
+    let json = serde_json::to_string(find("link.txt")).unwrap();
+    assert!(json.contains("\"nlink\":2"));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 28. test_responsive_layout – panels stack vertically on narrow terminals
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_responsive_layout() {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(sample_tree()));
+
+    // (row, column) of the first occurrence of `needle` on screen
+    let locate = |width: u16, needle: &str| -> (usize, usize) {
+        let mut terminal = Terminal::new(TestBackend::new(width, 40)).unwrap();
+        terminal.draw(|frame| disklens::ui::renderer::render(frame, &state)).unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        (0..buffer.area.height)
+            .find_map(|y| {
+                let row: String = (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect();
+                row.find(needle).map(|col| (y as usize, col))
+            })
+            .unwrap_or_else(|| panic!("{:?} not rendered at width {}", needle, width))
+    };
+
+    let (ring_row, ring_col) = locate(120, "Ring Chart");
+    let (files_row, files_col) = locate(120, "Files (");
+    assert_eq!(ring_row, files_row);
+    assert!(ring_col < files_col);
+
+    let (ring_row, _) = locate(50, "Ring Chart");
+    let (files_row, _) = locate(50, "Files (");
+    assert!(ring_row < files_row);
+}
+
+// ---------------------------------------------------------------------------
+// 29. test_scan_sampled – large directories extrapolated, small ones exact
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_scan_sampled() {
+    use disklens::core::scanner::SAMPLE_MIN_FILES;
+
+    let dir = make_test_dir("sampled");
+    std::fs::create_dir_all(dir.join("big")).unwrap();
+    std::fs::create_dir_all(dir.join("small")).unwrap();
+    for i in 0..SAMPLE_MIN_FILES * 3 {
+        std::fs::write(dir.join(format!("big/{}.bin", i)), vec![0u8; 100]).unwrap();
+    }
+    for i in 0..10 {
+        std::fs::write(dir.join(format!("small/{}.bin", i)), vec![0u8; 7]).unwrap();
+    }
+
+    let exact = scan_with(&dir, test_settings()).await;
+    assert!(exact.estimate.is_none());
+
+    let settings = Settings {
+        sample_ratio: Some(0.2),
+        ..test_settings()
+    };
+    let sampled = scan_with(&dir, settings).await;
+    let estimate = sampled.estimate.expect("sampled scans are flagged");
+    assert!(estimate.estimated_files > 0);
+    assert!(sampled.root.children.len() == 2);
+
+    // Counts stay exact; identical file sizes extrapolate exactly with no margin
+    assert_eq!(sampled.total_files, exact.total_files);
+    assert_eq!(sampled.total_size, exact.total_size);
+    assert_eq!(estimate.margin_bytes, 0);
+
+    let find = |name: &str| sampled.root.children.iter().find(|c| c.name == name).unwrap();
+    assert!(find("big").children.iter().any(|c| c.name.contains("(estimated)")));
+    assert_eq!(find("small").children.len(), 10);
+    assert!(sampled.estimate_note().unwrap().starts_with("Estimated:"));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 30. test_directory_notes – notes edited in the TUI persist to the notes file
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_directory_notes() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::config::notes::NotesStore;
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::handle_key_event;
+
+    let dir = make_test_dir("notes");
+    let file = dir.join("config/notes.json");
+
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(sample_tree()));
+    state.notes = NotesStore::load(file.clone()).unwrap();
+    assert!(state.notes.is_empty());
+
+    // Sorted by size: b.txt, a.txt, sub
+    state.selected_index = 2;
+    handle_key_event(KeyEvent::from(KeyCode::Char('n')), &mut state);
+    assert_eq!(state.view_mode, ViewMode::NoteInput);
+    for c in "safe to delete!".chars() {
+        handle_key_event(KeyEvent::from(KeyCode::Char(c)), &mut state);
+    }
+    handle_key_event(KeyEvent::from(KeyCode::Backspace), &mut state);
+    handle_key_event(KeyEvent::from(KeyCode::Enter), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+
+    let reloaded = NotesStore::load(file.clone()).unwrap();
+    assert_eq!(reloaded.get(Path::new("/test/sub")), Some("safe to delete"));
+
+    // Reopening prefills the note; Esc leaves it untouched
+    handle_key_event(KeyEvent::from(KeyCode::Char('n')), &mut state);
+    assert_eq!(state.note_input, "safe to delete");
+    handle_key_event(KeyEvent::from(KeyCode::Esc), &mut state);
+    assert_eq!(state.notes.get(Path::new("/test/sub")), Some("safe to delete"));
+
+    // Saving an empty note removes it
+    handle_key_event(KeyEvent::from(KeyCode::Char('n')), &mut state);
+    state.note_input.clear();
+    handle_key_event(KeyEvent::from(KeyCode::Enter), &mut state);
+    assert!(NotesStore::load(file).unwrap().is_empty());
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 31. test_zero_size_directory – empty children still listed, with dashes
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_zero_size_directory() {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    let dir = make_test_dir("zero_size");
+    for name in ["empty1.txt", "empty2.txt", "empty3.txt"] {
+        std::fs::write(dir.join(name), b"").unwrap();
+    }
+    let result = scan_with(&dir, test_settings()).await;
+    assert_eq!(result.total_size, 0);
+    assert_eq!(result.root.children.len(), 3);
+
+    let mut state = AppState::new(dir.clone());
+    state.set_scan_result(result);
+
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+    terminal.draw(|frame| disklens::ui::renderer::render(frame, &state)).unwrap();
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..buffer.area.height)
+        .map(|y| {
+            let row: String = (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect();
+            row + "\n"
+        })
+        .collect();
+
+    for name in ["empty1.txt", "empty2.txt", "empty3.txt"] {
+        assert!(screen.contains(name), "{} missing from:\n{}", name, screen);
+    }
+    assert!(!screen.contains("0.0%"));
+    assert!(!screen.contains("No data"));
+    assert!(screen.contains('\u{2014}'));
+    // Equal placeholder slices are drawn in the ring
+    assert!(screen.contains('\u{2588}'));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 32. test_error_summary – errors rolled up by type in every export format
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_error_summary() {
+    let error = |path: &str, error_type| ScanError {
+        path: PathBuf::from(path),
+        error_type,
+        message: "failed".into(),
+    };
+    let mut result = make_scan_result(sample_tree());
+    result.errors = vec![
+        error("/test/p1", ScanErrorType::PermissionDenied),
+        error("/test/io1", ScanErrorType::IoError),
+        error("/test/p2", ScanErrorType::PermissionDenied),
+        error("/test/loop", ScanErrorType::SymlinkCycle),
+        error("/test/p3", ScanErrorType::PermissionDenied),
+        error("/test/io2", ScanErrorType::IoError),
+        error("/test/p4", ScanErrorType::PermissionDenied),
+    ];
+
+    let summary = result.error_summary();
+    assert_eq!(summary.total, 7);
+    assert_eq!(
+        summary.counts(),
+        vec![
+            (ScanErrorType::PermissionDenied, 4),
+            (ScanErrorType::IoError, 2),
+            (ScanErrorType::SymlinkCycle, 1),
+        ]
+    );
+    assert_eq!(summary.sample.len(), disklens::models::scan_result::ERROR_SAMPLE_SIZE);
+    assert_eq!(summary.sample[0].path, PathBuf::from("/test/p1"));
+
+    let dir = make_test_dir("error_summary");
+    export_json(&result, &dir.join("r.json")).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("r.json")).unwrap()).unwrap();
+    assert_eq!(json["error_summary"]["total"], 7);
+    assert_eq!(json["error_summary"]["by_type"]["PermissionDenied"], 4);
+    assert_eq!(json["error_summary"]["by_type"]["IoError"], 2);
+
+    disklens::export::markdown::export_markdown(&result, &dir.join("r.md")).unwrap();
+    let md = std::fs::read_to_string(dir.join("r.md")).unwrap();
+    assert!(md.contains("| PermissionDenied | 4 |"));
+    assert!(md.contains("| SymlinkCycle | 1 |"));
+
+    disklens::export::html::export_html(&result, &dir.join("r.html"), &Default::default()).unwrap();
+    let html = std::fs::read_to_string(dir.join("r.html")).unwrap();
+    assert!(html.contains("<tr><td>IoError</td><td>2</td></tr>"));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 33. test_toggle_symlinks_rescan – F requests a rescan from the root
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_toggle_symlinks_rescan() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::{handle_key_event, InputAction};
+
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(sample_tree()));
+    state.selected_index = 2;
+    state.enter_directory();
+    assert_eq!(state.current_path, PathBuf::from("/test/sub"));
+
+    let action = handle_key_event(KeyEvent::from(KeyCode::Char('F')), &mut state);
+    assert!(matches!(action, InputAction::ToggleSymlinks));
+
+    state.begin_rescan();
+    assert_eq!(state.view_mode, ViewMode::Scanning);
+    assert!(state.scan_result.is_none());
+    assert!(state.path_stack.is_empty());
+    assert_eq!(state.current_path, PathBuf::from("/test"));
+    assert_eq!(state.scan_root(), PathBuf::from("/test"));
+}
+
+// ---------------------------------------------------------------------------
+// 34. test_streaming_html – streamed report matches the string-built one
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_streaming_html() {
+    use disklens::export::html::{export_html, write_html, HtmlOptions, MAX_CHILDREN_PER_DIR};
+
+    let result = make_scan_result(sample_tree());
+    let mut streamed = Vec::new();
+    write_html(&result, &mut streamed, &HtmlOptions::default()).unwrap();
+    let streamed = String::from_utf8(streamed).unwrap();
+
+    // Tree section as produced when the report was built in a single String
+    let bar = |w: u32| {
+        format!(
+            "<span class=\"bar\"><span class=\"bar-fill\" style=\"width:{}px\"></span></span>",
+            w
+        )
+    };
+    let row = |class: &str, name: &str, size: &str, pct: &str, w: u32| {
+        format!(
+            "<span class=\"node\"><span class=\"name {}\">{}</span><span class=\"size\">{}</span><span class=\"pct\">{}</span>{}</span>",
+            class, name, size, pct, bar(w)
+        )
+    };
+    let expected = [
+        "<h2>Directory Tree</h2>".to_string(),
+        "<details open>".to_string(),
+        format!("<summary>{}</summary>", row("dir", "📁 test", "3.42 KB", "100.0%", 200)),
+        format!("<div style=\"margin-left:20px\">{}</div>", row("file", "📄 b.txt", "1.95 KB", "57.1%", 114)),
+        format!("<div style=\"margin-left:20px\">{}</div>", row("file", "📄 a.txt", "1000 B", "28.6%", 57)),
+        "<details>".to_string(),
+        format!("<summary>{}</summary>", row("dir", "📁 sub", "500 B", "14.3%", 29)),
+        format!("<div style=\"margin-left:20px\">{}</div>", row("file", "📄 c.txt", "500 B", "14.3%", 29)),
+        "</details>".to_string(),
+        "</details>".to_string(),
+        "</body>".to_string(),
+        "</html>".to_string(),
+    ]
+    .join("\n");
+    assert!(streamed.ends_with(&expected), "unexpected tree:\n{}", streamed);
+
+    let dir = make_test_dir("streaming_html");
+    export_html(&result, &dir.join("r.html"), &HtmlOptions::default()).unwrap();
+    assert_eq!(std::fs::read_to_string(dir.join("r.html")).unwrap(), streamed);
+
+    // Wide directories are capped, with the remainder summarized
+    let files: Vec<Node> = (0..MAX_CHILDREN_PER_DIR + 50)
+        .map(|i| {
+            Node::from_file(PathBuf::from(format!("/wide/{}", i)), i.to_string(), 10, None, None)
+        })
+        .collect();
+    let wide = Node::from_directory(PathBuf::from("/wide"), "wide".into(), files);
+    let mut out = Vec::new();
+    write_html(&make_scan_result(wide), &mut out, &HtmlOptions::default()).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(out.matches("📄").count(), MAX_CHILDREN_PER_DIR);
+    assert!(out.contains("… 50 smaller items (500 B)"));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 35. test_skip_pseudo_fs – pseudo filesystem mount points are not descended into
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_skip_pseudo_fs() {
+    use disklens::core::events::create_event_channel;
+    use disklens::core::mounts::MountTable;
+    use disklens::core::scanner::Scanner;
+
+    let dir = make_test_dir("pseudo_fs");
+    std::fs::create_dir_all(dir.join("fake proc/self")).unwrap();
+    std::fs::create_dir_all(dir.join("data")).unwrap();
+    std::fs::write(dir.join("fake proc/self/status"), b"kernel state").unwrap();
+    std::fs::write(dir.join("data/file.bin"), vec![0u8; 64]).unwrap();
+
+    let mounts = MountTable::parse(&format!(
+        "/dev/sda1 / ext4 rw 0 0\nproc {} proc rw,nosuid 0 0\n",
+        dir.join("fake proc").display().to_string().replace(' ', "\\040")
+    ));
+    assert_eq!(mounts.fs_type(&dir.join("fake proc")), Some("proc"));
+
+    let scan = |settings: Settings, mounts: MountTable| {
+        let dir = dir.clone();
+        async move {
+            let (tx, _rx) = create_event_channel();
+            Scanner::with_mounts(settings, tx, mounts).scan(dir).await.unwrap()
+        }
+    };
+
+    let result = scan(test_settings(), mounts.clone()).await;
+    assert_eq!(child_names(&result.root), vec!["data"]);
+    assert_eq!(result.total_size, 64);
+    assert_eq!(result.skipped.len(), 1);
+    assert_eq!(result.skipped[0].path, dir.join("fake proc"));
+    assert!(result.skipped[0].reason.contains("proc"));
+
+    // An empty skip-list scans everything
+    let settings = Settings {
+        pseudo_fs_types: Vec::new(),
+        ..test_settings()
+    };
+    let result = scan(settings, mounts.clone()).await;
+    assert_eq!(child_names(&result.root).len(), 2);
+    assert!(result.skipped.is_empty());
+
+    // Mounted since the previous scan, which leaves the parent's mtime alone
+    let unmounted = MountTable::parse("/dev/sda1 / ext4 rw 0 0\n");
+    let previous = scan(test_settings(), unmounted).await;
+    assert_eq!(child_names(&previous.root).len(), 2);
+    let (tx, _rx) = create_event_channel();
+    let result = Scanner::with_mounts(test_settings(), tx, mounts)
+        .with_previous(previous)
+        .scan(dir.clone())
+        .await
+        .unwrap();
+    assert_eq!(child_names(&result.root), vec!["data"]);
+    assert_eq!(result.total_size, 64);
+    assert_eq!(result.skipped.len(), 1);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 36. test_enter_from_ring_focus – drilling works from either panel
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_enter_from_ring_focus() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::ui::app_state::FocusPanel;
+    use disklens::ui::input::handle_key_event;
+
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(sample_tree()));
+    state.selected_index = 2; // sub
+
+    // Arrows only switch focus, even over a directory
+    handle_key_event(KeyEvent::from(KeyCode::Right), &mut state);
+    assert_eq!(state.focus, FocusPanel::RingChart);
+    assert_eq!(state.current_path, PathBuf::from("/test"));
+
+    handle_key_event(KeyEvent::from(KeyCode::Enter), &mut state);
+    assert_eq!(state.current_path, PathBuf::from("/test/sub"));
+    assert_eq!(state.focus, FocusPanel::RingChart);
+
+    handle_key_event(KeyEvent::from(KeyCode::Char('h')), &mut state);
+    handle_key_event(KeyEvent::from(KeyCode::Left), &mut state);
+    assert_eq!(state.focus, FocusPanel::FileList);
+    state.selected_index = 2;
+    handle_key_event(KeyEvent::from(KeyCode::Char('l')), &mut state);
+    assert_eq!(state.current_path, PathBuf::from("/test/sub"));
+}
+
+// ---------------------------------------------------------------------------
+// 37. test_export_marked – only marked entries and their ancestors are exported
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_export_marked() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::ui::input::handle_key_event;
+
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(sample_tree()));
+
+    // Sorted by size: b.txt, a.txt, sub
+    state.selected_index = 1;
+    handle_key_event(KeyEvent::from(KeyCode::Char(' ')), &mut state);
+    assert_eq!(state.selected_index, 2);
+    state.enter_directory();
+    handle_key_event(KeyEvent::from(KeyCode::Char(' ')), &mut state);
+    let marked: Vec<PathBuf> = state.marked.iter().cloned().collect();
+    assert_eq!(marked, vec![PathBuf::from("/test/a.txt"), PathBuf::from("/test/sub/c.txt")]);
+
+    let subset = state.scan_result.as_ref().unwrap().select_paths(&marked);
+    assert_eq!(subset.total_size, 1500);
+    assert_eq!(subset.total_files, 2);
+
+    let dir = make_test_dir("export_marked");
+    export_json(&subset, &dir.join("marked.json")).unwrap();
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(dir.join("marked.json")).unwrap()).unwrap();
+    fn collect(node: &serde_json::Value, out: &mut Vec<String>) {
+        out.push(node["path"].as_str().unwrap().to_string());
+        for child in node["children"].as_array().into_iter().flatten() {
+            collect(child, out);
+        }
+    }
+    let mut paths = Vec::new();
+    collect(&json["root"], &mut paths);
+    paths.sort();
+    assert_eq!(paths, vec!["/test", "/test/a.txt", "/test/sub", "/test/sub/c.txt"]);
+    assert_eq!(json["total_size"], 1500);
+
+    handle_key_event(KeyEvent::from(KeyCode::Char('u')), &mut state);
+    assert!(state.marked.is_empty());
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 38. test_html_expand_depth – the first N levels of the HTML tree start open
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_html_expand_depth() {
+    use disklens::export::html::{write_html, HtmlOptions};
+
+    // /test > sub > deep > d.txt, plus sample_tree's files
+    let d_txt = Node::from_file(PathBuf::from("/test/sub/deep/d.txt"), "d.txt".into(), 10, None, None);
+    let deep = Node::from_directory(PathBuf::from("/test/sub/deep"), "deep".into(), vec![d_txt]);
+    let mut root = sample_tree();
+    let sub = root.children.iter_mut().find(|c| c.name == "sub").unwrap();
+    let c_txt = sub.children[0].clone();
+    *sub = Node::from_directory(sub.path.clone(), "sub".into(), vec![c_txt, deep]);
+    let root = Node::from_directory(root.path.clone(), root.name.clone(), root.children);
+
+    let render = |expand_depth: usize| {
+        let mut out = Vec::new();
+        let options = HtmlOptions {
+            expand_depth,
+            ..Default::default()
+        };
+        write_html(&make_scan_result(root.clone()), &mut out, &options).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        html.lines()
+            .filter(|l| l.starts_with("<details"))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(render(1), vec!["<details open>", "<details>", "<details>"]);
+    assert_eq!(render(2), vec!["<details open>", "<details open>", "<details>"]);
+    assert_eq!(render(0), vec!["<details>", "<details>", "<details>"]);
+}
+
+// ---------------------------------------------------------------------------
+// 39. test_jump_to_largest_file – B reveals the biggest files in turn
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_jump_to_largest_file() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::ui::input::handle_key_event;
+
+    // /test: a.txt 1000, b.txt 2000, sub/c.txt 500, sub/big.bin 5000
+    let mut root = sample_tree();
+    let sub = root.children.iter_mut().find(|c| c.name == "sub").unwrap();
+    let big_path = PathBuf::from("/test/sub/big.bin");
+    let big = Node::from_file(big_path, "big.bin".into(), 5000, None, None);
+    let c_txt = sub.children[0].clone();
+    *sub = Node::from_directory(sub.path.clone(), "sub".into(), vec![c_txt, big]);
+    let root = Node::from_directory(root.path.clone(), root.name.clone(), root.children);
+
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(root));
+    state.node_type_filter = NodeTypeFilter::DirsOnly;
+
+    let press = |state: &mut AppState| {
+        handle_key_event(KeyEvent::from(KeyCode::Char('B')), state);
+        state.sorted_children()[state.selected_index].path.clone()
+    };
+
+    assert_eq!(press(&mut state), PathBuf::from("/test/sub/big.bin"));
+    assert_eq!(state.current_path, PathBuf::from("/test/sub"));
+    assert_eq!(state.path_stack, vec![PathBuf::from("/test")]);
+    assert_eq!(state.node_type_filter, NodeTypeFilter::All);
+    assert!(state.status_message.as_deref().unwrap().contains("/test/sub/big.bin"));
+
+    assert_eq!(press(&mut state), PathBuf::from("/test/b.txt"));
+    assert!(state.path_stack.is_empty());
+    assert_eq!(press(&mut state), PathBuf::from("/test/a.txt"));
+    assert_eq!(press(&mut state), PathBuf::from("/test/sub/c.txt"));
+    assert_eq!(press(&mut state), PathBuf::from("/test/sub/big.bin"));
+
+    // Moving the selection away restarts from the largest
+    state.go_back();
+    assert_eq!(press(&mut state), PathBuf::from("/test/sub/big.bin"));
+}
+
+// ---------------------------------------------------------------------------
+// 40. test_html_categories – category colors, legend and breakdown bar
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_html_categories() {
+    use disklens::export::html::{write_html, HtmlOptions};
+    use disklens::models::category::{category_totals, FileCategory};
+
+    let file = |name: &str, size| {
+        Node::from_file(PathBuf::from(format!("/r/{}", name)), name.into(), size, None, None)
+    };
+    let root = Node::from_directory(
+        PathBuf::from("/r"),
+        "r".into(),
+        vec![file("movie.MP4", 8000), file("main.rs", 1000), file("notes.txt", 1000)],
+    );
+    assert_eq!(
+        category_totals(&root),
+        vec![
+            (FileCategory::Media, 8000),
+            (FileCategory::Code, 1000),
+            (FileCategory::Document, 1000),
+        ]
+    );
+
+    let render = |categories: bool| {
+        let mut out = Vec::new();
+        let options = HtmlOptions {
+            categories,
+            ..Default::default()
+        };
+        write_html(&make_scan_result(root.clone()), &mut out, &options).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+
+    let html = render(true);
+    assert!(html.contains("<h2>File Categories</h2>"));
+    assert!(html.contains("class=\"category-bar\""));
+    assert!(html.contains(&format!(
+        "<span style=\"width:80.00%;background:{}\"",
+        FileCategory::Media.css_color()
+    )));
+    assert!(html.contains("Media 7.81 KB (80.0%)</li>"));
+    assert!(html.contains("class=\"name file cat-media\""));
+    assert!(html.contains("class=\"name file cat-code\""));
+
+    let plain = render(false);
+    assert!(!plain.contains("File Categories"));
+    assert!(!plain.contains("cat-"));
+}
+
+// ---------------------------------------------------------------------------
+// 41. test_followed_symlink_navigation – entering a followed link shows its contents
+// ---------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_followed_symlink_navigation() {
+    let dir = make_test_dir("symlink_nav");
+    let target = dir.join("outside/target");
+    std::fs::create_dir_all(&target).unwrap();
+    std::fs::create_dir_all(dir.join("root")).unwrap();
+    std::fs::write(target.join("file.bin"), vec![0u8; 42]).unwrap();
+    std::os::unix::fs::symlink(&target, dir.join("root/link")).unwrap();
+
+    let settings = Settings {
+        follow_symlinks: true,
+        ..test_settings()
+    };
+    let result = scan_with(&dir.join("root"), settings).await;
+    let link = &result.root.children[0];
+    assert_eq!(link.name, "link");
+    assert_eq!(link.path, dir.join("root/link"));
+    assert_eq!(link.symlink_target.as_deref(), Some(target.canonicalize().unwrap().as_path()));
+
+    let mut state = AppState::new(dir.join("root"));
+    state.set_scan_result(result);
+    state.enter_directory();
+    assert_eq!(state.current_path, dir.join("root/link"));
+    let node = state.current_node().expect("link path resolves");
+    assert_eq!(child_names(node), vec!["file.bin"]);
+    assert_eq!(state.sorted_children().len(), 1);
+
+    // The resolved path finds the same node
+    state.current_path = target.canonicalize().unwrap();
+    assert_eq!(state.current_node().unwrap().name, "link");
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 42. test_list_density – comfortable rows show the path and keep selection visible
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_list_density() {
+    use disklens::config::settings::ListDensity;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    let dir = make_test_dir("list_density");
+    for i in 0..20 {
+        std::fs::write(dir.join(format!("file{:02}.bin", i)), vec![0u8; 100 * (i + 1)]).unwrap();
+    }
+    let result = scan_with(&dir, test_settings()).await;
+
+    let mut state = AppState::new(dir.clone());
+    state.set_scan_result(result);
+    let render = |state: &AppState| {
+        let mut terminal = Terminal::new(TestBackend::new(140, 24)).unwrap();
+        terminal.draw(|frame| disklens::ui::renderer::render(frame, state)).unwrap();
+        let buffer = terminal.backend().buffer().clone();
+        (0..buffer.area.height)
+            .map(|y| {
+                let row: String =
+                    (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect();
+                row + "\n"
+            })
+            .collect::<String>()
+    };
+    let visible = |screen: &str| screen.matches('\u{1f4c4}').count();
+
+    let compact = render(&state);
+    assert!(!compact.contains(&dir.join("file19.bin").display().to_string()));
+
+    state.toggle_list_density();
+    assert_eq!(state.list_density, ListDensity::Comfortable);
+    let comfortable = render(&state);
+    assert!(comfortable.contains(&dir.join("file19.bin").display().to_string()));
+    assert_eq!(visible(&compact), 15);
+    assert_eq!(visible(&comfortable), 7);
+
+    // The last (smallest) entry scrolls into view with its detail line
+    state.go_to_last();
+    let scrolled = render(&state);
+    assert!(scrolled.contains("file00.bin "));
+    assert!(scrolled.contains(&dir.join("file00.bin").display().to_string()));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 43. test_exclude_patterns – configured globs skip entries at any depth
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_exclude_patterns() {
+    let dir = make_test_dir("exclude_patterns");
+    std::fs::create_dir_all(dir.join("node_modules/pkg")).unwrap();
+    std::fs::create_dir_all(dir.join("web/node_modules/pkg")).unwrap();
+    std::fs::write(dir.join("node_modules/pkg/index.js"), vec![0u8; 4096]).unwrap();
+    std::fs::write(dir.join("web/node_modules/pkg/index.js"), vec![0u8; 4096]).unwrap();
+    std::fs::write(dir.join("web/app.js"), "app").unwrap();
+    std::fs::write(dir.join("disk.iso"), vec![0u8; 8192]).unwrap();
+    std::fs::write(dir.join("notes.txt"), "notes").unwrap();
+
+    let settings = Settings {
+        ignore_patterns: vec!["**/node_modules".to_string(), "*.iso".to_string()],
+        ..test_settings()
+    };
+    let result = scan_with(&dir, settings.clone()).await;
+
+    assert_eq!(child_names(&result.root), vec!["notes.txt", "web"]);
+    let web = result.root.children.iter().find(|c| c.name == "web").unwrap();
+    assert_eq!(child_names(web), vec!["app.js"]);
+    assert_eq!(result.total_size, 8);
+
+    // The streaming top-N walk honors the same patterns
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(settings, event_tx);
+    let top = scanner.scan_top_n(dir.clone(), 10).await.unwrap();
+    assert_eq!(top.files.len(), 2);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 44. test_hardlink_dedup – shared data counted once unless disabled
+// ---------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_hardlink_dedup() {
+    let dir = make_test_dir("hardlink_dedup");
+    std::fs::create_dir_all(dir.join("a")).unwrap();
+    std::fs::create_dir_all(dir.join("b")).unwrap();
+    std::fs::write(dir.join("a/data.bin"), vec![0u8; 1000]).unwrap();
+    std::fs::hard_link(dir.join("a/data.bin"), dir.join("b/data.bin")).unwrap();
+    std::fs::write(dir.join("single.bin"), vec![0u8; 10]).unwrap();
+
+    let result = scan_with(&dir, test_settings()).await;
+    assert_eq!(result.total_size, 1010);
+    assert_eq!(result.total_files, 3);
+    let sizes: Vec<u64> = ["a", "b"]
+        .iter()
+        .map(|name| result.root.children.iter().find(|c| c.name == *name).unwrap().size)
+        .collect();
+    assert!(sizes == vec![1000, 0] || sizes == vec![0, 1000], "{:?}", sizes);
+
+    let settings = Settings {
+        dedupe_hardlinks: false,
+        ..test_settings()
+    };
+    let result = scan_with(&dir, settings).await;
+    assert_eq!(result.total_size, 2010);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 45. test_disk_usage_mode – allocated blocks reported and toggled app-wide
+// ---------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_disk_usage_mode() {
+    let dir = make_test_dir("disk_usage_mode");
+    // A sparse file: large apparent size, almost nothing allocated
+    let sparse = std::fs::File::create(dir.join("sparse.img")).unwrap();
+    sparse.set_len(8 * 1024 * 1024).unwrap();
+    drop(sparse);
+
+    let result = scan_with(&dir, test_settings()).await;
+    assert_eq!(result.size_mode, SizeMode::Apparent);
+    assert_eq!(result.total_size, 8 * 1024 * 1024);
+    let file = &result.root.children[0];
+    assert!(file.size_on_disk < file.size, "{} allocated", file.size_on_disk);
+
+    let settings = Settings {
+        size_mode: SizeMode::DiskUsage,
+        ..test_settings()
+    };
+    let usage = scan_with(&dir, settings).await;
+    assert_eq!(usage.size_mode, SizeMode::DiskUsage);
+    assert_eq!(usage.total_size, file.size_on_disk);
+    assert_eq!(usage.root.children[0].size_on_disk, 8 * 1024 * 1024);
+
+    let mut state = AppState::new(dir.clone());
+    state.set_scan_result(result);
+    state.toggle_size_mode();
+    let shown = state.scan_result.as_ref().unwrap();
+    assert_eq!(shown.size_mode, SizeMode::DiskUsage);
+    assert_eq!(shown.total_size, usage.total_size);
+    assert_eq!(state.sorted_children()[0].size, usage.root.children[0].size);
+    state.toggle_size_mode();
+    assert_eq!(state.scan_result.as_ref().unwrap().total_size, 8 * 1024 * 1024);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 46. test_one_file_system – mount points on other devices are skipped
+// ---------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_one_file_system() {
+    use std::os::unix::fs::MetadataExt;
+
+    // Needs a mount point directly below /dev (devpts, mqueue, shm, ...)
+    let root = PathBuf::from("/dev");
+    let Ok(root_meta) = std::fs::metadata(&root) else {
+        return;
+    };
+    let mount = std::fs::read_dir(&root).ok().and_then(|entries| {
+        entries.flatten().map(|e| e.path()).find(|p| {
+            std::fs::symlink_metadata(p).is_ok_and(|m| m.is_dir() && m.dev() != root_meta.dev())
+        })
+    });
+    let Some(mount) = mount else {
+        return;
+    };
+
+    let settings = Settings {
+        max_depth: Some(1),
+        pseudo_fs_types: Vec::new(),
+        one_file_system: true,
+        ..test_settings()
+    };
+    let result = scan_with(&root, settings).await;
+    assert!(result
+        .skipped
+        .iter()
+        .any(|s| s.path == mount && s.reason == "other filesystem"));
+    assert!(!result.root.children.iter().any(|c| c.path == mount));
+
+    let settings = Settings {
+        max_depth: Some(1),
+        pseudo_fs_types: Vec::new(),
+        ..test_settings()
+    };
+    let result = scan_with(&root, settings).await;
+    assert!(result.skipped.is_empty());
+    assert!(result.root.children.iter().any(|c| c.path == mount));
+}
+
+// ---------------------------------------------------------------------------
+// 47. test_cancel_scan – a cancelled scan returns a partial, flagged result
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_cancel_scan() {
+    use disklens::core::events::Event;
+
+    let dir = make_test_dir("cancel_scan");
+    for i in 0..5 {
+        std::fs::create_dir_all(dir.join(format!("sub{}/deeper", i))).unwrap();
+        std::fs::write(dir.join(format!("sub{}/deeper/file.bin", i)), vec![0u8; 100]).unwrap();
+    }
+
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(test_settings(), event_tx);
+    let token = scanner.cancel_token();
+    let scanner = scanner.on_event(move |event| {
+        if matches!(event, Event::ScanStarted { .. }) {
+            token.cancel();
+        }
+    });
+    let result = scanner.scan(dir.clone()).await.expect("cancelled scan still returns");
+    assert!(result.cancelled);
+    assert_eq!(result.total_files, 0);
+    assert!(result.root.children.is_empty());
+
+    let complete = scan_with(&dir, test_settings()).await;
+    assert!(!complete.cancelled);
+    assert_eq!(complete.total_files, 5);
+
+    // Esc while scanning asks the app to stop the scan
+    let mut state = AppState::new(dir.clone());
+    let esc = crossterm::event::KeyEvent::from(crossterm::event::KeyCode::Esc);
+    let action = disklens::ui::input::handle_key_event(esc, &mut state);
+    assert!(matches!(action, disklens::ui::input::InputAction::CancelScan));
+    assert!(!state.should_quit);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 48. test_pause_resume_scan – a paused scan reads nothing until resumed
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_pause_resume_scan() {
+    use disklens::core::events::Event;
+
+    let dir = make_test_dir("pause_resume");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("sub/file.bin"), vec![0u8; 100]).unwrap();
+
+    let (event_tx, mut rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(test_settings(), event_tx);
+    let control = scanner.control();
+    let progress = scanner.progress().clone();
+
+    control.pause();
+    control.pause();
+    assert!(control.is_paused());
+    let root = dir.clone();
+    let handle = tokio::spawn(async move { scanner.scan(root).await });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!handle.is_finished());
+    assert_eq!(progress.snapshot().files_scanned, 0);
+    let frozen = progress.elapsed();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(progress.elapsed(), frozen);
+
+    control.toggle_pause();
+    assert!(!control.is_paused());
+    let result = handle.await.unwrap().expect("scan should succeed");
+    assert_eq!(result.total_files, 1);
+    assert!(result.scan_duration < Duration::from_millis(150));
+
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event);
+    }
+    // Pausing twice emits a single event
+    assert_eq!(events.iter().filter(|e| matches!(e, Event::ScanPaused)).count(), 1);
+    assert_eq!(events.iter().filter(|e| matches!(e, Event::ScanResumed)).count(), 1);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 49. test_incremental_rescan – unchanged directories reused, changed ones re-read
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_incremental_rescan() {
+    let dir = make_test_dir("incremental");
+    std::fs::create_dir_all(dir.join("static/nested")).unwrap();
+    std::fs::create_dir_all(dir.join("active")).unwrap();
+    std::fs::write(dir.join("static/stable.bin"), vec![0u8; 100]).unwrap();
+    std::fs::write(dir.join("static/nested/deep.bin"), vec![0u8; 10]).unwrap();
+    std::fs::write(dir.join("active/old.bin"), vec![0u8; 50]).unwrap();
+
+    let previous = scan_with(&dir, test_settings()).await;
+    let static_dir = previous.root.children.iter().find(|c| c.name == "static").unwrap();
+    assert!(static_dir.modified.is_some(), "directory mtimes are recorded");
+
+    // A new file changes active/'s mtime; a file in nested/ changes nested/'s mtime
+    // even though its parent static/ is untouched; stable.bin is rewritten in place
+    std::fs::write(dir.join("active/new.bin"), vec![0u8; 7]).unwrap();
+    std::fs::write(dir.join("static/nested/added.bin"), vec![0u8; 3]).unwrap();
+    std::fs::write(dir.join("static/stable.bin"), vec![0u8; 200]).unwrap();
+
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(test_settings(), event_tx)
+        .with_previous(previous);
+    let result = scanner.scan(dir.clone()).await.expect("scan should succeed");
+
+    let index = PathIndex::build(&result.root);
+    assert_eq!(index.search("new.bin"), vec![dir.join("active/new.bin")]);
+    assert_eq!(index.search("added.bin"), vec![dir.join("static/nested/added.bin")]);
+    let stable = result
+        .root
+        .children
+        .iter()
+        .find(|c| c.name == "static")
+        .and_then(|d| d.children.iter().find(|c| c.name == "stable.bin"))
+        .unwrap();
+    assert_eq!(stable.size, 100, "unchanged directory keeps its previous file sizes");
+    assert_eq!(result.total_files, 5);
+    assert_eq!(result.total_size, 100 + 10 + 3 + 50 + 7);
+
+    // A full scan sees the rewrite
+    let full = scan_with(&dir, test_settings()).await;
+    assert_eq!(full.total_size, 200 + 10 + 3 + 50 + 7);
+
+    let rescan = |settings: Settings, previous: ScanResult| {
+        let (event_tx, _rx) = disklens::core::events::create_event_channel();
+        let scanner = disklens::core::scanner::Scanner::new(settings, event_tx)
+            .with_previous(previous);
+        let dir = dir.clone();
+        async move { scanner.scan(dir).await.expect("scan should succeed") }
+    };
+
+    // A scan made with other settings isn't reused
+    let folding = Settings {
+        min_file_size: Some(20),
+        ..test_settings()
+    };
+    let folded = rescan(folding.clone(), result).await;
+    assert_eq!(folded.total_size, 200 + 10 + 3 + 50 + 7);
+
+    // Nor is a directory whose files were folded into a stand-in
+    std::fs::write(dir.join("static/nested/deep.bin"), vec![0u8; 15]).unwrap();
+    let refolded = rescan(folding, folded).await;
+    assert_eq!(refolded.total_size, 200 + 15 + 3 + 50 + 7);
+
+    // A symlink that isn't followed doesn't keep its directory from being reused
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink("stable.bin", dir.join("static/link")).unwrap();
+        let previous = scan_with(&dir, test_settings()).await;
+        std::fs::write(dir.join("static/stable.bin"), vec![0u8; 100]).unwrap();
+        let relinked = rescan(test_settings(), previous).await;
+        let link_size = std::fs::symlink_metadata(dir.join("static/link")).unwrap().len();
+        assert_eq!(relinked.total_size, 200 + 15 + 3 + 50 + 7 + link_size);
+        std::fs::write(dir.join("static/stable.bin"), vec![0u8; 200]).unwrap();
+        std::fs::remove_file(dir.join("static/link")).unwrap();
+    }
+
+    // Hard links in reused directories are still counted once
+    #[cfg(unix)]
+    {
+        std::fs::create_dir_all(dir.join("links")).unwrap();
+        std::fs::hard_link(dir.join("static/stable.bin"), dir.join("links/stable.bin")).unwrap();
+        let linked = scan_with(&dir, test_settings()).await;
+        assert_eq!(linked.total_size, 200 + 15 + 3 + 50 + 7);
+        let relinked = rescan(test_settings(), linked).await;
+        assert_eq!(relinked.total_size, 200 + 15 + 3 + 50 + 7);
+    }
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 50. test_multiple_roots – several paths scanned under one virtual root
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_multiple_roots() {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    let first = make_test_dir("multi_root_a");
+    let second = make_test_dir("multi_root_b");
+    std::fs::create_dir_all(first.join("sub")).unwrap();
+    std::fs::write(first.join("sub/a.bin"), vec![0u8; 100]).unwrap();
+    std::fs::write(second.join("b.bin"), vec![0u8; 40]).unwrap();
+
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(test_settings(), event_tx);
+    let result = scanner
+        .scan_roots(vec![first.clone(), second.clone()])
+        .await
+        .expect("scan should succeed");
+
+    assert!(result.is_multi_root());
+    assert_eq!(result.roots(), vec![first.clone(), second.clone()]);
+    assert_eq!(result.total_size, 140);
+    assert_eq!(result.total_files, 2);
+    // Both roots and sub/, but not the virtual root
+    assert_eq!(result.total_dirs, 3);
+    assert!(result.display_path().contains(", "));
+
+    let mut state = AppState::new(PathBuf::new());
+    state.set_scan_result(result);
+    assert_eq!(state.sorted_children().len(), 2);
+
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+    terminal.draw(|frame| disklens::ui::renderer::render(frame, &state)).unwrap();
+    let buffer = terminal.backend().buffer().clone();
+    let title: String = (0..buffer.area.width).map(|x| buffer[(x, 1)].symbol()).collect();
+    assert!(title.contains("All roots"), "{}", title);
+
+    // The largest file sits below the first root, reached from the virtual root
+    state.jump_to_largest_file();
+    assert_eq!(state.current_path, first.join("sub"));
+    assert_eq!(state.path_stack, vec![PathBuf::new(), first.clone()]);
+    state.jump_to_ancestor(0);
+    assert!(state.current_path.as_os_str().is_empty());
+    assert_eq!(state.current_children().len(), 2);
+
+    cleanup(&first);
+    cleanup(&second);
+}
+
+// ---------------------------------------------------------------------------
+// 51. test_respect_gitignore – git ignore files skip or tag entries
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_respect_gitignore() {
+    let dir = make_test_dir("respect_gitignore");
+    std::fs::create_dir_all(dir.join(".git/info")).unwrap();
+    std::fs::create_dir_all(dir.join("target/debug")).unwrap();
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join(".git/info/exclude"), "*.swp\n").unwrap();
+    std::fs::write(dir.join(".gitignore"), "target/\n*.log\n!keep.log\n").unwrap();
+    std::fs::write(dir.join("target/debug/app"), vec![0u8; 1000]).unwrap();
+    std::fs::write(dir.join("src/main.rs"), vec![0u8; 10]).unwrap();
+    std::fs::write(dir.join("src/main.rs.swp"), vec![0u8; 20]).unwrap();
+    std::fs::write(dir.join("build.log"), vec![0u8; 30]).unwrap();
+    std::fs::write(dir.join("keep.log"), vec![0u8; 40]).unwrap();
+
+    // Off by default: everything is scanned and nothing is tagged
+    let plain = scan_with(&dir, test_settings()).await;
+    assert_eq!(plain.root.gitignored_size(), 0);
+    assert!(plain.root.children.iter().any(|c| c.name == "target"));
+
+    let skip = Settings {
+        gitignore: GitignoreMode::Skip,
+        ..test_settings()
+    };
+    let skipped = scan_with(&dir, skip).await;
+    assert!(!skipped.root.children.iter().any(|c| c.name == "target" || c.name == "build.log"));
+    assert!(skipped.root.children.iter().any(|c| c.name == "keep.log"));
+    let src = skipped.root.children.iter().find(|c| c.name == "src").unwrap();
+    assert_eq!(child_names(src), vec!["main.rs"]);
+
+    let tag = Settings {
+        gitignore: GitignoreMode::Tag,
+        ..test_settings()
+    };
+    let tagged = scan_with(&dir, tag).await;
+    assert_eq!(tagged.total_size, plain.total_size);
+    assert_eq!(tagged.root.gitignored_size(), 1000 + 20 + 30);
+    let target = tagged.root.children.iter().find(|c| c.name == "target").unwrap();
+    assert!(target.gitignored && target.children[0].children[0].gitignored);
+    let keep = tagged.root.children.iter().find(|c| c.name == "keep.log").unwrap();
+    assert!(!keep.gitignored);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 52. test_min_file_size – small files folded into one entry per directory
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_min_file_size() {
+    use disklens::config::size_spec::parse_size_spec;
+
+    assert_eq!(parse_size_spec("4096").unwrap(), 4096);
+    assert_eq!(parse_size_spec("10M").unwrap(), 10 * 1024 * 1024);
+    assert_eq!(parse_size_spec("1.5GiB").unwrap(), 3 * 512 * 1024 * 1024);
+    assert_eq!(parse_size_spec("512kb").unwrap(), 512 * 1024);
+    assert!(parse_size_spec("10X").is_err());
+    assert!(parse_size_spec("M").is_err());
+
+    let dir = make_test_dir("min_file_size");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("big.bin"), vec![0u8; 5000]).unwrap();
+    std::fs::write(dir.join("a.txt"), vec![0u8; 10]).unwrap();
+    std::fs::write(dir.join("b.txt"), vec![0u8; 20]).unwrap();
+    std::fs::write(dir.join("sub/c.txt"), vec![0u8; 30]).unwrap();
+
+    let settings = Settings {
+        min_file_size: Some(1024),
+        ..test_settings()
+    };
+    let result = scan_with(&dir, settings).await;
+
+    assert_eq!(result.total_size, 5060);
+    assert_eq!(result.total_files, 4);
+    assert_eq!(
+        child_names(&result.root),
+        vec!["2 files under 1.00 KB", "big.bin", "sub"]
+    );
+    let folded = result.root.children.iter().find(|c| c.name.starts_with('2')).unwrap();
+    assert_eq!((folded.size, folded.file_count), (30, 2));
+    assert_eq!(folded.node_type, NodeType::Summary);
+    let sub = result.root.children.iter().find(|c| c.name == "sub").unwrap();
+    assert_eq!(child_names(sub), vec!["1 file under 1.00 KB"]);
+
+    // A stand-in for a single file is still no file to reveal
+    let index = SizeIndex::build_files(&result.root);
+    let ranked: Vec<_> = index.top_n(10).iter().map(|(path, _)| path.clone()).collect();
+    assert_eq!(ranked, [dir.join("big.bin")]);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 53. test_skip_hidden – hidden entries skipped at scan time or hidden in the list
+// ---------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_skip_hidden() {
+    let dir = make_test_dir("skip_hidden");
+    std::fs::create_dir_all(dir.join(".cache")).unwrap();
+    std::fs::write(dir.join(".cache/blob"), vec![0u8; 500]).unwrap();
+    std::fs::write(dir.join(".env"), vec![0u8; 50]).unwrap();
+    std::fs::write(dir.join("visible.txt"), vec![0u8; 5]).unwrap();
+
+    let settings = Settings {
+        skip_hidden: true,
+        ..test_settings()
+    };
+    let skipped = scan_with(&dir, settings).await;
+    assert_eq!(child_names(&skipped.root), vec!["visible.txt"]);
+    assert_eq!(skipped.total_size, 5);
+
+    // Scanned in full, hidden entries can be toggled out of the list
+    let result = scan_with(&dir, test_settings()).await;
+    let mut state = AppState::new(dir.clone());
+    state.set_scan_result(result);
+    assert_eq!(state.sorted_children().len(), 3);
+    state.toggle_hidden();
+    let names: Vec<&str> = state.sorted_children().iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["visible.txt"]);
+
+    // Revealing the largest file shows hidden entries again
+    state.jump_to_largest_file();
+    assert!(state.show_hidden);
+    assert_eq!(state.current_path, dir.join(".cache"));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 54. test_clone_detection – shared extents reported as a unique-size column
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_clone_detection() {
+    let dir = make_test_dir("clone_detection");
+    std::fs::write(dir.join("original.img"), vec![7u8; 64 * 1024]).unwrap();
+    // Only copy-on-write filesystems can share the copy's extents
+    let reflinked = std::process::Command::new("cp")
+        .arg("--reflink=always")
+        .arg(dir.join("original.img"))
+        .arg(dir.join("clone.img"))
+        .status()
+        .is_ok_and(|status| status.success());
+
+    let settings = Settings {
+        detect_clones: true,
+        ..test_settings()
+    };
+    let result = scan_with(&dir, settings).await;
+    if reflinked {
+        assert!(result.root.shared_size > 0);
+        assert!(result.root.unique_size() < result.root.size);
+    } else {
+        assert_eq!(result.root.shared_size, 0);
+        assert_eq!(result.root.unique_size(), result.root.size);
+    }
+
+    // Reports grow a unique-size column only when something is shared
+    let mut shared = Node::from_file(dir.join("a.img"), "a.img".into(), 1000, None, None);
+    shared.shared_size = 600;
+    let other = Node::from_file(dir.join("b.img"), "b.img".into(), 500, None, None);
+    let root = Node::from_directory(dir.clone(), "root".into(), vec![shared, other]);
+    assert_eq!(root.shared_size, 600);
+    let md_path = dir.join("report.md");
+    disklens::export::markdown::export_markdown(&make_scan_result(root), &md_path).unwrap();
+    let md = std::fs::read_to_string(&md_path).unwrap();
+    assert!(md.contains("| Name | Size | Unique | % |"));
+    assert!(md.contains("| 1000 B | 400 B |"));
+    assert!(md.contains("| 500 B | 500 B |"));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 55. test_archive_peek – archive contents listed as browsable entries
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_archive_peek() {
+    let dir = make_test_dir("archive_peek");
+    fn append<W: std::io::Write>(builder: &mut tar::Builder<W>, name: &str, data: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, name, data).unwrap();
+    }
+    let mut plain = tar::Builder::new(std::fs::File::create(dir.join("bundle.tar")).unwrap());
+    append(&mut plain, "docs/readme.txt", &[b'a'; 300]);
+    append(&mut plain, "./data.bin", &[0u8; 1000]);
+    plain.finish().unwrap();
+    let gz = flate2::write::GzEncoder::new(
+        std::fs::File::create(dir.join("logs.tar.gz")).unwrap(),
+        flate2::Compression::default(),
+    );
+    let mut packed = tar::Builder::new(gz);
+    append(&mut packed, "app.log", &[b'x'; 4000]);
+    packed.into_inner().unwrap().finish().unwrap();
+    std::fs::write(dir.join("broken.zip"), b"not a zip").unwrap();
+
+    // Off by default: archives are plain files
+    let result = scan_with(&dir, test_settings()).await;
+    assert!(result.root.children.iter().all(|c| c.children.is_empty()));
+
+    let settings = Settings {
+        peek_archives: true,
+        ..test_settings()
+    };
+    let result = scan_with(&dir, settings).await;
+    assert_eq!(result.total_files, 3);
+    let child = |name: &str| result.root.children.iter().find(|c| c.name == name).unwrap();
+
+    let bundle = child("bundle.tar");
+    assert_eq!(bundle.node_type, NodeType::File);
+    assert!(bundle.is_container());
+    let docs = bundle.children.iter().find(|c| c.name == "docs").unwrap();
+    assert_eq!(docs.node_type, NodeType::ArchiveEntry);
+    assert_eq!(docs.path, dir.join("bundle.tar").join("docs"));
+    assert_eq!(docs.children[0].name, "readme.txt");
+    assert_eq!(docs.uncompressed_size, Some(300));
+    let data = bundle.children.iter().find(|c| c.name == "data.bin").unwrap();
+    assert_eq!((data.size, data.uncompressed_size), (1000, Some(1000)));
+
+    // A gzipped tarball's entries share out the compressed size
+    let logs = child("logs.tar.gz");
+    assert_eq!(logs.children.len(), 1);
+    assert_eq!(logs.children[0].uncompressed_size, Some(4000));
+    assert!(logs.children[0].size <= logs.size && logs.children[0].size < 4000);
+
+    // An unreadable archive is kept as a file and reported
+    assert!(child("broken.zip").children.is_empty());
+    assert!(result.errors.iter().any(|e| e.path == dir.join("broken.zip")));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 56. test_usage_by_owner – file sizes broken down by owning user
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_usage_by_owner() {
+    let dir = make_test_dir("usage_by_owner");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("a.bin"), vec![0u8; 300]).unwrap();
+    std::fs::write(dir.join("sub/b.bin"), vec![0u8; 200]).unwrap();
+
+    let result = scan_with(&dir, test_settings()).await;
+    let usage = Analyzer::usage_by_owner(&result.root);
+    assert_eq!(usage.len(), 1);
+    assert_eq!((usage[0].size, usage[0].file_count), (500, 2));
+    #[cfg(unix)]
+    {
+        let uid = std::os::unix::fs::MetadataExt::uid(&std::fs::metadata(&dir).unwrap());
+        assert_eq!(usage[0].uid, Some(uid));
+        assert_eq!(disklens::core::owners::user_name(0).as_deref(), Some("root"));
+        assert_eq!(disklens::core::owners::user_name(u32::MAX - 1), None);
+
+        // Several owners are listed largest first
+        let mut other = Node::from_file(dir.join("c.bin"), "c.bin".into(), 900, None, None);
+        other.uid = Some(uid + 1);
+        let mut root = result.root.clone();
+        root.children.push(other);
+        let usage = Analyzer::usage_by_owner(&root);
+        assert_eq!(usage[0].uid, Some(uid + 1));
+        assert_eq!(usage[1].size, 500);
+    }
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 57. test_file_category_recorded – scanned files carry their category
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_file_category_recorded() {
+    use disklens::models::category::{category_totals, FileCategory};
+
+    let dir = make_test_dir("file_category_recorded");
+    std::fs::create_dir_all(dir.join("src")).unwrap();
+    std::fs::write(dir.join("src/main.rs"), vec![b'x'; 100]).unwrap();
+    std::fs::write(dir.join("clip.mkv"), vec![0u8; 400]).unwrap();
+    std::fs::write(dir.join("LICENSE"), vec![b'x'; 50]).unwrap();
+
+    let result = scan_with(&dir, test_settings()).await;
+    let child = |name: &str| result.root.children.iter().find(|c| c.name == name).unwrap();
+    assert_eq!(child("clip.mkv").category, Some(FileCategory::Media));
+    assert_eq!(child("LICENSE").category, Some(FileCategory::Other));
+    assert_eq!(child("src").category, None);
+    assert_eq!(child("src").children[0].category, Some(FileCategory::Code));
+    assert_eq!(
+        category_totals(&result.root),
+        vec![
+            (FileCategory::Media, 400),
+            (FileCategory::Code, 100),
+            (FileCategory::Other, 50),
+        ]
+    );
+
+    // The recorded category wins over the path; nodes without one fall back to it
+    let mut node = Node::from_file(dir.join("data.bin"), "data.bin".into(), 10, None, None);
+    assert_eq!(node.file_category(), FileCategory::Binary);
+    node.category = Some(FileCategory::Document);
+    assert_eq!(node.file_category(), FileCategory::Document);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 58. test_max_duration – an exhausted time budget truncates the scan
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_max_duration() {
+    use disklens::config::time_spec::parse_duration_spec;
+
+    assert_eq!(parse_duration_spec("30s").unwrap(), Duration::from_secs(30));
+    assert_eq!(parse_duration_spec("5m").unwrap(), Duration::from_secs(300));
+    assert!(parse_duration_spec("soon").is_err());
+
+    let dir = make_test_dir("max_duration");
+    std::fs::create_dir_all(dir.join("sub1/deep")).unwrap();
+    std::fs::create_dir_all(dir.join("sub2")).unwrap();
+    std::fs::write(dir.join("top.bin"), vec![0u8; 100]).unwrap();
+    std::fs::write(dir.join("sub1/deep/a.bin"), vec![0u8; 200]).unwrap();
+    std::fs::write(dir.join("sub2/b.bin"), vec![0u8; 300]).unwrap();
+
+    let result = scan_with(&dir, test_settings()).await;
+    assert!(!result.truncated());
+    assert_eq!(result.total_size, 600);
+
+    // A budget already spent reads the root but starts none of its subdirectories
+    let settings = Settings {
+        max_duration: Some(Duration::ZERO),
+        ..test_settings()
+    };
+    let result = scan_with(&dir, settings).await;
+    assert!(result.truncated() && result.is_partial() && !result.cancelled);
+    let mut unscanned = result.unscanned.clone();
+    unscanned.sort();
+    assert_eq!(unscanned, vec![dir.join("sub1"), dir.join("sub2")]);
+    assert_eq!((result.total_size, result.total_files), (100, 1));
+    assert_eq!(child_names(&result.root), vec!["sub1", "sub2", "top.bin"]);
+    assert!(result.root.children.iter().all(|c| c.children.is_empty()));
+
+    let md_path = dir.join("report.md");
+    disklens::export::markdown::export_markdown(&result, &md_path).unwrap();
+    let md = std::fs::read_to_string(&md_path).unwrap();
+    assert!(md.contains("Truncated: the time budget ran out with 2 directories unscanned"));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 59. test_network_fs_throttle – network mounts are announced and paced
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_network_fs_throttle() {
+    use std::sync::{Arc, Mutex};
+
+    use disklens::core::events::{create_event_channel, Event};
+    use disklens::core::mounts::{detect_network_fs, MountTable};
+    use disklens::core::scanner::Scanner;
+
+    let dir = make_test_dir("network_fs_throttle");
+    for sub in ["remote/a", "remote/b", "remote/c", "local"] {
+        std::fs::create_dir_all(dir.join(sub)).unwrap();
+        std::fs::write(dir.join(sub).join("f.bin"), vec![0u8; 10]).unwrap();
+    }
+    let mounts = MountTable::parse(&format!(
+        "/dev/sda1 / ext4 rw 0 0\nserver:/export {} nfs4 rw 0 0\n",
+        dir.join("remote").display()
+    ));
+    assert_eq!(mounts.network_fs_type(&dir.join("remote")), Some("nfs4"));
+    assert_eq!(mounts.network_fs_type(&dir.join("local")), None);
+    assert_eq!(mounts.containing_fs_type(&dir.join("remote/a")), Some("nfs4"));
+    assert_eq!(detect_network_fs(&dir.join("remote/a"), &mounts).as_deref(), Some("nfs4"));
+
+    let scan = |settings: Settings| {
+        let (dir, mounts) = (dir.clone(), mounts.clone());
+        async move {
+            let seen = Arc::new(Mutex::new(Vec::new()));
+            let sink = Arc::clone(&seen);
+            let (tx, _rx) = create_event_channel();
+            let scanner = Scanner::with_mounts(settings, tx, mounts).on_event(move |event| {
+                if let Event::NetworkFilesystem { path, fs_type } = event {
+                    sink.lock().unwrap().push((path.clone(), fs_type.clone()));
+                }
+            });
+            let result = scanner.scan(dir).await.unwrap();
+            let seen = seen.lock().unwrap().clone();
+            (result, seen)
+        }
+    };
+
+    // Four reads on the mount, each started at least 40ms after the one before
+    let settings = Settings {
+        network_request_interval: Duration::from_millis(40),
+        ..test_settings()
+    };
+    let (result, seen) = scan(settings.clone()).await;
+    assert_eq!(seen, vec![(dir.join("remote"), "nfs4".to_string())]);
+    assert_eq!(result.total_size, 40);
+    assert!(result.scan_duration >= Duration::from_millis(120));
+
+    let settings = Settings {
+        throttle_network_fs: false,
+        ..settings
+    };
+    let (result, seen) = scan(settings).await;
+    assert!(seen.is_empty());
+    assert_eq!(result.total_size, 40);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 60. test_scan_profiles – per-path overrides, most specific path winning
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_scan_profiles() {
+    use disklens::config::profiles::load_profiles;
+    use disklens::config::settings::ScanProfile;
+
+    let dir = make_test_dir("scan_profiles");
+    for sub in ["nas/a/b", "nas/c", "local"] {
+        std::fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    std::fs::write(dir.join("nas/keep.bin"), vec![0u8; 10]).unwrap();
+    std::fs::write(dir.join("nas/disk.iso"), vec![0u8; 1000]).unwrap();
+    std::fs::write(dir.join("nas/a/b/deep.bin"), vec![0u8; 20]).unwrap();
+    std::fs::write(dir.join("nas/a/b/image.iso"), vec![0u8; 1000]).unwrap();
+    std::fs::write(dir.join("nas/c/hidden.bin"), vec![0u8; 40]).unwrap();
+    std::fs::write(dir.join("local/x.iso"), vec![0u8; 100]).unwrap();
+
+    let config = make_test_dir("scan_profiles_config");
+    let json = config.join("profiles.json");
+    std::fs::write(
+        &json,
+        format!(
+            r#"{{"{}/**": {{"max_concurrent_io": 2, "max_depth": 1, "ignore_patterns": ["*.iso"]}},
+                "{}": {{}}}}"#,
+            dir.join("nas").display(),
+            dir.join("nas/a").display()
+        ),
+    )
+    .unwrap();
+    let profiles = load_profiles(&json).unwrap();
+    assert_eq!(profiles[&dir.join("nas")].max_depth, Some(1));
+    assert_eq!(profiles[&dir.join("nas/a")], ScanProfile::default());
+
+    let settings = Settings {
+        profiles,
+        ..test_settings()
+    };
+    let result = scan_with(&dir, settings.clone()).await;
+    let nas = result.root.children.iter().find(|c| c.name == "nas").unwrap();
+    // Below nas only one level is read, but nas/a has its own unlimited profile;
+    // nas's exclude patterns still apply inside it
+    assert_eq!(child_names(nas), vec!["a", "c", "keep.bin"]);
+    // Past the profile's depth nas/c has no children but still counts its files
+    let c = nas.children.iter().find(|c| c.name == "c").unwrap();
+    assert!(c.children.is_empty());
+    assert_eq!((c.size, c.file_count), (40, 1));
+    let a = nas.children.iter().find(|c| c.name == "a").unwrap();
+    assert_eq!(a.size, 20);
+    // Outside the profiles nothing changes
+    assert_eq!(result.root.children.iter().find(|c| c.name == "local").unwrap().size, 100);
+    assert_eq!(result.total_size, 170);
+
+    // Scanning inside a profile's path picks up its patterns too
+    let result = scan_with(&dir.join("nas/a"), settings).await;
+    assert_eq!(result.total_size, 20);
+
+    cleanup(&dir);
+    cleanup(&config);
+}
+
+// ---------------------------------------------------------------------------
+// 61. test_progress_current_path – the tracker follows the directory being read
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_progress_current_path() {
+    use disklens::core::progress::ProgressTracker;
+
+    let tracker = ProgressTracker::new();
+    assert_eq!(tracker.current_path(), PathBuf::new());
+    tracker.set_current_path(Path::new("/data/long/path"));
+    tracker.set_current_path(Path::new("/tmp"));
+    assert_eq!(tracker.snapshot().current_path, PathBuf::from("/tmp"));
+
+    let dir = make_test_dir("progress_current_path");
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(dir.join("sub/f.bin"), b"data").unwrap();
+
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let scanner = disklens::core::scanner::Scanner::new(test_settings(), event_tx);
+    scanner.scan(dir.clone()).await.unwrap();
+    let current = scanner.progress().current_path();
+    assert!(current == dir || current == dir.join("sub"));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 62. test_merge_rescan – unreadable directories are rescanned and grafted in
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_merge_rescan() {
+    use disklens::core::elevated::permission_denied_dirs;
+
+    let dir = make_test_dir("merge_rescan");
+    std::fs::create_dir_all(dir.join("open")).unwrap();
+    std::fs::create_dir_all(dir.join("locked/inner")).unwrap();
+    std::fs::write(dir.join("open/a.bin"), vec![0u8; 100]).unwrap();
+    std::fs::write(dir.join("locked/b.bin"), vec![0u8; 200]).unwrap();
+    std::fs::write(dir.join("locked/inner/c.bin"), vec![0u8; 300]).unwrap();
+
+    let full = scan_with(&dir, test_settings()).await;
+
+    // Stand in for an unreadable directory: present, but nothing inside it counted
+    let settings = Settings {
+        ignore_patterns: vec!["**/locked/*".to_string()],
+        ..test_settings()
+    };
+    let mut partial = scan_with(&dir, settings).await;
+    assert_eq!(partial.total_size, 100);
+    let error = |path: PathBuf, error_type| ScanError {
+        path,
+        error_type,
+        message: String::new(),
+    };
+    partial.errors = vec![
+        error(dir.join("locked"), ScanErrorType::PermissionDenied),
+        error(dir.join("locked/inner"), ScanErrorType::PermissionDenied),
+        error(dir.join("open/a.bin"), ScanErrorType::PermissionDenied),
+        error(dir.join("open"), ScanErrorType::IoError),
+    ];
+    // Only directories qualify, and the nested one is covered by its parent
+    assert_eq!(permission_denied_dirs(&partial), vec![dir.join("locked")]);
+
+    let rescan = scan_with(&dir.join("locked"), test_settings()).await;
+    assert_eq!(partial.merge_rescan(rescan), 1);
+    assert_eq!(partial.total_size, full.total_size);
+    assert_eq!(partial.total_files, full.total_files);
+    assert_eq!(partial.total_dirs, full.total_dirs);
+    let locked = partial.root.find(&dir.join("locked")).unwrap();
+    assert_eq!(child_names(locked), vec!["b.bin", "inner"]);
+    assert_eq!(locked.size, 500);
+    // Errors inside the rescanned directory are gone; the rest stay
+    assert_eq!(partial.errors.len(), 2);
+    assert!(permission_denied_dirs(&partial).is_empty());
+
+    // A rescan from elsewhere has nowhere to go
+    let elsewhere = make_scan_result(sample_tree());
+    assert_eq!(partial.merge_rescan(elsewhere), 0);
+    assert_eq!(partial.total_size, full.total_size);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 63. test_dir_modified_times – directories carry their mtime, read or not
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_dir_modified_times() {
+    use disklens::ui::app_state::{SortMode, SortOrder};
+
+    let dir = make_test_dir("dir_modified_times");
+    let hour = Duration::from_secs(3600);
+    let now = SystemTime::now();
+    for (name, age) in [("old", 3), ("new", 1), ("mid", 2)] {
+        std::fs::create_dir_all(dir.join(name).join("inner")).unwrap();
+        std::fs::write(dir.join(name).join("f.bin"), b"data").unwrap();
+        let handle = std::fs::File::open(dir.join(name)).unwrap();
+        handle.set_modified(now - hour * age).unwrap();
+    }
+
+    // Read directories and ones cut off by the depth limit alike
+    for max_depth in [None, Some(1)] {
+        let settings = Settings {
+            max_depth,
+            ..test_settings()
+        };
+        let result = scan_with(&dir, settings).await;
+        for child in &result.root.children {
+            let expected = std::fs::metadata(&child.path).unwrap().modified().unwrap();
+            assert_eq!(child.modified, Some(expected), "{}", child.name);
+        }
+
+        let mut state = AppState::new(dir.clone());
+        state.set_scan_result(result);
+        state.sort_mode = SortMode::Modified;
+        state.sort_order = SortOrder::Descending;
+        let names: Vec<&str> = state.sorted_children().iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["new", "mid", "old"]);
+    }
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 64. test_newest_contents_sort – directories rank by their newest descendant
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_newest_contents_sort() {
+    use disklens::ui::app_state::{SortMode, SortOrder};
+
+    let base = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+    let at = |secs: u64| Some(base + Duration::from_secs(secs));
+    let file = |path: &str, modified| {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        Node::from_file(path, name, 10, modified, None)
+    };
+    let dir = |path: &str, modified, children| {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let mut node = Node::from_directory(path, name, children);
+        node.modified = modified;
+        node
+    };
+    // "stale" was touched last itself, but "active" holds the newest file, deep down
+    let root = dir(
+        "/test",
+        at(0),
+        vec![
+            dir("/test/stale", at(500), vec![file("/test/stale/a", at(100))]),
+            dir(
+                "/test/active",
+                at(50),
+                vec![dir("/test/active/deep", at(60), vec![file("/test/active/deep/b", at(900))])],
+            ),
+            dir("/test/empty", at(300), vec![]),
+            file("/test/loose", at(400)),
+        ],
+    );
+
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(root));
+    let root = &state.scan_result.as_ref().unwrap().root;
+    assert_eq!(root.newest_modified, at(900));
+    assert_eq!(root.children[1].children[0].newest_modified, at(900));
+    assert_eq!(root.children[2].newest_modified, None);
+    assert_eq!(root.children[3].newest_modified, None);
+
+    state.sort_mode = SortMode::Modified;
+    let names: Vec<&str> = state.sorted_children().iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["stale", "loose", "empty", "active"]);
+
+    // Cycling reaches the new mode after modified time
+    state.toggle_sort();
+    assert_eq!(state.sort_mode, SortMode::NewestContent);
+    assert_eq!(state.sort_order, SortOrder::Descending);
+    let names: Vec<&str> = state.sorted_children().iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["active", "loose", "empty", "stale"]);
+    state.toggle_sort();
+    assert_eq!(state.sort_mode, SortMode::Size);
+}
+
+// ---------------------------------------------------------------------------
+// 65. test_threads_backend – the rayon scanner builds the same tree
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_threads_backend() {
+    use disklens::core::scanner::ScannerSync;
+
+    fn flatten(node: &Node, out: &mut Vec<(PathBuf, u64, usize, usize)>) {
+        out.push((node.path.clone(), node.size, node.file_count, node.dir_count));
+        for child in &node.children {
+            flatten(child, out);
+        }
+    }
+    fn entries(result: &ScanResult) -> Vec<(PathBuf, u64, usize, usize)> {
+        let mut out = Vec::new();
+        flatten(&result.root, &mut out);
+        out.sort();
+        out
+    }
+
+    let dir = make_test_dir("threads_backend");
+    let outside = make_test_dir("threads_backend_outside");
+    std::fs::write(outside.join("linked.bin"), vec![0u8; 64]).unwrap();
+    for sub in ["a/b/c", "a/d", "e", "skipped/deep"] {
+        std::fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    std::fs::write(dir.join("a/b/c/big.bin"), vec![0u8; 5000]).unwrap();
+    std::fs::write(dir.join("a/b/small.txt"), b"tiny").unwrap();
+    std::fs::write(dir.join("a/d/mid.dat"), vec![0u8; 1200]).unwrap();
+    std::fs::write(dir.join("e/.hidden"), vec![0u8; 300]).unwrap();
+    std::fs::write(dir.join("skipped/deep/x.bin"), vec![0u8; 700]).unwrap();
+    std::fs::write(dir.join(".disklensignore"), "skipped\n").unwrap();
+    #[cfg(unix)]
+    {
+        std::fs::hard_link(dir.join("a/d/mid.dat"), dir.join("a/d/mid-link.dat")).unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("e/outside")).unwrap();
+    }
+
+    let variants = [
+        test_settings(),
+        Settings {
+            skip_hidden: true,
+            min_file_size: Some(1000),
+            ..test_settings()
+        },
+        Settings {
+            max_depth: Some(2),
+            max_concurrent_io: 1,
+            ..test_settings()
+        },
+    ];
+    for settings in variants {
+        let tasks = scan_with(&dir, settings.clone()).await;
+
+        let (event_tx, _rx) = disklens::core::events::create_event_channel();
+        let scanner = ScannerSync::new(settings, event_tx);
+        let root = dir.clone();
+        let threads = tokio::task::spawn_blocking(move || scanner.scan(root))
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(threads.total_size, tasks.total_size);
+        assert_eq!(threads.total_files, tasks.total_files);
+        assert_eq!(threads.total_dirs, tasks.total_dirs);
+        assert_eq!(threads.errors.len(), tasks.errors.len());
+        assert_eq!(entries(&threads), entries(&tasks));
+    }
+
+    // Followed symlinks keep the link's path, as with the default backend
+    let settings = Settings {
+        follow_symlinks: true,
+        ..test_settings()
+    };
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let result = ScannerSync::new(settings, event_tx).scan(dir.clone()).unwrap();
+    assert!(result.root.find(&dir.join("skipped")).is_none());
+    #[cfg(unix)]
+    {
+        let link = result.root.find(&dir.join("e/outside")).unwrap();
+        assert_eq!(link.symlink_target, Some(outside.canonicalize().unwrap()));
+        assert_eq!(child_names(link), vec!["linked.bin"]);
+        assert_eq!(link.size, 64);
+    }
+
+    cleanup(&dir);
+    cleanup(&outside);
+}
+
+// ---------------------------------------------------------------------------
+// 66. test_uring_backend – batched statx matches std::fs, or is refused unbuilt
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_uring_backend() {
+    use disklens::config::settings::ScanBackend;
+    use disklens::core::scanner::ScannerSync;
+
+    fn flatten(node: &Node, out: &mut Vec<(PathBuf, u64, u64, Option<SystemTime>, usize)>) {
+        out.push((
+            node.path.clone(),
+            node.size,
+            node.size_on_disk,
+            node.modified,
+            node.file_count,
+        ));
+        for child in &node.children {
+            flatten(child, out);
+        }
+    }
+
+    let dir = make_test_dir("uring_backend");
+    std::fs::create_dir_all(dir.join("a/b")).unwrap();
+    std::fs::write(dir.join("a/b/big.bin"), vec![0u8; 9000]).unwrap();
+    std::fs::write(dir.join("a/small.txt"), b"tiny").unwrap();
+    std::fs::write(dir.join(".hidden"), vec![0u8; 300]).unwrap();
+    for i in 0..300 {
+        std::fs::write(dir.join(format!("a/many-{}.txt", i)), vec![0u8; i]).unwrap();
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("a/small.txt", dir.join("link")).unwrap();
+
+    let scan = |backend: ScanBackend| {
+        let settings = Settings {
+            backend,
+            count_dir_overhead: true,
+            ..test_settings()
+        };
+        let (event_tx, _rx) = disklens::core::events::create_event_channel();
+        let scanner = ScannerSync::new(settings, event_tx);
+        let root = dir.clone();
+        tokio::task::spawn_blocking(move || scanner.scan(root))
+    };
+
+    let uring = scan(ScanBackend::Uring).await.unwrap();
+    #[cfg(all(target_os = "linux", feature = "uring"))]
+    {
+        let uring = uring.unwrap();
+        let threads = scan(ScanBackend::Threads).await.unwrap().unwrap();
+        assert_eq!(uring.total_size, threads.total_size);
+        assert_eq!(uring.total_files, 303);
+        assert!(uring.errors.is_empty());
+        let (mut expected, mut actual) = (Vec::new(), Vec::new());
+        flatten(&threads.root, &mut expected);
+        flatten(&uring.root, &mut actual);
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+    }
+    #[cfg(not(all(target_os = "linux", feature = "uring")))]
+    {
+        let _ = flatten;
+        let err = uring.unwrap_err().to_string();
+        assert!(err.contains("--features uring"), "{}", err);
+    }
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 67. test_subtree_events – finished top-level directories build a provisional tree
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_subtree_events() {
+    use disklens::core::events::Event;
+    use disklens::core::scanner::Scanner;
+    use disklens::models::node::SizeMode;
+    use disklens::ui::app_state::ViewMode;
+
+    let dir = make_test_dir("subtree_events");
+    for sub in ["a/b/c", "d"] {
+        std::fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    std::fs::write(dir.join("a/b/c/deep.bin"), vec![0u8; 4000]).unwrap();
+    std::fs::write(dir.join("a/b/mid.bin"), vec![0u8; 2000]).unwrap();
+    std::fs::write(dir.join("d/top.bin"), vec![0u8; 1000]).unwrap();
+    std::fs::write(dir.join("root.bin"), vec![0u8; 500]).unwrap();
+
+    let (event_tx, mut rx) = disklens::core::events::create_event_channel();
+    let scanner = Scanner::new(test_settings(), event_tx).with_subtree_events();
+    let result = scanner.scan(dir.clone()).await.unwrap();
+    drop(scanner);
+
+    let mut subtrees = Vec::new();
+    while let Some(event) = rx.recv().await {
+        if let Event::SubtreeCompleted { node } = event {
+            subtrees.push(*node);
+        }
+    }
+    let mut paths: Vec<PathBuf> = subtrees.iter().map(|n| n.path.clone()).collect();
+    paths.sort();
+    // Two levels below the root; deeper directories arrive inside their ancestors
+    assert_eq!(paths, vec![dir.join("a"), dir.join("a/b"), dir.join("d")]);
+
+    // Children finish first, so the provisional tree fills in from the bottom up
+    let mut state = AppState::new(dir.clone());
+    let roots = [dir.clone()];
+    for node in subtrees {
+        state.add_subtree(&roots, SizeMode::Apparent, node);
+    }
+    let provisional = state.scan_result.as_ref().unwrap();
+    assert_eq!(provisional.total_size, 7000);
+    assert_eq!(provisional.root.find(&dir.join("a/b/c")).unwrap().size, 4000);
+
+    // Browsing the provisional tree survives the final result arriving
+    state.browse_partial_scan();
+    assert_eq!(state.view_mode, ViewMode::Normal);
+    state.go_to_first();
+    state.enter_directory();
+    assert_eq!(state.current_path, dir.join("a"));
+    state.set_scan_result(result);
+    assert!(!state.scanning);
+    assert_eq!(state.current_path, dir.join("a"));
+    assert_eq!(state.scan_result.as_ref().unwrap().total_size, 7500);
+
+    // Without the builder call no subtrees are sent
+    let (event_tx, mut rx) = disklens::core::events::create_event_channel();
+    Scanner::new(test_settings(), event_tx).scan(dir.clone()).await.unwrap();
+    while let Some(event) = rx.recv().await {
+        assert!(!matches!(event, Event::SubtreeCompleted { .. }));
+    }
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 68. test_depth_limit_aggregates – directories past max_depth keep their totals
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_depth_limit_aggregates() {
+    use disklens::core::scanner::ScannerSync;
+
+    let dir = make_test_dir("depth_limit_aggregates");
+    for sub in ["a/b/c/d", "a/e", "f"] {
+        std::fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    std::fs::write(dir.join("a/b/c/d/deep.bin"), vec![0u8; 4000]).unwrap();
+    std::fs::write(dir.join("a/b/c/skip.tmp"), vec![0u8; 900]).unwrap();
+    std::fs::write(dir.join("a/b/c/.disklensignore"), "*.tmp\n").unwrap();
+    std::fs::write(dir.join("a/b/mid.bin"), vec![0u8; 2000]).unwrap();
+    std::fs::write(dir.join("a/e/.hidden"), vec![0u8; 300]).unwrap();
+    std::fs::write(dir.join("f/top.bin"), vec![0u8; 1000]).unwrap();
+
+    for skip_hidden in [false, true] {
+        let full = scan_with(
+            &dir,
+            Settings {
+                skip_hidden,
+                ..test_settings()
+            },
+        )
+        .await;
+        let settings = Settings {
+            skip_hidden,
+            max_depth: Some(1),
+            ..test_settings()
+        };
+        let limited = scan_with(&dir, settings.clone()).await;
+        assert_eq!(limited.total_size, full.total_size);
+        assert_eq!(limited.total_files, full.total_files);
+        assert_eq!(limited.total_dirs, full.total_dirs);
+
+        // Children stop at the cutoff, but each carries its subtree's totals
+        let a = limited.root.find(&dir.join("a")).unwrap();
+        assert!(a.children.is_empty());
+        let full_a = full.root.find(&dir.join("a")).unwrap();
+        assert_eq!((a.size, a.file_count, a.dir_count), (full_a.size, full_a.file_count, 5));
+
+        let (event_tx, _rx) = disklens::core::events::create_event_channel();
+        let scanner = ScannerSync::new(settings, event_tx);
+        let root = dir.clone();
+        let threads = tokio::task::spawn_blocking(move || scanner.scan(root))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(threads.total_size, full.total_size);
+        assert_eq!(threads.total_files, full.total_files);
+        assert_eq!(threads.total_dirs, full.total_dirs);
+    }
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 69. test_symlink_targets – unfollowed links record where they point
+// ---------------------------------------------------------------------------
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_symlink_targets() {
+    use disklens::export::html::{write_html, HtmlOptions};
+
+    let dir = make_test_dir("symlink_targets");
+    std::fs::create_dir_all(dir.join("real")).unwrap();
+    std::fs::write(dir.join("real/data.bin"), vec![0u8; 100]).unwrap();
+    std::os::unix::fs::symlink("real/data.bin", dir.join("relative")).unwrap();
+    std::os::unix::fs::symlink(dir.join("real"), dir.join("absolute")).unwrap();
+    std::os::unix::fs::symlink("missing", dir.join("dangling")).unwrap();
+
+    let result = scan_with(&dir, test_settings()).await;
+    let target = |name: &str| result.root.find(&dir.join(name)).unwrap().symlink_target.clone();
+    assert_eq!(target("relative"), Some(PathBuf::from("real/data.bin")));
+    assert_eq!(target("absolute"), Some(dir.join("real")));
+    assert_eq!(target("dangling"), Some(PathBuf::from("missing")));
+    assert_eq!(target("real"), None);
+
+    // A link's target doesn't make it answer for the path it points at
+    let absolute = result.root.find(&dir.join("absolute")).unwrap();
+    assert!(!absolute.matches_path(&dir.join("real")));
+    let mut state = AppState::new(dir.clone());
+    state.set_scan_result(result.clone());
+    state.current_path = dir.join("real");
+    assert_eq!(state.current_node().unwrap().node_type, NodeType::Directory);
+
+    // Shown in the file list and kept in exports
+    let expected = format!("absolute \u{2192} {}", dir.join("real").display());
+    assert_eq!(absolute.name_with_target(), expected);
+    let mut out = Vec::new();
+    write_html(&result, &mut out, &HtmlOptions::default()).unwrap();
+    assert!(String::from_utf8(out).unwrap().contains("relative \u{2192} real/data.bin"));
+    let json = dir.join("export.json");
+    export_json(&result, &json).unwrap();
+    let loaded: ScanResult = serde_json::from_slice(&std::fs::read(&json).unwrap()).unwrap();
+    let relative = loaded.root.find(&dir.join("relative")).unwrap();
+    assert_eq!(relative.symlink_target, Some(PathBuf::from("real/data.bin")));
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 70. test_sparse_files – files with holes are reported and flagged
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_sparse_files() {
+    use disklens::export::html::{write_html, HtmlOptions};
+
+    const MIB: u64 = 1024 * 1024;
+    let file = |name: &str, size: u64, size_on_disk: u64| {
+        let path = PathBuf::from("/t").join(name);
+        let mut node = Node::from_file(path, name.into(), size, None, None);
+        node.size_on_disk = size_on_disk;
+        node
+    };
+    let root = Node::from_directory(
+        PathBuf::from("/t"),
+        "t".into(),
+        vec![
+            file("disk.img", 64 * MIB, 4 * MIB),
+            file("core", 8 * MIB, 0),
+            // Half allocated is still sparse; small holes and dense files are not
+            file("half.bin", 4 * MIB, 2 * MIB),
+            file("tiny", 100_000, 0),
+            file("dense.bin", 8 * MIB, 8 * MIB),
+        ],
+    );
+    let mut result = make_scan_result(root);
+
+    let sparse = Analyzer::sparse_files(&result.root, result.size_mode);
+    let names: Vec<_> = sparse.iter().map(|f| f.path.file_name().unwrap().to_owned()).collect();
+    assert_eq!(names, ["disk.img", "core", "half.bin"]);
+    assert_eq!(sparse[0].hole_size(), 60 * MIB);
+    assert!(!result.root.is_sparse(result.size_mode));
+
+    // The same files are found when sizes hold disk usage
+    result.set_size_mode(SizeMode::DiskUsage);
+    let swapped = Analyzer::sparse_files(&result.root, result.size_mode);
+    assert_eq!(swapped, sparse);
+
+    let mut out = Vec::new();
+    write_html(&result, &mut out, &HtmlOptions::default()).unwrap();
+    let html = String::from_utf8(out).unwrap();
+    assert!(html.contains("Sparse Files (3 total)"));
+    assert!(html.contains("<td>/t/disk.img</td><td>64.00 MB</td><td>4.00 MB</td>"));
+
+    // A real file with a hole, where the filesystem supports them
+    let dir = make_test_dir("sparse_files");
+    let sparse_file = std::fs::File::create(dir.join("holes.img")).unwrap();
+    sparse_file.set_len(16 * MIB).unwrap();
+    drop(sparse_file);
+    let scanned = scan_with(&dir, test_settings()).await;
+    let node = scanned.root.find(&dir.join("holes.img")).unwrap();
+    if node.size_on_disk == 0 {
+        assert!(node.is_sparse(scanned.size_mode));
+    }
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 71. test_mount_picker – list mounted filesystems and pick the ones to scan
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_mount_picker() {
+    use disklens::core::mounts::{list_mounts, parse_entries, MountInfo};
+    use disklens::ui::app_state::ViewMode;
+
+    let entries = parse_entries(
+        "/dev/sda1 / ext4 rw 0 0\nproc /proc proc rw 0 0\n/dev/sdb1 /mnt/my\\040disk xfs rw 0 0\n",
+    );
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[2].device, "/dev/sdb1");
+    assert_eq!(entries[2].mount_point, PathBuf::from("/mnt/my disk"));
+
+    // Whatever this machine has mounted, pseudo filesystems are left out
+    let pseudo = Settings::default().pseudo_fs_types;
+    for mount in list_mounts(&pseudo) {
+        assert!(mount.total > 0 && mount.used() <= mount.total);
+        assert!(!pseudo.contains(&mount.fs_type));
+    }
+
+    let mount = |path: &str| MountInfo {
+        mount_point: PathBuf::from(path),
+        device: format!("dev{}", path),
+        fs_type: "ext4".into(),
+        total: 100,
+        free: 40,
+        available: 30,
+    };
+    assert_eq!(mount("/").used(), 60);
+    let mut state = AppState::new(PathBuf::new());
+    state.open_mount_picker(vec![mount("/"), mount("/home"), mount("/data")]);
+    assert_eq!(state.view_mode, ViewMode::MountPicker);
+    assert!(!state.scanning);
+
+    // Picked mounts are scanned in table order
+    state.move_mount_cursor(2);
+    state.toggle_mount();
+    state.move_mount_cursor(-2);
+    state.toggle_mount();
+    state.move_mount_cursor(5);
+    assert_eq!(state.mount_cursor, 2);
+    let roots = state.confirm_mounts();
+    assert_eq!(roots, [PathBuf::from("/"), PathBuf::from("/data")]);
+    assert_eq!(state.view_mode, ViewMode::Scanning);
+    assert!(state.scanning);
+    assert_eq!(state.current_path, PathBuf::new());
+
+    // With nothing picked, Enter scans the highlighted mount; `a` picks them all
+    state.open_mount_picker(vec![mount("/"), mount("/home")]);
+    state.move_mount_cursor(1);
+    state.toggle_all_mounts();
+    assert_eq!(state.picked_mounts.len(), 2);
+    state.toggle_all_mounts();
+    assert!(state.picked_mounts.is_empty());
+    assert_eq!(state.confirm_mounts(), [PathBuf::from("/home")]);
+    assert_eq!(state.current_path, PathBuf::from("/home"));
+}
+
+// ---------------------------------------------------------------------------
+// 72. test_scan_estimate – skipped subdirectories are extrapolated and marked
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_scan_estimate() {
+    use disklens::core::scanner::ScannerSync;
+    use disklens::models::scan_result::SampleUnit;
+
+    let dir = make_test_dir("estimate");
+    for i in 0..20 {
+        let sub = dir.join(format!("wide/d{:02}", i));
+        std::fs::create_dir_all(&sub).unwrap();
+        for j in 0..3 {
+            std::fs::write(sub.join(format!("{}.bin", j)), vec![0u8; 100]).unwrap();
+        }
+    }
+    std::fs::create_dir_all(dir.join("narrow/a")).unwrap();
+    std::fs::write(dir.join("narrow/a/f.bin"), vec![0u8; 10]).unwrap();
+
+    let exact = scan_with(&dir, test_settings()).await;
+    let settings = Settings {
+        estimate_ratio: Some(0.2),
+        ..test_settings()
+    };
+    let estimated = scan_with(&dir, settings.clone()).await;
+    let estimate = estimated.estimate.expect("estimated scans are flagged");
+    assert_eq!(estimate.unit, SampleUnit::Directories);
+    assert!(estimate.estimated_dirs >= 16);
+    assert!(estimated.estimate_note().unwrap().contains("directories"));
+
+    // Identical subdirectories extrapolate exactly, with no margin
+    assert_eq!(estimated.total_size, exact.total_size);
+    assert_eq!(estimated.total_files, exact.total_files);
+    assert_eq!(estimated.total_dirs, exact.total_dirs);
+    assert_eq!(estimate.margin_bytes, 0);
+
+    // Only the directory with many subdirectories was sampled
+    let wide = estimated.root.find(&dir.join("wide")).unwrap();
+    let stand_in = wide.children.iter().find(|c| c.estimated).unwrap();
+    assert!(stand_in.name.contains("more directories (estimated)"));
+    assert!(wide.children.len() <= 5);
+    assert!(wide.estimated && estimated.root.estimated);
+    assert!(stand_in.size_label().starts_with('\u{2248}'));
+    let narrow = estimated.root.find(&dir.join("narrow")).unwrap();
+    assert!(!narrow.estimated);
+    assert_eq!(narrow.children.len(), 1);
+
+    // The threads backend draws the same sample
+    let (event_tx, _rx) = disklens::core::events::create_event_channel();
+    let threads = tokio::task::spawn_blocking({
+        let dir = dir.clone();
+        move || ScannerSync::new(settings, event_tx).scan(dir).unwrap()
+    })
+    .await
+    .unwrap();
+    assert_eq!(threads.total_size, estimated.total_size);
+    assert_eq!(threads.estimate, estimated.estimate);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 73. test_by_extension – size and count per extension and category
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_by_extension() {
+    use disklens::core::analyzer::CategoryUsage;
+    use disklens::export::html::{write_html, HtmlOptions};
+    use disklens::models::category::FileCategory;
+    use disklens::ui::app_state::ViewMode;
+
+    let file = |path: &str, size: u64| {
+        let path = PathBuf::from("/t").join(path);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        Node::from_file(path, name, size, None, None)
+    };
+    let mut folded = file("2 files under 1.00 KB", 300);
+    folded.file_count = 2;
+    let sub = Node::from_directory(
+        PathBuf::from("/t/sub"),
+        "sub".into(),
+        vec![file("sub/b.MP4", 5000), file("sub/c.rs", 40)],
+    );
+    let root = Node::from_directory(
+        PathBuf::from("/t"),
+        "t".into(),
+        vec![file("a.mp4", 1000), file("Makefile", 60), folded, sub],
+    );
+
+    let extensions = Analyzer::by_extension(&root);
+    let summary: Vec<_> = extensions
+        .iter()
+        .map(|e| (e.label(), e.category, e.size, e.file_count))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (".mp4".to_string(), FileCategory::Media, 6000, 2),
+            ("(none)".to_string(), FileCategory::Other, 360, 3),
+            (".rs".to_string(), FileCategory::Code, 40, 1),
+        ]
+    );
+    let categories = Analyzer::by_category(&extensions);
+    assert_eq!(
+        categories[0],
+        CategoryUsage { category: FileCategory::Media, size: 6000, file_count: 2 }
+    );
+    assert_eq!(categories.len(), 3);
+
+    // Both reports carry the breakdown
+    let result = make_scan_result(root);
+    let mut out = Vec::new();
+    write_html(&result, &mut out, &HtmlOptions::default()).unwrap();
+    let html = String::from_utf8(out).unwrap();
+    assert!(html.contains("<h2>File Types</h2>"));
+    assert!(html.contains(">.mp4</td><td>Media</td>"));
+    let dir = make_test_dir("by_extension");
+    let md_path = dir.join("report.md");
+    disklens::export::markdown::export_markdown(&result, &md_path).unwrap();
+    let md = std::fs::read_to_string(&md_path).unwrap();
+    assert!(md.contains("| .mp4 | Media | 5.86 KB | 93.8% | 2 |"));
+
+    // The TUI view opens over the current directory and scrolls through it
+    let mut state = AppState::new(PathBuf::from("/t"));
+    state.set_scan_result(result);
+    state.toggle_file_types();
+    assert_eq!(state.view_mode, ViewMode::FileTypes);
+    state.scroll_file_types(10);
+    assert_eq!(state.file_types_scroll, 2);
+    state.toggle_file_types();
+    assert_eq!(state.view_mode, ViewMode::Normal);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 74. test_age_breakdown – bytes bucketed by time since last modification
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_age_breakdown() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::core::analyzer::AgeBreakdown;
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::handle_key_event;
+
+    let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000 * 86_400);
+    let days_ago = |days: u64| Some(now - Duration::from_secs(days * 86_400));
+    let file = |path: &str, size: u64, modified: Option<SystemTime>| {
+        let path = PathBuf::from("/t").join(path);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        Node::from_file(path, name, size, modified, None)
+    };
+    let mut archive = Node::from_directory_with_overhead(
+        PathBuf::from("/t/archive"),
+        "archive".into(),
+        10,
+        vec![file("archive/old.tar", 5000, days_ago(400)), file("archive/x", 7, None)],
+    );
+    archive.modified = days_ago(200);
+    let work = Node::from_directory(
+        PathBuf::from("/t/work"),
+        "work".into(),
+        vec![
+            file("work/a", 100, days_ago(0)),
+            file("work/b", 200, days_ago(90)),
+            // Clock skew: a file from the future is as new as it gets
+            file("work/c", 1, Some(now + Duration::from_secs(3600))),
+        ],
+    );
+    let root = Node::from_directory(
+        PathBuf::from("/t"),
+        "t".into(),
+        vec![work, file("notes", 30, days_ago(30)), archive],
+    );
+
+    let total = Analyzer::age_breakdown(&root, now);
+    assert_eq!(total, AgeBreakdown { buckets: [101, 230, 10, 5000], unknown: 7 });
+    assert_eq!(total.total(), root.size);
+
+    let report = Analyzer::age_report(&root, now);
+    let names: Vec<_> = report.iter().map(|e| (e.name.as_str(), e.is_container)).collect();
+    assert_eq!(names, [("archive", true), ("work", true), ("notes", false)]);
+    assert_eq!(report[1].ages.buckets, [101, 200, 0, 0]);
+
+    // `A` opens the overlay for the current directory and closes it again
+    let mut state = AppState::new(PathBuf::from("/t"));
+    state.set_scan_result(make_scan_result(root));
+    handle_key_event(KeyEvent::from(KeyCode::Char('A')), &mut state);
+    assert_eq!(state.view_mode, ViewMode::AgeReport);
+    state.scroll_age_report(10);
+    assert_eq!(state.age_report_scroll, 2);
+    handle_key_event(KeyEvent::from(KeyCode::Esc), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+}
+
+// ---------------------------------------------------------------------------
+// 75. test_largest_files_view – N lists the largest files and jumps to one
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_largest_files_view() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::export::html::{write_html, HtmlOptions};
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::handle_key_event;
+
+    // /test: a.txt 1000, b.txt 2000, sub/c.txt 500, plus a stand-in for folded files
+    let mut root = sample_tree();
+    let mut folded = Node::from_file(
+        PathBuf::from("/test/sub/9 files under 10.00 MB"),
+        "9 files under 10.00 MB".into(),
+        9000,
+        None,
+        None,
+    );
+    folded.file_count = 9;
+    let sub = root.children.iter_mut().find(|c| c.name == "sub").unwrap();
+    sub.children.push(folded);
+    let root = Node::from_directory(root.path.clone(), root.name.clone(), root.children);
+    let result = make_scan_result(root);
+
+    // Stand-ins are not files of their own
+    let index = SizeIndex::build_files(&result.root);
+    let ranked: Vec<_> = index.top_n(10).iter().map(|(_, size)| *size).collect();
+    assert_eq!(ranked, [2000, 1000, 500]);
+
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(result.clone());
+    handle_key_event(KeyEvent::from(KeyCode::Char('N')), &mut state);
+    assert_eq!(state.view_mode, ViewMode::LargestFiles);
+    assert_eq!(state.largest_files().len(), 3);
+    state.move_largest_files_cursor(5);
+    assert_eq!(state.largest_files_cursor, 2);
+    handle_key_event(KeyEvent::from(KeyCode::Enter), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+    assert_eq!(state.current_path, PathBuf::from("/test/sub"));
+    let selected = &state.sorted_children()[state.selected_index];
+    assert_eq!(selected.path, PathBuf::from("/test/sub/c.txt"));
+
+    // The report lists as many as asked for, in order
+    let mut out = Vec::new();
+    let options = HtmlOptions {
+        top_files: 2,
+        ..Default::default()
+    };
+    write_html(&result, &mut out, &options).unwrap();
+    let html = String::from_utf8(out).unwrap();
+    let section = &html[html.find("<h2>Largest Files</h2>").unwrap()..];
+    let section = &section[..section.find("</table>").unwrap()];
+    assert!(section.find("/test/b.txt").unwrap() < section.find("/test/a.txt").unwrap());
+    assert!(!section.contains("c.txt"));
+
+    let dir = make_test_dir("largest_files_view");
+    let md_path = dir.join("report.md");
+    disklens::export::markdown::export_markdown(&result, &md_path).unwrap();
+    let md = std::fs::read_to_string(&md_path).unwrap();
+    assert!(md.contains("## Largest Files\n\n| # | Path | Size |"));
+    assert!(md.contains("| 1 | /test/b.txt | 1.95 KB |"));
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 76. test_empty_dirs – finding empty directories and deleting the picked ones
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_empty_dirs() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::core::cleanup::remove_dir_checked;
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::{handle_key_event, InputAction};
+
+    let dir = make_test_dir("empty_dirs");
+    std::fs::create_dir_all(dir.join("hollow/a/b")).unwrap();
+    std::fs::create_dir_all(dir.join("hollow/c")).unwrap();
+    std::fs::create_dir_all(dir.join("tiny")).unwrap();
+    std::fs::write(dir.join("tiny/.keep"), "x").unwrap();
+    std::fs::create_dir_all(dir.join("full/empty")).unwrap();
+    std::fs::write(dir.join("full/data.bin"), vec![0u8; 10_000]).unwrap();
+    let result = scan_with(&dir, Settings::default()).await;
+
+    // Only the outermost match is listed; `full` is too big but holds an empty one
+    let found = Analyzer::find_empty_dirs(&result.root, 16);
+    let listed: Vec<_> = found
+        .iter()
+        .map(|d| (d.path.strip_prefix(&dir).unwrap().to_path_buf(), d.is_empty()))
+        .collect();
+    assert_eq!(
+        listed,
+        [
+            (PathBuf::from("full/empty"), true),
+            (PathBuf::from("hollow"), true),
+            (PathBuf::from("tiny"), false),
+        ]
+    );
+    assert_eq!(Analyzer::find_empty_dirs(&result.root, 0).len(), 2);
+
+    // Nothing is deleted that holds more on disk than it was listed with
+    std::fs::write(dir.join("hollow/c/new.txt"), "new").unwrap();
+    assert!(remove_dir_checked(&dir.join("hollow"), 0, 0).is_err());
+    assert!(dir.join("hollow/c/new.txt").exists());
+    std::fs::remove_file(dir.join("hollow/c/new.txt")).unwrap();
+
+    // Pick two in the finder, confirm, and the tree follows the deletion
+    let mut state = AppState::new(dir.clone());
+    state.near_empty_size = 16;
+    state.set_scan_result(result);
+    handle_key_event(KeyEvent::from(KeyCode::Char('E')), &mut state);
+    assert_eq!(state.view_mode, ViewMode::EmptyDirs);
+    assert_eq!(state.empty_dirs.len(), 3);
+    state.move_empty_dirs_cursor(1);
+    handle_key_event(KeyEvent::from(KeyCode::Char(' ')), &mut state);
+    handle_key_event(KeyEvent::from(KeyCode::Char(' ')), &mut state);
+    assert_eq!(state.picked_empty_dirs.len(), 2);
+    handle_key_event(KeyEvent::from(KeyCode::Char('d')), &mut state);
+    assert!(state.confirm_delete);
+    // Any other key backs out
+    let action = handle_key_event(KeyEvent::from(KeyCode::Char('n')), &mut state);
+    assert!(matches!(action, InputAction::None) && !state.confirm_delete);
+    handle_key_event(KeyEvent::from(KeyCode::Char('d')), &mut state);
+    let action = handle_key_event(KeyEvent::from(KeyCode::Char('y')), &mut state);
+    assert!(matches!(action, InputAction::DeleteEmptyDirs));
+
+    let mut deleted = Vec::new();
+    for target in state.dirs_to_delete() {
+        remove_dir_checked(&target.path, target.file_count, target.size).unwrap();
+        deleted.push(target.path.clone());
+    }
+    state.remove_deleted(&deleted);
+    assert!(!dir.join("hollow").exists() && !dir.join("tiny").exists());
+    assert!(dir.join("full/empty").exists());
+    let result = state.scan_result.as_ref().unwrap();
+    assert_eq!(result.total_files, 1);
+    assert!(result.root.find(&dir.join("tiny")).is_none());
+    assert_eq!(state.empty_dirs.len(), 1);
+    assert!(state.picked_empty_dirs.is_empty());
+
+    // By default a directory holding only empty files isn't listed
+    std::fs::create_dir_all(dir.join("full/blank")).unwrap();
+    std::fs::write(dir.join("full/blank/.keep"), "").unwrap();
+    let result = scan_with(&dir.join("full"), Settings::default()).await;
+    let near_empty_size = Settings::default().near_empty_size;
+    let found = Analyzer::find_empty_dirs(&result.root, near_empty_size);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].path, dir.join("full/empty"));
+    assert_eq!(Analyzer::find_empty_dirs(&result.root, 16).len(), 2);
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 77. test_scan_diff – changes between two scans, from exports and in the TUI
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_scan_diff() {
+    use disklens::core::diff::{ChangeKind, DiffResult};
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    // /test: a.txt 1000, b.txt 2000, sub/c.txt 500
+    let old = make_scan_result(sample_tree());
+    let file = |path: &str, size: u64| {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        Node::from_file(path, name, size, None, None)
+    };
+    let added = Node::from_directory(
+        PathBuf::from("/test/new"),
+        "new".into(),
+        vec![file("/test/new/d.bin", 4000)],
+    );
+    let sub = Node::from_directory(
+        PathBuf::from("/test/sub"),
+        "sub".into(),
+        vec![file("/test/sub/c.txt", 200)],
+    );
+    let root = Node::from_directory(
+        PathBuf::from("/test"),
+        "test".into(),
+        vec![file("/test/a.txt", 1000), sub, added],
+    );
+    let mut new = make_scan_result(root);
+    new.timestamp = old.timestamp + Duration::from_secs(3600);
+
+    let diff = DiffResult::compare(&old, &new);
+    assert_eq!(diff.delta(), 5200 - 3500);
+    let changes: Vec<_> = diff
+        .changes
+        .iter()
+        .map(|c| (c.path.to_str().unwrap(), c.kind, c.delta()))
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            // Added subtrees are listed at their top only
+            ("/test/new", ChangeKind::Added, 4000),
+            ("/test/b.txt", ChangeKind::Removed, -2000),
+            ("/test", ChangeKind::Grown, 1700),
+            ("/test/sub", ChangeKind::Shrunk, -300),
+            ("/test/sub/c.txt", ChangeKind::Shrunk, -300),
+        ]
+    );
+    assert_eq!(diff.deltas().get(&PathBuf::from("/test/sub")), Some(&-300));
+    assert!(!diff.deltas().contains_key(&PathBuf::from("/test/b.txt")));
+
+    // Exports read back compare the same
+    let dir = make_test_dir("scan_diff");
+    export_json(&old, &dir.join("old.json")).unwrap();
+    let reloaded = disklens::export::json::import_json(&dir.join("old.json")).unwrap();
+    assert_eq!(DiffResult::compare(&reloaded, &new).changes, diff.changes);
+    cleanup(&dir);
+
+    // The file list shows each entry's change beside its size
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(new);
+    state.set_scan_diff(Some(&diff));
+    let mut terminal = Terminal::new(TestBackend::new(140, 24)).unwrap();
+    terminal.draw(|frame| disklens::ui::renderer::render(frame, &state)).unwrap();
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..buffer.area.height)
+        .map(|y| {
+            let row: String = (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect();
+            row + "\n"
+        })
+        .collect();
+    assert!(screen.contains("+3.91 KB"), "{}", screen);
+    assert!(screen.contains("-300 B"), "{}", screen);
+    assert!(screen.contains("changes since"), "{}", screen);
+}
+
+// ---------------------------------------------------------------------------
+// 78. test_growth_trends – per-directory growth over past scans, and its view
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_growth_trends() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::core::cache::{HistorySnapshot, SizeHistory};
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::handle_key_event;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    let day = |n: u64| SystemTime::UNIX_EPOCH + Duration::from_secs(n * 86_400);
+    let snapshot = |n: u64, children: &[(&str, u64)]| HistorySnapshot {
+        timestamp: day(n),
+        total_size: children.iter().map(|(_, size)| size).sum(),
+        children: children.iter().map(|(path, size)| (PathBuf::from(path), *size)).collect(),
+    };
+    let mut history = SizeHistory {
+        snapshots: vec![snapshot(0, &[("/test/a", 100), ("/test/b", 500)])],
+    };
+    // One scan has nothing to compare with
+    assert!(Analyzer::total_growth(&history).is_none());
+    assert!(Analyzer::growth_trends(&history).is_empty());
+
+    history.snapshots.push(snapshot(1, &[("/test/a", 200), ("/test/b", 400)]));
+    history.snapshots.push(snapshot(2, &[("/test/a", 300), ("/test/b", 300), ("/test/c", 60)]));
+
+    let total = Analyzer::total_growth(&history).unwrap();
+    assert_eq!(total.sizes, [600, 600, 660]);
+    assert!((total.bytes_per_day - 30.0).abs() < 1e-9);
+
+    let trends = Analyzer::growth_trends(&history);
+    let summary: Vec<_> = trends
+        .iter()
+        .map(|t| (t.path.to_str().unwrap(), t.bytes_per_day.round() as i64, t.change()))
+        .collect();
+    // Fastest growing first; a directory missing from a scan counts as empty there
+    assert_eq!(summary, [("/test/a", 100, 200), ("/test/c", 30, 60), ("/test/b", -100, -200)]);
+    assert_eq!(trends[1].sizes, [0, 0, 60]);
+
+    // 'H' opens the view in the TUI
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(sample_tree()));
+    state.size_history = Some(history);
+    handle_key_event(KeyEvent::from(KeyCode::Char('H')), &mut state);
+    assert_eq!(state.view_mode, ViewMode::History);
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+    terminal.draw(|frame| disklens::ui::renderer::render(frame, &state)).unwrap();
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..buffer.area.height)
+        .map(|y| {
+            let row: String = (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect();
+            row + "\n"
+        })
+        .collect();
+    assert!(screen.contains("Growth over 3 scans"), "{}", screen);
+    assert!(screen.contains("+100 B/day"), "{}", screen);
+    assert!(screen.contains("-100 B/day"), "{}", screen);
+    handle_key_event(KeyEvent::from(KeyCode::Esc), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+}
+
+// ---------------------------------------------------------------------------
+// 79. test_treemap_layout – squarified tiles fill their parent without overlapping
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_treemap_layout() {
+    use disklens::core::treemap::{layout, squarify, TreemapRect};
+
+    let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+    let overlap = |a: &TreemapRect, b: &TreemapRect| {
+        a.x + a.w > b.x + 1e-9
+            && b.x + b.w > a.x + 1e-9
+            && a.y + a.h > b.y + 1e-9
+            && b.y + b.h > a.y + 1e-9
+    };
+
+    // The worked example from the squarified treemap paper, on a 6 x 4 rectangle
+    let bounds = TreemapRect { x: 0.0, y: 0.0, w: 6.0, h: 4.0 };
+    let sizes = [2, 6, 1, 6, 4, 3, 2];
+    let rects = squarify(&sizes, bounds);
+    assert_eq!(rects.len(), sizes.len());
+    for (rect, &size) in rects.iter().zip(&sizes) {
+        // Results come back in input order, sized in proportion
+        assert!(close(rect.area(), size as f64), "{:?} for {}", rect, size);
+        assert!(rect.x >= -1e-9 && rect.y >= -1e-9);
+        assert!(rect.x + rect.w <= 6.0 + 1e-9 && rect.y + rect.h <= 4.0 + 1e-9);
+    }
+    for (i, a) in rects.iter().enumerate() {
+        for b in &rects[i + 1..] {
+            assert!(!overlap(a, b), "{:?} overlaps {:?}", a, b);
+        }
+    }
+    // The two largest share the first column, as in the paper
+    assert!(close(rects[1].x, 0.0) && close(rects[3].x, 0.0));
+    assert!(close(rects[1].w, 3.0));
+
+    // Nothing to divide
+    assert!(squarify(&[0, 0], bounds).iter().all(|r| r.area() == 0.0));
+    assert!(squarify(&[], bounds).is_empty());
+
+    // /test: a.txt 1000, b.txt 2000, sub/c.txt 500
+    let tiles = layout(&sample_tree(), 2);
+    let names: Vec<_> = tiles.iter().map(|t| (t.name.as_str(), t.depth)).collect();
+    assert_eq!(names, [("a.txt", 0), ("b.txt", 0), ("sub", 0), ("c.txt", 1)]);
+    let top_area: f64 = tiles.iter().filter(|t| t.depth == 0).map(|t| t.rect.area()).sum();
+    assert!(close(top_area, 1.0));
+    assert!(close(tiles[1].rect.area(), 2000.0 / 3500.0));
+    // Entries of a directory fill its tile
+    assert_eq!(tiles[3].rect, tiles[2].rect);
+    assert_eq!(layout(&sample_tree(), 1).len(), 3);
+}
+
+// ---------------------------------------------------------------------------
+// 80. test_junk_dirs – regenerable directories are tagged and listed for cleanup
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_junk_dirs() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::handle_key_event;
+
+    let file = |path: &str, size: u64| {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        Node::from_file(path, name, size, None, None)
+    };
+    let dir = |path: &str, children: Vec<Node>| {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        Node::from_directory(path, name, children)
+    };
+    let tree = || {
+        dir(
+            "/w",
+            vec![
+                dir(
+                    "/w/app",
+                    vec![
+                        file("/w/app/Cargo.toml", 10),
+                        dir("/w/app/target", vec![file("/w/app/target/app", 5000)]),
+                        dir(
+                            "/w/app/node_modules",
+                            vec![dir(
+                                "/w/app/node_modules/x/node_modules",
+                                vec![file("/w/app/node_modules/x/node_modules/y.js", 300)],
+                            )],
+                        ),
+                    ],
+                ),
+                // A target directory without a Cargo.toml beside it is someone's data
+                dir("/w/target", vec![file("/w/target/keep.dat", 7000)]),
+                dir("/w/scratch", vec![file("/w/scratch/tmp.bin", 200)]),
+            ],
+        )
+    };
+
+    let mut root = tree();
+    Analyzer::tag_junk(&mut root, &[]);
+    let dirs = Analyzer::junk_dirs(&root);
+    let summary: Vec<_> =
+        dirs.iter().map(|d| (d.path.to_str().unwrap(), d.label.as_str(), d.size)).collect();
+    // Nested node_modules count once, with the outer one
+    assert_eq!(
+        summary,
+        [("/w/app/target", "Rust build output", 5000), ("/w/app/node_modules", "npm packages", 300)]
+    );
+    assert_eq!(root.junk_size(), 5300);
+
+    // Names added by the user are recognized too
+    let mut root = tree();
+    Analyzer::tag_junk(&mut root, &["scratch".to_string()]);
+    assert_eq!(Analyzer::junk_dirs(&root).len(), 3);
+    assert_eq!(root.find(Path::new("/w/scratch")).unwrap().junk.as_deref(), Some("custom"));
+
+    // The cleanup view lists them and Enter goes to the one picked
+    let mut state = AppState::new(PathBuf::from("/w"));
+    state.set_scan_result(make_scan_result(tree()));
+    handle_key_event(KeyEvent::from(KeyCode::Char('C')), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Cleanup);
+    assert_eq!(state.cleanup_candidates.len(), 2);
+    handle_key_event(KeyEvent::from(KeyCode::Char('j')), &mut state);
+    handle_key_event(KeyEvent::from(KeyCode::Enter), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+    assert_eq!(state.current_path, PathBuf::from("/w/app"));
+    let selected = &state.sorted_children()[state.selected_index];
+    assert_eq!(selected.path, PathBuf::from("/w/app/node_modules"));
+}
+
+// ---------------------------------------------------------------------------
+// 81. test_cleanup_suggestions – ranked ways to free space, in the TUI and reports
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_cleanup_suggestions() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::core::analyzer::{Risk, SuggestionKind};
+    use disklens::export::html::{write_html, HtmlOptions};
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::handle_key_event;
+
+    const MIB: u64 = 1024 * 1024;
+    let now = SystemTime::now();
+    let file = |path: &str, size: u64, modified: Option<SystemTime>| {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        Node::from_file(path, name, size, modified, None)
+    };
+    let dir = |path: &str, children: Vec<Node>| {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        Node::from_directory(path, name, children)
+    };
+    let two_years_ago = now - Duration::from_secs(730 * 86_400);
+    let mut root = dir(
+        "/h",
+        vec![
+            dir(
+                "/h/app",
+                vec![dir(
+                    "/h/app/node_modules",
+                    vec![file("/h/app/node_modules/m.js", 50 * MIB, None)],
+                )],
+            ),
+            dir("/h/.Trash", vec![file("/h/.Trash/gone.txt", 10 * MIB, None)]),
+            dir("/h/a", vec![file("/h/a/movie.mkv", 40 * MIB, None)]),
+            dir("/h/b", vec![file("/h/b/movie.mkv", 40 * MIB, None)]),
+            file("/h/old.iso", 300 * MIB, Some(two_years_ago)),
+            // Large but recently modified
+            file("/h/new.iso", 300 * MIB, Some(now)),
+            // Copies too small to be worth suggesting
+            dir("/h/c", vec![file("/h/c/note.txt", 100, None)]),
+            dir("/h/d", vec![file("/h/d/note.txt", 100, None)]),
+        ],
+    );
+    Analyzer::tag_junk(&mut root, &[]);
+
+    let suggestions = Analyzer::suggestions(&root, now);
+    let summary: Vec<_> = suggestions
+        .iter()
+        .map(|s| (s.path.to_str().unwrap(), s.kind, s.risk, s.reclaimable / MIB))
+        .collect();
+    // Bytes weighed by risk: 300 MiB at high risk still outranks 50 MiB at low
+    assert_eq!(
+        summary,
+        [
+            ("/h/old.iso", SuggestionKind::OldFile, Risk::High, 300),
+            ("/h/app/node_modules", SuggestionKind::Junk, Risk::Low, 50),
+            ("/h/b/movie.mkv", SuggestionKind::Duplicate, Risk::Medium, 40),
+            ("/h/.Trash", SuggestionKind::Trash, Risk::Low, 10),
+        ]
+    );
+    assert_eq!(suggestions[2].reason, "same name and size as /h/a/movie.mkv");
+    assert!(suggestions[0].reason.starts_with("not modified in 730 days"));
+
+    // The report lists them too
+    let result = make_scan_result(root.clone());
+    let mut html = Vec::new();
+    write_html(&result, &mut html, &HtmlOptions::default()).unwrap();
+    let html = String::from_utf8(html).unwrap();
+    assert!(html.contains("<h2>Cleanup Suggestions (400.00 MB reclaimable)</h2>"), "{}", html);
+    assert!(html.contains("<td class=\"risk-high\">high</td><td>old file</td><td>/h/old.iso</td>"));
+
+    // 'S' opens the panel and Enter goes to the suggestion under the cursor
+    let mut state = AppState::new(PathBuf::from("/h"));
+    state.set_scan_result(make_scan_result(root));
+    handle_key_event(KeyEvent::from(KeyCode::Char('S')), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Suggestions);
+    assert_eq!(state.suggestions.len(), 4);
+    handle_key_event(KeyEvent::from(KeyCode::Char('j')), &mut state);
+    handle_key_event(KeyEvent::from(KeyCode::Enter), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+    assert_eq!(state.current_path, PathBuf::from("/h/app"));
+}
+
+// ---------------------------------------------------------------------------
+// 82. test_owners_csv – the per-user table as CSV, and its TUI overlay
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_owners_csv() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::core::analyzer::OwnerUsage;
+    use disklens::export::csv::write_owners_csv;
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::{handle_key_event, InputAction};
+
+    let usage = vec![
+        OwnerUsage { uid: Some(1000), name: Some("doe, \"jd\"".into()), size: 300, file_count: 2 },
+        OwnerUsage { uid: Some(1001), name: None, size: 100, file_count: 1 },
+        OwnerUsage { uid: None, name: None, size: 0, file_count: 0 },
+    ];
+    assert_eq!(usage[1].label(), "uid 1001");
+    assert_eq!(usage[2].label(), "unknown");
+
+    let mut out = Vec::new();
+    write_owners_csv(&usage, &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "user,uid,size_bytes,files,percent\n\
+         \"doe, \"\"jd\"\"\",1000,300,2,75.00\n\
+         ,1001,100,1,25.00\n\
+         ,,0,0,0.00\n"
+    );
+
+    // 'O' lists the owners of the current directory; x asks for the CSV
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(sample_tree()));
+    handle_key_event(KeyEvent::from(KeyCode::Char('O')), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Owners);
+    assert_eq!(state.owners.len(), 1);
+    assert_eq!(state.owners[0].size, 3500);
+    let action = handle_key_event(KeyEvent::from(KeyCode::Char('x')), &mut state);
+    assert!(matches!(action, InputAction::ExportOwners));
+    handle_key_event(KeyEvent::from(KeyCode::Esc), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+    assert!(state.owners.is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// 83. test_compression_estimate – sampled zstd ratios per entry, and the Z view
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_compression_estimate() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::core::compression::estimate_children;
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::{handle_key_event, InputAction};
+
+    let dir = make_test_dir("compression_estimate");
+    std::fs::create_dir_all(dir.join("logs")).unwrap();
+    std::fs::create_dir_all(dir.join("media")).unwrap();
+    for i in 0..3 {
+        let text = format!("request {} served in 12ms\n", i).repeat(4000);
+        std::fs::write(dir.join(format!("logs/{}.log", i)), text).unwrap();
+    }
+    // Pseudo-random bytes don't compress
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let noise: Vec<u8> = (0..100_000)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect();
+    std::fs::write(dir.join("media/clip.bin"), &noise).unwrap();
+
+    let result = scan_with(&dir, test_settings()).await;
+    let estimates = estimate_children(&result.root);
+    let names: Vec<&str> = estimates.iter().map(|e| e.name.as_str()).collect();
+    // The most to gain first
+    assert_eq!(names, ["logs", "media"]);
+    assert!(estimates[0].ratio.unwrap() < 0.1, "{:?}", estimates[0]);
+    assert_eq!(estimates[0].sampled_files, 3);
+    assert!(estimates[1].ratio.unwrap() > 0.99, "{:?}", estimates[1]);
+    assert_eq!(estimates[1].savings(), 0);
+
+    // Files gone since the scan are skipped
+    std::fs::remove_file(dir.join("media/clip.bin")).unwrap();
+    let estimates = estimate_children(&result.root);
+    let media = estimates.iter().find(|e| e.name == "media").unwrap();
+    assert_eq!((media.ratio, media.sampled_files, media.savings()), (None, 0, 0));
+
+    // Z opens the view and asks for the estimate, which lands if still wanted
+    let mut state = AppState::new(dir.clone());
+    state.set_scan_result(result);
+    let action = handle_key_event(KeyEvent::from(KeyCode::Char('Z')), &mut state);
+    assert!(matches!(action, InputAction::EstimateCompression));
+    assert_eq!(state.view_mode, ViewMode::Compression);
+    assert!(state.compression.is_none());
+    state.set_compression(&dir.join("logs"), estimates.clone());
+    assert!(state.compression.is_none());
+    state.set_compression(&dir, estimates);
+    assert_eq!(state.compression.as_ref().map(Vec::len), Some(2));
+    handle_key_event(KeyEvent::from(KeyCode::Esc), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+    assert!(state.compression.is_none());
+
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 84. test_tree_stats – depth and fan-out of a tree, and the I view
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_tree_stats() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::handle_key_event;
+
+    let file = |path: &str| {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        Node::from_file(path, name, 10, None, None)
+    };
+    let dir = |path: &str, children: Vec<Node>| {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        Node::from_directory(path, name, children)
+    };
+    // /t holds ten files and two directories; a/b/c/deep.txt is the deepest entry
+    let mut entries: Vec<Node> = (0..10).map(|i| file(&format!("/t/f{}", i))).collect();
+    let chain = dir("/t/a/b/c", vec![file("/t/a/b/c/deep.txt")]);
+    entries.push(dir("/t/a", vec![dir("/t/a/b", vec![chain])]));
+    entries.push(dir("/t/e", Vec::new()));
+    let root = dir("/t", entries);
+
+    let stats = Analyzer::tree_stats(&root);
+    assert_eq!(stats.max_depth, 4);
+    // Fan-outs: /t 12, a 1, b 1, c 1, e 0
+    assert_eq!(stats.directories, 5);
+    assert!((stats.average_fan_out - 3.0).abs() < 1e-9);
+    assert_eq!(stats.fan_out_percentiles, [1, 12, 12]);
+    assert_eq!(stats.max_fan_out, 12);
+    assert_eq!(stats.deepest.len(), 10);
+    assert_eq!(stats.deepest[0].path, PathBuf::from("/t/a/b/c/deep.txt"));
+    assert_eq!(stats.deepest[0].value, 4);
+    assert_eq!(stats.deepest[1].path, PathBuf::from("/t/e"));
+    assert_eq!(stats.deepest[1].value, 1);
+    let widest: Vec<(&Path, usize)> =
+        stats.widest.iter().map(|w| (w.path.as_path(), w.value)).collect();
+    assert_eq!(
+        widest,
+        vec![
+            (Path::new("/t"), 12),
+            (Path::new("/t/a"), 1),
+            (Path::new("/t/a/b"), 1),
+            (Path::new("/t/a/b/c"), 1),
+        ]
+    );
+
+    let empty = Analyzer::tree_stats(&file("/t/f0"));
+    assert_eq!((empty.max_depth, empty.directories, empty.max_fan_out), (0, 0, 0));
+
+    // 'I' shows the statistics for the current directory
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.set_scan_result(make_scan_result(sample_tree()));
+    handle_key_event(KeyEvent::from(KeyCode::Char('I')), &mut state);
+    assert_eq!(state.view_mode, ViewMode::TreeStats);
+    let stats = state.tree_stats.as_ref().unwrap();
+    assert_eq!((stats.max_depth, stats.directories, stats.max_fan_out), (2, 2, 3));
+    handle_key_event(KeyEvent::from(KeyCode::Esc), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+    assert!(state.tree_stats.is_none());
+}
+
+// ---------------------------------------------------------------------------
+// 85. test_recently_modified – newest files first, and the M view
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_recently_modified() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::ui::app_state::ViewMode;
+    use disklens::ui::input::handle_key_event;
+
+    let now = SystemTime::now();
+    let hours_ago = |h: u64| Some(now - Duration::from_secs(h * 3600));
+    let file = |path: &str, modified: Option<SystemTime>| {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        Node::from_file(path, name, 100, modified, None)
+    };
+    let mut estimate = file("/r/sub/estimate", hours_ago(0));
+    estimate.estimated = true;
+    let sub = Node::from_directory(
+        PathBuf::from("/r/sub"),
+        "sub".into(),
+        vec![file("/r/sub/new.log", hours_ago(1)), estimate],
+    );
+    let root = Node::from_directory(
+        PathBuf::from("/r"),
+        "r".into(),
+        vec![
+            file("/r/old.txt", hours_ago(48)),
+            file("/r/mid.txt", hours_ago(3)),
+            file("/r/unknown", None),
+            sub,
+        ],
+    );
+
+    let recent = Analyzer::recently_modified(&root, 10);
+    let paths: Vec<&Path> = recent.iter().map(|f| f.path.as_path()).collect();
+    assert_eq!(
+        paths,
+        vec![Path::new("/r/sub/new.log"), Path::new("/r/mid.txt"), Path::new("/r/old.txt")]
+    );
+    assert_eq!(recent[0].modified, hours_ago(1).unwrap());
+    assert_eq!(Analyzer::recently_modified(&root, 1).len(), 1);
+
+    // 'M' lists them; Enter goes to the one under the cursor
+    let mut state = AppState::new(PathBuf::from("/r"));
+    state.set_scan_result(make_scan_result(root));
+    handle_key_event(KeyEvent::from(KeyCode::Char('M')), &mut state);
+    assert_eq!(state.view_mode, ViewMode::RecentFiles);
+    assert_eq!(state.recent_files.len(), 3);
+    handle_key_event(KeyEvent::from(KeyCode::Enter), &mut state);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+    assert!(state.recent_files.is_empty());
+    assert_eq!(state.current_path, PathBuf::from("/r/sub"));
+    let selected = &state.sorted_children()[state.selected_index];
+    assert_eq!(selected.path, PathBuf::from("/r/sub/new.log"));
+}
+
+// ---------------------------------------------------------------------------
+// 86. test_error_attribution – scan errors counted on the nodes they affect
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_error_attribution() {
+    let error = |path: &str, error_type: ScanErrorType| ScanError {
+        path: PathBuf::from(path),
+        error_type,
+        message: "denied".into(),
+    };
+    let mut result = make_scan_result(sample_tree());
+    result.errors = vec![
+        // An unreadable directory, an entry of /test/sub that couldn't be statted, and
+        // a symlink cycle, which leaves nothing out
+        error("/test/sub", ScanErrorType::PermissionDenied),
+        error("/test/sub/locked.txt", ScanErrorType::PermissionDenied),
+        error("/test/loop", ScanErrorType::SymlinkCycle),
+    ];
+    result.attribute_errors();
+    let count = |result: &ScanResult, path: &str| {
+        result.root.find(Path::new(path)).unwrap().error_count
+    };
+    assert_eq!(result.root.error_count, 2);
+    assert_eq!(count(&result, "/test/sub"), 2);
+    assert_eq!(count(&result, "/test/sub/c.txt"), 0);
+    assert_eq!(count(&result, "/test/a.txt"), 0);
+
+    // Counts follow the error list as the tree changes
+    let exported = result.filter_files(|node| node.name != "b.txt");
+    assert_eq!(count(&exported, "/test/sub"), 2);
+    result.remove_paths(&[PathBuf::from("/test/sub")]);
+    assert_eq!(result.root.error_count, 0);
+}
+
+// ---------------------------------------------------------------------------
+// 87. test_cache_size_limit – least recently used scans are evicted past the limit
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_cache_size_limit() {
+    use disklens::core::cache::Cache;
+
+    let cache_dir = make_test_dir("cache_size_limit");
+    let result_at = |root: &str| {
+        let path = PathBuf::from(root);
+        make_scan_result(Node::from_directory(path, root.into(), Vec::new()))
+    };
+    let dir_size = || -> u64 {
+        let files = std::fs::read_dir(&cache_dir).unwrap();
+        files.map(|e| e.unwrap().metadata().unwrap().len()).sum()
+    };
+    let pause = || std::thread::sleep(Duration::from_millis(20));
+
+    // Measure one entry, then allow a little over two of them
+    Cache::new(cache_dir.clone()).save(&result_at("/a")).await.unwrap();
+    let entry_size = dir_size();
+    let cache = Cache::new(cache_dir.clone()).with_max_size(entry_size * 5 / 2);
+    pause();
+    cache.save(&result_at("/b")).await.unwrap();
+    pause();
+    // Loading /a makes /b the least recently used
+    assert!(cache.load_previous(Path::new("/a")).await.is_some());
+    pause();
+    cache.save(&result_at("/c")).await.unwrap();
+
+    assert!(cache.load_previous(Path::new("/a")).await.is_some());
+    assert!(cache.load_previous(Path::new("/b")).await.is_none());
+    assert!(cache.load_history(Path::new("/b")).await.snapshots.is_empty());
+    assert!(cache.load_previous(Path::new("/c")).await.is_some());
+    assert!(dir_size() <= entry_size * 5 / 2);
+
+    // The entry just saved stays even when it alone is over the limit
+    let tiny = Cache::new(cache_dir.clone()).with_max_size(1);
+    tiny.save(&result_at("/d")).await.unwrap();
+    assert!(tiny.load_previous(Path::new("/d")).await.is_some());
+    assert!(tiny.load_previous(Path::new("/a")).await.is_none());
+
+    cleanup(&cache_dir);
+}
+
+// ---------------------------------------------------------------------------
+// 88. test_cache_expiration – scans past the age limit are ignored and swept
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_cache_expiration() {
+    use disklens::core::cache::Cache;
+
+    let cache_dir = make_test_dir("cache_expiration");
+    let result_at = |root: &str, age: Duration| {
+        let path = PathBuf::from(root);
+        let mut result = make_scan_result(Node::from_directory(path, root.into(), Vec::new()));
+        result.timestamp = SystemTime::now() - age;
+        result
+    };
+    let day = Duration::from_secs(86_400);
+    let cache = Cache::new(cache_dir.clone()).with_max_age(7 * day);
+    cache.save(&result_at("/old", 8 * day)).await.unwrap();
+    cache.save(&result_at("/new", day)).await.unwrap();
+
+    assert!(cache.load(Path::new("/old")).await.is_none());
+    assert!(cache.load_previous(Path::new("/old")).await.is_none());
+    assert!(cache.load(Path::new("/new")).await.is_some());
+    // Without a limit the old scan is still there
+    let unlimited = Cache::new(cache_dir.clone());
+    assert!(unlimited.load_previous(Path::new("/old")).await.is_some());
+
+    // The sweep deletes the old scan but keeps its history
+    assert_eq!(cache.remove_expired().await.unwrap(), 1);
+    assert_eq!(cache.remove_expired().await.unwrap(), 0);
+    assert!(unlimited.load_previous(Path::new("/old")).await.is_none());
+    assert_eq!(unlimited.load_history(Path::new("/old")).await.snapshots.len(), 1);
+    assert!(unlimited.load_previous(Path::new("/new")).await.is_some());
+    let names: Vec<String> = std::fs::read_dir(&cache_dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(names.len(), 4, "{:?}", names);
+
+    cleanup(&cache_dir);
+}
+
+// ---------------------------------------------------------------------------
+// 89. test_cached_subtree_reuse – directory entries saved by other roots' scans seed a scan
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_cached_subtree_reuse() {
+    use disklens::core::cache::Cache;
+    use disklens::core::events::create_event_channel;
+    use disklens::core::scanner::Scanner;
+
+    let dir = make_test_dir("cached_subtree_reuse");
+    let cache_dir = make_test_dir("cached_subtree_reuse_cache");
+    std::fs::create_dir_all(dir.join("a/sub")).unwrap();
+    std::fs::create_dir_all(dir.join("b")).unwrap();
+    std::fs::write(dir.join("a/sub/x.bin"), vec![0u8; 100]).unwrap();
+    std::fs::write(dir.join("b/y.bin"), vec![0u8; 50]).unwrap();
+
+    let cache = Cache::new(cache_dir.clone());
+    for root in ["a", "b", "a/sub"] {
+        let result = scan_with(&dir.join(root), test_settings()).await;
+        cache.save(&result).await.unwrap();
+        cache.save_dirs(&result).unwrap().await.unwrap().unwrap();
+    }
+
+    // Rewrites in place leave directory mtimes alone, so reused directories keep the
+    // old sizes; the new file changes the root's mtime
+    std::fs::write(dir.join("a/sub/x.bin"), vec![0u8; 200]).unwrap();
+    std::fs::write(dir.join("b/y.bin"), vec![0u8; 80]).unwrap();
+    std::fs::write(dir.join("top.bin"), vec![0u8; 5]).unwrap();
+
+    // The root was never cached, but a/, a/sub/ and b/ have directory entries
+    let (event_tx, _rx) = create_event_channel();
+    let result = Scanner::new(test_settings(), event_tx)
+        .with_cache(Cache::new(cache_dir.clone()))
+        .scan(dir.clone())
+        .await
+        .unwrap();
+    assert_eq!(result.total_size, 100 + 50 + 5);
+
+    // Saving the root's directories adds an entry for it alone; the others were saved
+    // before, and saving the scan itself adds none
+    let dir_files = || std::fs::read_dir(cache_dir.join("dirs")).unwrap().count();
+    assert_eq!(dir_files(), 3);
+    cache.save(&result).await.unwrap();
+    assert_eq!(dir_files(), 3);
+    cache.save_dirs(&result).unwrap().await.unwrap().unwrap();
+    assert_eq!(dir_files(), 4);
+    assert_eq!(cache.dir_entry_usage().await.unwrap().0, 4);
+
+    // A changed directory is read again, and entries saved with other settings are
+    // left alone
+    std::fs::write(dir.join("b/z.bin"), vec![0u8; 20]).unwrap();
+    let (event_tx, _rx) = create_event_channel();
+    let result = Scanner::new(test_settings(), event_tx)
+        .with_cache(Cache::new(cache_dir.clone()))
+        .scan(dir.clone())
+        .await
+        .unwrap();
+    assert_eq!(result.total_size, 100 + 80 + 20 + 5);
+    let settings = Settings { skip_hidden: !test_settings().skip_hidden, ..test_settings() };
+    let (event_tx, _rx) = create_event_channel();
+    let result = Scanner::new(settings, event_tx)
+        .with_cache(Cache::new(cache_dir.clone()))
+        .scan(dir.clone())
+        .await
+        .unwrap();
+    assert_eq!(result.total_size, 200 + 80 + 20 + 5);
+
+    // Only trees with every size measured are saved for later runs to build on
+    assert!(result.is_cacheable());
+    for settings in [
+        Settings { max_depth: Some(1), ..test_settings() },
+        Settings { min_file_size: Some(10), ..test_settings() },
+        Settings { sample_ratio: Some(0.5), ..test_settings() },
+    ] {
+        assert!(!scan_with(&dir, settings).await.is_cacheable());
+    }
+
+    cleanup(&dir);
+    cleanup(&cache_dir);
+}
+
+// ---------------------------------------------------------------------------
+// 90. test_cache_compression – cached scans are stored zstd-compressed
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_cache_compression() {
+    use disklens::core::cache::Cache;
+
+    let cache_dir = make_test_dir("cache_compression");
+    // Many similar entries, as real trees have
+    let files: Vec<Node> = (0..500)
+        .map(|i| {
+            let path = PathBuf::from(format!("/z/file_{:04}.log", i));
+            Node::from_file(path, format!("file_{:04}.log", i), 4096, None, None)
+        })
+        .collect();
+    let result = make_scan_result(Node::from_directory(PathBuf::from("/z"), "z".into(), files));
+    let cache = Cache::new(cache_dir.clone());
+    cache.save(&result).await.unwrap();
+
+    let cache_file = std::fs::read_dir(&cache_dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.extension().is_some_and(|ext| ext == "cache"))
+        .unwrap();
+    let stored = std::fs::read(&cache_file).unwrap();
+    let plain = bincode::serde::encode_to_vec(&result, bincode::config::standard()).unwrap();
+    assert!(stored.starts_with(b"DLZC"));
+    assert!(stored.len() * 5 < plain.len(), "{} vs {}", stored.len(), plain.len());
+    let loaded = cache.load(Path::new("/z")).await.unwrap();
+    assert_eq!(loaded.total_files, 500);
+    assert_eq!(loaded.root.children[499].name, "file_0499.log");
+
+    cleanup(&cache_dir);
+}
+
+// ---------------------------------------------------------------------------
+// 91. test_cache_integrity – other versions are rejected, damaged entries deleted
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_cache_integrity() {
+    use disklens::core::cache::Cache;
+
+    let cache_dir = make_test_dir("cache_integrity");
+    let cache = Cache::new(cache_dir.clone());
+    let result = make_scan_result(sample_tree());
+    cache.save(&result).await.unwrap();
+    let file_with = |ext: &str| {
+        std::fs::read_dir(&cache_dir)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| p.to_string_lossy().ends_with(ext))
+    };
+    let cache_file = file_with(".cache").unwrap();
+    let stored = std::fs::read(&cache_file).unwrap();
+    assert!(cache.load_previous(Path::new("/test")).await.is_some());
+
+    // Another version's file, or one from before versioning, is ignored but kept
+    let mut other_version = stored.clone();
+    other_version[4] = 99;
+    let plain = bincode::serde::encode_to_vec(&result, bincode::config::standard()).unwrap();
+    for bytes in [other_version, plain] {
+        std::fs::write(&cache_file, &bytes).unwrap();
+        assert!(cache.load_previous(Path::new("/test")).await.is_none());
+        assert!(file_with(".cache").is_some());
+    }
+
+    // A flipped bit or a cut-off write removes the entry, keeping its history
+    let mut flipped = stored.clone();
+    let last = flipped.len() - 1;
+    flipped[last] ^= 0x01;
+    let truncated = stored[..stored.len() - 3].to_vec();
+    for bytes in [flipped, truncated] {
+        cache.save(&result).await.unwrap();
+        std::fs::write(&cache_file, &bytes).unwrap();
+        assert!(cache.load_previous(Path::new("/test")).await.is_none());
+        assert!(file_with(".cache").is_none());
+        assert!(file_with(".meta.json").is_none());
+        assert!(file_with(".history.json").is_some());
+    }
+
+    // So does unreadable metadata
+    cache.save(&result).await.unwrap();
+    std::fs::write(file_with(".meta.json").unwrap(), b"{").unwrap();
+    assert!(cache.load(Path::new("/test")).await.is_none());
+    assert!(file_with(".cache").is_none());
+
+    cleanup(&cache_dir);
+}
+
+// ---------------------------------------------------------------------------
+// 92. test_cache_listing – saved scans are listed by last use and pruned to size
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_cache_listing() {
+    use disklens::config::time_spec::format_age;
+    use disklens::core::cache::Cache;
+
+    let cache_dir = make_test_dir("cache_listing");
+    let cache = Cache::new(cache_dir.clone());
+    assert!(cache.list().await.unwrap().is_empty());
+    let result_at = |root: &str| {
+        let path = PathBuf::from(root);
+        make_scan_result(Node::from_directory(path, root.into(), Vec::new()))
+    };
+    cache.save(&make_scan_result(sample_tree())).await.unwrap();
+    std::thread::sleep(Duration::from_millis(20));
+    cache.save(&result_at("/other")).await.unwrap();
+
+    let scans = cache.list().await.unwrap();
+    assert_eq!(scans.len(), 2);
+    assert_eq!(scans[0].path.as_deref(), Some(Path::new("/other")));
+    assert_eq!(scans[1].path.as_deref(), Some(Path::new("/test")));
+    assert_eq!(scans[1].tree_size, Some(3500));
+    assert!(scans.iter().all(|s| s.has_scan && s.size > 0 && s.scanned_at.is_some()));
+
+    // Shrinking to one entry's size keeps the most recently used one
+    let limit = scans[0].size;
+    let pruned = Cache::new(cache_dir.clone()).with_max_size(limit);
+    assert_eq!(pruned.shrink_to_limit().await.unwrap(), 1);
+    let scans = cache.list().await.unwrap();
+    assert_eq!(scans.len(), 1);
+    assert_eq!(scans[0].path.as_deref(), Some(Path::new("/other")));
+
+    assert_eq!(format_age(Duration::from_secs(45)), "45s");
+    assert_eq!(format_age(Duration::from_secs(2 * 3600 + 59)), "2h");
+    assert_eq!(format_age(Duration::from_secs(9 * 86400)), "1w");
+
+    cleanup(&cache_dir);
+}
+
+// ---------------------------------------------------------------------------
+// 93. test_cached_scan_banner – a cached tree is shown with its age until replaced
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_cached_scan_banner() {
+    use disklens::ui::app_state::ViewMode;
+
+    let now = SystemTime::now();
+    let mut cached = make_scan_result(sample_tree());
+    cached.timestamp = now - Duration::from_secs(2 * 3600 + 300);
+
+    // Fresh enough: shown as is, with a hint to rescan
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.show_cached_scan(cached.clone(), false);
+    assert!(!state.scanning);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+    assert_eq!(state.cache_banner(now).as_deref(), Some("age: 2h, press r to rescan"));
+
+    // Stale: the background rescan's subtrees don't replace the cached tree
+    let mut state = AppState::new(PathBuf::from("/test"));
+    state.show_cached_scan(cached, true);
+    assert!(state.cache_banner(now).unwrap().starts_with("age: 2h, rescanning"));
+    let partial = Node::from_directory(PathBuf::from("/test/sub"), "sub".into(), Vec::new());
+    state.add_subtree(&[PathBuf::from("/test")], SizeMode::Apparent, partial);
+    assert_eq!(state.scan_result.as_ref().unwrap().total_size, 3500);
+
+    // The finished rescan takes over and the banner goes away
+    let fresh = make_scan_result(Node::from_directory(
+        PathBuf::from("/test"),
+        "test".into(),
+        Vec::new(),
+    ));
+    state.set_scan_result(fresh);
+    assert!(!state.refreshing);
+    assert_eq!(state.cache_banner(now), None);
+    assert_eq!(state.scan_result.as_ref().unwrap().total_size, 0);
+}
+
+// ---------------------------------------------------------------------------
+// 94. test_snapshots – named scans are kept apart from the cache until deleted
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_snapshots() {
+    use disklens::core::cache::Cache;
+
+    let cache_dir = make_test_dir("snapshots");
+    let cache = Cache::new(cache_dir.clone());
+    assert!(cache.list_snapshots().await.unwrap().is_empty());
+
+    let mut before = make_scan_result(sample_tree());
+    before.timestamp = SystemTime::now() - Duration::from_secs(3600);
+    let after = make_scan_result(Node::from_directory(
+        PathBuf::from("/test"),
+        "test".into(),
+        Vec::new(),
+    ));
+    cache.save_snapshot("before-cleanup", &before, false).await.unwrap();
+    cache.save_snapshot("after", &after, false).await.unwrap();
+    assert!(cache.save_snapshot("after", &before, false).await.is_err());
+    assert!(cache.has_snapshot("after").unwrap());
+    for name in ["", "../up", "a/b", ".hidden"] {
+        assert!(cache.save_snapshot(name, &after, false).await.is_err());
+    }
+
+    let snapshots = cache.list_snapshots().await.unwrap();
+    let names: Vec<&str> = snapshots.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["before-cleanup", "after"]);
+    assert_eq!(snapshots[0].total_size, 3500);
+    assert_eq!(snapshots[0].path, PathBuf::from("/test"));
+    let loaded = cache.load_snapshot("before-cleanup").await.unwrap();
+    assert_eq!(loaded.total_size, 3500);
+    assert!(cache.load_snapshot("missing").await.is_err());
+
+    // Replacing, and clearing the cache, which leaves snapshots alone
+    cache.save_snapshot("after", &before, true).await.unwrap();
+    assert_eq!(cache.load_snapshot("after").await.unwrap().total_size, 3500);
+    cache.clear().await.unwrap();
+    assert!(cache.list().await.unwrap().is_empty());
+    assert_eq!(cache.list_snapshots().await.unwrap().len(), 2);
+
+    cache.remove_snapshot("after").await.unwrap();
+    assert!(cache.remove_snapshot("after").await.is_err());
+    assert_eq!(cache.list_snapshots().await.unwrap().len(), 1);
+
+    cleanup(&cache_dir);
+}
+
+// ---------------------------------------------------------------------------
+// 95. test_cache_encryption – scans saved with a passphrase need it to be read
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_cache_encryption() {
+    use disklens::core::cache::Cache;
+
+    let cache_dir = make_test_dir("cache_encryption");
+    let locked = || Cache::new(cache_dir.clone()).with_passphrase(Some("hunter2".into()));
+    let mut result = make_scan_result(sample_tree());
+    let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    result.root.modified = Some(mtime);
+    // Directory entries saved in plain text are sealed once a passphrase is set
+    Cache::new(cache_dir.clone()).save_dirs(&result).unwrap().await.unwrap().unwrap();
+    locked().save(&result).await.unwrap();
+    locked().save_dirs(&result).unwrap().await.unwrap().unwrap();
+    let cache_file = std::fs::read_dir(&cache_dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .find(|p| p.to_string_lossy().ends_with(".cache"))
+        .unwrap();
+    let stored = std::fs::read(&cache_file).unwrap();
+    assert!(!stored.windows(5).any(|w| w == b"c.txt"));
+
+    let loaded = locked().load_previous(Path::new("/test")).await.unwrap();
+    assert_eq!(loaded.total_size, 3500);
+
+    // Without the passphrase, or with another, the scan is unreadable but kept
+    let wrong = Cache::new(cache_dir.clone()).with_passphrase(Some("hunter3".into()));
+    assert!(wrong.load_previous(Path::new("/test")).await.is_none());
+    assert!(Cache::new(cache_dir.clone()).load_previous(Path::new("/test")).await.is_none());
+    assert_eq!(std::fs::read(&cache_file).unwrap(), stored);
+
+    // The directory entries are sealed as well
+    let settings = &result.tree_settings;
+    let entry = locked().load_dir(Path::new("/test"), mtime, settings).await.unwrap();
+    assert_eq!(entry.children.len(), 3);
+    assert!(wrong.load_dir(Path::new("/test"), mtime, settings).await.is_none());
+    for file in std::fs::read_dir(cache_dir.join("dirs")).unwrap() {
+        let stored = std::fs::read(file.unwrap().path()).unwrap();
+        assert!(!stored.windows(5).any(|w| w == b"a.txt"));
+    }
+
+    // Scans saved before encryption was turned on are still read
+    Cache::new(cache_dir.clone()).save(&result).await.unwrap();
+    assert!(locked().load_previous(Path::new("/test")).await.is_some());
+
+    // Snapshots too, with an error naming the problem
+    locked().save_snapshot("secret", &result, false).await.unwrap();
+    assert!(locked().load_snapshot("secret").await.is_ok());
+    let error = wrong.load_snapshot("secret").await.unwrap_err().to_string();
+    assert!(error.contains("passphrase"), "{}", error);
+
+    cleanup(&cache_dir);
+}
+
+// ---------------------------------------------------------------------------
+// 96. test_tree_csv – one row per node, limited in depth, with any delimiter
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_tree_csv() {
+    use disklens::export::csv::{write_tree_csv, CsvOptions};
+
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+    let mut file = Node::from_file(
+        PathBuf::from("/test/a; b/c.txt"),
+        "c.txt".into(),
+        100,
+        Some(modified),
+        Some(1),
+    );
+    file.size_on_disk = 4096;
+    let dir = Node::from_directory(PathBuf::from("/test/a; b"), "a; b".into(), vec![file]);
+    let mut result =
+        make_scan_result(Node::from_directory(PathBuf::from("/test"), "test".into(), vec![dir]));
+
+    let csv = |result: &ScanResult, options: CsvOptions| {
+        let mut out = Vec::new();
+        write_tree_csv(result, &mut out, &options).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert_eq!(
+        csv(&result, CsvOptions::default()),
+        "path,type,size,size_on_disk,file_count,mtime,depth\n\
+         /test,directory,100,4096,1,,0\n\
+         /test/a; b,directory,100,4096,1,,1\n\
+         /test/a; b/c.txt,file,100,4096,1,2023-11-14T22:13:20Z,2\n"
+    );
+
+    // Both sizes keep their columns in disk usage mode; the delimiter is quoted around
+    result.set_size_mode(SizeMode::DiskUsage);
+    let options = CsvOptions { max_depth: Some(1), delimiter: ';' };
+    assert_eq!(
+        csv(&result, options),
+        "path;type;size;size_on_disk;file_count;mtime;depth\n\
+         /test;directory;100;4096;1;;0\n\
+         \"/test/a; b\";directory;100;4096;1;;1\n"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 97. test_sqlite_export – nodes link to their parents, errors and scan are stored
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_sqlite_export() {
+    use disklens::export::sqlite::write_sqlite;
+
+    let mut result = make_scan_result(sample_tree());
+    result.errors.push(ScanError {
+        path: PathBuf::from("/test/sub/locked"),
+        error_type: ScanErrorType::PermissionDenied,
+        message: "Permission denied".into(),
+    });
+    let mut conn = rusqlite::Connection::open_in_memory().unwrap();
+    write_sqlite(&result, &mut conn).unwrap();
+
+    let count = |sql: &str| conn.query_row(sql, [], |row| row.get::<_, i64>(0)).unwrap();
+    assert_eq!(count("SELECT COUNT(*) FROM nodes"), 5);
+    assert_eq!(count("SELECT total_size FROM scan"), 3500);
+    assert_eq!(count("SELECT COUNT(*) FROM errors WHERE type = 'PermissionDenied'"), 1);
+    assert_eq!(count("SELECT COUNT(*) FROM nodes WHERE parent_id IS NULL"), 1);
+    assert_eq!(count("SELECT MAX(depth) FROM nodes"), 2);
+
+    // The children of a directory, found through the parent link
+    let mut query = conn
+        .prepare(
+            "SELECT c.name, c.type, c.size FROM nodes c JOIN nodes p ON c.parent_id = p.id
+             WHERE p.path = '/test' ORDER BY c.size DESC",
+        )
+        .unwrap();
+    let rows: Vec<(String, String, i64)> = query
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("b.txt".to_string(), "file".to_string(), 2000),
+            ("a.txt".to_string(), "file".to_string(), 1000),
+            ("sub".to_string(), "directory".to_string(), 500),
+        ]
+    );
+}
+
+// ---------------------------------------------------------------------------
+// 98. test_ndjson_export – one object per line, nodes pointing at their parent
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_ndjson_export() {
+    use disklens::export::json::write_ndjson;
+
+    let mut result = make_scan_result(sample_tree());
+    result.errors.push(ScanError {
+        path: PathBuf::from("/test/sub/locked"),
+        error_type: ScanErrorType::PermissionDenied,
+        message: "Permission denied".into(),
+    });
+    let mut out = Vec::new();
+    write_ndjson(&result, &mut out).unwrap();
+
+    let records: Vec<serde_json::Value> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 7);
+    assert_eq!(records[0]["record"], "scan");
+    assert_eq!(records[0]["total_size"], 3500);
+    assert_eq!(records[0]["error_count"], 1);
+    assert_eq!(records[6]["record"], "error");
+    assert_eq!(records[6]["path"], "/test/sub/locked");
+
+    let nodes = &records[1..6];
+    assert!(nodes.iter().all(|n| n["record"] == "node" && n.get("children").is_none()));
+    assert_eq!(nodes[0]["path"], "/test");
+    assert!(nodes[0]["parent"].is_null());
+    // Every other node comes after the directory holding it
+    for node in &nodes[1..] {
+        let parent = nodes
+            .iter()
+            .position(|p| p["id"] == node["parent"])
+            .expect("parent written");
+        let path = node["path"].as_str().unwrap();
+        assert_eq!(
+            std::path::Path::new(path).parent().unwrap(),
+            std::path::Path::new(nodes[parent]["path"].as_str().unwrap())
+        );
+        assert_eq!(node["depth"], nodes[parent]["depth"].as_u64().unwrap() + 1);
+    }
+    let c = nodes.iter().find(|n| n["name"] == "c.txt").unwrap();
+    assert_eq!(c["size"], 500);
+    assert_eq!(c["depth"], 2);
+}
+
+// ---------------------------------------------------------------------------
+// 99. test_export_filters – subtree root, depth and size limits narrow exports
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_export_filters() {
+    let mut result = make_scan_result(sample_tree());
+    result.errors.push(ScanError {
+        path: PathBuf::from("/test/sub/locked"),
+        error_type: ScanErrorType::PermissionDenied,
+        message: "Permission denied".into(),
+    });
+    result.errors.push(ScanError {
+        path: PathBuf::from("/test/other"),
+        error_type: ScanErrorType::IoError,
+        message: "I/O error".into(),
+    });
+    let names = |node: &Node| node.children.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+
+    // No filters: the export is the scan
+    let settings = Settings::default();
+    let view = settings.export_view(&result).unwrap();
+    assert_eq!(view.root.children.len(), 3);
+
+    // A subtree becomes the root, with its own totals and errors
+    let settings = Settings {
+        export_root: Some(PathBuf::from("/test/sub")),
+        ..Settings::default()
+    };
+    let view = settings.export_view(&result).unwrap();
+    assert_eq!(view.scan_path, PathBuf::from("/test/sub"));
+    assert_eq!(view.total_size, 500);
+    assert_eq!(view.total_files, 1);
+    assert_eq!(names(&view.root), ["c.txt"]);
+    assert_eq!(view.errors.len(), 1);
+
+    let settings = Settings {
+        export_root: Some(PathBuf::from("/elsewhere")),
+        ..Settings::default()
+    };
+    assert!(settings.export_view(&result).is_err());
+
+    // Depth 1 keeps the root's entries only; the directory keeps its size
+    let settings = Settings {
+        export_depth: Some(1),
+        ..Settings::default()
+    };
+    let view = settings.export_view(&result).unwrap();
+    let sub = view.root.children.iter().find(|c| c.name == "sub").unwrap();
+    assert!(sub.children.is_empty());
+    assert_eq!(sub.size, 500);
+    assert_eq!(view.total_size, 3500);
+
+    // Small entries are left out, still counted in the totals
+    let settings = Settings {
+        export_min_size: Some(1000),
+        ..Settings::default()
+    };
+    let view = settings.export_view(&result).unwrap();
+    let mut kept = names(&view.root);
+    kept.sort();
+    assert_eq!(kept, ["a.txt", "b.txt"]);
+    assert_eq!(view.total_size, 3500);
+
+    // The exporters see the narrowed tree
+    let dir = make_test_dir("export_filters");
+    let md_path = dir.join("report.md");
+    disklens::export::markdown::export_markdown(&view, &md_path).unwrap();
+    let md = std::fs::read_to_string(&md_path).unwrap();
+    assert!(md.contains("b.txt"));
+    assert!(!md.contains("c.txt"));
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 100. test_html_treemap – the report embeds the tree as JSON for the treemap script
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_html_treemap() {
+    use disklens::export::html::{write_html, HtmlOptions};
+
+    let mut children = sample_tree().children;
+    children.push(Node::from_file(
+        PathBuf::from("/test/</script><b>.txt"),
+        "</script><b>.txt".into(),
+        10,
+        None,
+        Some(4),
+    ));
+    let root = Node::from_directory(PathBuf::from("/test"), "test".into(), children);
+    let result = make_scan_result(root);
+    let render = |treemap| {
+        let mut out = Vec::new();
+        let options = HtmlOptions {
+            treemap,
+            ..Default::default()
+        };
+        write_html(&result, &mut out, &options).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+
+    assert!(!render(false).contains("treemap-data"));
+
+    let html = render(true);
+    let start = html.find("id=\"treemap-data\">").unwrap() + "id=\"treemap-data\">".len();
+    let end = start + html[start..].find("</script>").unwrap();
+    let data: serde_json::Value = serde_json::from_str(&html[start..end]).unwrap();
+    assert_eq!(data[0], "/test");
+    assert_eq!(data[1], 3510);
+    assert_eq!(data[2], "dir");
+    // Largest first, directories nested, files tagged with their category
+    let children = data[3].as_array().unwrap();
+    let names: Vec<&str> = children.iter().map(|c| c[0].as_str().unwrap()).collect();
+    assert_eq!(names, ["b.txt", "a.txt", "sub", "</script><b>.txt"]);
+    assert_eq!(children[0][2], "document");
+    assert_eq!(children[2][3][0][0], "c.txt");
+    // Names can't end the script element early
+    assert!(!html[start..end].contains('<'));
+    assert!(html.contains("const TREEMAP_COLORS"));
+}
+
+// ---------------------------------------------------------------------------
+// 101. test_compressed_export – .gz and .zst exports are compressed and read back
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_compressed_export() {
+    use disklens::export::compress::{compressed_path, Compression};
+    use disklens::export::json::import_json;
+    use std::io::Read;
+
+    assert_eq!(compressed_path(Path::new("r.json")), PathBuf::from("r.json.zst"));
+    assert_eq!(compressed_path(Path::new("r.json.gz")), PathBuf::from("r.json.gz"));
+    assert_eq!(Compression::from_path(Path::new("t.csv.zst")), Some(Compression::Zstd));
+    assert_eq!(Compression::from_path(Path::new("t.csv")), None);
+
+    let dir = make_test_dir("compressed_export");
+    let result = make_scan_result(sample_tree());
+    let plain = dir.join("r.json");
+    export_json(&result, &plain).unwrap();
+    let plain_len = std::fs::metadata(&plain).unwrap().len();
+    let magics = [("r.json.gz", &[0x1f, 0x8b][..]), ("r.json.zst", &[0x28, 0xb5, 0x2f, 0xfd])];
+    for (name, magic) in magics {
+        let path = dir.join(name);
+        export_json(&result, &path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert!(bytes.starts_with(magic), "{name}");
+        assert!((bytes.len() as u64) < plain_len, "{name}");
+        let back = import_json(&path).unwrap();
+        assert_eq!(back.total_size, 3500);
+        assert_eq!(back.root.children.len(), 3);
+    }
+
+    // The other exporters go through the same files
+    let csv = dir.join("t.csv.gz");
+    disklens::export::csv::export_tree_csv(&result, &csv, &Default::default()).unwrap();
+    let mut text = String::new();
+    flate2::read::GzDecoder::new(std::fs::File::open(&csv).unwrap())
+        .read_to_string(&mut text)
+        .unwrap();
+    assert!(text.starts_with("path,type,size"));
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 102. test_flamegraph_export – folded stacks carry each entry's own bytes
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_flamegraph_export() {
+    use disklens::export::flamegraph::write_folded;
+
+    let mut children = sample_tree().children;
+    children.push(Node::from_file(
+        PathBuf::from("/test/odd;name"),
+        "odd;name".into(),
+        7,
+        None,
+        Some(4),
+    ));
+    let mut root = Node::from_directory(PathBuf::from("/test"), "test".into(), children);
+    // Bytes of the directory's own, beyond its entries
+    root.size += 100;
+    let result = make_scan_result(root);
+
+    let mut out = Vec::new();
+    write_folded(&result, &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "test 100",
+            "test;a.txt 1000",
+            "test;b.txt 2000",
+            "test;sub;c.txt 500",
+            "test;odd_name 7",
+        ]
+    );
+    // Frame widths add up to the whole scan
+    let total: u64 = lines
+        .iter()
+        .map(|l| l.rsplit_once(' ').unwrap().1.parse::<u64>().unwrap())
+        .sum();
+    assert_eq!(total, result.root.size);
+}
+
+// ---------------------------------------------------------------------------
+// 103. test_diff_report – growers, new and removed directories in both formats
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_diff_report() {
+    use disklens::export::diff::{write_diff_html, write_diff_markdown};
+
+    let old = make_scan_result(sample_tree());
+    let file = |path: &str, size| {
+        let path = PathBuf::from(path);
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        Node::from_file(path, name, size, None, None)
+    };
+    let new = make_scan_result(Node::from_directory(
+        PathBuf::from("/test"),
+        "test".into(),
+        vec![
+            file("/test/a.txt", 400),
+            file("/test/b.txt", 5000),
+            Node::from_directory(
+                PathBuf::from("/test/<new>"),
+                "<new>".into(),
+                vec![file("/test/<new>/d.bin", 800)],
+            ),
+        ],
+    ));
+
+    let mut out = Vec::new();
+    write_diff_markdown(&old, &new, &mut out, 20).unwrap();
+    let md = String::from_utf8(out).unwrap();
+    let section = |title: &str| {
+        let start = md.find(&format!("## {title}")).unwrap();
+        let rest = &md[start + 3..];
+        rest[..rest.find("## ").unwrap_or(rest.len())].to_string()
+    };
+    assert!(md.contains("- **Change:** +2.64 KB"));
+    assert!(section("Biggest Growers").contains("| /test/b.txt | 1.95 KB | 4.88 KB | +2.93 KB |"));
+    assert!(section("New Directories").contains("/test/<new>/"));
+    assert!(section("Removed Directories").contains("| /test/sub/ | 500 B | 0 B | -500 B |"));
+    assert!(section("Biggest Shrinkers").contains("/test/a.txt"));
+    // Only directories are listed as new or removed, and the root only in the summary
+    assert!(!md.contains("d.bin"));
+    assert!(!md.contains("| /test/ |"));
+
+    let mut out = Vec::new();
+    write_diff_html(&old, &new, &mut out, 1).unwrap();
+    let html = String::from_utf8(out).unwrap();
+    assert!(html.contains("/test/&lt;new&gt;/"));
+    assert!(html.contains("<td class=\"size grown\">+2.93 KB</td>"));
+    assert!(html.contains("<h2>Removed Directories</h2>"));
+}
+
+// ---------------------------------------------------------------------------
+// 104. test_export_dialog – x opens a dialog choosing format, scope and file
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_export_dialog() {
+    use crossterm::event::{KeyCode, KeyEvent};
+    use disklens::export::ExportFormat;
+    use disklens::ui::app_state::{ExportField, ExportScope, ViewMode};
+    use disklens::ui::input::{handle_key_event, InputAction};
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    let press = |state: &mut AppState, code| handle_key_event(KeyEvent::from(code), state);
+    let mut state = AppState::new(PathBuf::from("/test"));
+
+    // Nothing to export while scanning
+    state.set_scan_result(make_scan_result(sample_tree()));
+    state.scanning = true;
+    press(&mut state, KeyCode::Char('x'));
+    assert_eq!(state.view_mode, ViewMode::Normal);
+    assert!(state.status_message.is_some());
+    state.scanning = false;
+
+    press(&mut state, KeyCode::Char('x'));
+    assert_eq!(state.view_mode, ViewMode::Export);
+    assert_eq!(state.export_format, ExportFormat::Json);
+    assert_eq!(state.export_scope, ExportScope::Scan);
+    assert!(state.export_path.starts_with("disklens_report_"));
+    assert!(state.export_path.ends_with(".json"));
+
+    // The format follows the arrows, and the extension with it
+    press(&mut state, KeyCode::Right);
+    press(&mut state, KeyCode::Right);
+    assert_eq!(state.export_format, ExportFormat::Html);
+    assert!(state.export_path.ends_with(".html"));
+    press(&mut state, KeyCode::Left);
+    press(&mut state, KeyCode::Left);
+    press(&mut state, KeyCode::Left);
+    assert_eq!(state.export_format, ExportFormat::Markdown);
+    assert!(state.export_path.ends_with(".md"));
+
+    // Marked entries are only offered once something is marked
+    press(&mut state, KeyCode::Down);
+    assert_eq!(state.export_field, ExportField::Scope);
+    press(&mut state, KeyCode::Right);
+    assert_eq!(state.export_scope, ExportScope::CurrentDirectory);
+    press(&mut state, KeyCode::Right);
+    assert_eq!(state.export_scope, ExportScope::Scan);
+
+    // Letters type into the file name rather than acting as keys
+    press(&mut state, KeyCode::Tab);
+    assert_eq!(state.export_field, ExportField::Path);
+    state.export_path.clear();
+    for c in "q.md".chars() {
+        press(&mut state, KeyCode::Char(c));
+    }
+    press(&mut state, KeyCode::Backspace);
+    assert_eq!(state.export_path, "q.m");
+    assert_eq!(state.view_mode, ViewMode::Export);
+    // A renamed file keeps its name when the format changes
+    press(&mut state, KeyCode::Up);
+    press(&mut state, KeyCode::Up);
+    press(&mut state, KeyCode::Right);
+    assert_eq!(state.export_path, "q.m");
+
+    let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+    terminal.draw(|frame| disklens::ui::renderer::render(frame, &state)).unwrap();
+    let buffer = terminal.backend().buffer().clone();
+    let screen: String = (0..buffer.area.height)
+        .map(|y| {
+            let row: String = (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect();
+            row + "\n"
+        })
+        .collect();
+    for text in [" Export ", "Markdown", "Whole scan", "Current directory", "q.m"] {
+        assert!(screen.contains(text), "{} missing from:\n{}", text, screen);
+    }
+
+    assert!(matches!(press(&mut state, KeyCode::Enter), InputAction::Export));
+    press(&mut state, KeyCode::Esc);
+    assert_eq!(state.view_mode, ViewMode::Normal);
+
+    // With marks, the dialog starts on them
+    state.toggle_mark();
+    press(&mut state, KeyCode::Char('x'));
+    assert_eq!(state.export_scope, ExportScope::Marked);
+    assert_eq!(state.export_scopes().len(), 3);
+
+    // Each format writes its file
+    let dir = make_test_dir("export_dialog");
+    let result = make_scan_result(sample_tree());
+    for format in ExportFormat::ALL {
+        let path = dir.join(format!("report.{}", format.extension()));
+        format.export(&result, &path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.contains("b.txt"), "{}", format.label());
+    }
+    cleanup(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// 105. test_mft_scan – directories listed from an NTFS MFT build the same tree
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_mft_scan() {
+    use disklens::core::mft::MftIndex;
+    use disklens::core::scanner::ScannerSync;
+    use std::io::Cursor;
+
+    const RECORD: usize = 1024;
+    const CLUSTER: usize = 4096;
+
+    fn attribute(kind: u32, value: &[u8]) -> Vec<u8> {
+        let len = (0x18 + value.len()).div_ceil(8) * 8;
+        let mut attr = vec![0u8; len];
+        attr[0..4].copy_from_slice(&kind.to_le_bytes());
+        attr[4..8].copy_from_slice(&(len as u32).to_le_bytes());
+        attr[0x10..0x14].copy_from_slice(&(value.len() as u32).to_le_bytes());
+        attr[0x14..0x16].copy_from_slice(&0x18u16.to_le_bytes());
+        attr[0x18..0x18 + value.len()].copy_from_slice(value);
+        attr
+    }
+
+    /// Unnamed `$DATA` held in extents, with `sizes` as (length, allocated, on disk).
+    fn extents(flags: u16, start_vcn: u64, sizes: (u64, u64, u64), runs: &[u8]) -> Vec<u8> {
+        let len = (0x48 + runs.len()).div_ceil(8) * 8;
+        let mut attr = vec![0u8; len];
+        attr[0..4].copy_from_slice(&0x80u32.to_le_bytes());
+        attr[4..8].copy_from_slice(&(len as u32).to_le_bytes());
+        attr[8] = 1;
+        attr[0x0C..0x0E].copy_from_slice(&flags.to_le_bytes());
+        attr[0x10..0x18].copy_from_slice(&start_vcn.to_le_bytes());
+        attr[0x20..0x22].copy_from_slice(&0x48u16.to_le_bytes());
+        attr[0x28..0x30].copy_from_slice(&sizes.1.to_le_bytes());
+        attr[0x30..0x38].copy_from_slice(&sizes.0.to_le_bytes());
+        attr[0x38..0x40].copy_from_slice(&sizes.0.to_le_bytes());
+        attr[0x40..0x48].copy_from_slice(&sizes.2.to_le_bytes());
+        attr[0x48..0x48 + runs.len()].copy_from_slice(runs);
+        attr
+    }
+
+    fn info(modified_secs: u64, attributes: u32) -> Vec<u8> {
+        let mut value = vec![0u8; 0x48];
+        let ticks = (modified_secs + 11_644_473_600) * 10_000_000;
+        value[0x08..0x10].copy_from_slice(&ticks.to_le_bytes());
+        value[0x20..0x24].copy_from_slice(&attributes.to_le_bytes());
+        attribute(0x10, &value)
+    }
+
+    fn name(parent: u64, name: &str, namespace: u8) -> Vec<u8> {
+        let units: Vec<u16> = name.encode_utf16().collect();
+        let mut value = vec![0u8; 0x42 + units.len() * 2];
+        value[0..8].copy_from_slice(&(parent | 1 << 48).to_le_bytes());
+        value[0x40] = units.len() as u8;
+        value[0x41] = namespace;
+        for (i, unit) in units.iter().enumerate() {
+            value[0x42 + i * 2..0x44 + i * 2].copy_from_slice(&unit.to_le_bytes());
+        }
+        attribute(0x30, &value)
+    }
+
+    /// A record with the update sequence applied, as it is stored on disk.
+    fn record(flags: u16, base: u64, attributes: &[Vec<u8>]) -> Vec<u8> {
+        let mut bytes = vec![0u8; RECORD];
+        bytes[0..4].copy_from_slice(b"FILE");
+        bytes[4..6].copy_from_slice(&0x30u16.to_le_bytes());
+        bytes[6..8].copy_from_slice(&3u16.to_le_bytes());
+        bytes[0x14..0x16].copy_from_slice(&0x38u16.to_le_bytes());
+        bytes[0x16..0x18].copy_from_slice(&flags.to_le_bytes());
+        bytes[0x20..0x28].copy_from_slice(&base.to_le_bytes());
+        let mut offset = 0x38;
+        for attr in attributes {
+            bytes[offset..offset + attr.len()].copy_from_slice(attr);
+            offset += attr.len();
+        }
+        bytes[offset..offset + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        bytes[0x18..0x1C].copy_from_slice(&(offset as u32 + 8).to_le_bytes());
+        bytes[0x30..0x32].copy_from_slice(&[7, 0]);
+        for stride in 1..=2 {
+            let end = stride * 512;
+            let saved = [bytes[end - 2], bytes[end - 1]];
+            bytes[0x30 + stride * 2..0x32 + stride * 2].copy_from_slice(&saved);
+            bytes[end - 2..end].copy_from_slice(&[7, 0]);
+        }
+        bytes
+    }
+
+    // 23 records in two extents: clusters 4-6 and 10-12
+    let (file, dir, hidden) = (0x1, 0x3, 0x2);
+    let mut records = vec![vec![0u8; RECORD]; 23];
+    let mft_runs = [0x11, 3, 4, 0x11, 3, 6, 0];
+    records[0] = record(
+        file,
+        0,
+        &[
+            info(0, 0x6),
+            name(5, "$MFT", 3),
+            extents(0, 0, (23 * RECORD as u64, 24 * RECORD as u64, 0), &mft_runs),
+        ],
+    );
+    records[5] = record(dir, 0, &[info(0, 0x6), name(5, ".", 3)]);
+    records[16] = record(dir, 0, &[info(0, 0), name(5, "docs", 1)]);
+    records[17] = record(
+        file,
+        0,
+        &[info(1_600_000_000, 0x20), name(5, "a.txt", 3), attribute(0x80, &[b'a'; 100])],
+    );
+    records[18] = record(file, 0, &[info(0, 0x200), name(16, "big.bin", 1)]);
+    records[19] = record(
+        file,
+        0,
+        &[
+            info(0, hidden),
+            name(5, "secret.txt", 1),
+            name(5, "SECRET.TXT", 2),
+            attribute(0x80, &[0; 10]),
+        ],
+    );
+    records[20] = record(
+        file,
+        0,
+        &[info(0, 0), name(5, "link1", 1), name(16, "link2", 1), attribute(0x80, &[0; 30])],
+    );
+    records[21] = record(0, 0, &[info(0, 0), name(5, "deleted.txt", 1)]);
+    // big.bin's data, sparse, in an extension record
+    records[22] = record(
+        file,
+        18 | 1 << 48,
+        &[extents(0x8000, 0, (5000, 8192, 4096), &[0x11, 1, 20, 0x01, 1, 0])],
+    );
+
+    let mut image = vec![0u8; 13 * CLUSTER];
+    image[3..11].copy_from_slice(b"NTFS    ");
+    image[0x0B..0x0D].copy_from_slice(&512u16.to_le_bytes());
+    image[0x0D] = 8;
+    image[0x30..0x38].copy_from_slice(&4u64.to_le_bytes());
+    image[0x40] = 0xF6;
+    let bytes: Vec<u8> = records.concat();
+    image[4 * CLUSTER..7 * CLUSTER].copy_from_slice(&bytes[..3 * CLUSTER]);
+    image[10 * CLUSTER..10 * CLUSTER + bytes.len() - 3 * CLUSTER]
+        .copy_from_slice(&bytes[3 * CLUSTER..]);
+
+    let index = MftIndex::read(Cursor::new(&image)).unwrap();
+    // The root and records 16-20; `$MFT` and the deleted file are left out
+    assert_eq!(index.len(), 6);
+
+    let root = PathBuf::from("/ntfs_test");
+    let scan = |settings: Settings, path: &Path| {
+        let (event_tx, _rx) = disklens::core::events::create_event_channel();
+        let index = MftIndex::read(Cursor::new(&image)).unwrap();
+        ScannerSync::new(settings, event_tx)
+            .with_mft(root.clone(), index)
+            .scan(path.to_path_buf())
+            .unwrap()
+    };
+    let settings = Settings {
+        dedupe_hardlinks: false,
+        ..test_settings()
+    };
+
+    let result = scan(settings.clone(), &root);
+    assert_eq!(child_names(&result.root), ["a.txt", "docs", "link1", "secret.txt"]);
+    assert_eq!(result.total_size, 100 + 5000 + 10 + 30 + 30);
+    assert_eq!(result.total_files, 5);
+    let a = result.root.find(&root.join("a.txt")).unwrap();
+    assert_eq!(a.modified, Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000)));
+    let docs = result.root.find(&root.join("docs")).unwrap();
+    assert_eq!(docs.node_type, NodeType::Directory);
+    assert_eq!(child_names(docs), ["big.bin", "link2"]);
+    let big = result.root.find(&root.join("docs/big.bin")).unwrap();
+    assert_eq!((big.size, big.size_on_disk), (5000, 4096));
+
+    // Names resolve regardless of case, and hidden files can be skipped
+    let docs = scan(settings.clone(), &root.join("DOCS"));
+    assert_eq!(docs.total_size, 5030);
+    let visible = scan(
+        Settings {
+            skip_hidden: true,
+            ..settings
+        },
+        &root,
+    );
+    assert_eq!(child_names(&visible.root), ["a.txt", "docs", "link1"]);
+
+    // Other filesystems and cut-off boot sectors are refused
+    assert!(MftIndex::read(Cursor::new(vec![0u8; CLUSTER])).is_err());
+    assert!(MftIndex::read(Cursor::new(&image[..12])).is_err());
+}